@@ -1,11 +1,17 @@
-#[cfg(any(not(target_os="macos"), feature = "qt_backend"))]
+#[cfg(all(any(not(target_os="macos"), feature = "qt_backend"), not(feature = "headless_backend")))]
 use std::{path::PathBuf};
-#[cfg(all(target_os = "macos", feature = "qt_backend"))]
+#[cfg(all(target_os = "macos", feature = "qt_backend", not(feature = "headless_backend")))]
 use std::process::{Command, Stdio};
 
+#[cfg(not(feature = "headless_backend"))]
 use cc;
 
-#[cfg(all(target_os="macos", not(feature = "qt_backend")))]
+#[cfg(feature = "headless_backend")]
+fn build() {
+    // the backend is implemented in rust (src/native/headless.rs)
+}
+
+#[cfg(all(target_os="macos", not(feature = "qt_backend"), not(feature = "headless_backend")))]
 fn build() {
     println!("cargo:rerun-if-changed=macos");
 
@@ -29,7 +35,7 @@ fn build() {
     println!("cargo:rustc-link-lib=framework=UniformTypeIdentifiers");
 }
 
-#[cfg(any(not(target_os="macos"), feature = "qt_backend"))]
+#[cfg(all(any(not(target_os="macos"), feature = "qt_backend"), not(feature = "headless_backend")))]
 fn build() {
     println!("cargo:rerun-if-changed=qt");
 
@@ -234,6 +234,12 @@ int performing_subview_insertion = 0;
     }
 }
 
+- (void)windowDidChangeOcclusionState:(NSNotification *)notification {
+    if (handle.p0) {
+        front_window_set_visible(handle, (self.occlusionState & NSWindowOcclusionStateVisible) != 0);
+    }
+}
+
 - (NSText *)fieldEditor:(BOOL)createFlag
               forObject:(id)object {
     static FieldEditor *fieldEditor = nil;
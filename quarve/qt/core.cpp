@@ -100,6 +100,10 @@ protected:
             if (!executing_back_fullscreen && (newState & Qt::WindowFullScreen) != (oldState & Qt::WindowFullScreen)) {
                 front_window_will_fullscreen(this->handle, (newState & Qt::WindowFullScreen) != 0);
             }
+
+            if (this->handle.p0 && (newState & Qt::WindowMinimized) != (oldState & Qt::WindowMinimized)) {
+                front_window_set_visible(this->handle, (newState & Qt::WindowMinimized) == 0);
+            }
         }
         QWidget::changeEvent(event);
     }
@@ -18,6 +18,9 @@ extern "C" uint8_t front_window_dispatch_event(fat_pointer handle, buffer_event
 // fp: &'static dyn WindowBase
 extern "C" void front_window_will_fullscreen(fat_pointer p, uint8_t fs);
 
+// fp: &'static dyn WindowBase
+extern "C" void front_window_set_visible(fat_pointer p, uint8_t visible);
+
 // box: Box<dyn FnOnce(MainThreadMarker) + Send + 'static>
 extern "C" void front_execute_fn_once(fat_pointer box);
 
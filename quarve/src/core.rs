@@ -1,19 +1,25 @@
 use std::cell::OnceCell;
-use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::Sender;
 use std::sync::OnceLock;
-use std::time::{Duration, Instant};
 
 pub use application::*;
 pub use environment::*;
+pub use frame_scheduler::*;
 pub use global::*;
 // life cycle methods only needed outside of this module
 // when testing
 #[cfg(test)]
 pub(crate) use life_cycle::*;
+pub use life_cycle::TestClock;
 pub use slock::*;
+pub use slock_profiler::{DurationHistogram, SlockProfiler, SlockReport, SlockSiteStats, SlockStarvation};
 pub use window::*;
 
-static TIMER_WORKER: OnceLock<SyncSender<(Box<dyn for<'a> FnMut(Duration, Slock<'a>) -> bool + Send>, Instant)>> = OnceLock::new();
+use crate::core::frame_scheduler::FrameSubscriber;
+
+// unbounded so that registering never blocks
+// (in virtual time, the registering thread is the one that ticks)
+static TIMER_WORKER: OnceLock<Sender<FrameSubscriber>> = OnceLock::new();
 
 thread_local! {
     pub(crate) static APP: OnceCell<Application> = OnceCell::new();
@@ -64,68 +70,640 @@ mod debug_stats {
     }
 }
 
-mod life_cycle {
-    use std::sync::mpsc::{Receiver, sync_channel};
-    use std::thread;
+mod slock_profiler {
+    use std::cmp::Reverse;
+    use std::collections::HashMap;
+    use std::fmt::{Display, Formatter};
+    use std::panic::Location;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::Mutex;
     use std::time::{Duration, Instant};
 
-    use crate::core::{Slock, slock_owner, TIMER_WORKER};
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    static REPORT_AT_EXIT: AtomicBool = AtomicBool::new(false);
+    static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+    // site that currently holds the slock (only tracked while profiling)
+    static HOLDER: Mutex<Option<&'static Location<'static>>> = Mutex::new(None);
+
+    const HISTOGRAM_BUCKETS: usize = 24;
+
+    /// Log scale histogram of durations.
+    /// Bucket `i` counts durations in [2^i, 2^(i + 1)) microseconds
+    /// (the first bucket also counts anything shorter)
+    #[derive(Clone, Debug, Default)]
+    pub struct DurationHistogram {
+        buckets: [u64; HISTOGRAM_BUCKETS],
+        count: u64,
+        total: Duration,
+        max: Duration,
+    }
 
-    const ANIMATION_THREAD_TICK: Duration = Duration::from_nanos(1_000_000_000 / 60);
+    impl DurationHistogram {
+        fn record(&mut self, duration: Duration) {
+            let micros = duration.as_micros().max(1);
+            let bucket = (127 - micros.leading_zeros() as usize).min(HISTOGRAM_BUCKETS - 1);
+            self.buckets[bucket] += 1;
+            self.count += 1;
+            self.total += duration;
+            self.max = self.max.max(duration);
+        }
 
-    fn timer_worker(receiver: Receiver<(Box<dyn for <'a> FnMut(Duration, Slock<'a>) -> bool + Send>, Instant)>) {
-        let mut subscribers: Vec<(Box<dyn for <'a> FnMut(Duration, Slock<'a>) -> bool + Send>, Instant)> = Vec::new();
+        pub fn buckets(&self) -> &[u64] {
+            &self.buckets
+        }
 
-        loop {
-            let start_time = Instant::now();
+        pub fn count(&self) -> u64 {
+            self.count
+        }
 
-            while let Ok(handle) = receiver.try_recv() {
-                subscribers.push(handle);
+        pub fn total(&self) -> Duration {
+            self.total
+        }
+
+        pub fn max(&self) -> Duration {
+            self.max
+        }
+
+        pub fn mean(&self) -> Duration {
+            if self.count == 0 {
+                Duration::ZERO
+            }
+            else {
+                self.total.div_f64(self.count as f64)
             }
+        }
 
-            if !subscribers.is_empty() {
-                let s = slock_owner();
-                subscribers.retain_mut(|(f, start) | f(start_time.duration_since(*start), s.marker()));
+        /// Upper bound of the bucket containing the given percentile (in [0, 1])
+        pub fn percentile(&self, p: f64) -> Duration {
+            let threshold = (p.clamp(0.0, 1.0) * self.count as f64).ceil() as u64;
+            let mut seen = 0;
+            for (i, count) in self.buckets.iter().enumerate() {
+                seen += count;
+                if seen >= threshold && seen > 0 {
+                    return Duration::from_micros(1 << (i + 1)).min(self.max);
+                }
             }
 
-            if subscribers.is_empty() {
-                // if no subscribers, wait until a subscriber comes
-                match receiver.recv() {
-                    Ok(handle) => {
-                        subscribers.push(handle);
-                    }
-                    Err(_) => break
+            self.max
+        }
+    }
+
+    /// Statistics of all acquisitions made from one call site
+    #[derive(Clone, Debug)]
+    pub struct SlockSiteStats {
+        pub site: &'static Location<'static>,
+        pub main_thread_acquisitions: u64,
+        pub wait: DurationHistogram,
+        pub hold: DurationHistogram,
+    }
+
+    /// The main thread waited for the slock longer
+    /// than the starvation threshold
+    #[derive(Clone, Debug)]
+    pub struct SlockStarvation {
+        pub waiting_site: &'static Location<'static>,
+        // site holding the slock when the main thread started waiting
+        pub holding_site: Option<&'static Location<'static>>,
+        pub waited: Duration,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct SlockReport {
+        /// Sorted by descending total hold time
+        pub sites: Vec<SlockSiteStats>,
+        pub starvations: Vec<SlockStarvation>,
+    }
+
+    struct Profile {
+        starvation_threshold: Duration,
+        sites: HashMap<&'static Location<'static>, SlockSiteStats>,
+        starvations: Vec<SlockStarvation>,
+    }
+
+    impl Profile {
+        fn site(&mut self, site: &'static Location<'static>) -> &mut SlockSiteStats {
+            self.sites.entry(site)
+                .or_insert_with(|| SlockSiteStats {
+                    site,
+                    main_thread_acquisitions: 0,
+                    wait: DurationHistogram::default(),
+                    hold: DurationHistogram::default(),
+                })
+        }
+    }
+
+    /// Opt-in profiler of slock contention. While enabled, every
+    /// acquisition records how long it waited for the slock and how long
+    /// it held it, keyed by the call site of `slock_owner`/`slock_main_owner`.
+    pub struct SlockProfiler;
+
+    impl SlockProfiler {
+        /// Starts profiling. Main thread waits longer than
+        /// `starvation_threshold` are recorded as starvations
+        pub fn enable(starvation_threshold: Duration) {
+            let mut profile = PROFILE.lock().unwrap();
+            match profile.as_mut() {
+                Some(profile) => profile.starvation_threshold = starvation_threshold,
+                None => *profile = Some(Profile {
+                    starvation_threshold,
+                    sites: HashMap::new(),
+                    starvations: Vec::new(),
+                })
+            }
+            ENABLED.store(true, Relaxed);
+        }
+
+        /// Stops profiling, keeping the data collected so far
+        pub fn disable() {
+            ENABLED.store(false, Relaxed);
+        }
+
+        pub fn is_enabled() -> bool {
+            ENABLED.load(Relaxed)
+        }
+
+        pub fn reset() {
+            if let Some(profile) = PROFILE.lock().unwrap().as_mut() {
+                profile.sites.clear();
+                profile.starvations.clear();
+            }
+        }
+
+        pub fn report() -> SlockReport {
+            let profile = PROFILE.lock().unwrap();
+            let Some(profile) = profile.as_ref() else {
+                return SlockReport::default();
+            };
+
+            let mut sites: Vec<_> = profile.sites.values().cloned().collect();
+            sites.sort_by_key(|site| Reverse(site.hold.total()));
+
+            SlockReport {
+                sites,
+                starvations: profile.starvations.clone(),
+            }
+        }
+
+        /// Prints the report when the application exits
+        pub fn report_at_exit(report: bool) {
+            REPORT_AT_EXIT.store(report, Relaxed);
+        }
+    }
+
+    pub(crate) fn print_exit_report() {
+        // swap so that the report is printed at most once
+        if REPORT_AT_EXIT.swap(false, Relaxed) {
+            println!("{}", SlockProfiler::report());
+        }
+    }
+
+    impl Display for SlockReport {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            writeln!(f, "quarve slock profile")?;
+            writeln!(f, "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10}  site",
+                     "count", "wait p50", "wait max", "hold p50", "hold p99", "hold max")?;
+            for site in &self.sites {
+                writeln!(f, "{:>8} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?}  {}",
+                         site.hold.count(),
+                         site.wait.percentile(0.5), site.wait.max(),
+                         site.hold.percentile(0.5), site.hold.percentile(0.99), site.hold.max(),
+                         site.site)?;
+            }
+
+            for starvation in &self.starvations {
+                write!(f, "main thread starved for {:.2?} at {}", starvation.waited, starvation.waiting_site)?;
+                match starvation.holding_site {
+                    Some(holder) => writeln!(f, " (held by {})", holder)?,
+                    None => writeln!(f)?
                 }
             }
 
-            let curr_time = Instant::now();
-            let passed = curr_time.duration_since(start_time);
-            if passed < ANIMATION_THREAD_TICK {
-                // FIXME this is sleeping too long
-                // we may want to look at https://crates.io/crates/spin_sleep
-                // at some point
-                thread::sleep(ANIMATION_THREAD_TICK - passed);
+            Ok(())
+        }
+    }
+
+    /// Profiling state of a single slock acquisition
+    pub(crate) struct Acquisition {
+        site: &'static Location<'static>,
+        acquired: Instant,
+        #[cfg(feature = "tracing")]
+        _span: tracing::span::EnteredSpan,
+    }
+
+    pub(crate) struct PendingAcquisition {
+        site: &'static Location<'static>,
+        main: bool,
+        holder: Option<&'static Location<'static>>,
+        start: Instant,
+    }
+
+    #[inline]
+    pub(crate) fn profiling() -> bool {
+        cfg!(feature = "tracing") || ENABLED.load(Relaxed)
+    }
+
+    // called right before blocking on the slock
+    pub(crate) fn will_acquire(site: &'static Location<'static>, main: bool) -> PendingAcquisition {
+        PendingAcquisition {
+            site,
+            main,
+            holder: *HOLDER.lock().unwrap(),
+            start: Instant::now(),
+        }
+    }
+
+    // called right after the slock was attained
+    pub(crate) fn did_acquire(pending: PendingAcquisition) -> Acquisition {
+        let acquired = Instant::now();
+        let waited = acquired.duration_since(pending.start);
+        *HOLDER.lock().unwrap() = Some(pending.site);
+
+        if ENABLED.load(Relaxed) {
+            if let Some(profile) = PROFILE.lock().unwrap().as_mut() {
+                if pending.main && waited > profile.starvation_threshold {
+                    profile.starvations.push(SlockStarvation {
+                        waiting_site: pending.site,
+                        holding_site: pending.holder,
+                        waited,
+                    });
+                }
+
+                let stats = profile.site(pending.site);
+                stats.wait.record(waited);
+                if pending.main {
+                    stats.main_thread_acquisitions += 1;
+                }
             }
         }
+
+        Acquisition {
+            site: pending.site,
+            acquired,
+            #[cfg(feature = "tracing")]
+            _span: tracing::trace_span!(
+                "slock",
+                site = %pending.site,
+                main_thread = pending.main,
+                wait_us = waited.as_micros() as u64
+            ).entered(),
+        }
     }
 
+    // called right before the slock is released
+    pub(crate) fn will_release(acquisition: Acquisition) {
+        *HOLDER.lock().unwrap() = None;
+
+        if ENABLED.load(Relaxed) {
+            if let Some(profile) = PROFILE.lock().unwrap().as_mut() {
+                profile.site(acquisition.site).hold.record(acquisition.acquired.elapsed());
+            }
+        }
+    }
+}
+
+mod life_cycle {
+    use std::sync::mpsc::channel;
+    use std::sync::{Mutex, OnceLock};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::core::frame_scheduler::{frame_interval, run_frame_scheduler, scheduler_now, set_virtual_time, FrameRunner};
+    use crate::core::TIMER_WORKER;
+
+    static TEST_CLOCK: OnceLock<Mutex<FrameRunner>> = OnceLock::new();
+
     // may also be used in some testing code
     pub(crate) fn setup_timing_thread() {
-        let (sender, receiver) = sync_channel(5);
+        let (sender, receiver) = channel();
         /* join handle not needed */
         let _ = thread::spawn(move || {
-            timer_worker(receiver)
+            run_frame_scheduler(receiver)
         });
 
         TIMER_WORKER.set(sender).expect("Application should only be run once");
     }
+
+    /// Virtual time for the frame scheduler, meant for deterministic tests.
+    /// Once installed, timed workers (and therefore clock signals,
+    /// capacitors and debounce-style operators) are only ticked by
+    /// `advance`, which runs them on the calling thread.
+    /// There is one clock per process, so tests that share a process
+    /// also share the clock.
+    #[derive(Copy, Clone)]
+    pub struct TestClock {
+        _private: ()
+    }
+
+    impl TestClock {
+        /// Installs the virtual clock, or returns the existing one
+        /// if already installed. Panics if the real timing thread
+        /// has already been set up (i.e. the application was launched)
+        pub fn install() -> TestClock {
+            TEST_CLOCK.get_or_init(|| {
+                let (sender, receiver) = channel();
+                TIMER_WORKER.set(sender)
+                    .expect("Cannot install a test clock after the timing thread has been set up");
+                set_virtual_time(Duration::ZERO);
+
+                Mutex::new(FrameRunner::new(receiver))
+            });
+
+            TestClock {
+                _private: ()
+            }
+        }
+
+        /// Total virtual time that has been advanced
+        pub fn now(&self) -> Duration {
+            scheduler_now()
+        }
+
+        /// Advances virtual time by `by`, running one frame per frame interval
+        /// and a final frame at exactly the new time.
+        /// The state lock must not be held by the calling thread
+        pub fn advance(&self, by: Duration) {
+            let mut runner = TEST_CLOCK.get().unwrap().lock().unwrap();
+            let target = scheduler_now() + by;
+
+            while scheduler_now() < target {
+                let now = (scheduler_now() + frame_interval()).min(target);
+                set_virtual_time(now);
+                runner.run_frame(now);
+            }
+        }
+
+        /// Advances virtual time in one frame intervals until
+        /// no timed workers remain, or `limit` has passed
+        pub fn settle(&self, limit: Duration) {
+            let start = self.now();
+            while self.now() - start < limit && !TEST_CLOCK.get().unwrap().lock().unwrap().is_idle() {
+                self.advance(frame_interval());
+            }
+        }
+    }
+}
+
+pub(crate) mod frame_scheduler {
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicBool, AtomicU32};
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::mpsc::Receiver;
+    use std::sync::{Mutex, OnceLock};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::core::{Slock, slock_owner};
+    use crate::native::WindowHandle;
+
+    type FrameCallback = Box<dyn for<'a> FnMut(Duration, Slock<'a>) -> bool + Send>;
+
+    static TARGET_FPS: AtomicU32 = AtomicU32::new(60);
+    static REAL_ORIGIN: OnceLock<Instant> = OnceLock::new();
+    // set once a test clock is installed
+    static VIRTUAL_TIME: Mutex<Option<Duration>> = Mutex::new(None);
+    static REDUCED_MOTION: AtomicBool = AtomicBool::new(false);
+    static PAUSED_WINDOWS: Mutex<Vec<WindowHandle>> = Mutex::new(Vec::new());
+    static FRAME_STATS: Mutex<FrameStats> = Mutex::new(FrameStats::new());
+
+    thread_local! {
+        // window whose callback is currently running on this thread
+        // subscribers created during that time are attributed to it
+        static CURRENT_WINDOW: Cell<Option<WindowHandle>> = const { Cell::new(None) };
+    }
+
+    /// Timing information of the frames run by the scheduler.
+    /// Frame time only measures the work done by subscribers,
+    /// not the time spent waiting for the next frame
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct FrameStats {
+        /// Number of frames with at least one subscriber
+        pub frames: u64,
+        /// Frames whose work exceeded the target frame interval
+        pub dropped_frames: u64,
+        pub last_frame_time: Duration,
+        pub max_frame_time: Duration,
+        total_frame_time: Duration,
+    }
+
+    impl FrameStats {
+        const fn new() -> Self {
+            FrameStats {
+                frames: 0,
+                dropped_frames: 0,
+                last_frame_time: Duration::ZERO,
+                max_frame_time: Duration::ZERO,
+                total_frame_time: Duration::ZERO,
+            }
+        }
+
+        pub fn average_frame_time(&self) -> Duration {
+            if self.frames == 0 {
+                Duration::ZERO
+            }
+            else {
+                self.total_frame_time.div_f64(self.frames as f64)
+            }
+        }
+
+        fn record(&mut self, frame_time: Duration, interval: Duration) {
+            self.frames += 1;
+            if frame_time > interval {
+                self.dropped_frames += 1;
+            }
+            self.last_frame_time = frame_time;
+            self.max_frame_time = self.max_frame_time.max(frame_time);
+            self.total_frame_time += frame_time;
+        }
+    }
+
+    pub(crate) struct FrameSubscriber {
+        callback: FrameCallback,
+        window: Option<WindowHandle>,
+        // time since subscription, excluding
+        // the time that the window was paused
+        elapsed: Duration,
+        last_tick: Duration,
+    }
+
+    impl FrameSubscriber {
+        pub(crate) fn new(callback: FrameCallback) -> Self {
+            FrameSubscriber {
+                callback,
+                window: CURRENT_WINDOW.with(|w| w.get()),
+                elapsed: Duration::ZERO,
+                last_tick: scheduler_now(),
+            }
+        }
+
+        fn tick(&mut self, now: Duration, paused: &[WindowHandle], s: Slock) -> bool {
+            let delta = now.saturating_sub(self.last_tick);
+            self.last_tick = now;
+
+            if self.window.is_some_and(|w| paused.contains(&w)) {
+                return true;
+            }
+
+            self.elapsed += delta;
+            (self.callback)(self.elapsed, s)
+        }
+    }
+
+    /// Sets the rate at which timed workers (and hence
+    /// capacitors and clock signals) are ticked
+    pub fn set_target_fps(fps: u32) {
+        assert!(fps > 0, "Target fps must be positive");
+        TARGET_FPS.store(fps, Relaxed);
+    }
+
+    pub fn target_fps() -> u32 {
+        TARGET_FPS.load(Relaxed)
+    }
+
+    pub fn frame_interval() -> Duration {
+        Duration::from_nanos(1_000_000_000 / target_fps() as u64)
+    }
+
+    /// When enabled, capacitors that represent motion
+    /// jump directly to their target rather than animating
+    pub fn set_reduced_motion(reduced: bool) {
+        REDUCED_MOTION.store(reduced, Relaxed);
+    }
+
+    pub fn reduced_motion() -> bool {
+        REDUCED_MOTION.load(Relaxed)
+    }
+
+    pub fn frame_stats() -> FrameStats {
+        *FRAME_STATS.lock().unwrap()
+    }
+
+    pub fn reset_frame_stats() {
+        *FRAME_STATS.lock().unwrap() = FrameStats::new();
+    }
+
+    /// Subscribers attributed to a paused window are not ticked,
+    /// and their elapsed time does not advance while paused.
+    /// Windows are paused automatically when hidden
+    pub fn set_window_paused(window: WindowHandle, paused: bool) {
+        let mut paused_windows = PAUSED_WINDOWS.lock().unwrap();
+        paused_windows.retain(|w| *w != window);
+        if paused {
+            paused_windows.push(window);
+        }
+    }
+
+    pub fn is_window_paused(window: WindowHandle) -> bool {
+        PAUSED_WINDOWS.lock().unwrap().contains(&window)
+    }
+
+    /// Attributes any subscribers created during `f` to `window`
+    pub(crate) fn with_frame_window<R>(window: WindowHandle, f: impl FnOnce() -> R) -> R {
+        let prev = CURRENT_WINDOW.with(|w| w.replace(Some(window)));
+        let ret = f();
+        CURRENT_WINDOW.with(|w| w.set(prev));
+
+        ret
+    }
+
+    /// Time according to the scheduler (virtual if a test clock is installed)
+    pub(crate) fn scheduler_now() -> Duration {
+        if let Some(virtual_time) = *VIRTUAL_TIME.lock().unwrap() {
+            virtual_time
+        }
+        else {
+            REAL_ORIGIN.get_or_init(Instant::now).elapsed()
+        }
+    }
+
+    pub(crate) fn set_virtual_time(time: Duration) {
+        *VIRTUAL_TIME.lock().unwrap() = Some(time);
+    }
+
+    pub(crate) struct FrameRunner {
+        receiver: Receiver<FrameSubscriber>,
+        subscribers: Vec<FrameSubscriber>,
+    }
+
+    impl FrameRunner {
+        pub(crate) fn new(receiver: Receiver<FrameSubscriber>) -> Self {
+            FrameRunner {
+                receiver,
+                subscribers: Vec::new()
+            }
+        }
+
+        // all subscribers of a frame are ticked under a single state lock
+        // so listeners never observe a partially updated frame
+        pub(crate) fn run_frame(&mut self, now: Duration) {
+            let start_time = Instant::now();
+
+            while let Ok(subscriber) = self.receiver.try_recv() {
+                self.subscribers.push(subscriber);
+            }
+
+            if !self.subscribers.is_empty() {
+                // copied so that the lock is never held alongside the slock
+                let paused = PAUSED_WINDOWS.lock().unwrap().clone();
+
+                let s = slock_owner();
+                self.subscribers.retain_mut(|sub| sub.tick(now, &paused, s.marker()));
+                drop(s);
+
+                FRAME_STATS.lock().unwrap().record(start_time.elapsed(), frame_interval());
+            }
+        }
+
+        pub(crate) fn is_idle(&mut self) -> bool {
+            while let Ok(subscriber) = self.receiver.try_recv() {
+                self.subscribers.push(subscriber);
+            }
+
+            self.subscribers.is_empty()
+        }
+
+        // blocks until a subscriber comes
+        // returns false if the channel was closed
+        fn wait(&mut self) -> bool {
+            match self.receiver.recv() {
+                Ok(subscriber) => {
+                    self.subscribers.push(subscriber);
+                    true
+                }
+                Err(_) => false
+            }
+        }
+    }
+
+    pub(crate) fn run_frame_scheduler(receiver: Receiver<FrameSubscriber>) {
+        let mut runner = FrameRunner::new(receiver);
+
+        loop {
+            let start_time = Instant::now();
+            runner.run_frame(scheduler_now());
+
+            // if no subscribers, wait until a subscriber comes
+            if runner.is_idle() && !runner.wait() {
+                break;
+            }
+
+            let passed = start_time.elapsed();
+            let interval = frame_interval();
+            if passed < interval {
+                // FIXME this is sleeping too long
+                // we may want to look at https://crates.io/crates/spin_sleep
+                // at some point
+                thread::sleep(interval - passed);
+            }
+        }
+    }
 }
 
 mod environment {
     use crate::resource::Resource;
     use crate::util::geo::ScreenUnit;
     use crate::view::menu::MenuChannel;
-    use crate::view::undo_manager::UndoManager;
+    use crate::view::undo_manager::{UndoManager, UndoRouter};
     use crate::view::util::Color;
 
     pub trait Environment: 'static {
@@ -149,20 +727,27 @@ mod environment {
     }
 
     pub struct StandardConstEnv {
-        pub channels: StandardChannels
+        pub channels: StandardChannels,
+        // routes the undo and redo channels to the focused undo manager
+        pub undo_router: UndoRouter
     }
 
     impl StandardConstEnv {
         pub fn new() -> Self {
+            let undo_menu = MenuChannel::new();
+            let redo_menu = MenuChannel::new();
+            let undo_router = UndoRouter::new(&undo_menu, &redo_menu);
+
             Self {
                 channels: StandardChannels {
-                    undo_menu: MenuChannel::new(),
-                    redo_menu: MenuChannel::new(),
+                    undo_menu,
+                    redo_menu,
                     cut_menu: MenuChannel::new(),
                     copy_menu: MenuChannel::new(),
                     paste_menu: MenuChannel::new(),
                     select_all_menu: MenuChannel::new(),
-                }
+                },
+                undo_router
             }
         }
     }
@@ -231,6 +816,7 @@ mod application {
 
     use crate::core::{MSlock, slock_main_owner};
     use crate::core::life_cycle::setup_timing_thread;
+    use crate::core::slock_profiler::print_exit_report;
     use crate::core::window::{new_window, WindowNativeCallback, WindowProvider};
     use crate::native;
     use crate::state::slock_cell::MainSlockCell;
@@ -256,10 +842,24 @@ mod application {
         }
 
         pub(crate) fn run(&self) {
+            #[cfg(not(feature = "headless_backend"))]
             setup_timing_thread();
 
+            // headless applications may be launched many times per process,
+            // and may already have a test clock installed
+            #[cfg(feature = "headless_backend")]
+            {
+                static TIMING: std::sync::Once = std::sync::Once::new();
+                TIMING.call_once(|| {
+                    if crate::core::TIMER_WORKER.get().is_none() {
+                        setup_timing_thread();
+                    }
+                });
+            }
+
             /* run app */
             native::global::main_loop();
+            print_exit_report();
         }
 
         pub(crate) fn will_spawn(&self) {
@@ -278,6 +878,7 @@ mod application {
 
         #[cold]
         pub fn exit(&self, _s: MSlock) {
+            print_exit_report();
             native::global::exit();
         }
     }
@@ -290,7 +891,8 @@ mod window {
     use std::sync::{Arc, Weak};
 
     use crate::{native, util};
-    use crate::core::{APP, Environment, MSlock, run_main_async, run_main_maybe_sync, Slock};
+    use crate::core::{APP, Environment, MSlock, run_main_async, run_main_maybe_sync, set_window_paused, Slock};
+    use crate::core::frame_scheduler::with_frame_window;
     use crate::core::window::invalidated_entry::InvalidatedEntry;
     use crate::event::{Event, EventPayload, EventResult};
     use crate::native::window::{window_exit, window_set_menu};
@@ -381,6 +983,7 @@ mod window {
 
         fn dispatch_native_event(&self, event: Event, s: MSlock) -> u8;
         fn set_fullscreen(&self, fs: bool, s: MSlock);
+        fn set_visible(&self, visible: bool, s: MSlock);
     }
 
     pub(crate) trait WindowViewCallback<E> where E: Environment {
@@ -435,6 +1038,10 @@ mod window {
         let root_env = <P::Environment>::root_environment();
 
         let handle = native::window::window_init(s);
+        with_frame_window(handle, || new_window_with_handle(provider, root_env, handle, s))
+    }
+
+    fn new_window_with_handle<P: WindowProvider>(provider: P, root_env: P::Environment, handle: WindowHandle, s: MSlock) -> Arc<MainSlockCell<dyn WindowNativeCallback>> {
         let content_view = provider.root(root_env.const_env(), s)
             .into_view(s).0;
 
@@ -992,6 +1599,10 @@ mod window {
 
             self.environment.set(Some(stolen_env));
         }
+
+        fn set_visible(&self, visible: bool, _s: MSlock) {
+            set_window_paused(self.handle, !visible);
+        }
     }
 
     impl<P, B> WindowViewCallback<P::Environment> for Window<P, B> where P: WindowProvider, B: Binding<Filterless<bool>> {
@@ -1190,6 +1801,7 @@ mod window {
 
     impl<P, B> Drop for Window<P, B> where P: WindowProvider, B: Binding<Filterless<bool>> {
         fn drop(&mut self) {
+            set_window_paused(self.handle, false);
             native::window::window_free(self.handle);
         }
     }
@@ -1197,11 +1809,14 @@ mod window {
 
 mod slock {
     use std::marker::PhantomData;
+    use std::panic::Location;
     use std::sync::{Mutex, MutexGuard};
     use std::thread;
 
     use crate::core::debug_stats::DebugInfo;
+    use crate::core::slock_profiler::{did_acquire, profiling, will_acquire, will_release, Acquisition};
     use crate::native;
+    use crate::state::{run_transaction, Transaction};
     use crate::util::marker::{AnyThreadMarker, MainThreadMarker, ThreadMarker};
     use crate::util::rust_util::PhantomUnsendUnsync;
 
@@ -1216,6 +1831,7 @@ mod slock {
         // if forced, then don't do regular dealloc
         is_nested: bool,
         pub(crate) debug_info: DebugInfo,
+        acquisition: Option<Acquisition>,
         unsend_unsync: PhantomUnsendUnsync,
         thread_marker: PhantomData<M>,
     }
@@ -1271,6 +1887,18 @@ mod slock {
         }
     }
 
+    #[inline]
+    fn profiled_guard(site: &'static Location<'static>, main: bool) -> (MutexGuard<'static, ()>, Option<Acquisition>) {
+        if profiling() {
+            let pending = will_acquire(site, main);
+            let guard = global_guard();
+            (guard, Some(did_acquire(pending)))
+        }
+        else {
+            (global_guard(), None)
+        }
+    }
+
     pub fn slock_init_listener(f: impl FnMut(Slock) -> bool + Send + 'static) {
         SLOCK_INIT_LISTENER.lock().unwrap()
             .push(Box::new(f))
@@ -1298,10 +1926,13 @@ mod slock {
     /// However, do not feel the need to drop it and reacquire after every micro-operation;
     /// this may cause the user to view the result of a partially applied transaction.
     #[inline]
+    #[track_caller]
     pub fn slock_owner() -> SlockOwner {
+        let (guard, acquisition) = profiled_guard(Location::caller(), false);
         let ret = SlockOwner {
-            _guard: global_guard(),
+            _guard: guard,
             debug_info: DebugInfo::new(),
+            acquisition,
             unsend_unsync: PhantomData,
             thread_marker: PhantomData,
             is_nested: false,
@@ -1313,15 +1944,18 @@ mod slock {
     }
 
     #[inline]
+    #[track_caller]
     pub fn slock_main_owner() -> SlockOwner<MainThreadMarker> {
         if !native::global::is_main() {
             panic!("Cannot call slock_main_owner outside of main thread")
         }
 
+        let (guard, acquisition) = profiled_guard(Location::caller(), true);
         let ret = SlockOwner {
-            _guard: global_guard(),
+            _guard: guard,
             is_nested: false,
             debug_info: DebugInfo::new(),
+            acquisition,
             unsend_unsync: PhantomData,
             thread_marker: PhantomData,
         };
@@ -1335,6 +1969,7 @@ mod slock {
     // some ffi makes it awkward to pass slock arround
     // If you are sure the thread currently owns the slock
     // you can call this method
+    #[track_caller]
     pub unsafe fn slock_force_main_owner() -> SlockOwner<MainThreadMarker> {
         static FAKE_GLOBAL_STATE_LOCK: Mutex<()> = Mutex::new(());
 
@@ -1353,11 +1988,18 @@ mod slock {
             _guard: FAKE_GLOBAL_STATE_LOCK.lock().unwrap(),
             is_nested: true,
             debug_info: DebugInfo::new(),
+            acquisition: None,
             unsend_unsync: PhantomData,
             thread_marker: PhantomData,
         }
     }
 
+    // for contexts (e.g. serde) where a slock marker cannot be passed through
+    #[cfg(feature = "serde")]
+    pub(crate) fn slock_is_held() -> bool {
+        *LOCKED_THREAD.lock().unwrap() == Some(thread::current().id())
+    }
+
     impl<M> SlockOwner<M> where M: ThreadMarker {
         // note that the global state lock is kept for entire
         // lifetime of slockowner; calling marker does not acquire the state lock
@@ -1388,6 +2030,10 @@ mod slock {
                 SLOCK_DROP_LISTENER.lock().unwrap()
                     .retain_mut(|f| f(self.marker().to_general_slock()));
 
+                if let Some(acquisition) = self.acquisition.take() {
+                    will_release(acquisition);
+                }
+
                 *LOCKED_THREAD.lock().unwrap() = None;
             }
         }
@@ -1408,6 +2054,17 @@ mod slock {
             }
         }
 
+        /// Runs `f` as an atomic transaction over all stores it applies actions to.
+        /// If `f` returns `Err`, every action applied within the transaction is reverted
+        /// (in reverse order) without being recorded by undo managers.
+        /// If `f` returns `Ok`, the actions are registered as a single undo group.
+        /// Transactions may be nested, in which case the inner transaction
+        /// is only committed once the outermost one is.
+        /// Note that derived stores do not record inverses and are therefore not reverted
+        pub fn transaction<T, E>(self, f: impl FnOnce(&mut Transaction, Slock<'a, M>) -> Result<T, E>) -> Result<T, E> {
+            run_transaction(f, self)
+        }
+
         /// Given a slock that may be say the main slock
         /// convert it into the general slock
         /// Some methods require the general slock
@@ -1426,9 +2083,10 @@ mod slock {
 }
 
 mod global {
-    use std::time::{Duration, Instant};
+    use std::time::Duration;
 
     use crate::core::application::{Application, ApplicationProvider};
+    use crate::core::frame_scheduler::FrameSubscriber;
     use crate::native;
     use crate::state::{CapacitatedSignal, FixedSignal};
     use crate::state::capacitor::IncreasingCapacitor;
@@ -1436,10 +2094,13 @@ mod global {
 
     use super::{APP, MSlock, Slock, TIMER_WORKER};
 
+    /// Ticks `func` once per frame with the time since it was
+    /// registered, until it returns false. The time does not advance
+    /// while the window it was registered from is paused
     pub fn timed_worker<F: for<'a> FnMut(Duration, Slock<'a>) -> bool + Send + 'static>(func: F) {
         TIMER_WORKER.get()
             .expect("Cannot call quarve functions before launch!")
-            .send((Box::new(func), Instant::now()))
+            .send(FrameSubscriber::new(Box::new(func)))
             .unwrap()
     }
 
@@ -1449,6 +2110,26 @@ mod global {
     }
 
 
+    /// Executor agnostic bridge for asynchronous work.
+    /// `spawner` is handed a task that first awaits `future` and then
+    /// acquires the slock to deliver the output to `on_complete`.
+    /// The slock is never held while the future is being polled, so `spawner`
+    /// may run the task on any executor (but not on a thread that holds the slock).
+    /// Example: `spawn_task(|task| { tokio::spawn(task); }, fut, |val, s| ...)`
+    #[cfg(feature = "futures")]
+    pub fn spawn_task<T, F, G, C>(spawner: G, future: F, on_complete: C)
+        where T: Send + 'static,
+              F: std::future::Future<Output=T> + Send + 'static,
+              G: FnOnce(futures::future::BoxFuture<'static, ()>),
+              C: for<'a> FnOnce(T, Slock<'a>) + Send + 'static
+    {
+        spawner(Box::pin(async move {
+            let output = future.await;
+            let s = super::slock_owner();
+            on_complete(output, s.marker());
+        }))
+    }
+
     /// Must be called from the main thread in the main function
     #[cold]
     pub fn launch(provider: impl ApplicationProvider) {
@@ -1490,10 +2171,16 @@ mod global {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
     use std::thread::sleep;
     use std::time::Duration;
 
-    use crate::core::slock_owner;
+    use crate::core::{frame_interval, frame_stats, set_reduced_motion, set_target_fps, set_window_paused, setup_timing_thread, slock_main_owner, slock_owner, timed_worker, SlockProfiler};
+    use crate::core::frame_scheduler::with_frame_window;
+    use crate::native::global::mark_thread_main;
+    use crate::state::capacitor::ConstantTimeCapacitor;
+    use crate::state::SetAction::Set;
+    use crate::state::{Binding, Signal, Store, WithCapacitor};
 
     /* of course, should only panic in debug scenarios */
     #[test]
@@ -1518,4 +2205,211 @@ mod tests {
 
         assert_eq!(res.join().unwrap(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_frame_scheduler() {
+        setup_timing_thread();
+        set_target_fps(120);
+        assert_eq!(frame_interval(), Duration::from_nanos(1_000_000_000 / 120));
+
+        // (ticks, last elapsed)
+        let window_ticks = Arc::new(Mutex::new((0, Duration::ZERO)));
+        let global_ticks = Arc::new(Mutex::new(0));
+
+        set_window_paused(1, true);
+        let wt = window_ticks.clone();
+        with_frame_window(1, || timed_worker(move |elapsed, _s| {
+            let mut ticks = wt.lock().unwrap();
+            ticks.0 += 1;
+            ticks.1 = elapsed;
+            true
+        }));
+        let gt = global_ticks.clone();
+        timed_worker(move |_elapsed, _s| {
+            *gt.lock().unwrap() += 1;
+            true
+        });
+
+        sleep(Duration::from_millis(200));
+        assert_eq!(window_ticks.lock().unwrap().0, 0);
+        assert!(*global_ticks.lock().unwrap() > 0);
+
+        set_window_paused(1, false);
+        sleep(Duration::from_millis(100));
+        let (ticks, elapsed) = *window_ticks.lock().unwrap();
+        assert!(ticks > 0);
+        // paused time is excluded
+        assert!(elapsed < Duration::from_millis(180));
+
+        let stats = frame_stats();
+        assert!(stats.frames > 0);
+        assert!(stats.average_frame_time() <= stats.max_frame_time);
+
+        // reduced motion
+        set_reduced_motion(true);
+        let store = Store::new(0.0);
+        let capacitated = {
+            let s = slock_owner();
+            let ret = store.with_capacitor(ConstantTimeCapacitor::new(10.0), s.marker());
+            store.apply(Set(5.0), s.marker());
+            // settled without waiting for a frame
+            assert_eq!(*ret.borrow(s.marker()), 5.0);
+            ret
+        };
+        sleep(Duration::from_millis(100));
+        assert_eq!(*capacitated.borrow(slock_owner().marker()), 5.0);
+        set_reduced_motion(false);
+    }
+
+    #[test]
+    fn test_slock_profiler() {
+        unsafe {
+            mark_thread_main();
+        }
+
+        SlockProfiler::enable(Duration::from_millis(20));
+
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let holder = std::thread::spawn(move || {
+            let _s = slock_owner();
+            started_tx.send(()).unwrap();
+            sleep(Duration::from_millis(100));
+        });
+
+        started_rx.recv().unwrap();
+        drop(slock_main_owner());
+        holder.join().unwrap();
+        SlockProfiler::disable();
+        // not recorded
+        drop(slock_owner());
+
+        let report = SlockProfiler::report();
+        assert_eq!(report.sites.len(), 2);
+        // the background site held the longest
+        assert!(report.sites[0].hold.max() >= Duration::from_millis(100));
+        assert_eq!(report.sites[0].main_thread_acquisitions, 0);
+        assert_eq!(report.sites[1].main_thread_acquisitions, 1);
+        assert!(report.sites[1].wait.max() >= Duration::from_millis(50));
+
+        assert_eq!(report.starvations.len(), 1);
+        assert_eq!(report.starvations[0].waiting_site, report.sites[1].site);
+        assert_eq!(report.starvations[0].holding_site, Some(report.sites[0].site));
+        assert!(report.to_string().contains("main thread starved"));
+
+        SlockProfiler::reset();
+        assert!(SlockProfiler::report().sites.is_empty());
+    }
+
+    #[cfg(feature = "headless_backend")]
+    mod headless {
+        use crate::core::{Application, ApplicationProvider, Environment, MSlock, StandardConstEnv, StandardVarEnv, WindowProvider};
+        use crate::headless::{text_size, Headless, HeadlessMenuItem, HeadlessViewKind};
+        use crate::prelude::*;
+        use crate::state::{Binding, Filterless, FixedSignal, SetAction, Signal, Store};
+        use crate::view::text::Text;
+        use crate::util::geo::{Size, Point};
+        use crate::view::ViewProvider;
+
+        struct Env(StandardConstEnv, StandardVarEnv);
+
+        impl Environment for Env {
+            type Const = StandardConstEnv;
+            type Variable = StandardVarEnv;
+
+            fn root_environment() -> Self {
+                Env(StandardConstEnv::new(), StandardVarEnv::new())
+            }
+
+            fn const_env(&self) -> &Self::Const {
+                &self.0
+            }
+
+            fn variable_env(&self) -> &Self::Variable {
+                &self.1
+            }
+
+            fn variable_env_mut(&mut self) -> &mut Self::Variable {
+                &mut self.1
+            }
+        }
+
+        struct App<B>(B);
+
+        impl<B> ApplicationProvider for App<B> where B: Binding<Filterless<u32>> + Clone + 'static {
+            fn name(&self) -> &str {
+                "Headless Test"
+            }
+
+            fn will_spawn(&self, app: &Application, s: MSlock) {
+                app.spawn_window(MainWindow(self.0.clone()), s);
+            }
+        }
+
+        struct MainWindow<B>(B);
+
+        impl<B> WindowProvider for MainWindow<B> where B: Binding<Filterless<u32>> + Clone + 'static {
+            type Environment = Env;
+
+            fn title(&self, _env: &StandardConstEnv, _s: MSlock) -> impl Signal<Target=String> {
+                FixedSignal::new("Counter".to_string())
+            }
+
+            fn size(&self, _env: &StandardConstEnv, _s: MSlock) -> (Size, Size, Size) {
+                (Size::new(100.0, 100.0), Size::new(200.0, 200.0), Size::new(400.0, 400.0))
+            }
+
+            fn root(&self, env: &StandardConstEnv, s: MSlock) -> impl ViewProvider<Env, DownContext=()> {
+                let count = self.0.clone();
+                let label = self.0.map(|c| format!("count {}", c), s);
+
+                vstack()
+                    .push(Text::from_signal(label))
+                    .push(button("increment", move |s| {
+                        let curr = *count.borrow(s);
+                        count.apply(SetAction::Set(curr + 1), s);
+                    }))
+                    .into_view_provider(env, s)
+            }
+
+            fn menu(&self, env: &StandardConstEnv, s: MSlock) -> WindowMenu {
+                WindowMenu::standard(env, Menu::new("File"), Menu::new("Edit"), Menu::new("View"), Menu::new("Help"), s)
+            }
+        }
+
+        #[test]
+        fn test_headless_layout_and_click() {
+            let store = Store::new(0);
+            crate::headless::run(App(store.binding()), |h: &Headless| {
+                let window = h.windows().remove(0);
+                assert_eq!(window.title, "Counter");
+                assert_eq!(window.size, Size::new(200.0, 200.0));
+
+                // text is measured with the deterministic model
+                let root = window.root.unwrap();
+                let label = root.find_text("count 0").unwrap();
+                assert_eq!(label.kind, HeadlessViewKind::Text { max_lines: 1, font_size: 14.0 });
+                let expected = text_size("count 0", 14.0, f64::INFINITY, 1);
+                assert_eq!((label.frame.w, label.frame.h), (expected.w, expected.h));
+
+                let button = root.find(&|v| matches!(v.kind, HeadlessViewKind::Button { .. }))
+                    .unwrap();
+                assert!(h.click_view(button.id));
+                assert!(h.click_view(button.id));
+                let root = h.windows().remove(0).root.unwrap();
+                assert!(root.find_text("count 2").is_some());
+
+                // clicking outside of any view
+                h.click(window.id, Point::new(199.0, 199.0));
+                assert!(h.windows()[0].root.as_ref().unwrap().find_text("count 2").is_some());
+
+                let edit = h.windows()[0].menu("Edit").unwrap().clone();
+                assert!(matches!(edit.item("Undo"), Some(HeadlessMenuItem::Button { .. })));
+
+                h.resize(window.id, Size::new(1000.0, 50.0));
+                assert_eq!(h.windows()[0].size, Size::new(400.0, 100.0));
+            });
+
+            assert_eq!(*store.borrow(crate::core::slock_owner().marker()), 2);
+        }
+    }
+}
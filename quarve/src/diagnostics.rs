@@ -0,0 +1,37 @@
+//! Debug-only diagnostics for finding leaked state.
+//!
+//! In debug builds, every store, signal, buffer, and listener is
+//! registered along with its value type and the site that created it.
+//! Listeners that are currently keeping the store or signal they are
+//! attached to alive are reported as retain cycles: a listener is known to
+//! hold its source once it uses a strong handle to it while being invoked,
+//! and it is reported when no strong references to the source remain
+//! besides those. Cycles whose handles are never used by the listener
+//! are not detected, but the source and the listener still remain in
+//! [`live_allocations`] with their creation sites.
+//! In release builds, nothing is tracked and every query is empty.
+
+pub use crate::util::test_util::{AllocKind, LiveAllocation, RetainCycle};
+#[cfg(debug_assertions)]
+pub use crate::util::test_util::HeapChecker;
+
+/// All currently live allocations, grouped by kind, value type,
+/// and creation site
+pub fn live_allocations() -> Vec<LiveAllocation> {
+    crate::util::test_util::live_allocations()
+}
+
+/// The total number of live allocations of the given kind
+pub fn live_count(kind: AllocKind) -> usize {
+    live_allocations()
+        .into_iter()
+        .filter(|alloc| alloc.kind == kind)
+        .map(|alloc| alloc.count)
+        .sum()
+}
+
+/// Every registered listener that is currently the only
+/// thing keeping its own source alive
+pub fn retain_cycles() -> Vec<RetainCycle> {
+    crate::util::test_util::retain_cycles()
+}
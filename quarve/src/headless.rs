@@ -0,0 +1,18 @@
+//! A native backend that runs without a display, enabled by
+//! the `headless_backend` feature.
+//!
+//! Windows, views, and menus are tracked in memory so that
+//! applications can be launched, laid out, and interacted with from
+//! `cargo test`. Text is measured with a deterministic model: every
+//! character is `CHAR_WIDTH * font_size` wide and every line is
+//! `LINE_HEIGHT * font_size` tall (see [`text_size`]).
+//! Nothing is painted, so colors, fonts and cursors are only recorded.
+//!
+//! Use [`run`] to launch an application and drive it through [`Headless`].
+//! Message boxes and file pickers do not block; they return whatever
+//! response was queued beforehand.
+
+pub use crate::native::headless::{
+    run, text_size, Headless, HeadlessMenu, HeadlessMenuItem, HeadlessMessageBox, HeadlessView,
+    HeadlessViewKind, HeadlessWindow, TextViewKey, ViewId, WindowId, CHAR_WIDTH, LINE_HEIGHT
+};
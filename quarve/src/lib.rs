@@ -11,6 +11,16 @@ pub mod util;
 pub mod core;
 pub mod resource;
 pub mod prelude;
+pub mod diagnostics;
+#[cfg(feature = "headless_backend")]
+pub mod headless;
 
 /* private */
-mod native;
\ No newline at end of file
+mod native;
+
+// used by quarve_derive
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+}
\ No newline at end of file
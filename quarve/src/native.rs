@@ -28,6 +28,7 @@ struct BufferEvent {
 
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 // apparently usize for the vtable is undefined behavior
 struct FatPointer(usize, *mut ());
 
@@ -109,6 +110,7 @@ mod callbacks {
     use std::ffi::{c_char, CStr, CString};
 
     use crate::core::{APP, MSlock, slock_force_main_owner, slock_main_owner, SlockOwner};
+    use crate::core::frame_scheduler::with_frame_window;
     use crate::native::{BufferEvent, FatPointer};
     use crate::util::geo::ScreenUnit;
     use crate::util::marker::MainThreadMarker;
@@ -139,15 +141,16 @@ mod callbacks {
         // slock during the layout so there is always a slock
         let s = slock_main_owner();
 
-        handle.into_window().layout_full(w, h, s.marker());
+        let window = handle.into_window();
+        with_frame_window(window.handle(), || window.layout_full(w, h, s.marker()));
     }
 
     #[no_mangle]
     extern "C" fn front_window_dispatch_event(handle: FatPointer, event: BufferEvent) -> u8 {
         let s = slock_main_owner();
 
-        handle.into_window()
-            .dispatch_native_event(event.into(), s.marker())
+        let window = handle.into_window();
+        with_frame_window(window.handle(), || window.dispatch_native_event(event.into(), s.marker()))
     }
 
     #[no_mangle]
@@ -160,6 +163,14 @@ mod callbacks {
             .set_fullscreen(fs, s.marker());
     }
 
+    #[no_mangle]
+    extern "C" fn front_window_set_visible(p: FatPointer, visible: bool) {
+        let s = slock_main_owner();
+
+        p.into_window()
+            .set_visible(visible, s.marker());
+    }
+
     #[no_mangle]
     extern "C" fn front_execute_fn_once(bx: FatPointer) {
         /* ownership taken */
@@ -1590,9 +1601,16 @@ pub mod path {
 }
 
 pub mod backend {
-    #[cfg(any(not(target_os = "macos"), feature = "qt_backend"))]
+    #[cfg(feature = "headless_backend")]
+    pub const AUTO_CLIPS_CHILDREN: bool = true;
+
+    #[cfg(all(any(not(target_os = "macos"), feature = "qt_backend"), not(feature = "headless_backend")))]
     pub const AUTO_CLIPS_CHILDREN: bool = true;
 
-    #[cfg(all(target_os = "macos", not(feature = "qt_backend")))]
+    #[cfg(all(target_os = "macos", not(feature = "qt_backend"), not(feature = "headless_backend")))]
     pub const AUTO_CLIPS_CHILDREN: bool = false;
 }
+
+// implements the back_* interface in rust
+#[cfg(feature = "headless_backend")]
+pub(crate) mod headless;
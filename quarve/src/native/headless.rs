@@ -0,0 +1,2243 @@
+// pure rust implementation of the back_* interface
+// all view, window, and menu handles are ids into a registry
+// (they are only ever dereferenced by the backend itself)
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{c_char, c_double, c_int, c_ulonglong, c_void, CStr, CString};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::core::{launch, ApplicationProvider};
+use crate::event::EventModifiers;
+use crate::native::{BufferEvent, FatPointer};
+use crate::native::callbacks::{TEXTVIEW_CALLBACK_KEYCODE_ALT_NEWLINE, TEXTVIEW_CALLBACK_KEYCODE_DOWN, TEXTVIEW_CALLBACK_KEYCODE_ESCAPE, TEXTVIEW_CALLBACK_KEYCODE_LEFT, TEXTVIEW_CALLBACK_KEYCODE_NEWLINE, TEXTVIEW_CALLBACK_KEYCODE_RIGHT, TEXTVIEW_CALLBACK_KEYCODE_TAB, TEXTVIEW_CALLBACK_KEYCODE_UNTAB, TEXTVIEW_CALLBACK_KEYCODE_UP};
+use crate::util::geo::{Point, Rect, ScreenUnit, Size};
+use crate::view::util::Color;
+
+/// Width of every character, as a multiple of the font size
+pub const CHAR_WIDTH: f64 = 0.5;
+/// Height of every line, as a multiple of the font size
+pub const LINE_HEIGHT: f64 = 1.25;
+
+// font size of controls that do not take one
+const CONTROL_FONT_SIZE: f64 = 12.0;
+const DROPDOWN_PADDING: f64 = 32.0;
+const DROPDOWN_HEIGHT: f64 = 24.0;
+const DEFAULT_FONT_SIZE: f64 = 14.0;
+
+extern "C" {
+    fn front_will_spawn();
+    fn front_window_should_close(handle: FatPointer) -> bool;
+    fn front_window_layout(handle: FatPointer, w: f64, h: f64);
+    fn front_window_dispatch_event(handle: FatPointer, event: BufferEvent) -> u8;
+    fn front_execute_fn_once(bx: FatPointer);
+    fn front_execute_fn_mut(bx: FatPointer);
+    fn front_free_fn_mut(bx: FatPointer);
+    fn front_set_screen_unit_binding(bx: FatPointer, value: f64);
+    fn front_free_screen_unit_binding(bx: FatPointer);
+    fn front_set_opt_string_binding(bx: FatPointer, value: *const u8);
+    fn front_free_opt_string_binding(bx: FatPointer);
+    fn front_set_token_binding(bx: FatPointer, has_value: u8, value: i32);
+    fn front_free_token_binding(bx: FatPointer);
+    fn front_replace_textview_range(bx: FatPointer, start: usize, len: usize, value: *const u8);
+    fn front_set_textview_selection(bx: FatPointer, start: usize, len: usize);
+    fn front_free_textview_state(bx: FatPointer);
+    fn front_execute_key_callback(bx: FatPointer, keycode: usize) -> u8;
+    fn front_free_key_callback(bx: FatPointer);
+}
+
+/* text measurement */
+
+// (characters consumed, characters visible) of each wrapped line
+// words are wrapped greedily, and trailing spaces may hang past the edge
+fn wrap_paragraph(paragraph: &str, capacity: usize) -> Vec<(usize, usize)> {
+    let mut lines = Vec::new();
+    let mut consumed = 0;
+    let mut visible = 0;
+
+    for word in paragraph.split_inclusive(' ') {
+        let mut word_len = word.chars().count();
+        let mut word_visible = word.trim_end_matches(' ').chars().count();
+
+        // earlier trailing spaces are part of the line, unlike the spaces after this word
+        if consumed > 0 && word_visible > 0 && consumed + word_visible > capacity {
+            lines.push((consumed, visible));
+            consumed = 0;
+            visible = 0;
+        }
+
+        // words that do not fit on a line at all are broken
+        while consumed == 0 && word_visible > capacity {
+            lines.push((capacity, capacity));
+            word_len -= capacity;
+            word_visible -= capacity;
+        }
+
+        if word_visible > 0 {
+            visible = consumed + word_visible;
+        }
+        consumed += word_len;
+    }
+
+    lines.push((consumed, visible));
+    lines
+}
+
+fn line_capacity(font_size: f64, max_width: f64) -> usize {
+    let char_width = font_size * CHAR_WIDTH;
+    if char_width <= 0.0 || max_width >= usize::MAX as f64 * char_width {
+        usize::MAX
+    }
+    else {
+        ((max_width / char_width + 1e-9).floor() as usize).max(1)
+    }
+}
+
+fn wrapped_lines(text: &str, font_size: f64, max_width: f64) -> Vec<(usize, usize)> {
+    let capacity = line_capacity(font_size, max_width);
+    text.split('\n')
+        .flat_map(|paragraph| wrap_paragraph(paragraph, capacity))
+        .collect()
+}
+
+/// The size of `text` under the headless text model:
+/// every character is `CHAR_WIDTH * font_size` wide and every line is
+/// `LINE_HEIGHT * font_size` tall. Words are wrapped to `max_width`,
+/// and at most `max_lines` lines are counted (unless it is 0)
+pub fn text_size(text: &str, font_size: f64, max_width: f64, max_lines: u32) -> Size {
+    let lines = wrapped_lines(text, font_size, max_width);
+    let count = if max_lines == 0 {
+        lines.len()
+    } else {
+        lines.len().min(max_lines as usize)
+    };
+    let widest = lines.iter()
+        .take(count)
+        .map(|(_, visible)| *visible)
+        .max()
+        .unwrap_or(0);
+
+    Size::new(
+        widest as f64 * font_size * CHAR_WIDTH,
+        count as f64 * font_size * LINE_HEIGHT
+    )
+}
+
+/* utf16 indexing (as used by text views) */
+
+fn utf16_len(str: &str) -> usize {
+    str.encode_utf16().count()
+}
+
+fn utf16_to_byte(str: &str, utf16: usize) -> usize {
+    let mut count = 0;
+    for (i, c) in str.char_indices() {
+        if count >= utf16 {
+            return i;
+        }
+        count += c.len_utf16();
+    }
+    str.len()
+}
+
+fn utf16_slice(str: &str, range: Range<usize>) -> &str {
+    &str[utf16_to_byte(str, range.start)..utf16_to_byte(str, range.end)]
+}
+
+fn utf16_replace(str: &mut String, range: Range<usize>, with: &str) {
+    let start = utf16_to_byte(str, range.start);
+    let end = utf16_to_byte(str, range.end);
+    str.replace_range(start..end, with);
+}
+
+unsafe fn read_string(ptr: *const u8) -> String {
+    CStr::from_ptr(ptr as *const c_char)
+        .to_string_lossy()
+        .into_owned()
+}
+
+unsafe fn read_opt_string(ptr: *const u8) -> Option<String> {
+    if ptr.is_null() {
+        None
+    }
+    else {
+        Some(read_string(ptr))
+    }
+}
+
+/* registry */
+
+struct WindowState {
+    handle: Option<FatPointer>,
+    title: String,
+    size: Size,
+    min_size: Size,
+    max_size: Size,
+    fullscreen: bool,
+    open: bool,
+    root: Option<usize>,
+    menu: Option<usize>,
+    needs_layout: bool,
+    cursor: Point,
+}
+
+struct TextFieldState {
+    text: String,
+    selection: Range<usize>,
+    text_binding: FatPointer,
+    focused_binding: FatPointer,
+    callback: FatPointer,
+    token: i32,
+    secret: bool,
+    max_lines: u32,
+    font_size: f64,
+}
+
+struct TextViewState {
+    text: String,
+    selection: Range<usize>,
+    // set on the first full replace
+    page: Option<FatPointer>,
+    selected: Option<FatPointer>,
+    key_callback: Option<FatPointer>,
+    page_id: i32,
+    font_size: f64,
+}
+
+enum NodeKind {
+    Layout,
+    Layer {
+        background: Color,
+        border: Color,
+        corner_radius: f64,
+        border_width: f64,
+        opacity: f32,
+    },
+    Image {
+        path: PathBuf,
+        size: Size,
+    },
+    Cursor,
+    Scroll {
+        vertical: bool,
+        horizontal: bool,
+        offset: Point,
+        binding_x: FatPointer,
+        binding_y: FatPointer,
+    },
+    ScrollContent,
+    Button {
+        clicked: bool
+    },
+    Dropdown {
+        binding: FatPointer,
+        options: Vec<String>,
+        selection: Option<String>,
+    },
+    Text {
+        text: String,
+        max_lines: u32,
+        font_size: f64,
+    },
+    TextField(TextFieldState),
+    TextView(TextViewState),
+}
+
+struct Node {
+    kind: NodeKind,
+    frame: Rect,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+enum MenuObject {
+    Bar {
+        menus: Vec<usize>
+    },
+    Menu {
+        title: String,
+        items: Vec<usize>,
+    },
+    Separator,
+    Button {
+        title: String,
+        key: String,
+        enabled: bool,
+        action: Option<FatPointer>,
+        submenu: Option<usize>,
+    },
+}
+
+enum Modal {
+    MessageBox(HeadlessMessageBox),
+    Picker {
+        mask: Option<String>,
+        result: Option<CString>,
+    },
+}
+
+struct Backend {
+    next_id: usize,
+    // in order of creation
+    windows: Vec<(usize, WindowState)>,
+    nodes: BTreeMap<usize, Node>,
+    menus: BTreeMap<usize, MenuObject>,
+    modals: BTreeMap<usize, Modal>,
+    focused: Option<usize>,
+    clipboard: String,
+    message_box_responses: VecDeque<usize>,
+    picker_responses: VecDeque<Option<PathBuf>>,
+    message_boxes: Vec<HeadlessMessageBox>,
+}
+
+// safety: the registry is only ever accessed from the main thread
+// (it is behind a mutex so that it may also be reached by thread local destructors)
+unsafe impl Send for Backend { }
+
+impl Backend {
+    const fn new() -> Self {
+        Backend {
+            next_id: 1,
+            windows: Vec::new(),
+            nodes: BTreeMap::new(),
+            menus: BTreeMap::new(),
+            modals: BTreeMap::new(),
+            focused: None,
+            clipboard: String::new(),
+            message_box_responses: VecDeque::new(),
+            picker_responses: VecDeque::new(),
+            message_boxes: Vec::new(),
+        }
+    }
+
+    fn fresh_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn window(&mut self, id: usize) -> Option<&mut WindowState> {
+        self.windows.iter_mut()
+            .find(|(w, _)| *w == id)
+            .map(|(_, w)| w)
+    }
+
+    fn insert_node(&mut self, kind: NodeKind) -> *mut c_void {
+        let id = self.fresh_id();
+        self.nodes.insert(id, Node {
+            kind,
+            frame: Rect::default(),
+            parent: None,
+            children: Vec::new(),
+        });
+        id as *mut c_void
+    }
+
+    fn node(&mut self, view: *mut c_void) -> &mut Node {
+        self.nodes.get_mut(&(view as usize))
+            .expect("Invalid headless view handle")
+    }
+
+    fn detach(&mut self, child: usize) {
+        let parent = self.nodes.get_mut(&child).and_then(|c| c.parent.take());
+        if let Some(parent) = parent.and_then(|p| self.nodes.get_mut(&p)) {
+            parent.children.retain(|c| *c != child);
+        }
+    }
+
+    fn text_field(&mut self, view: *mut c_void) -> &mut TextFieldState {
+        match &mut self.node(view).kind {
+            NodeKind::TextField(field) => field,
+            _ => panic!("Headless view is not a text field")
+        }
+    }
+
+    fn text_view(&mut self, view: *mut c_void) -> &mut TextViewState {
+        match &mut self.node(view).kind {
+            NodeKind::TextView(tv) => tv,
+            _ => panic!("Headless view is not a text view")
+        }
+    }
+
+    // the window the view is (transitively) mounted in, and its frame relative to it
+    fn absolute_frame(&self, view: usize) -> Option<(usize, Rect)> {
+        let node = self.nodes.get(&view)?;
+        let mut frame = node.frame;
+        let mut current = view;
+        while let Some(parent) = self.nodes.get(&current)?.parent {
+            let parent_node = self.nodes.get(&parent)?;
+            frame.x += parent_node.frame.x;
+            frame.y += parent_node.frame.y;
+            if let NodeKind::Scroll { offset, .. } = &parent_node.kind {
+                frame.x -= offset.x;
+                frame.y -= offset.y;
+            }
+            current = parent;
+        }
+
+        self.windows.iter()
+            .find(|(_, w)| w.open && w.root == Some(current))
+            .map(|(id, _)| (*id, frame))
+    }
+
+    fn snapshot_view(&self, id: usize) -> HeadlessView {
+        let node = &self.nodes[&id];
+        let focused = self.focused == Some(id);
+        let (kind, text) = match &node.kind {
+            NodeKind::Layout => (HeadlessViewKind::Layout, None),
+            NodeKind::Layer { background, border, corner_radius, border_width, opacity } => (
+                HeadlessViewKind::Layer {
+                    background: *background,
+                    border: *border,
+                    corner_radius: *corner_radius,
+                    border_width: *border_width,
+                    opacity: *opacity,
+                },
+                None
+            ),
+            NodeKind::Image { path, size } => (HeadlessViewKind::Image { path: path.clone(), size: *size }, None),
+            NodeKind::Cursor => (HeadlessViewKind::Cursor, None),
+            NodeKind::Scroll { vertical, horizontal, offset, .. } => (
+                HeadlessViewKind::Scroll { vertical: *vertical, horizontal: *horizontal, offset: *offset },
+                None
+            ),
+            NodeKind::ScrollContent => (HeadlessViewKind::ScrollContent, None),
+            NodeKind::Button { clicked } => (HeadlessViewKind::Button { clicked: *clicked }, None),
+            NodeKind::Dropdown { options, selection, .. } => (
+                HeadlessViewKind::Dropdown { options: options.clone(), selection: selection.clone() },
+                selection.clone()
+            ),
+            NodeKind::Text { text, max_lines, font_size } => (
+                HeadlessViewKind::Text { max_lines: *max_lines, font_size: *font_size },
+                Some(text.clone())
+            ),
+            NodeKind::TextField(field) => (
+                HeadlessViewKind::TextField {
+                    token: field.token,
+                    secret: field.secret,
+                    focused,
+                },
+                Some(field.text.clone())
+            ),
+            NodeKind::TextView(tv) => (
+                HeadlessViewKind::TextView {
+                    page_id: tv.page_id,
+                    selection: tv.selection.clone(),
+                    focused,
+                },
+                Some(tv.text.clone())
+            ),
+        };
+
+        HeadlessView {
+            id: ViewId(id),
+            kind,
+            frame: node.frame,
+            text,
+            children: node.children.iter()
+                .map(|c| self.snapshot_view(*c))
+                .collect(),
+        }
+    }
+
+    fn snapshot_menu(&self, id: usize) -> HeadlessMenu {
+        let MenuObject::Menu { title, items } = &self.menus[&id] else {
+            panic!("Headless menu handle is not a menu")
+        };
+
+        HeadlessMenu {
+            title: title.clone(),
+            items: items.iter()
+                .map(|item| match &self.menus[item] {
+                    MenuObject::Button { title, key, enabled, submenu, .. } => HeadlessMenuItem::Button {
+                        title: title.clone(),
+                        key: key.clone(),
+                        enabled: *enabled,
+                        submenu: submenu.map(|s| self.snapshot_menu(s)),
+                    },
+                    _ => HeadlessMenuItem::Separator,
+                })
+                .collect(),
+        }
+    }
+
+    fn snapshot_window(&self, id: usize, window: &WindowState) -> HeadlessWindow {
+        let menu = match window.menu.and_then(|m| self.menus.get(&m)) {
+            Some(MenuObject::Bar { menus }) => menus.iter()
+                .map(|m| self.snapshot_menu(*m))
+                .collect(),
+            _ => Vec::new()
+        };
+
+        HeadlessWindow {
+            id: WindowId(id),
+            title: window.title.clone(),
+            size: window.size,
+            min_size: window.min_size,
+            max_size: window.max_size,
+            fullscreen: window.fullscreen,
+            root: window.root.map(|r| self.snapshot_view(r)),
+            menu,
+        }
+    }
+
+    fn find_menu_action(&self, window: usize, path: &[&str]) -> Option<FatPointer> {
+        let (first, rest) = path.split_first()?;
+        let bar = self.windows.iter().find(|(w, _)| *w == window)?.1.menu?;
+        let MenuObject::Bar { menus } = self.menus.get(&bar)? else {
+            return None;
+        };
+
+        let mut menu = *menus.iter()
+            .find(|m| matches!(&self.menus[m], MenuObject::Menu { title, .. } if title == first))?;
+        for (i, target) in rest.iter().enumerate() {
+            let MenuObject::Menu { items, .. } = &self.menus[&menu] else {
+                return None;
+            };
+            let (enabled, action, submenu) = items.iter()
+                .find_map(|item| match &self.menus[item] {
+                    MenuObject::Button { title, enabled, action, submenu, .. } if title == target =>
+                        Some((*enabled, *action, *submenu)),
+                    _ => None
+                })?;
+
+            if i + 1 == rest.len() {
+                return action.filter(|_| enabled);
+            }
+            menu = submenu?;
+        }
+
+        None
+    }
+}
+
+static BACKEND: Mutex<Backend> = Mutex::new(Backend::new());
+
+fn backend() -> MutexGuard<'static, Backend> {
+    // a panicking test should not take down later ones
+    BACKEND.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/* run loop */
+
+enum Task {
+    // Box<dyn FnOnce(SlockOwner<MainThreadMarker>) + Send>
+    Run(FatPointer),
+    Layout(usize),
+    // text field changes that were made by the front
+    // are reported after the current task (since the slock is held)
+    TextFieldChanged(usize),
+}
+
+// safety: the only task that is not plain data is a Send box
+unsafe impl Send for Task { }
+
+static QUEUE: Mutex<VecDeque<Task>> = Mutex::new(VecDeque::new());
+static QUEUE_CHANGED: Condvar = Condvar::new();
+static TERMINATED: AtomicBool = AtomicBool::new(false);
+// only one headless application may run at a time
+static RUNNING: Mutex<()> = Mutex::new(());
+
+type Driver = Box<dyn FnOnce(&Headless)>;
+
+thread_local! {
+    static DRIVER: RefCell<Option<Driver>> = const { RefCell::new(None) };
+    // panics cannot unwind through back_main_loop,
+    // so they are resumed once the application has exited
+    static DRIVER_PANIC: RefCell<Option<Box<dyn Any + Send>>> = const { RefCell::new(None) };
+}
+
+fn push_task(task: Task) {
+    QUEUE.lock().unwrap().push_back(task);
+    QUEUE_CHANGED.notify_all();
+}
+
+fn execute(task: Task) {
+    match task {
+        Task::Run(bx) => unsafe {
+            front_execute_fn_once(bx)
+        },
+        Task::Layout(window) => {
+            let layout = {
+                let mut backend = backend();
+                backend.window(window)
+                    .filter(|w| w.needs_layout)
+                    .and_then(|w| {
+                        w.needs_layout = false;
+                        w.handle.map(|h| (h, w.size))
+                    })
+            };
+
+            if let Some((handle, size)) = layout {
+                unsafe {
+                    front_window_layout(handle, size.w, size.h);
+                }
+            }
+        }
+        Task::TextFieldChanged(view) => {
+            let field = match backend().nodes.get(&view).map(|n| &n.kind) {
+                Some(NodeKind::TextField(field)) => Some((field.text.clone(), field.text_binding, field.callback)),
+                _ => None
+            };
+
+            if let Some((text, binding, callback)) = field {
+                let text = CString::new(text).unwrap();
+                unsafe {
+                    front_set_opt_string_binding(binding, text.as_bytes().as_ptr());
+                    front_execute_fn_mut(callback);
+                }
+            }
+        }
+    }
+}
+
+fn settle() {
+    while !TERMINATED.load(Ordering::SeqCst) {
+        let Some(task) = QUEUE.lock().unwrap().pop_front() else {
+            break;
+        };
+        execute(task);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_main_loop() {
+    let _running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    TERMINATED.store(false, Ordering::SeqCst);
+
+    unsafe {
+        front_will_spawn();
+    }
+
+    if let Some(driver) = DRIVER.take() {
+        settle();
+        if !TERMINATED.load(Ordering::SeqCst) {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                driver(&Headless { unsend: PhantomData })
+            }));
+            if let Err(payload) = result {
+                DRIVER_PANIC.set(Some(payload));
+            }
+        }
+        return;
+    }
+
+    loop {
+        let task = {
+            let mut queue = QUEUE.lock().unwrap();
+            loop {
+                if TERMINATED.load(Ordering::SeqCst) {
+                    return;
+                }
+                if let Some(task) = queue.pop_front() {
+                    break task;
+                }
+                queue = QUEUE_CHANGED.wait(queue).unwrap();
+            }
+        };
+
+        execute(task);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_run_main(bx: FatPointer) {
+    push_task(Task::Run(bx));
+}
+
+#[no_mangle]
+extern "C" fn back_terminate() {
+    TERMINATED.store(true, Ordering::SeqCst);
+    QUEUE_CHANGED.notify_all();
+}
+
+/* window */
+
+#[no_mangle]
+extern "C" fn back_window_init() -> *mut c_void {
+    let mut backend = backend();
+    let id = backend.fresh_id();
+    backend.windows.push((id, WindowState {
+        handle: None,
+        title: String::new(),
+        size: Size::default(),
+        min_size: Size::default(),
+        max_size: Size::new(f64::MAX, f64::MAX),
+        fullscreen: false,
+        open: true,
+        root: None,
+        menu: None,
+        needs_layout: false,
+        cursor: Point::default(),
+    }));
+    id as *mut c_void
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_handle(window: *mut c_void, handle: FatPointer) {
+    if let Some(w) = backend().window(window as usize) {
+        w.handle = Some(handle);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_title(window: *mut c_void, title: *const u8) {
+    let title = unsafe { read_string(title) };
+    if let Some(w) = backend().window(window as usize) {
+        w.title = title;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_needs_layout(window: *mut c_void) {
+    let schedule = backend().window(window as usize)
+        .is_some_and(|w| !std::mem::replace(&mut w.needs_layout, true));
+    if schedule {
+        push_task(Task::Layout(window as usize));
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_root(window: *mut c_void, root: *mut c_void) {
+    if let Some(w) = backend().window(window as usize) {
+        w.root = Some(root as usize);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_size(window: *mut c_void, w: f64, h: f64) {
+    if let Some(window) = backend().window(window as usize) {
+        window.size = Size::new(w, h);
+    }
+    back_window_set_needs_layout(window);
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_min_size(window: *mut c_void, w: f64, h: f64) {
+    if let Some(window) = backend().window(window as usize) {
+        window.min_size = Size::new(w, h);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_max_size(window: *mut c_void, w: f64, h: f64) {
+    if let Some(window) = backend().window(window as usize) {
+        window.max_size = Size::new(w, h);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_fullscreen(window: *mut c_void, fs: bool) {
+    if let Some(window) = backend().window(window as usize) {
+        window.fullscreen = fs;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_set_menu(window: *mut c_void, menu: *mut c_void) {
+    if let Some(window) = backend().window(window as usize) {
+        window.menu = Some(menu as usize);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_exit(window: *mut c_void) {
+    if let Some(window) = backend().window(window as usize) {
+        window.open = false;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_window_free(window: *mut c_void) {
+    backend().windows.retain(|(w, _)| *w != window as usize);
+}
+
+/* views */
+
+#[no_mangle]
+extern "C" fn back_view_layout_init() -> *mut c_void {
+    backend().insert_node(NodeKind::Layout)
+}
+
+#[no_mangle]
+extern "C" fn back_view_clear_children(view: *mut c_void) {
+    let mut backend = backend();
+    let children = std::mem::take(&mut backend.node(view).children);
+    for child in children {
+        if let Some(child) = backend.nodes.get_mut(&child) {
+            child.parent = None;
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_view_remove_child(view: *mut c_void, index: c_ulonglong) {
+    let mut backend = backend();
+    let child = backend.node(view).children.remove(index as usize);
+    backend.node(child as *mut c_void).parent = None;
+}
+
+#[no_mangle]
+extern "C" fn back_view_insert_child(view: *mut c_void, subview: *mut c_void, index: c_ulonglong) {
+    let mut backend = backend();
+    backend.detach(subview as usize);
+    backend.node(subview).parent = Some(view as usize);
+
+    let children = &mut backend.node(view).children;
+    let index = (index as usize).min(children.len());
+    children.insert(index, subview as usize);
+}
+
+#[no_mangle]
+extern "C" fn back_view_set_frame(view: *mut c_void, left: f64, top: f64, width: f64, height: f64) {
+    backend().node(view).frame = Rect::new(left, top, width, height);
+}
+
+#[no_mangle]
+extern "C" fn back_free_view(view: *mut c_void) {
+    let node = {
+        let mut backend = backend();
+        back_view_clear_children_locked(&mut backend, view as usize);
+        backend.detach(view as usize);
+        if backend.focused == Some(view as usize) {
+            backend.focused = None;
+        }
+        backend.nodes.remove(&(view as usize))
+    };
+
+    // front callbacks are released without holding the registry
+    let Some(node) = node else {
+        return;
+    };
+    unsafe {
+        match node.kind {
+            NodeKind::Scroll { binding_x, binding_y, .. } => {
+                front_free_screen_unit_binding(binding_x);
+                front_free_screen_unit_binding(binding_y);
+            }
+            NodeKind::Dropdown { binding, .. } => {
+                front_free_opt_string_binding(binding);
+            }
+            NodeKind::TextField(field) => {
+                front_free_token_binding(field.focused_binding);
+                front_free_opt_string_binding(field.text_binding);
+                front_free_fn_mut(field.callback);
+            }
+            NodeKind::TextView(tv) => {
+                if let Some(selected) = tv.selected {
+                    front_free_token_binding(selected);
+                }
+                if let Some(page) = tv.page {
+                    front_free_textview_state(page);
+                }
+                if let Some(key_callback) = tv.key_callback {
+                    front_free_key_callback(key_callback);
+                }
+            }
+            _ => { }
+        }
+    }
+}
+
+fn back_view_clear_children_locked(backend: &mut Backend, view: usize) {
+    let children = backend.nodes.get_mut(&view)
+        .map(|n| std::mem::take(&mut n.children))
+        .unwrap_or_default();
+    for child in children {
+        if let Some(child) = backend.nodes.get_mut(&child) {
+            child.parent = None;
+        }
+    }
+}
+
+/* layer */
+
+#[no_mangle]
+extern "C" fn back_view_layer_init() -> *mut c_void {
+    backend().insert_node(NodeKind::Layer {
+        background: Color::clear(),
+        border: Color::clear(),
+        corner_radius: 0.0,
+        border_width: 0.0,
+        opacity: 1.0,
+    })
+}
+
+#[no_mangle]
+extern "C" fn back_view_layer_update(view: *mut c_void, bg_color: Color, border_color: Color, corner_radius: f64, border_width: f64, opacity: f32) -> *mut c_void {
+    if let NodeKind::Layer { background, border, corner_radius: cr, border_width: bw, opacity: op } = &mut backend().node(view).kind {
+        *background = bg_color;
+        *border = border_color;
+        *cr = corner_radius;
+        *bw = border_width;
+        *op = opacity;
+    }
+    view
+}
+
+#[no_mangle]
+extern "C" fn back_view_layer_set_frame(_view: *mut c_void, _left: f64, _top: f64, _width: f64, _height: f64) {
+    // only affects painting
+}
+
+/* image */
+
+// reads the dimensions of png and gif files
+fn image_dimensions(bytes: &[u8]) -> Option<Size> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.len() >= 24 {
+        let w = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+        let h = u32::from_be_bytes(bytes[20..24].try_into().unwrap());
+        Some(Size::new(w as f64, h as f64))
+    }
+    else if bytes.starts_with(b"GIF8") && bytes.len() >= 10 {
+        let w = u16::from_le_bytes(bytes[6..8].try_into().unwrap());
+        let h = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+        Some(Size::new(w as f64, h as f64))
+    }
+    else {
+        None
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_view_image_init(path: *const u8) -> *mut c_void {
+    let path = PathBuf::from(unsafe { read_string(path) });
+    let Ok(bytes) = std::fs::read(&path) else {
+        return std::ptr::null_mut();
+    };
+
+    // other formats are still accepted, they are just given no size
+    let size = image_dimensions(&bytes).unwrap_or_default();
+    backend().insert_node(NodeKind::Image { path, size })
+}
+
+#[no_mangle]
+extern "C" fn back_view_image_size(image: *mut c_void) -> Size {
+    match backend().node(image).kind {
+        NodeKind::Image { size, .. } => size,
+        _ => Size::default()
+    }
+}
+
+/* cursor */
+
+#[no_mangle]
+extern "C" fn back_view_cursor_init(_cursor_type: c_int) -> *mut c_void {
+    backend().insert_node(NodeKind::Cursor)
+}
+
+#[no_mangle]
+extern "C" fn back_view_cursor_update(_view: *mut c_void, _cursor_type: c_int) {
+
+}
+
+#[no_mangle]
+extern "C" fn back_push_cursor(_cursor_type: c_int) {
+
+}
+
+#[no_mangle]
+extern "C" fn back_pop_cursor() {
+
+}
+
+/* scroll */
+
+#[no_mangle]
+extern "C" fn back_view_scroll_init(allow_vertical: bool, allow_horizontal: bool, binding_y: FatPointer, binding_x: FatPointer) -> *mut c_void {
+    backend().insert_node(NodeKind::Scroll {
+        vertical: allow_vertical,
+        horizontal: allow_horizontal,
+        offset: Point::default(),
+        binding_x,
+        binding_y,
+    })
+}
+
+#[no_mangle]
+extern "C" fn back_view_scroll_content_init() -> *mut c_void {
+    backend().insert_node(NodeKind::ScrollContent)
+}
+
+#[no_mangle]
+extern "C" fn back_view_scroll_set_x(backing: *mut c_void, value: f64) {
+    if let NodeKind::Scroll { offset, .. } = &mut backend().node(backing).kind {
+        offset.x = value;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_view_scroll_set_y(backing: *mut c_void, value: f64) {
+    if let NodeKind::Scroll { offset, .. } = &mut backend().node(backing).kind {
+        offset.y = value;
+    }
+}
+
+/* button */
+
+#[no_mangle]
+extern "C" fn back_view_button_init() -> *mut c_void {
+    backend().insert_node(NodeKind::Button { clicked: false })
+}
+
+#[no_mangle]
+extern "C" fn back_view_button_update(view: *mut c_void, clicked: bool) {
+    if let NodeKind::Button { clicked: c } = &mut backend().node(view).kind {
+        *c = clicked;
+    }
+}
+
+/* dropdown */
+
+#[no_mangle]
+extern "C" fn back_view_dropdown_init(binding: FatPointer) -> *mut c_void {
+    backend().insert_node(NodeKind::Dropdown {
+        binding,
+        options: Vec::new(),
+        selection: None,
+    })
+}
+
+#[no_mangle]
+extern "C" fn back_view_dropdown_add(view: *mut c_void, option: *const u8) {
+    let option = unsafe { read_string(option) };
+    if let NodeKind::Dropdown { options, .. } = &mut backend().node(view).kind {
+        options.push(option);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_view_dropdown_clear(view: *mut c_void) {
+    if let NodeKind::Dropdown { options, selection, .. } = &mut backend().node(view).kind {
+        options.clear();
+        *selection = None;
+    }
+}
+
+// returns 0 on success
+#[no_mangle]
+extern "C" fn back_view_dropdown_select(view: *mut c_void, selection: *const u8) -> u8 {
+    let target = unsafe { read_opt_string(selection) };
+    let mut backend = backend();
+    let NodeKind::Dropdown { options, selection, .. } = &mut backend.node(view).kind else {
+        return 1;
+    };
+
+    match target {
+        Some(target) if !options.contains(&target) => 1,
+        target => {
+            *selection = target;
+            0
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_view_dropdown_size(view: *mut c_void) -> Size {
+    let mut backend = backend();
+    let NodeKind::Dropdown { options, .. } = &backend.node(view).kind else {
+        return Size::default();
+    };
+
+    let widest = options.iter()
+        .map(|o| text_size(o, CONTROL_FONT_SIZE, f64::INFINITY, 1).w)
+        .fold(0.0, f64::max);
+    Size::new(widest + DROPDOWN_PADDING, DROPDOWN_HEIGHT)
+}
+
+/* text */
+
+#[no_mangle]
+extern "C" fn back_text_init() -> *mut c_void {
+    backend().insert_node(NodeKind::Text {
+        text: String::new(),
+        max_lines: 0,
+        font_size: DEFAULT_FONT_SIZE,
+    })
+}
+
+#[no_mangle]
+extern "C" fn back_text_update(
+    view: *mut c_void,
+    str: *const u8,
+    max_lines: c_int,
+    _bold: u8,
+    _italic: u8,
+    _underline: u8,
+    _strikethrough: u8,
+    _back: Color,
+    _front: Color,
+    _font: *const u8,
+    font_size: f64
+) {
+    let str = unsafe { read_string(str) };
+    if let NodeKind::Text { text, max_lines: ml, font_size: fs } = &mut backend().node(view).kind {
+        *text = str;
+        *ml = max_lines as u32;
+        *fs = font_size;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_text_size(view: *mut c_void, suggested: Size) -> Size {
+    match &backend().node(view).kind {
+        NodeKind::Text { text, max_lines, font_size } => text_size(text, *font_size, suggested.w, *max_lines),
+        _ => Size::default()
+    }
+}
+
+/* text field */
+
+#[no_mangle]
+extern "C" fn back_text_field_init(text_binding: FatPointer, focused_binding: FatPointer, callback: FatPointer, token: i32, _unstyled: u8, secret: u8) -> *mut c_void {
+    backend().insert_node(NodeKind::TextField(TextFieldState {
+        text: String::new(),
+        selection: 0..0,
+        text_binding,
+        focused_binding,
+        callback,
+        token,
+        secret: secret != 0,
+        max_lines: 0,
+        font_size: DEFAULT_FONT_SIZE,
+    }))
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_focus(view: *mut c_void) {
+    backend().focused = Some(view as usize);
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_unfocus(view: *mut c_void) {
+    let mut backend = backend();
+    if backend.focused == Some(view as usize) {
+        backend.focused = None;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_update(
+    view: *mut c_void,
+    str: *const u8,
+    max_lines: c_int,
+    _bold: u8,
+    _italic: u8,
+    _underline: u8,
+    _strikethrough: u8,
+    _back: Color,
+    _front: Color,
+    _font: *const u8,
+    font_size: f64
+) {
+    let str = unsafe { read_string(str) };
+    let mut backend = backend();
+    let field = backend.text_field(view);
+    if field.text != str {
+        let len = str.chars().count();
+        field.text = str;
+        field.selection = len..len;
+    }
+    field.max_lines = max_lines as u32;
+    field.font_size = font_size;
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_size(view: *mut c_void, suggested: Size) -> Size {
+    let mut backend = backend();
+    let field = backend.text_field(view);
+    text_size(&field.text, field.font_size, suggested.w, field.max_lines)
+}
+
+fn field_selection(field: &TextFieldState) -> Range<usize> {
+    let start = field.text.char_indices().nth(field.selection.start).map_or(field.text.len(), |(i, _)| i);
+    let end = field.text.char_indices().nth(field.selection.end).map_or(field.text.len(), |(i, _)| i);
+    start..end
+}
+
+fn field_replace_selection(field: &mut TextFieldState, with: &str) {
+    let range = field_selection(field);
+    field.text.replace_range(range, with);
+    let cursor = field.selection.start + with.chars().count();
+    field.selection = cursor..cursor;
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_select_all(view: *mut c_void) {
+    let mut backend = backend();
+    let field = backend.text_field(view);
+    field.selection = 0..field.text.chars().count();
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_cut(view: *mut c_void) {
+    let mut backend = backend();
+    let field = backend.text_field(view);
+    let cut = field.text[field_selection(field)].to_string();
+    field_replace_selection(field, "");
+    backend.clipboard = cut;
+    drop(backend);
+
+    push_task(Task::TextFieldChanged(view as usize));
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_copy(view: *mut c_void) {
+    let mut backend = backend();
+    let field = backend.text_field(view);
+    let copied = field.text[field_selection(field)].to_string();
+    backend.clipboard = copied;
+}
+
+#[no_mangle]
+extern "C" fn back_text_field_paste(view: *mut c_void) {
+    let mut backend = backend();
+    let clipboard = backend.clipboard.clone();
+    field_replace_selection(backend.text_field(view), &clipboard);
+    drop(backend);
+
+    push_task(Task::TextFieldChanged(view as usize));
+}
+
+/* text view */
+
+#[no_mangle]
+extern "C" fn back_text_view_init() -> *mut c_void {
+    backend().insert_node(NodeKind::TextView(TextViewState {
+        text: String::new(),
+        selection: 0..0,
+        page: None,
+        selected: None,
+        key_callback: None,
+        page_id: 0,
+        font_size: DEFAULT_FONT_SIZE,
+    }))
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_full_replace(tv: *mut c_void, with: *const u8, state: FatPointer, selected: FatPointer, key_callback: FatPointer) {
+    let with = unsafe { read_string(with) };
+    let (old_page, old_selected, old_key_callback) = {
+        let mut backend = backend();
+        let text_view = backend.text_view(tv);
+        text_view.text = with;
+        text_view.selection = 0..0;
+        (
+            text_view.page.replace(state),
+            text_view.selected.replace(selected),
+            text_view.key_callback.replace(key_callback)
+        )
+    };
+
+    unsafe {
+        if let Some(page) = old_page {
+            front_free_textview_state(page);
+        }
+        if let Some(selected) = old_selected {
+            front_free_token_binding(selected);
+        }
+        if let Some(key_callback) = old_key_callback {
+            front_free_key_callback(key_callback);
+        }
+    }
+}
+
+fn shift_selection(selection: &Range<usize>, edit: Range<usize>, inserted: usize) -> Range<usize> {
+    let shift = |i: usize| {
+        if i >= edit.end {
+            i - edit.len() + inserted
+        }
+        else if i > edit.start {
+            edit.start + inserted
+        }
+        else {
+            i
+        }
+    };
+
+    shift(selection.start)..shift(selection.end)
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_replace(tv: *mut c_void, start: usize, len: usize, with: *const u8) {
+    let with = unsafe { read_string(with) };
+    let mut backend = backend();
+    let text_view = backend.text_view(tv);
+    utf16_replace(&mut text_view.text, start..start + len, &with);
+    text_view.selection = shift_selection(&text_view.selection, start..start + len, utf16_len(&with));
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_selection(tv: *mut c_void, start: usize, len: usize) {
+    backend().text_view(tv).selection = start..start + len;
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_get_selection(tv: *mut c_void, start: *mut usize, end: *mut usize) {
+    let selection = backend().text_view(tv).selection.clone();
+    unsafe {
+        *start = selection.start;
+        *end = selection.end;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_font(tv: *mut c_void, _font_path: *const u8, font_size: f64) {
+    backend().text_view(tv).font_size = font_size;
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_editing_state(_tv: *mut c_void, _editing: u8, _is_first_editing_block: u8) {
+
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_line_attributes(
+    _tv: *mut c_void, _line_no: usize, _start: usize, _end: usize,
+    _justification_sign: c_int, _leading_indentation: c_double, _trailing_indentation: c_double
+) {
+    // attributes do not affect the text model
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_char_attributes(
+    _tv: *mut c_void, _start: usize, _end: usize,
+    _bold: u8, _italic: u8, _underline: u8, _strikethrough: u8,
+    _back_color: Color, _fore_color: Color
+) {
+    // attributes do not affect the text model
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_set_page_id(tv: *mut c_void, page_id: i32) {
+    backend().text_view(tv).page_id = page_id;
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_focus(tv: *mut c_void) {
+    backend().focused = Some(tv as usize);
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_unfocus(tv: *mut c_void) {
+    let mut backend = backend();
+    if backend.focused == Some(tv as usize) {
+        backend.focused = None;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_get_line_height(tv: *mut c_void, _line: usize, start: usize, end: usize, width: f64) -> f64 {
+    let mut backend = backend();
+    let text_view = backend.text_view(tv);
+    let end = end.min(utf16_len(&text_view.text));
+    let line = utf16_slice(&text_view.text, start.min(end)..end);
+
+    text_size(line, text_view.font_size, width, 0).h
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_get_cursor_pos(tv: *mut c_void, x: *mut f64, y: *mut f64) {
+    let mut backend = backend();
+    let width = backend.node(tv).frame.w;
+    let text_view = backend.text_view(tv);
+    let char_width = text_view.font_size * CHAR_WIDTH;
+    let line_height = text_view.font_size * LINE_HEIGHT;
+    let capacity = if width > 0.0 {
+        line_capacity(text_view.font_size, width)
+    } else {
+        usize::MAX
+    };
+
+    let prefix = utf16_slice(&text_view.text, 0..text_view.selection.end);
+    let paragraphs: Vec<_> = prefix.split('\n').collect();
+    let (last, previous) = paragraphs.split_last().unwrap();
+    let mut line = previous.iter()
+        .map(|p| wrap_paragraph(p, capacity).len())
+        .sum::<usize>();
+
+    // locate the column within the (full) current paragraph
+    let full_paragraph = text_view.text[prefix.len() - last.len()..]
+        .split('\n')
+        .next()
+        .unwrap();
+    let mut column = last.chars().count();
+    for (consumed, _) in wrap_paragraph(full_paragraph, capacity) {
+        if column < consumed || consumed == 0 {
+            break;
+        }
+        column -= consumed;
+        line += 1;
+    }
+
+    unsafe {
+        *x = column as f64 * char_width;
+        *y = line as f64 * line_height;
+    }
+}
+
+// the selection is reported back to the front since the edit was not initiated by it
+fn text_view_replace_selection(tv: usize, with: &str) {
+    let (page, selection) = {
+        let mut backend = backend();
+        let text_view = backend.text_view(tv as *mut c_void);
+        let selection = text_view.selection.clone();
+        utf16_replace(&mut text_view.text, selection.clone(), with);
+        let cursor = selection.start + utf16_len(with);
+        text_view.selection = cursor..cursor;
+        (text_view.page, selection)
+    };
+
+    if let Some(page) = page {
+        let cstring = CString::new(with).unwrap();
+        unsafe {
+            front_replace_textview_range(page, selection.start, selection.len(), cstring.as_bytes().as_ptr());
+            let cursor = selection.start + utf16_len(with);
+            front_set_textview_selection(page, cursor, 0);
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_copy(tv: *mut c_void) {
+    let mut backend = backend();
+    let text_view = backend.text_view(tv);
+    let copied = utf16_slice(&text_view.text, text_view.selection.clone()).to_string();
+    backend.clipboard = copied;
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_cut(tv: *mut c_void) {
+    back_text_view_copy(tv);
+    text_view_replace_selection(tv as usize, "");
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_paste(tv: *mut c_void) {
+    let clipboard = backend().clipboard.clone();
+    text_view_replace_selection(tv as usize, &clipboard);
+}
+
+#[no_mangle]
+extern "C" fn back_text_view_select_all(tv: *mut c_void) {
+    let (page, len) = {
+        let mut backend = backend();
+        let text_view = backend.text_view(tv);
+        let len = utf16_len(&text_view.text);
+        text_view.selection = 0..len;
+        (text_view.page, len)
+    };
+
+    if let Some(page) = page {
+        unsafe {
+            front_set_textview_selection(page, 0, len);
+        }
+    }
+}
+
+/* message box */
+
+#[no_mangle]
+extern "C" fn back_message_box_init(title: *const u8, message: *const u8) -> *mut c_void {
+    let message_box = HeadlessMessageBox {
+        title: unsafe { read_opt_string(title) },
+        message: unsafe { read_opt_string(message) },
+        buttons: Vec::new(),
+    };
+
+    let mut backend = backend();
+    let id = backend.fresh_id();
+    backend.modals.insert(id, Modal::MessageBox(message_box));
+    id as *mut c_void
+}
+
+#[no_mangle]
+extern "C" fn back_message_box_add_button(mb: *mut c_void, button_type: u8) {
+    if let Some(Modal::MessageBox(message_box)) = backend().modals.get_mut(&(mb as usize)) {
+        message_box.buttons.push(button_type);
+    }
+}
+
+// returns the index of the queued response (or 0)
+#[no_mangle]
+extern "C" fn back_message_box_run(mb: *mut c_void) -> c_int {
+    let mut backend = backend();
+    let Some(Modal::MessageBox(message_box)) = backend.modals.remove(&(mb as usize)) else {
+        return 0;
+    };
+
+    let response = backend.message_box_responses.pop_front()
+        .filter(|r| *r < message_box.buttons.len())
+        .unwrap_or(0);
+    backend.message_boxes.push(message_box);
+    response as c_int
+}
+
+/* menus */
+
+fn insert_menu_object(object: MenuObject) -> *mut c_void {
+    let mut backend = backend();
+    let id = backend.fresh_id();
+    backend.menus.insert(id, object);
+    id as *mut c_void
+}
+
+#[no_mangle]
+extern "C" fn back_menu_bar_init() -> *mut c_void {
+    insert_menu_object(MenuObject::Bar { menus: Vec::new() })
+}
+
+#[no_mangle]
+extern "C" fn back_menu_bar_add(mb: *mut c_void, item: *mut c_void, _title: *const u8) {
+    if let Some(MenuObject::Bar { menus }) = backend().menus.get_mut(&(mb as usize)) {
+        menus.push(item as usize);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_init(title: *const u8) -> *mut c_void {
+    let title = unsafe { read_string(title) };
+    insert_menu_object(MenuObject::Menu { title, items: Vec::new() })
+}
+
+#[no_mangle]
+extern "C" fn back_menu_add(menu: *mut c_void, item: *mut c_void) {
+    if let Some(MenuObject::Menu { items, .. }) = backend().menus.get_mut(&(menu as usize)) {
+        items.push(item as usize);
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_separator_init() -> *mut c_void {
+    insert_menu_object(MenuObject::Separator)
+}
+
+#[no_mangle]
+extern "C" fn back_menu_separator_free(view: *mut c_void) {
+    let mut backend = backend();
+    backend.menus.remove(&(view as usize));
+    for menu in backend.menus.values_mut() {
+        if let MenuObject::Menu { items, .. } = menu {
+            items.retain(|i| *i != view as usize);
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_button_init(title: *const u8, key: *const u8, _modifier: u8) -> *mut c_void {
+    let title = unsafe { read_string(title) };
+    let key = unsafe { read_string(key) };
+    insert_menu_object(MenuObject::Button {
+        title,
+        key,
+        enabled: true,
+        action: None,
+        submenu: None,
+    })
+}
+
+#[no_mangle]
+extern "C" fn back_menu_button_set_title(button: *mut c_void, title: *const u8) {
+    let new_title = unsafe { read_string(title) };
+    if let Some(MenuObject::Button { title, .. }) = backend().menus.get_mut(&(button as usize)) {
+        *title = new_title;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_button_set_action(button: *mut c_void, action: FatPointer) {
+    let old = match backend().menus.get_mut(&(button as usize)) {
+        Some(MenuObject::Button { action: a, .. }) => a.replace(action),
+        _ => None
+    };
+
+    if let Some(old) = old {
+        unsafe {
+            front_free_fn_mut(old);
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_button_set_enabled(button: *mut c_void, enabled: u8) {
+    if let Some(MenuObject::Button { enabled: e, .. }) = backend().menus.get_mut(&(button as usize)) {
+        *e = enabled != 0;
+    }
+}
+
+#[no_mangle]
+extern "C" fn back_menu_button_set_submenu(button: *mut c_void, menu: *mut c_void) {
+    if let Some(MenuObject::Button { submenu, .. }) = backend().menus.get_mut(&(button as usize)) {
+        *submenu = Some(menu as usize);
+    }
+}
+
+/* file pickers */
+
+fn picker_init(allowed_mask: *const u8) -> *mut c_void {
+    let mask = unsafe { read_opt_string(allowed_mask) };
+    let mut backend = backend();
+    let id = backend.fresh_id();
+    backend.modals.insert(id, Modal::Picker { mask, result: None });
+    id as *mut c_void
+}
+
+// returns the queued response, which is None by default
+fn picker_run(op: *mut c_void) -> *const u8 {
+    let mut backend = backend();
+    let response = backend.picker_responses.pop_front().flatten();
+    let Some(Modal::Picker { mask, result }) = backend.modals.get_mut(&(op as usize)) else {
+        return std::ptr::null();
+    };
+
+    let allowed = |path: &PathBuf| mask.as_ref().is_none_or(|mask| {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        mask.split('|').any(|m| m == extension)
+    });
+
+    *result = response
+        .filter(allowed)
+        .map(|path| CString::new(path.to_string_lossy().into_owned()).unwrap());
+    result.as_ref().map_or(std::ptr::null(), |r| r.as_bytes().as_ptr())
+}
+
+#[no_mangle]
+extern "C" fn back_file_open_picker_init(allowed_mask: *const u8) -> *mut c_void {
+    picker_init(allowed_mask)
+}
+
+#[no_mangle]
+extern "C" fn back_file_open_picker_run(op: *mut c_void) -> *const u8 {
+    picker_run(op)
+}
+
+#[no_mangle]
+extern "C" fn back_file_open_picker_free(op: *mut c_void) {
+    backend().modals.remove(&(op as usize));
+}
+
+#[no_mangle]
+extern "C" fn back_file_save_picker_init(allowed_mask: *const u8) -> *mut c_void {
+    picker_init(allowed_mask)
+}
+
+#[no_mangle]
+extern "C" fn back_file_save_picker_run(sp: *mut c_void) -> *const u8 {
+    picker_run(sp)
+}
+
+#[no_mangle]
+extern "C" fn back_file_save_picker_free(sp: *mut c_void) {
+    backend().modals.remove(&(sp as usize));
+}
+
+/* path */
+
+thread_local! {
+    static STORAGE_DIRECTORY: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+#[no_mangle]
+extern "C" fn back_app_storage_directory(app_name: *const u8) -> *const u8 {
+    let app_name = unsafe { read_string(app_name) };
+    STORAGE_DIRECTORY.with_borrow_mut(|dir| {
+        let path = dir.get_or_insert_with(|| {
+            let path = std::env::temp_dir().join("quarve_headless").join(app_name);
+            std::fs::create_dir_all(&path).expect("Unable to create storage directory");
+            CString::new(path.to_string_lossy().into_owned()).unwrap()
+        });
+
+        path.as_bytes().as_ptr()
+    })
+}
+
+/* public interface */
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WindowId(usize);
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ViewId(usize);
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeadlessViewKind {
+    Layout,
+    Layer {
+        background: Color,
+        border: Color,
+        corner_radius: f64,
+        border_width: f64,
+        opacity: f32,
+    },
+    Image {
+        path: PathBuf,
+        size: Size,
+    },
+    Cursor,
+    Scroll {
+        vertical: bool,
+        horizontal: bool,
+        offset: Point,
+    },
+    ScrollContent,
+    Button {
+        clicked: bool
+    },
+    Dropdown {
+        options: Vec<String>,
+        selection: Option<String>,
+    },
+    Text {
+        max_lines: u32,
+        font_size: f64,
+    },
+    TextField {
+        token: i32,
+        secret: bool,
+        focused: bool,
+    },
+    TextView {
+        page_id: i32,
+        // in utf16 code units
+        selection: Range<usize>,
+        focused: bool,
+    },
+}
+
+/// Snapshot of a native view and its subviews
+#[derive(Clone, PartialEq, Debug)]
+pub struct HeadlessView {
+    pub id: ViewId,
+    pub kind: HeadlessViewKind,
+    /// Relative to the parent view
+    pub frame: Rect,
+    /// Content of text, text field and text views
+    /// (and the selection of dropdowns)
+    pub text: Option<String>,
+    pub children: Vec<HeadlessView>,
+}
+
+impl HeadlessView {
+    /// First view in pre-order satisfying `pred`
+    pub fn find(&self, pred: &impl Fn(&HeadlessView) -> bool) -> Option<&HeadlessView> {
+        if pred(self) {
+            return Some(self);
+        }
+
+        self.children.iter()
+            .find_map(|c| c.find(pred))
+    }
+
+    /// First view whose text is exactly `text`
+    pub fn find_text(&self, text: &str) -> Option<&HeadlessView> {
+        self.find(&|v| v.text.as_deref() == Some(text))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeadlessMenuItem {
+    Separator,
+    Button {
+        title: String,
+        key: String,
+        enabled: bool,
+        submenu: Option<HeadlessMenu>,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HeadlessMenu {
+    pub title: String,
+    pub items: Vec<HeadlessMenuItem>,
+}
+
+impl HeadlessMenu {
+    /// The button titled `title` (not searching submenus)
+    pub fn item(&self, title: &str) -> Option<&HeadlessMenuItem> {
+        self.items.iter()
+            .find(|i| matches!(i, HeadlessMenuItem::Button { title: t, .. } if t == title))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HeadlessWindow {
+    pub id: WindowId,
+    pub title: String,
+    pub size: Size,
+    pub min_size: Size,
+    pub max_size: Size,
+    pub fullscreen: bool,
+    pub root: Option<HeadlessView>,
+    pub menu: Vec<HeadlessMenu>,
+}
+
+impl HeadlessWindow {
+    pub fn menu(&self, title: &str) -> Option<&HeadlessMenu> {
+        self.menu.iter().find(|m| m.title == title)
+    }
+}
+
+/// A message box that was run
+#[derive(Clone, PartialEq, Debug)]
+pub struct HeadlessMessageBox {
+    pub title: Option<String>,
+    pub message: Option<String>,
+    /// As given by `MessageBoxButton as u8`
+    pub buttons: Vec<u8>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextViewKey {
+    Tab,
+    Untab,
+    Newline,
+    AltNewline,
+    Escape,
+    Left,
+    Right,
+    Down,
+    Up,
+}
+
+impl TextViewKey {
+    fn keycode(self) -> usize {
+        match self {
+            TextViewKey::Tab => TEXTVIEW_CALLBACK_KEYCODE_TAB,
+            TextViewKey::Untab => TEXTVIEW_CALLBACK_KEYCODE_UNTAB,
+            TextViewKey::Newline => TEXTVIEW_CALLBACK_KEYCODE_NEWLINE,
+            TextViewKey::AltNewline => TEXTVIEW_CALLBACK_KEYCODE_ALT_NEWLINE,
+            TextViewKey::Escape => TEXTVIEW_CALLBACK_KEYCODE_ESCAPE,
+            TextViewKey::Left => TEXTVIEW_CALLBACK_KEYCODE_LEFT,
+            TextViewKey::Right => TEXTVIEW_CALLBACK_KEYCODE_RIGHT,
+            TextViewKey::Down => TEXTVIEW_CALLBACK_KEYCODE_DOWN,
+            TextViewKey::Up => TEXTVIEW_CALLBACK_KEYCODE_UP,
+        }
+    }
+}
+
+/// Drives a headless application from the main thread.
+/// The state lock must not be held when calling any of these methods.
+/// Every interaction runs all tasks that it schedules on the main thread
+/// (including layout) before returning
+pub struct Headless {
+    unsend: PhantomData<*const ()>
+}
+
+/// Launches `provider` on the current thread without a display.
+/// Once the application has spawned and all scheduled main thread work has
+/// been performed, `driver` is called; the application exits when it returns
+/// (or earlier if the application exits itself).
+/// Applications run one at a time, so concurrent calls block
+pub fn run(provider: impl ApplicationProvider, driver: impl FnOnce(&Headless) + 'static) {
+    DRIVER.set(Some(Box::new(driver)));
+    launch(provider);
+
+    if let Some(payload) = DRIVER_PANIC.take() {
+        panic::resume_unwind(payload);
+    }
+}
+
+impl Headless {
+    /// Performs all pending main thread work
+    pub fn settle(&self) {
+        settle();
+    }
+
+    /// All open windows, in order of creation
+    pub fn windows(&self) -> Vec<HeadlessWindow> {
+        let backend = backend();
+        backend.windows.iter()
+            .filter(|(_, w)| w.open)
+            .map(|(id, w)| backend.snapshot_window(*id, w))
+            .collect()
+    }
+
+    pub fn window(&self, window: WindowId) -> Option<HeadlessWindow> {
+        self.windows().into_iter()
+            .find(|w| w.id == window)
+    }
+
+    /// Snapshot of a view that is currently alive
+    pub fn view(&self, view: ViewId) -> Option<HeadlessView> {
+        let backend = backend();
+        backend.nodes.contains_key(&view.0)
+            .then(|| backend.snapshot_view(view.0))
+    }
+
+    /// Frame of `view` relative to its window, taking scroll offsets into account
+    pub fn window_frame(&self, view: ViewId) -> Option<Rect> {
+        backend().absolute_frame(view.0)
+            .map(|(_, frame)| frame)
+    }
+
+    /// Resizes the window (clamped to its minimum and maximum size) and lays it out
+    pub fn resize(&self, window: WindowId, size: Size) {
+        let handle = {
+            let mut backend = backend();
+            backend.window(window.0).and_then(|w| {
+                w.size = Size::new(
+                    size.w.clamp(w.min_size.w, w.max_size.w.max(w.min_size.w)),
+                    size.h.clamp(w.min_size.h, w.max_size.h.max(w.min_size.h))
+                );
+                w.needs_layout = false;
+                w.handle.map(|h| (h, w.size))
+            })
+        };
+
+        if let Some((handle, size)) = handle {
+            unsafe {
+                front_window_layout(handle, size.w, size.h);
+            }
+        }
+        self.settle();
+    }
+
+    /// Requests the window to close, as if by the user.
+    /// Returns the value of `WindowProvider::can_close`
+    pub fn close(&self, window: WindowId) -> bool {
+        let Some(handle) = backend().window(window.0).and_then(|w| w.handle) else {
+            return false;
+        };
+
+        let ret = unsafe {
+            front_window_should_close(handle)
+        };
+        self.settle();
+        ret
+    }
+
+    fn dispatch(&self, window: WindowId, event: BufferEvent) -> bool {
+        let Some(handle) = backend().window(window.0).and_then(|w| w.handle) else {
+            return false;
+        };
+
+        let ret = unsafe {
+            front_window_dispatch_event(handle, event)
+        };
+        self.settle();
+        ret != 0
+    }
+
+    fn mouse_event(&self, window: WindowId, at: Point) -> BufferEvent {
+        let last = backend().window(window.0)
+            .map(|w| std::mem::replace(&mut w.cursor, at))
+            .unwrap_or_default();
+
+        BufferEvent {
+            is_mouse: true,
+            is_scroll: false,
+            is_up: false,
+            is_down: false,
+            is_left_button: false,
+            is_right_button: false,
+            modifiers: 0,
+            cursor_x: at.x,
+            cursor_y: at.y,
+            delta_x: at.x - last.x,
+            delta_y: at.y - last.y,
+            key_characters: std::ptr::null(),
+            native_event: std::ptr::null_mut(),
+        }
+    }
+
+    /// Moves the mouse to `at` (in window coordinates)
+    pub fn mouse_move(&self, window: WindowId, at: Point) -> bool {
+        let event = self.mouse_event(window, at);
+        self.dispatch(window, event)
+    }
+
+    /// Moves the mouse to `at` and then presses and releases the left button.
+    /// Returns whether the press was handled
+    pub fn click(&self, window: WindowId, at: Point) -> bool {
+        self.mouse_move(window, at);
+
+        let mut down = self.mouse_event(window, at);
+        down.is_left_button = true;
+        down.is_down = true;
+        let handled = self.dispatch(window, down);
+
+        let mut up = self.mouse_event(window, at);
+        up.is_left_button = true;
+        up.is_up = true;
+        self.dispatch(window, up);
+
+        handled
+    }
+
+    /// Clicks the center of `view`.
+    /// Returns false if the view is not mounted in an open window
+    pub fn click_view(&self, view: ViewId) -> bool {
+        let Some((window, frame)) = backend().absolute_frame(view.0) else {
+            return false;
+        };
+
+        self.click(WindowId(window), Point::new(frame.x + frame.w / 2.0, frame.y + frame.h / 2.0))
+    }
+
+    /// Scrolls by `delta` with the mouse at `at`
+    pub fn scroll(&self, window: WindowId, at: Point, delta: Point) -> bool {
+        let mut event = self.mouse_event(window, at);
+        event.is_scroll = true;
+        event.delta_x = delta.x;
+        event.delta_y = delta.y;
+        self.dispatch(window, event)
+    }
+
+    /// Presses and releases a key.
+    /// Returns whether the press was handled
+    pub fn key(&self, window: WindowId, characters: &str, modifiers: EventModifiers) -> bool {
+        let characters = CString::new(characters).unwrap();
+        let key_event = |is_down: bool| BufferEvent {
+            is_mouse: false,
+            is_scroll: false,
+            is_up: !is_down,
+            is_down,
+            is_left_button: false,
+            is_right_button: false,
+            modifiers: modifiers.modifiers,
+            cursor_x: 0.0,
+            cursor_y: 0.0,
+            delta_x: 0.0,
+            delta_y: 0.0,
+            key_characters: characters.as_bytes().as_ptr(),
+            native_event: std::ptr::null_mut(),
+        };
+
+        let handled = self.dispatch(window, key_event(true));
+        self.dispatch(window, key_event(false));
+        handled
+    }
+
+    /// Triggers the menu button at `path`, starting with the title of
+    /// the top level menu. Returns false if there is no such enabled button
+    pub fn trigger_menu(&self, window: WindowId, path: &[&str]) -> bool {
+        let Some(action) = backend().find_menu_action(window.0, path) else {
+            return false;
+        };
+
+        unsafe {
+            front_execute_fn_mut(action);
+        }
+        self.settle();
+        true
+    }
+
+    /// Focuses a text field or text view, as if it were clicked
+    pub fn focus(&self, view: ViewId) {
+        let (old, new) = {
+            let mut backend = backend();
+            let old = backend.focused.replace(view.0);
+            let binding = |id: usize, has_value: u8| match backend.nodes.get(&id).map(|n| &n.kind) {
+                Some(NodeKind::TextField(field)) => Some((field.focused_binding, has_value, field.token)),
+                Some(NodeKind::TextView(tv)) => tv.selected.map(|s| (s, has_value, tv.page_id)),
+                _ => None
+            };
+
+            (old.filter(|o| *o != view.0).and_then(|o| binding(o, 0)), binding(view.0, 1))
+        };
+
+        for (binding, has_value, token) in old.into_iter().chain(new) {
+            unsafe {
+                front_set_token_binding(binding, has_value, token);
+            }
+        }
+        self.settle();
+    }
+
+    /// Replaces the selection of a text field or text view with `text`,
+    /// as if it were typed
+    pub fn type_text(&self, view: ViewId, text: &str) {
+        let field = {
+            let mut backend = backend();
+            match &mut backend.node(view.0 as *mut c_void).kind {
+                NodeKind::TextField(field) => {
+                    field_replace_selection(field, text);
+                    Some((field.text.clone(), field.text_binding, field.callback))
+                }
+                NodeKind::TextView(_) => None,
+                _ => panic!("Headless view is not editable")
+            }
+        };
+
+        match field {
+            Some((content, binding, callback)) => {
+                let content = CString::new(content).unwrap();
+                unsafe {
+                    front_set_opt_string_binding(binding, content.as_bytes().as_ptr());
+                    front_execute_fn_mut(callback);
+                }
+            }
+            None => text_view_replace_selection(view.0, text)
+        }
+        self.settle();
+    }
+
+    /// Replaces the entire content of a text field or text view, as if it were typed
+    pub fn set_text(&self, view: ViewId, text: &str) {
+        self.select_all(view);
+        self.type_text(view, text);
+    }
+
+    fn select_all(&self, view: ViewId) {
+        let mut backend = backend();
+        match &mut backend.node(view.0 as *mut c_void).kind {
+            NodeKind::TextField(field) => field.selection = 0..field.text.chars().count(),
+            NodeKind::TextView(tv) => tv.selection = 0..utf16_len(&tv.text),
+            _ => panic!("Headless view is not editable")
+        }
+    }
+
+    /// Selects `range` (in utf16 code units) of a text view, as if by the user
+    pub fn select(&self, view: ViewId, range: Range<usize>) {
+        let page = {
+            let mut backend = backend();
+            let text_view = backend.text_view(view.0 as *mut c_void);
+            let len = utf16_len(&text_view.text);
+            text_view.selection = range.start.min(len)..range.end.min(len);
+            text_view.page.map(|p| (p, text_view.selection.clone()))
+        };
+
+        if let Some((page, selection)) = page {
+            unsafe {
+                front_set_textview_selection(page, selection.start, selection.len());
+            }
+        }
+        self.settle();
+    }
+
+    /// Presses a key that text views forward to their provider.
+    /// Returns whether the provider handled it
+    pub fn text_view_key(&self, view: ViewId, key: TextViewKey) -> bool {
+        let Some(callback) = backend().text_view(view.0 as *mut c_void).key_callback else {
+            return false;
+        };
+
+        let ret = unsafe {
+            front_execute_key_callback(callback, key.keycode())
+        };
+        self.settle();
+        ret != 0
+    }
+
+    /// Picks an option of a dropdown, as if by the user.
+    /// Returns false if the option does not exist
+    pub fn choose(&self, view: ViewId, option: Option<&str>) -> bool {
+        let binding = {
+            let mut backend = backend();
+            let NodeKind::Dropdown { binding, options, selection } = &mut backend.node(view.0 as *mut c_void).kind else {
+                panic!("Headless view is not a dropdown")
+            };
+
+            if option.is_some_and(|o| !options.iter().any(|x| x == o)) {
+                return false;
+            }
+            *selection = option.map(str::to_string);
+            *binding
+        };
+
+        let cstring = option.map(|o| CString::new(o).unwrap());
+        unsafe {
+            front_set_opt_string_binding(binding, cstring.as_ref().map_or(std::ptr::null(), |c| c.as_bytes().as_ptr()));
+        }
+        self.settle();
+        true
+    }
+
+    /// Scrolls a scroll view to `offset`, as if by the user
+    pub fn scroll_to(&self, view: ViewId, offset: Point) {
+        let bindings = {
+            let mut backend = backend();
+            let NodeKind::Scroll { offset: o, binding_x, binding_y, .. } = &mut backend.node(view.0 as *mut c_void).kind else {
+                panic!("Headless view is not a scroll view")
+            };
+            *o = offset;
+            (*binding_x, *binding_y)
+        };
+
+        unsafe {
+            front_set_screen_unit_binding(bindings.0, offset.x as ScreenUnit);
+            front_set_screen_unit_binding(bindings.1, offset.y as ScreenUnit);
+        }
+        self.settle();
+    }
+
+    /// The next message box will report that the button at `index` was clicked.
+    /// By default, the first button is chosen
+    pub fn respond_to_message_box(&self, index: usize) {
+        backend().message_box_responses.push_back(index);
+    }
+
+    /// Message boxes that have been run so far
+    pub fn message_boxes(&self) -> Vec<HeadlessMessageBox> {
+        backend().message_boxes.clone()
+    }
+
+    /// The next file picker will return `path`
+    /// (unless it does not satisfy the content types).
+    /// By default, pickers are cancelled
+    pub fn respond_to_file_picker(&self, path: Option<PathBuf>) {
+        backend().picker_responses.push_back(path);
+    }
+
+    pub fn clipboard(&self) -> String {
+        backend().clipboard.clone()
+    }
+
+    pub fn set_clipboard(&self, content: &str) {
+        backend().clipboard = content.to_string();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::native::headless::{text_size, wrap_paragraph, CHAR_WIDTH, LINE_HEIGHT};
+    use crate::util::geo::Size;
+
+    // font size at which every character is one unit wide
+    const UNIT_FONT: f64 = 1.0 / CHAR_WIDTH;
+
+    fn size(w: usize, lines: usize) -> Size {
+        Size::new(w as f64, lines as f64 * UNIT_FONT * LINE_HEIGHT)
+    }
+
+    #[test]
+    fn test_text_size_wrapping() {
+        // the space between words counts towards the line
+        assert_eq!(wrap_paragraph("ab cd", 4), vec![(3, 2), (2, 2)]);
+        assert_eq!(wrap_paragraph("ab cd", 5), vec![(5, 5)]);
+        assert_eq!(wrap_paragraph("ab cd ef", 5), vec![(6, 5), (2, 2)]);
+        assert_eq!(text_size("ab cd", UNIT_FONT, 4.0, 0), size(2, 2));
+        assert_eq!(text_size("ab cd", UNIT_FONT, 5.0, 0), size(5, 1));
+        assert_eq!(text_size("ab cd ef", UNIT_FONT, 5.0, 0), size(5, 2));
+        assert_eq!(text_size("ab cd ef gh", UNIT_FONT, 7.0, 0), size(5, 2));
+
+        // trailing spaces hang past the edge but are not measured
+        assert_eq!(wrap_paragraph("abcd   ef", 4), vec![(7, 4), (2, 2)]);
+        assert_eq!(text_size("abcd   ef", UNIT_FONT, 4.0, 0), size(4, 2));
+
+        // long words are broken, and the remainder shares a line with what follows
+        assert_eq!(wrap_paragraph("abcdefghij kl", 4), vec![(4, 4), (4, 4), (3, 2), (2, 2)]);
+        assert_eq!(wrap_paragraph("ab cdefghij", 4), vec![(3, 2), (4, 4), (4, 4)]);
+        assert_eq!(text_size("ab cdefghij", UNIT_FONT, 4.0, 0), size(4, 3));
+
+        // no line is wider than the available space
+        let text = "the quick brown fox jumps over the lazy dog";
+        for width in 1..=text.len() {
+            for (_, visible) in wrap_paragraph(text, width) {
+                assert!(visible <= width);
+            }
+            assert!(text_size(text, UNIT_FONT, width as f64, 0).w <= width as f64);
+        }
+
+        // max_lines bounds the height and the lines measured
+        assert_eq!(text_size("ab cd efgh", UNIT_FONT, 4.0, 1), size(2, 1));
+        assert_eq!(text_size("ab cd efgh", UNIT_FONT, 4.0, 0), size(4, 3));
+        assert_eq!(text_size("ab cd\nef", UNIT_FONT, 4.0, 0), size(2, 3));
+    }
+}
@@ -69,6 +69,12 @@ pub mod slock_cell {
             self.0.borrow_mut()
         }
 
+        // caller must ensure the current thread owns the state lock
+        #[cfg(feature = "serde")]
+        pub(crate) unsafe fn borrow_unchecked(&self) -> Ref<'_, T> {
+            self.0.borrow()
+        }
+
         pub unsafe fn as_ptr(&self) -> *const T {
             self.0.as_ptr()
         }
@@ -137,6 +143,8 @@ mod listener {
     use crate::core::{MSlock, Slock};
     use crate::state::Stateful;
     use crate::view::undo_manager::UndoBucket;
+    #[cfg(debug_assertions)]
+    use crate::util::test_util::{AllocKind, QuarveAllocTag};
 
     #[derive(Copy, Clone, Debug)]
     pub enum UndoBarrier {
@@ -148,6 +156,13 @@ mod listener {
         // (We cannot take ownership since the caller is often unsized)
         fn invert(&mut self, s: MSlock);
 
+        /// Applies the inverse without it being recorded by any inverse listener
+        /// This is used to roll back failed transactions, and hence must only
+        /// be supported by inverses that stores produce
+        fn rollback(&mut self, _s: Slock) {
+            panic!("This action does not support rollbacks")
+        }
+
         /// It must be guaranteed by the caller
         /// the other type is exactly the same as our type
         /// and with the same id
@@ -162,6 +177,22 @@ mod listener {
 
         // id = 0 implies that it cannot be combined at all
         fn id(&self) -> usize;
+
+        /// The label given by `history_label` when this action was recorded
+        fn history_label(&self) -> Option<String> {
+            None
+        }
+
+        /// Title of this action once it is applied as a redo
+        fn forward_description(&self) -> String {
+            "Change".to_string()
+        }
+
+        /// Title of the action that this inverts,
+        /// i.e. what it is called when applied as an undo
+        fn backward_description(&self) -> String {
+            "Change".to_string()
+        }
     }
 
     /* trait aliases */
@@ -179,6 +210,35 @@ mod listener {
         SignalListener(Box<dyn (FnMut(&S, Slock) -> bool) + Send>),
         GeneralListener(Box<dyn FnMut(Slock) -> bool + Send>),
     }
+
+    impl<S: Stateful> StateListener<S> {
+        // in debug builds, the listener carries an allocation tag
+        // so that it shows up in diagnostics for as long as it is alive
+        #[track_caller]
+        pub(super) fn tagged(self) -> Self {
+            #[cfg(debug_assertions)]
+            {
+                let tag = QuarveAllocTag::new::<S>(AllocKind::Listener);
+                match self {
+                    StateListener::ActionListener(mut f) => StateListener::ActionListener(Box::new(move |state, action, s| {
+                        let _tag = &tag;
+                        f(state, action, s)
+                    })),
+                    StateListener::SignalListener(mut f) => StateListener::SignalListener(Box::new(move |state, s| {
+                        let _tag = &tag;
+                        f(state, s)
+                    })),
+                    StateListener::GeneralListener(mut f) => StateListener::GeneralListener(Box::new(move |s| {
+                        let _tag = &tag;
+                        f(s)
+                    })),
+                }
+            }
+
+            #[cfg(not(debug_assertions))]
+            self
+        }
+    }
 }
 
 mod group {
@@ -271,6 +331,7 @@ mod group {
         use crate::state::{GroupAction, GroupBasis, IntoAction};
 
         #[derive(Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
         pub struct Word<T> where T: 'static {
             items: Vec<T>,
         }
@@ -433,6 +494,8 @@ mod group {
     }
 
     mod action {
+        pub use component_action::*;
+        pub use map_action::*;
         pub use numeric_action::*;
         pub use set_action::*;
         pub use string_action::*;
@@ -445,6 +508,7 @@ mod group {
             use crate::util::marker::FalseMarker;
 
             #[derive(Clone)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
             pub enum SetAction<T>
             {
                 Set(T),
@@ -528,15 +592,20 @@ mod group {
         }
 
         mod string_action {
+            use std::fmt::{Display, Formatter};
             use std::ops::Range;
 
+            pub use ropey::Rope;
+
             use crate::state::{GroupBasis, Stateful, Word};
             use crate::util::marker::FalseMarker;
 
             #[derive(Clone, Debug, PartialEq, Eq, Default)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
             pub struct EditingString(pub String);
 
             #[derive(Clone)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
             pub enum StringActionBasis {
                 // start, length, with
                 ReplaceSubrange(Range<usize>, String),
@@ -556,11 +625,11 @@ mod group {
                 }
 
                 fn forward_description(&self) -> impl Into<String> {
-                    "Change"
+                    "Typing"
                 }
 
                 fn backward_description(&self) -> impl Into<String> {
-                    "Change"
+                    "Typing"
                 }
             }
 
@@ -568,9 +637,240 @@ mod group {
                 type Action = Word<StringActionBasis>;
                 type HasInnerStores = FalseMarker;
             }
+
+            /// Editable text backed by a rope, so that edits
+            /// and index conversions are logarithmic in the length of the document.
+            /// Edits are byte indexed, exactly like those of [`EditingString`]
+            #[derive(Clone, Debug, PartialEq, Eq, Default)]
+            pub struct EditingRope(pub Rope);
+
+            #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+            pub enum RopeActionBasis {
+                // byte range, with
+                ReplaceSubrange(Range<usize>, String),
+            }
+
+            impl From<StringActionBasis> for RopeActionBasis {
+                fn from(action: StringActionBasis) -> Self {
+                    match action {
+                        StringActionBasis::ReplaceSubrange(range, with) => RopeActionBasis::ReplaceSubrange(range, with)
+                    }
+                }
+            }
+
+            impl EditingRope {
+                pub fn new(text: &str) -> Self {
+                    EditingRope(Rope::from_str(text))
+                }
+            }
+
+            impl From<&str> for EditingRope {
+                fn from(text: &str) -> Self {
+                    EditingRope::new(text)
+                }
+            }
+
+            impl Display for EditingRope {
+                fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                    Display::fmt(&self.0, f)
+                }
+            }
+
+            impl GroupBasis<EditingRope> for RopeActionBasis {
+                fn apply(self, to: &mut EditingRope) -> Self {
+                    match self {
+                        RopeActionBasis::ReplaceSubrange(range, content) => {
+                            let chars = to.0.byte_to_char(range.start)..to.0.byte_to_char(range.end);
+                            let replaced = to.0.slice(chars.clone()).to_string();
+                            let next_range = range.start .. range.start + content.len();
+                            to.0.remove(chars.clone());
+                            to.0.insert(chars.start, &content);
+
+                            RopeActionBasis::ReplaceSubrange(next_range, replaced)
+                        }
+                    }
+                }
+
+                fn forward_description(&self) -> impl Into<String> {
+                    "Typing"
+                }
+
+                fn backward_description(&self) -> impl Into<String> {
+                    "Typing"
+                }
+            }
+
+            impl Stateful for EditingRope {
+                type Action = Word<RopeActionBasis>;
+                type HasInnerStores = FalseMarker;
+            }
+
+            #[cfg(feature = "serde")]
+            impl serde::Serialize for EditingRope {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(&self.0)
+                }
+            }
+
+            #[cfg(feature = "serde")]
+            impl<'de> serde::Deserialize<'de> for EditingRope {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    String::deserialize(deserializer)
+                        .map(|text| EditingRope::new(&text))
+                }
+            }
+
+            /// Conversions between byte, char, utf16 and line indices.
+            /// Lines are separated by `'\n'` (so there is always at least one line),
+            /// and every index may be equal to the corresponding length
+            pub trait TextIndex {
+                fn len_bytes(&self) -> usize;
+                fn len_chars(&self) -> usize;
+                fn len_lines(&self) -> usize;
+
+                fn byte_to_char(&self, byte: usize) -> usize;
+                fn char_to_byte(&self, char: usize) -> usize;
+
+                fn char_to_utf16(&self, char: usize) -> usize;
+                fn utf16_to_char(&self, utf16: usize) -> usize;
+
+                /// The line containing the given byte
+                fn byte_to_line(&self, byte: usize) -> usize;
+                /// The byte at which the given line starts
+                fn line_to_byte(&self, line: usize) -> usize;
+
+                fn char_to_line(&self, char: usize) -> usize {
+                    self.byte_to_line(self.char_to_byte(char))
+                }
+
+                fn line_to_char(&self, line: usize) -> usize {
+                    self.byte_to_char(self.line_to_byte(line))
+                }
+
+                /// Contents of the given line, excluding the new line
+                fn line(&self, line: usize) -> String;
+            }
+
+            impl TextIndex for EditingString {
+                fn len_bytes(&self) -> usize {
+                    self.0.len()
+                }
+
+                fn len_chars(&self) -> usize {
+                    self.0.chars().count()
+                }
+
+                fn len_lines(&self) -> usize {
+                    self.0.matches('\n').count() + 1
+                }
+
+                fn byte_to_char(&self, byte: usize) -> usize {
+                    self.0[..byte].chars().count()
+                }
+
+                fn char_to_byte(&self, char: usize) -> usize {
+                    self.0.char_indices()
+                        .nth(char)
+                        .map_or_else(|| {
+                            assert_eq!(char, self.len_chars(), "Char index out of bounds");
+                            self.0.len()
+                        }, |(byte, _)| byte)
+                }
+
+                fn char_to_utf16(&self, char: usize) -> usize {
+                    self.0[..self.char_to_byte(char)].encode_utf16().count()
+                }
+
+                fn utf16_to_char(&self, utf16: usize) -> usize {
+                    let mut count = 0;
+                    for (i, c) in self.0.chars().enumerate() {
+                        if count >= utf16 {
+                            return i;
+                        }
+                        count += c.len_utf16();
+                    }
+
+                    assert!(utf16 <= count, "Utf16 index out of bounds");
+                    self.len_chars()
+                }
+
+                fn byte_to_line(&self, byte: usize) -> usize {
+                    self.0[..byte].matches('\n').count()
+                }
+
+                fn line_to_byte(&self, line: usize) -> usize {
+                    if line == 0 {
+                        return 0;
+                    }
+
+                    self.0.match_indices('\n')
+                        .nth(line - 1)
+                        .map_or_else(|| {
+                            assert_eq!(line, self.len_lines(), "Line index out of bounds");
+                            self.0.len()
+                        }, |(byte, _)| byte + 1)
+                }
+
+                fn line(&self, line: usize) -> String {
+                    self.0.split('\n')
+                        .nth(line)
+                        .expect("Line index out of bounds")
+                        .to_string()
+                }
+            }
+
+            impl TextIndex for EditingRope {
+                fn len_bytes(&self) -> usize {
+                    self.0.len_bytes()
+                }
+
+                fn len_chars(&self) -> usize {
+                    self.0.len_chars()
+                }
+
+                fn len_lines(&self) -> usize {
+                    self.0.len_lines()
+                }
+
+                fn byte_to_char(&self, byte: usize) -> usize {
+                    self.0.byte_to_char(byte)
+                }
+
+                fn char_to_byte(&self, char: usize) -> usize {
+                    self.0.char_to_byte(char)
+                }
+
+                fn char_to_utf16(&self, char: usize) -> usize {
+                    self.0.char_to_utf16_cu(char)
+                }
+
+                fn utf16_to_char(&self, utf16: usize) -> usize {
+                    self.0.utf16_cu_to_char(utf16)
+                }
+
+                fn byte_to_line(&self, byte: usize) -> usize {
+                    self.0.byte_to_line(byte)
+                }
+
+                fn line_to_byte(&self, line: usize) -> usize {
+                    self.0.line_to_byte(line)
+                }
+
+                fn line(&self, line: usize) -> String {
+                    let line = self.0.line(line);
+                    let mut ret = line.to_string();
+                    if ret.ends_with('\n') {
+                        ret.pop();
+                    }
+                    ret
+                }
+            }
         }
 
         mod vec_action {
+            use std::collections::{HashMap, VecDeque};
+            use std::hash::Hash;
             use std::ops::Range;
 
             use crate::core::Slock;
@@ -579,6 +879,7 @@ mod group {
             use crate::view::undo_manager::UndoBucket;
 
             #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
             pub enum VecActionBasis<T> {
                 /* indices */
                 Insert(T, usize),
@@ -586,7 +887,31 @@ mod group {
                 InsertMany(Vec<T>, usize),
                 RemoveMany(Range<usize>),
                 // u, v
-                Swap(usize, usize)
+                Swap(usize, usize),
+                // from, to (index after the move)
+                Move(usize, usize),
+                Replace(usize, T),
+                // element i of the result is element permutation[i] of the original
+                Permute(Vec<usize>),
+            }
+
+            // applies a permutation in the sense of VecActionBasis::Permute
+            // the permutation is validated first, so that `to` is untouched if it panics
+            pub(crate) fn permute<T>(to: &mut Vec<T>, permutation: &[usize]) {
+                assert_eq!(to.len(), permutation.len(), "Permutation must cover the entire vector");
+                let mut seen = vec![false; permutation.len()];
+                for &i in permutation {
+                    assert!(i < seen.len() && !seen[i], "Invalid permutation");
+                    seen[i] = true;
+                }
+
+                let mut original: Vec<Option<T>> = std::mem::take(to)
+                    .into_iter()
+                    .map(Some)
+                    .collect();
+                *to = permutation.iter()
+                    .map(|&i| original[i].take().unwrap())
+                    .collect();
             }
 
             impl<T> GroupBasis<Vec<T>> for VecActionBasis<T> where T: Send + 'static
@@ -617,15 +942,45 @@ mod group {
                             to.swap(a, b);
                             VecActionBasis::Swap(a, b)
                         }
+                        VecActionBasis::Move(from, at) => {
+                            let elem = to.remove(from);
+                            to.insert(at, elem);
+                            VecActionBasis::Move(at, from)
+                        }
+                        VecActionBasis::Replace(at, elem) => {
+                            let replaced = std::mem::replace(&mut to[at], elem);
+                            VecActionBasis::Replace(at, replaced)
+                        }
+                        VecActionBasis::Permute(permutation) => {
+                            let mut inverse = vec![0; permutation.len()];
+                            for (i, &j) in permutation.iter().enumerate() {
+                                inverse[j] = i;
+                            }
+                            permute(to, &permutation);
+                            VecActionBasis::Permute(inverse)
+                        }
                     }
                 }
 
                 fn forward_description(&self) -> impl Into<String> {
-                    "Change"
+                    match self {
+                        VecActionBasis::Insert(..) | VecActionBasis::InsertMany(..) => "Insert",
+                        VecActionBasis::Remove(_) | VecActionBasis::RemoveMany(_) => "Delete",
+                        VecActionBasis::Swap(..) | VecActionBasis::Move(..) => "Move",
+                        VecActionBasis::Replace(..) => "Replace",
+                        VecActionBasis::Permute(_) => "Sort",
+                    }
                 }
 
+                // described by the action this is the inverse of
                 fn backward_description(&self) -> impl Into<String> {
-                    "Change"
+                    match self {
+                        VecActionBasis::Insert(..) | VecActionBasis::InsertMany(..) => "Delete",
+                        VecActionBasis::Remove(_) | VecActionBasis::RemoveMany(_) => "Insert",
+                        VecActionBasis::Swap(..) | VecActionBasis::Move(..) => "Move",
+                        VecActionBasis::Replace(..) => "Replace",
+                        VecActionBasis::Permute(_) => "Sort",
+                    }
                 }
             }
 
@@ -647,7 +1002,7 @@ mod group {
                     Some(move |_v: &Vec<T>, w: &Word<VecActionBasis<T>>, s: Slock| {
                         for a in w.iter() {
                             match a {
-                                VecActionBasis::Insert(store, _) => {
+                                VecActionBasis::Insert(store, _) | VecActionBasis::Replace(_, store) => {
                                     /* make sure it is updated of the listener */
                                     store.subtree_general_listener(f.clone(), s);
                                 },
@@ -676,7 +1031,7 @@ mod group {
                     Some(move |_v: &Vec<T>, w: &Word<VecActionBasis<T>>, s: Slock| {
                         for a in w.iter() {
                             match a {
-                                VecActionBasis::Insert(store, _) => {
+                                VecActionBasis::Insert(store, _) | VecActionBasis::Replace(_, store) => {
                                     /* make sure it is updated of the inverse listener */
                                     store.subtree_inverse_listener(f.clone(), s);
                                 },
@@ -704,7 +1059,7 @@ mod group {
                     Some(move |_v: &Vec<T>, w: &Word<VecActionBasis<T>>, s: Slock| {
                         for a in w.iter() {
                             match a {
-                                VecActionBasis::Insert(store, _) => {
+                                VecActionBasis::Insert(store, _) | VecActionBasis::Replace(_, store) => {
                                     store.subtree_undo_bucket(bucket, s);
                                 }
                                 VecActionBasis::InsertMany(stores, _) => {
@@ -730,6 +1085,283 @@ mod group {
                     Word::new(self.into_iter().collect())
                 }
             }
+
+            // Removes (in application order) every element that is not kept,
+            // batching runs of removed elements. Runs are removed back to front
+            // so that indices remain valid
+            pub(crate) fn removal_runs<T>(keep: &[bool]) -> Vec<VecActionBasis<T>> {
+                let mut ret = Vec::new();
+                let mut end = keep.len();
+                while end > 0 {
+                    if keep[end - 1] {
+                        end -= 1;
+                        continue;
+                    }
+
+                    let start = keep[..end].iter()
+                        .rposition(|&k| k)
+                        .map_or(0, |i| i + 1);
+                    if end - start == 1 {
+                        ret.push(VecActionBasis::Remove(start));
+                    }
+                    else {
+                        ret.push(VecActionBasis::RemoveMany(start..end));
+                    }
+                    end = start;
+                }
+
+                ret
+            }
+
+            // Edits (in application order) that transform a vector with keys `old`
+            // into `new`. Elements whose key appears in both are kept rather than replaced,
+            // duplicate keys being matched in order of appearance.
+            // Unmatched elements are removed (back to front) and inserted (front to back)
+            // in runs, and the kept elements are reordered with the minimum number of swaps.
+            // A kept element for which `unchanged(old index, new element)` is false
+            // is finally replaced at its new index
+            pub(crate) fn keyed_diff<T, K, U>(old: &[K], new: Vec<(K, T)>, mut unchanged: U) -> Vec<VecActionBasis<T>>
+                where K: Hash + Eq, U: FnMut(usize, &T) -> bool
+            {
+                let mut new_indices: HashMap<&K, VecDeque<usize>> = HashMap::new();
+                for (j, (key, _)) in new.iter().enumerate() {
+                    new_indices.entry(key).or_default().push_back(j);
+                }
+
+                let targets: Vec<Option<usize>> = old.iter()
+                    .map(|key| new_indices.get_mut(key).and_then(|indices| indices.pop_front()))
+                    .collect();
+                let mut matched = vec![None; new.len()];
+                for (i, &j) in targets.iter().enumerate() {
+                    if let Some(j) = j {
+                        matched[j] = Some(i);
+                    }
+                }
+
+                let keep: Vec<bool> = targets.iter()
+                    .map(Option::is_some)
+                    .collect();
+                let mut ret = removal_runs(&keep);
+
+                // reorder kept elements by cycle decomposition
+                let kept: Vec<usize> = targets.into_iter().flatten().collect();
+                let mut sorted = kept.clone();
+                sorted.sort_unstable();
+                let mut rank: Vec<usize> = kept.iter()
+                    .map(|j| sorted.binary_search(j).unwrap())
+                    .collect();
+                for p in 0..rank.len() {
+                    while rank[p] != p {
+                        let q = rank[p];
+                        ret.push(VecActionBasis::Swap(p, q));
+                        rank.swap(p, q);
+                    }
+                }
+
+                // insertions, front to back so that each lands at its final index
+                let mut run: Vec<T> = Vec::new();
+                let mut run_start = 0;
+                let flush = |run: &mut Vec<T>, start: usize, ret: &mut Vec<VecActionBasis<T>>| {
+                    if run.len() == 1 {
+                        ret.push(VecActionBasis::Insert(run.pop().unwrap(), start));
+                    }
+                    else if !run.is_empty() {
+                        ret.push(VecActionBasis::InsertMany(std::mem::take(run), start));
+                    }
+                };
+                let mut replacements = Vec::new();
+                for (j, (_, elem)) in new.into_iter().enumerate() {
+                    if let Some(i) = matched[j] {
+                        flush(&mut run, run_start, &mut ret);
+                        if !unchanged(i, &elem) {
+                            replacements.push(VecActionBasis::Replace(j, elem));
+                        }
+                        continue;
+                    }
+
+                    if run.is_empty() {
+                        run_start = j;
+                    }
+                    run.push(elem);
+                }
+                flush(&mut run, run_start, &mut ret);
+                ret.extend(replacements);
+
+                ret
+            }
+        }
+
+        mod map_action {
+            use std::collections::{BTreeMap, HashMap};
+            use std::hash::Hash;
+
+            use crate::core::Slock;
+            use crate::state::{GeneralListener, GroupBasis, IntoAction, InverseListener, Stateful, StoreContainer, Word};
+            use crate::util::marker::{ThreadMarker, TrueMarker};
+            use crate::view::undo_manager::UndoBucket;
+
+            #[derive(Clone, Debug)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+            pub enum MapActionBasis<K, V> {
+                // key must not be present
+                Insert(K, V),
+                // key must be present
+                Remove(K),
+                // key must be present
+                Replace(K, V),
+            }
+
+            /// Common interface of the keyed Stateful collections
+            /// (the values are stores, as with Vec)
+            pub trait StatefulMap: Stateful<Action=Word<MapActionBasis<Self::Key, Self::Value>>> {
+                type Key: Clone + PartialEq + Send + 'static;
+                type Value: StoreContainer;
+
+                fn entries(&self) -> impl Iterator<Item=(&Self::Key, &Self::Value)>;
+
+                fn value(&self, key: &Self::Key) -> Option<&Self::Value>;
+            }
+
+            macro_rules! impl_map_stateful {
+                ($map: ident, $($bound: path),*) => {
+                    impl<K, V> GroupBasis<$map<K, V>> for MapActionBasis<K, V>
+                        where K: Clone + Send + 'static $(+ $bound)*, V: Send + 'static
+                    {
+                        fn apply(self, to: &mut $map<K, V>) -> Self {
+                            match self {
+                                MapActionBasis::Insert(key, value) => {
+                                    assert!(!to.contains_key(&key), "Attempted to insert a key that is already present; use Replace instead");
+                                    to.insert(key.clone(), value);
+                                    MapActionBasis::Remove(key)
+                                }
+                                MapActionBasis::Remove(key) => {
+                                    let old = to.remove(&key)
+                                        .expect("Attempted to remove a key that is not present");
+                                    MapActionBasis::Insert(key, old)
+                                }
+                                MapActionBasis::Replace(key, value) => {
+                                    let old = to.insert(key.clone(), value)
+                                        .expect("Attempted to replace a key that is not present");
+                                    MapActionBasis::Replace(key, old)
+                                }
+                            }
+                        }
+
+                        fn forward_description(&self) -> impl Into<String> {
+                            match self {
+                                MapActionBasis::Insert(..) => "Insert",
+                                MapActionBasis::Remove(_) => "Delete",
+                                MapActionBasis::Replace(..) => "Change",
+                            }
+                        }
+
+                        fn backward_description(&self) -> impl Into<String> {
+                            match self {
+                                MapActionBasis::Insert(..) => "Delete",
+                                MapActionBasis::Remove(_) => "Insert",
+                                MapActionBasis::Replace(..) => "Change",
+                            }
+                        }
+                    }
+
+                    // listeners are managed just as in the vec case
+                    impl<K, V> Stateful for $map<K, V>
+                        where K: Clone + Send + 'static $(+ $bound)*, V: StoreContainer
+                    {
+                        type Action = Word<MapActionBasis<K, V>>;
+                        type HasInnerStores = TrueMarker;
+
+                        fn subtree_general_listener<F>(&self, mut f: F, s: Slock<impl ThreadMarker>)
+                            -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static>
+                            where F: GeneralListener + Clone {
+
+                            for store in self.values() {
+                                store.subtree_general_listener(f.clone(), s);
+                            }
+
+                            Some(move |_m: &$map<K, V>, w: &Word<MapActionBasis<K, V>>, s: Slock| {
+                                for a in w.iter() {
+                                    match a {
+                                        MapActionBasis::Insert(_, store) | MapActionBasis::Replace(_, store) => {
+                                            store.subtree_general_listener(f.clone(), s);
+                                        }
+                                        MapActionBasis::Remove(_) => { }
+                                    }
+                                }
+
+                                f(s)
+                            })
+                        }
+
+                        fn subtree_inverse_listener<F>(&self, f: F, s: Slock<impl ThreadMarker>)
+                            -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static>
+                            where F: InverseListener + Clone {
+                            for store in self.values() {
+                                store.subtree_inverse_listener(f.clone(), s);
+                            }
+
+                            Some(move |_m: &$map<K, V>, w: &Word<MapActionBasis<K, V>>, s: Slock| {
+                                for a in w.iter() {
+                                    match a {
+                                        MapActionBasis::Insert(_, store) | MapActionBasis::Replace(_, store) => {
+                                            store.subtree_inverse_listener(f.clone(), s);
+                                        }
+                                        MapActionBasis::Remove(_) => { }
+                                    }
+                                }
+
+                                true
+                            })
+                        }
+
+                        fn subtree_undo_bucket(&self, bucket: UndoBucket, s: Slock<impl ThreadMarker>)
+                                               -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static> {
+                            for store in self.values() {
+                                store.subtree_undo_bucket(bucket, s);
+                            }
+
+                            Some(move |_m: &$map<K, V>, w: &Word<MapActionBasis<K, V>>, s: Slock| {
+                                for a in w.iter() {
+                                    match a {
+                                        MapActionBasis::Insert(_, store) | MapActionBasis::Replace(_, store) => {
+                                            store.subtree_undo_bucket(bucket, s);
+                                        }
+                                        MapActionBasis::Remove(_) => { }
+                                    }
+                                }
+
+                                true
+                            })
+                        }
+                    }
+
+                    impl<K, V> StatefulMap for $map<K, V>
+                        where K: Clone + Send + 'static $(+ $bound)*, V: StoreContainer
+                    {
+                        type Key = K;
+                        type Value = V;
+
+                        fn entries(&self) -> impl Iterator<Item=(&K, &V)> {
+                            self.iter()
+                        }
+
+                        fn value(&self, key: &K) -> Option<&V> {
+                            self.get(key)
+                        }
+                    }
+
+                    impl<K, V, const N: usize> IntoAction<Word<MapActionBasis<K, V>>, $map<K, V>> for [MapActionBasis<K, V>; N]
+                        where K: Clone + Send + 'static $(+ $bound)*, V: Send + 'static
+                    {
+                        fn into_action(self, _target: &$map<K, V>) -> Word<MapActionBasis<K, V>> {
+                            Word::new(self.into_iter().collect())
+                        }
+                    }
+                };
+            }
+
+            impl_map_stateful!(HashMap, Hash, Eq);
+            impl_map_stateful!(BTreeMap, Ord);
         }
 
         mod vector_action {
@@ -749,11 +1381,16 @@ mod group {
             impl<T, const N: usize> VectorAction<T, N>
                 where T: Stateful<HasInnerStores=FalseMarker>
             {
-                fn from_array(arr: [T::Action; N]) -> Self {
+                pub(super) fn from_array(arr: [T::Action; N]) -> Self {
                     VectorAction {
                         actions: arr
                     }
                 }
+
+                /// The action on each component
+                pub fn actions(&self) -> &[T::Action; N] {
+                    &self.actions
+                }
             }
 
             impl<T, const N: usize> GroupBasis<Vector<T, N>> for VectorAction<T, N>
@@ -838,26 +1475,189 @@ mod group {
             }
         }
 
-        // pseudo action that converts into set action
-        mod numeric_action {
-            use std::ops::{Add, Mul, Sub};
+        mod component_action {
+            use std::array;
+            use std::ops::Mul;
 
-            use crate::state::{IntoAction, SetAction, Stateful};
+            use crate::state::{GroupAction, GroupBasis, IntoAction, SetAction, Stateful};
+            use super::vector_action::VectorAction;
+            use crate::util::geo::{Inset, Point, Rect, Size};
+            use crate::util::marker::FalseMarker;
+            use crate::util::Vector;
+            use crate::view::util::{Color, LinearColor};
 
-            /// Not an actual action; merely meant to convert to
-            /// SetAction
-            pub enum NumericAction<T>
-                where T: Stateful + Copy + Add<Output=T> + Mul<Output=T> + Sub<Output=T>
+            /// Plain values made up of a fixed number of
+            /// independently settable components
+            pub trait Components<const N: usize>: Copy + Send + 'static {
+                type Component: Stateful<Action=SetAction<Self::Component>, HasInnerStores=FalseMarker> + Copy;
+
+                fn components(&self) -> [Self::Component; N];
+                fn from_components(components: [Self::Component; N]) -> Self;
+            }
+
+            /// Sets any subset of the components of a value,
+            /// leaving the others untouched.
+            /// Conversions exist from a whole-value `SetAction` as
+            /// well as from an array of per-component `SetAction`s
+            pub struct ComponentAction<T, const N: usize>
+                where T: Components<N>
             {
-                Set(T),
-                Incr(T),
-                Decr(T),
-                Mul(T),
-                Identity
+                // acts on the components as a vector
+                vector: VectorAction<T::Component, N>
             }
 
-            impl<T> IntoAction<SetAction<T>, T> for NumericAction<T>
-                where T: Stateful<Action=SetAction<T>> + Copy + Add<Output=T> + Mul<Output=T> + Sub<Output=T> {
+            impl<T, const N: usize> ComponentAction<T, N>
+                where T: Components<N>
+            {
+                /// Sets only the component at `index`
+                /// (components are in field declaration order)
+                pub fn component(index: usize, value: T::Component) -> Self {
+                    ComponentAction {
+                        vector: VectorAction::from_array(array::from_fn(|i| {
+                            if i == index {
+                                SetAction::Set(value)
+                            }
+                            else {
+                                SetAction::Identity
+                            }
+                        }))
+                    }
+                }
+            }
+
+            impl<T, const N: usize> GroupBasis<T> for ComponentAction<T, N>
+                where T: Components<N>
+            {
+                fn apply(self, to: &mut T) -> Self {
+                    let mut components = Vector(to.components());
+                    let vector = self.vector.apply(&mut components);
+                    *to = T::from_components(components.0);
+
+                    ComponentAction {
+                        vector
+                    }
+                }
+
+                fn forward_description(&self) -> impl Into<String> {
+                    "Change"
+                }
+
+                fn backward_description(&self) -> impl Into<String> {
+                    "Change"
+                }
+            }
+
+            impl<T, const N: usize> Mul for ComponentAction<T, N>
+                where T: Components<N>
+            {
+                type Output = Self;
+
+                fn mul(self, rhs: Self) -> Self::Output {
+                    ComponentAction {
+                        vector: self.vector * rhs.vector
+                    }
+                }
+            }
+
+            impl<T, const N: usize> GroupAction<T> for ComponentAction<T, N>
+                where T: Components<N>
+            {
+                fn identity() -> Self {
+                    ComponentAction {
+                        vector: VectorAction::identity()
+                    }
+                }
+            }
+
+            impl<T, const N: usize> IntoAction<ComponentAction<T, N>, T> for [SetAction<T::Component>; N]
+                where T: Components<N> + Stateful<Action=ComponentAction<T, N>>
+            {
+                fn into_action(self, _target: &T) -> ComponentAction<T, N> {
+                    ComponentAction {
+                        vector: VectorAction::from_array(self)
+                    }
+                }
+            }
+
+            impl<T, const N: usize> IntoAction<ComponentAction<T, N>, T> for SetAction<T>
+                where T: Components<N> + Stateful<Action=ComponentAction<T, N>>
+            {
+                fn into_action(self, _target: &T) -> ComponentAction<T, N> {
+                    match self {
+                        SetAction::Set(val) => ComponentAction {
+                            vector: VectorAction::from_array(val.components().map(SetAction::Set))
+                        },
+                        SetAction::Identity => ComponentAction::identity()
+                    }
+                }
+            }
+
+            macro_rules! impl_component_stateful {
+                ($t:ident, $n:literal, $($f:ident), *) => {
+                    impl Components<$n> for $t {
+                        type Component = f64;
+
+                        fn components(&self) -> [f64; $n] {
+                            [$(self.$f), *]
+                        }
+
+                        fn from_components(components: [f64; $n]) -> Self {
+                            let [$($f), *] = components;
+                            $t { $($f), * }
+                        }
+                    }
+
+                    impl Stateful for $t {
+                        type Action = ComponentAction<$t, $n>;
+                        type HasInnerStores = FalseMarker;
+                    }
+                };
+            }
+
+            impl_component_stateful!(Point, 2, x, y);
+            impl_component_stateful!(Size, 2, w, h);
+            impl_component_stateful!(Rect, 4, x, y, w, h);
+            impl_component_stateful!(Inset, 4, l, r, b, t);
+            impl_component_stateful!(LinearColor, 4, r, g, b, a);
+
+            impl Components<4> for Color {
+                type Component = u8;
+
+                fn components(&self) -> [u8; 4] {
+                    [self.r(), self.g(), self.b(), self.a()]
+                }
+
+                fn from_components([r, g, b, a]: [u8; 4]) -> Self {
+                    Color::rgba(r, g, b, a)
+                }
+            }
+
+            impl Stateful for Color {
+                type Action = ComponentAction<Color, 4>;
+                type HasInnerStores = FalseMarker;
+            }
+        }
+
+        // pseudo action that converts into set action
+        mod numeric_action {
+            use std::ops::{Add, Mul, Sub};
+
+            use crate::state::{IntoAction, SetAction, Stateful};
+
+            /// Not an actual action; merely meant to convert to
+            /// SetAction
+            pub enum NumericAction<T>
+                where T: Stateful + Copy + Add<Output=T> + Mul<Output=T> + Sub<Output=T>
+            {
+                Set(T),
+                Incr(T),
+                Decr(T),
+                Mul(T),
+                Identity
+            }
+
+            impl<T> IntoAction<SetAction<T>, T> for NumericAction<T>
+                where T: Stateful<Action=SetAction<T>> + Copy + Add<Output=T> + Mul<Output=T> + Sub<Output=T> {
                 fn into_action(self, target: &T) -> T::Action {
                     match self {
                         NumericAction::Identity => SetAction::Identity,
@@ -888,11 +1688,21 @@ pub mod capacitor {
         /// Precondition: Must only be called after set_target has been called one or more times
         /// second parameter is whether or not to continue
         fn sample(&mut self, span_time: Duration) -> (Self::Target, bool);
+
+        /// Jumps to the current target and comes to rest there, returning the target.
+        /// This is used in place of sampling when reduced motion is enabled.
+        /// Capacitors that do not represent motion return None (the default)
+        /// and are sampled as usual
+        fn settle(&mut self) -> Option<Self::Target> {
+            None
+        }
     }
 
     // A degenerate capacitor used for ClockSignal
     pub struct IncreasingCapacitor;
 
+    // this is a measure of time rather than motion,
+    // so it never settles
     impl Capacitor for IncreasingCapacitor {
         type Target = f64;
 
@@ -971,6 +1781,13 @@ pub mod capacitor {
                 (T::lerp(inner.from, alpha, inner.target), true)
             }
         }
+
+        fn settle(&mut self) -> Option<Self::Target> {
+            let inner = self.inner.as_mut().unwrap();
+            inner.from = inner.target;
+            inner.start_time = -self.time;
+            Some(inner.target)
+        }
     }
 
     struct ConstantSpeedInner<T>
@@ -1052,6 +1869,14 @@ pub mod capacitor {
                 (T::lerp(inner.from, alpha, inner.target), true)
             }
         }
+
+        fn settle(&mut self) -> Option<Self::Target> {
+            let inner = self.inner.as_mut().unwrap();
+            inner.from = inner.target;
+            inner.start_time = -2.0;
+            inner.end_time = -1.0;
+            Some(inner.target)
+        }
     }
 
     pub struct SmoothCapacitor<T, F>
@@ -1121,7 +1946,7 @@ pub mod capacitor {
             let mut val = self.points[0].1;
             for i in 0 .. self.points.len() - 1 {
                 let diff = self.points[i + 1].1 - self.points[i].1;
-                let alpha = (self.ease_function)((time - self.points[i + 1].0) / self.trans_time).min(1.0);
+                let alpha = (self.ease_function)(((time - self.points[i + 1].0) / self.trans_time).min(1.0));
 
                 val = T::lerp(val, alpha, val + diff)
             }
@@ -1135,1589 +1960,4254 @@ pub mod capacitor {
             }
             (val, cont)
         }
+
+        fn settle(&mut self) -> Option<Self::Target> {
+            let target = self.points.back().unwrap().1;
+            self.points.clear();
+            self.points.push_back((-self.trans_time, target));
+            Some(target)
+        }
     }
-}
 
-mod store {
-    use std::cell::Cell;
-    use std::sync::atomic::{AtomicUsize, Ordering};
+    // position and velocity at the moment of the last retarget
+    // physical capacitors continue from these so that interrupted
+    // animations do not jerk
+    struct Trajectory<T> {
+        start_time: f64,
+        from: T,
+        velocity: T,
+        target: T,
+    }
 
-    pub use derived_store::*;
-    pub use general_binding::*;
-    pub use shared_store_container::*;
-    pub use store::*;
-    pub use token_store::*;
+    // there is no zero for general T, so derive it
+    #[allow(clippy::eq_op)]
+    fn zero<T>(val: T) -> T where T: Sub<Output=T> + Copy {
+        val - val
+    }
 
-    use crate::core::Slock;
-    use crate::state::listener::{GeneralListener, InverseListener};
-    use crate::state::{IntoAction, Signal, StateFilter, Stateful, UndoBarrier};
-    use crate::util::marker::ThreadMarker;
-    use crate::view::undo_manager::UndoBucket;
+    fn scale<T>(val: T, factor: f64) -> T where T: Lerp + Sub<Output=T> + Copy {
+        T::lerp(zero(val), factor, val)
+    }
 
-    static UNDO_BUCKET_COUNTER: AtomicUsize = AtomicUsize::new(1);
+    fn retarget<T>(
+        trajectory: &mut Option<Trajectory<T>>,
+        target: T,
+        span_time: Option<Duration>,
+        evaluate: impl Fn(&Trajectory<T>, f64) -> (T, T)
+    ) where T: Sub<Output=T> + Copy {
+        if let Some(ref mut curr) = trajectory {
+            if let Some(time) = span_time {
+                let time = time.as_secs_f64();
+                let (position, velocity) = evaluate(curr, time - curr.start_time);
+                *curr = Trajectory {
+                    start_time: time,
+                    from: position,
+                    velocity,
+                    target,
+                };
+            }
+            else {
+                // not currently active, so we are resting at the old target
+                *curr = Trajectory {
+                    start_time: 0.0,
+                    from: curr.target,
+                    velocity: zero(target),
+                    target,
+                };
+            }
+        }
+        else {
+            // initially at rest
+            *trajectory = Some(Trajectory {
+                start_time: 0.0,
+                from: target,
+                velocity: zero(target),
+                target,
+            });
+        }
+    }
 
-    thread_local! {
-        static EXECUTING_INVERSE: Cell<bool> = Cell::new(false);
+    // at rest on the target, finished as of start_time
+    fn rest<T>(trajectory: &mut Option<Trajectory<T>>, start_time: f64) -> T where T: Sub<Output=T> + Copy {
+        let trajectory = trajectory.as_mut().unwrap();
+        *trajectory = Trajectory {
+            start_time,
+            from: trajectory.target,
+            velocity: zero(trajectory.target),
+            target: trajectory.target,
+        };
+        trajectory.target
     }
 
-    /// It is the implementors job to guarantee that subtree_listener
-    /// and relatives do not get into call cycles
-    pub trait StoreContainer: Send + Sync + Sized + 'static {
-        fn subtree_general_listener<F: GeneralListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>);
+    /// Damped harmonic oscillator pulling the value towards the target.
+    /// The motion is solved analytically (rather than integrated)
+    /// so the result only depends on the sample times
+    pub struct SpringCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        stiffness: f64,
+        damping: f64,
+        mass: f64,
+        rest_threshold: f64,
+        trajectory: Option<Trajectory<T>>,
+    }
 
-        fn subtree_inverse_listener<F: InverseListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>);
+    impl<T> SpringCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        pub fn new(stiffness: f64, damping: f64, mass: f64) -> Self {
+            assert!(stiffness > 0.0, "stiffness must be positive");
+            assert!(damping >= 0.0, "damping must be non negative");
+            assert!(mass > 0.0, "mass must be positive");
 
-        fn subtree_undo_bucket(&self, bucket: UndoBucket, s: Slock<impl ThreadMarker>);
+            SpringCapacitor {
+                stiffness,
+                damping,
+                mass,
+                rest_threshold: 1e-3,
+                trajectory: None,
+            }
+        }
 
-        fn group_undos(&self, s: Slock<impl ThreadMarker>) {
-            let random_tag = UNDO_BUCKET_COUNTER.fetch_add(1, Ordering::SeqCst);
-            self.subtree_undo_bucket(UndoBucket::new(random_tag), s);
+        /// Spring that approaches the target as fast as
+        /// possible without overshooting
+        pub fn critically_damped(stiffness: f64) -> Self {
+            SpringCapacitor::new(stiffness, 2.0 * stiffness.sqrt(), 1.0)
         }
-    }
 
-    // FIXME at some point, this and RawStore[SharedOwner] should
-    // have F as associated type rather than input parameter
-    pub trait Filterable<S: Stateful> {
-        fn action_filter<G>(&self, filter: G, s: Slock<impl ThreadMarker>)
-            where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static;
-    }
+        /// Once both the distance to the target and the speed
+        /// are below the threshold, the spring snaps to the target
+        pub fn rest_threshold(mut self, threshold: f64) -> Self {
+            self.rest_threshold = threshold;
+            self
+        }
 
+        // displacement(t) = a(t) * d0 + b(t) * v0,
+        // velocity(t) = a'(t) * d0 + b'(t) * v0
+        fn coefficients(&self, t: f64) -> (f64, f64, f64, f64) {
+            let omega = (self.stiffness / self.mass).sqrt();
+            let zeta = self.damping / (2.0 * (self.stiffness * self.mass).sqrt());
 
-    // Like with signal, I believe it makes more sense for
-    pub trait Binding<F>: Signal<Target=F::Target> + Sized + Send + 'static where F: StateFilter {
-        fn address(&self) -> usize;
-        fn ptr_eq(&self, other: &Self) -> bool;
+            if (zeta - 1.0).abs() < 1e-6 {
+                let decay = (-omega * t).exp();
+                (
+                    decay * (1.0 + omega * t),
+                    t * decay,
+                    -omega * omega * t * decay,
+                    decay * (1.0 - omega * t),
+                )
+            }
+            else if zeta < 1.0 {
+                let alpha = zeta * omega;
+                let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+                let decay = (-alpha * t).exp();
+                let (sin, cos) = (omega_d * t).sin_cos();
+                (
+                    decay * (cos + alpha / omega_d * sin),
+                    decay * sin / omega_d,
+                    -decay * omega * omega / omega_d * sin,
+                    decay * (cos - alpha / omega_d * sin),
+                )
+            }
+            else {
+                let root = omega * (zeta * zeta - 1.0).sqrt();
+                let (r1, r2) = (-zeta * omega + root, -zeta * omega - root);
+                let (e1, e2) = ((r1 * t).exp(), (r2 * t).exp());
+                let diff = r1 - r2;
+                (
+                    (r1 * e2 - r2 * e1) / diff,
+                    (e1 - e2) / diff,
+                    r1 * r2 * (e2 - e1) / diff,
+                    (r1 * e1 - r2 * e2) / diff,
+                )
+            }
+        }
 
-        fn is_applying(&self, s: Slock<impl ThreadMarker>) -> bool;
-        fn is_borrowed(&self, s: Slock<impl ThreadMarker>) -> bool;
+        fn evaluate(&self, trajectory: &Trajectory<T>, t: f64) -> (T, T) {
+            let (a, b, da, db) = self.coefficients(t);
+            let displacement = trajectory.from - trajectory.target;
+            (
+                trajectory.target + scale(displacement, a) + scale(trajectory.velocity, b),
+                scale(displacement, da) + scale(trajectory.velocity, db)
+            )
+        }
+    }
 
-        fn apply(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>);
-        fn apply_coupled(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>) {
-            if !self.is_applying(s) {
-                self.apply(action, s);
-            }
+    impl<T> Capacitor for SpringCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        type Target = T;
+
+        fn target_set(&mut self, target: &Self::Target, span_time: Option<Duration>) {
+            let mut trajectory = self.trajectory.take();
+            retarget(&mut trajectory, *target, span_time, |t, time| self.evaluate(t, time));
+            self.trajectory = trajectory;
         }
 
-        fn undo_barrier(&self, undo_barrier_type: UndoBarrier, s: Slock<impl ThreadMarker>);
+        fn sample(&mut self, span_time: Duration) -> (Self::Target, bool) {
+            let trajectory = self.trajectory.as_ref().unwrap();
 
-        fn action_listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>)
-            where G: Send + FnMut(&F::Target, &<<F as StateFilter>::Target as Stateful>::Action, Slock) -> bool + 'static;
+            let (position, velocity) = self.evaluate(trajectory, span_time.as_secs_f64() - trajectory.start_time);
+            if (position - trajectory.target).norm() < self.rest_threshold && velocity.norm() < self.rest_threshold {
+                (trajectory.target, false)
+            }
+            else {
+                (position, true)
+            }
+        }
 
-        type WeakBinding: WeakBinding<F>;
-        fn downgrade(&self) -> Self::WeakBinding;
+        fn settle(&mut self) -> Option<Self::Target> {
+            Some(rest(&mut self.trajectory, 0.0))
+        }
     }
 
-    pub trait WeakBinding<F>: Clone where F: StateFilter {
-        type Binding: Binding<F>;
-        fn upgrade(&self) -> Option<Self::Binding>;
+    /// Inertial motion: any velocity the value has when the target
+    /// changes is bled off by friction (with the given time constant)
+    /// while the value glides into the target
+    pub struct DecayCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        time_constant: f64,
+        rest_threshold: f64,
+        trajectory: Option<Trajectory<T>>,
     }
 
-    pub trait Bindable<F> where F: StateFilter {
-        type Binding: Binding<F> + Clone;
-        type WeakBinding: WeakBinding<F>;
+    impl<T> DecayCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        pub fn new(time_constant: f64) -> Self {
+            assert!(time_constant > 1e-3, "Time constant too small");
 
-        fn binding(&self) -> Self::Binding;
-        fn weak_binding(&self) -> Self::WeakBinding;
-    }
+            DecayCapacitor {
+                time_constant,
+                rest_threshold: 1e-3,
+                trajectory: None,
+            }
+        }
 
-    mod shared_store_container {
-        use std::ops::Deref;
-        use std::sync::Arc;
+        /// Once both the distance to the target and the speed
+        /// are below the threshold, the value snaps to the target
+        pub fn rest_threshold(mut self, threshold: f64) -> Self {
+            self.rest_threshold = threshold;
+            self
+        }
 
-        use crate::core::Slock;
-        use crate::state::{GeneralListener, InverseListener, StoreContainer};
-        use crate::util::marker::ThreadMarker;
-        use crate::view::undo_manager::UndoBucket;
+        // coasting with v0 covers v0 * tau in total,
+        // the remainder is eased in with zero initial velocity
+        fn evaluate(&self, trajectory: &Trajectory<T>, t: f64) -> (T, T) {
+            let tau = self.time_constant;
+            let decay = (-t / tau).exp();
 
-        pub struct StoreContainerSource<S> where S: StoreContainer {
-            source: Arc<S>
+            let coast = scale(trajectory.velocity, tau);
+            let remaining = trajectory.target - trajectory.from - coast;
+            (
+                trajectory.from + scale(coast, 1.0 - decay) + scale(remaining, 1.0 - (1.0 + t / tau) * decay),
+                scale(trajectory.velocity, decay) + scale(remaining, t / (tau * tau) * decay)
+            )
         }
+    }
 
-        impl<S> StoreContainerSource<S> where S: StoreContainer {
-            pub fn new(source: S) -> Self {
-                Self {
-                    source: Arc::new(source),
-                }
-            }
+    impl<T> Capacitor for DecayCapacitor<T>
+        where T: Stateful + Lerp + Norm + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        type Target = T;
 
-            pub fn view(&self) -> StoreContainerView<S> {
-                StoreContainerView {
-                    source: self.source.clone()
-                }
-            }
+        fn target_set(&mut self, target: &Self::Target, span_time: Option<Duration>) {
+            let mut trajectory = self.trajectory.take();
+            retarget(&mut trajectory, *target, span_time, |t, time| self.evaluate(t, time));
+            self.trajectory = trajectory;
         }
 
-        impl<S> StoreContainer for StoreContainerSource<S> where S: StoreContainer {
-            fn subtree_general_listener<F: GeneralListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>) {
-                self.source.subtree_general_listener(f, s);
-            }
+        fn sample(&mut self, span_time: Duration) -> (Self::Target, bool) {
+            let trajectory = self.trajectory.as_ref().unwrap();
 
-            fn subtree_inverse_listener<F: InverseListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>) {
-                self.source.subtree_inverse_listener(f, s);
+            let (position, velocity) = self.evaluate(trajectory, span_time.as_secs_f64() - trajectory.start_time);
+            if (position - trajectory.target).norm() < self.rest_threshold && velocity.norm() < self.rest_threshold {
+                (trajectory.target, false)
             }
-
-            fn subtree_undo_bucket(&self, bucket: UndoBucket, s: Slock<impl ThreadMarker>) {
-                self.source.subtree_undo_bucket(bucket, s);
+            else {
+                (position, true)
             }
         }
 
-        impl<S> Deref for StoreContainerSource<S> where S: StoreContainer {
-            type Target = S;
+        fn settle(&mut self) -> Option<Self::Target> {
+            Some(rest(&mut self.trajectory, 0.0))
+        }
+    }
 
-            fn deref(&self) -> &Self::Target {
-                self.source.deref()
+    /// Eases towards the target over a fixed time, using a
+    /// CSS style `cubic-bezier(x1, y1, x2, y2)` timing function.
+    /// When retargeted mid animation, the current velocity is blended
+    /// out over the course of the new transition
+    pub struct CubicBezierCapacitor<T>
+        where T: Stateful + Lerp + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        control: [f64; 4],
+        time: f64,
+        trajectory: Option<Trajectory<T>>,
+    }
+
+    impl<T> CubicBezierCapacitor<T>
+        where T: Stateful + Lerp + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        pub fn new(x1: f64, y1: f64, x2: f64, y2: f64, time: f64) -> Self {
+            assert!((0.0..=1.0).contains(&x1) && (0.0..=1.0).contains(&x2), "x control points must be in [0, 1]");
+            assert!(time > 1e-3, "Time too small");
+
+            CubicBezierCapacitor {
+                control: [x1, y1, x2, y2],
+                time,
+                trajectory: None,
             }
         }
 
-        pub struct StoreContainerView<S> where S: StoreContainer {
-            source: Arc<S>
+        pub fn ease(time: f64) -> Self {
+            CubicBezierCapacitor::new(0.25, 0.1, 0.25, 1.0, time)
         }
 
-        impl<S> Clone for StoreContainerView<S> where S: StoreContainer {
-            fn clone(&self) -> Self {
-                Self {
-                    source: self.source.clone(),
-                }
-            }
+        pub fn ease_in(time: f64) -> Self {
+            CubicBezierCapacitor::new(0.42, 0.0, 1.0, 1.0, time)
         }
 
-        impl<S> Deref for StoreContainerView<S> where S: StoreContainer {
-            type Target = S;
-
-            fn deref(&self) -> &Self::Target {
-                self.source.deref()
-            }
+        pub fn ease_out(time: f64) -> Self {
+            CubicBezierCapacitor::new(0.0, 0.0, 0.58, 1.0, time)
         }
-    }
-
-    mod raw_store {
-        use std::sync::Arc;
-
-        use crate::core::Slock;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::store::store_dispatcher::StoreDispatcher;
-        use crate::state::{IntoAction, StateFilter, Stateful};
-        use crate::util::marker::ThreadMarker;
-
-        pub(super) trait RawStore<F: StateFilter>: Sized + Send + 'static {
-            type InverseListenerHolder: super::inverse_listener_holder::InverseListenerHolder;
 
-            fn dispatcher(&self) -> &StoreDispatcher<F::Target, F, Self::InverseListenerHolder>;
+        pub fn ease_in_out(time: f64) -> Self {
+            CubicBezierCapacitor::new(0.42, 0.0, 0.58, 1.0, time)
+        }
 
-            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<F::Target, F, Self::InverseListenerHolder>;
+        fn bezier(p1: f64, p2: f64, u: f64) -> f64 {
+            let v = 1.0 - u;
+            3.0 * v * v * u * p1 + 3.0 * v * u * u * p2 + u * u * u
+        }
 
-            // may introduce some additional behavior that the dispatcher does not handle
-            fn apply(inner: &Arc<SlockCell<Self>>, action: impl IntoAction<<F::Target as Stateful>::Action, F::Target>, is_inverted_action: bool, s: Slock<impl ThreadMarker>);
+        fn bezier_derivative(p1: f64, p2: f64, u: f64) -> f64 {
+            let v = 1.0 - u;
+            3.0 * v * v * p1 + 6.0 * v * u * (p2 - p1) + 3.0 * u * u * (1.0 - p2)
+        }
 
-            // Must be careful with these two methods
-            // since generally not called with the state lock
-            fn strong_count_decrement(_this: &Arc<SlockCell<Self>>) {
+        // progress at the given fraction of the duration
+        fn ease_value(&self, alpha: f64) -> f64 {
+            let [x1, y1, x2, y2] = self.control;
+            let alpha = alpha.clamp(0.0, 1.0);
 
+            // newton's method, falling back to bisection
+            // x is monotone since the x control points are within [0, 1]
+            let mut u = alpha;
+            let mut solved = false;
+            for _ in 0..8 {
+                let err = Self::bezier(x1, x2, u) - alpha;
+                if err.abs() < 1e-9 {
+                    solved = true;
+                    break;
+                }
+                let slope = Self::bezier_derivative(x1, x2, u);
+                if slope.abs() < 1e-9 {
+                    break;
+                }
+                u -= err / slope;
             }
 
-            fn strong_count_increment(_this: &Arc<SlockCell<Self>>) {
-
+            if !solved || !(0.0..=1.0).contains(&u) {
+                let (mut lo, mut hi) = (0.0, 1.0);
+                u = alpha;
+                for _ in 0..64 {
+                    if Self::bezier(x1, x2, u) < alpha {
+                        lo = u;
+                    }
+                    else {
+                        hi = u;
+                    }
+                    u = (lo + hi) / 2.0;
+                }
             }
+
+            Self::bezier(y1, y2, u)
         }
-    }
 
-    pub(super) mod raw_store_shared_owner {
-        use std::marker::PhantomData;
-        use std::sync::Arc;
+        // derivative of the progress with respect to alpha
+        fn ease_slope(&self, alpha: f64) -> f64 {
+            const H: f64 = 1e-5;
+            let lo = (alpha - H).max(0.0);
+            let hi = (alpha + H).min(1.0);
+            (self.ease_value(hi) - self.ease_value(lo)) / (hi - lo)
+        }
 
-        use crate::core::Slock;
-        use crate::state::listener::StateListener;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::store::general_binding::GeneralWeakBinding;
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::{Binding, Filter, Filterable, IntoAction, Signal, StateFilter, Stateful, UndoBarrier};
-        use crate::util::marker::ThreadMarker;
+        // the velocity correction decays as t(1 - t/T)^2, which has unit initial slope
+        // and vanishes (along with its slope) at the end of the transition
+        fn evaluate(&self, trajectory: &Trajectory<T>, t: f64) -> (T, T) {
+            let alpha = (t / self.time).clamp(0.0, 1.0);
+            let delta = trajectory.target - trajectory.from;
+            let correction = trajectory.velocity - scale(delta, self.ease_slope(0.0) / self.time);
 
-        pub(super) trait RawStoreSharedOwner<F: StateFilter> : Signal<Target=F::Target> + Sync {
-            type Inner: RawStore<F>;
+            let falloff = t * (1.0 - alpha) * (1.0 - alpha);
+            let falloff_slope = (1.0 - alpha) * (1.0 - 3.0 * alpha);
+            (
+                trajectory.from + scale(delta, self.ease_value(alpha)) + scale(correction, falloff),
+                scale(delta, self.ease_slope(alpha) / self.time) + scale(correction, falloff_slope)
+            )
+        }
+    }
 
-            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self;
+    impl<T> Capacitor for CubicBezierCapacitor<T>
+        where T: Stateful + Lerp + Add<Output=T> + Sub<Output=T> + Copy
+    {
+        type Target = T;
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>>;
+        fn target_set(&mut self, target: &Self::Target, span_time: Option<Duration>) {
+            let mut trajectory = self.trajectory.take();
+            let initial = trajectory.is_none();
+            retarget(&mut trajectory, *target, span_time, |t, time| self.evaluate(t, time));
 
-            // guaranteed to only be used for creating the binding
-            // This does not need to call strong_count_increment
-            // caller is expected to do so
-            fn arc_clone(&self) -> Self;
+            let trajectory_ref = trajectory.as_mut().unwrap();
+            if initial {
+                // mark it as already finished
+                trajectory_ref.start_time = -self.time;
+            }
+            else if span_time.is_none() {
+                // starting from rest follows the curve exactly
+                trajectory_ref.velocity = scale(trajectory_ref.target - trajectory_ref.from, self.ease_slope(0.0) / self.time);
+            }
+            self.trajectory = trajectory;
         }
 
-        impl<I, S: Stateful> Filterable<S> for I where I: RawStoreSharedOwner<Filter<S>> {
+        fn sample(&mut self, span_time: Duration) -> (Self::Target, bool) {
+            let trajectory = self.trajectory.as_ref().unwrap();
 
-            fn action_filter<G>(&self, filter: G, s: Slock<impl ThreadMarker>)
-                where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static {
-                self.inner_ref().borrow_mut(s).dispatcher_mut().action_filter(filter, s);
+            let elapsed = span_time.as_secs_f64() - trajectory.start_time;
+            if elapsed >= self.time {
+                (trajectory.target, false)
+            }
+            else {
+                (self.evaluate(trajectory, elapsed).0, true)
             }
         }
 
-        // Unfortunately can't do this for signal as well
-        // Since FixedSignal 'might' implement RawStoreSharedOwnerBase
-        // It's therefore done as macros
-        impl<F, R> Binding<F> for R where F: StateFilter, R: RawStoreSharedOwner<F> {
-            fn address(&self) -> usize {
-                Arc::as_ptr(&self.inner_ref()) as usize
-            }
+        fn settle(&mut self) -> Option<Self::Target> {
+            Some(rest(&mut self.trajectory, -self.time))
+        }
+    }
+}
 
-            fn ptr_eq(&self, other: &Self) -> bool {
-                Arc::ptr_eq(self.inner_ref(), other.inner_ref())
-            }
+pub mod timeline {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Weak};
 
-            fn is_applying(&self, s: Slock<impl ThreadMarker>) -> bool {
-                self.inner_ref().is_borrowed_mut(s)
-            }
+    use crate::core::{clock_signal, Slock};
+    use crate::state::capacitor::Capacitor;
+    use crate::state::{Bindable, Binding, Filterless, GeneralSignal, SetAction, Signal, Stateful, Store, WithCapacitor};
+    use crate::util::marker::ThreadMarker;
+    use crate::util::numeric::Lerp;
 
-            fn is_borrowed(&self, s: Slock<impl ThreadMarker>) -> bool {
-                self.inner_ref().is_borrowed(s)
-            }
+    type Easing = Box<dyn Fn(f64) -> f64 + Send + Sync>;
 
-            fn apply(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>) {
-                R::Inner::apply(self.inner_ref(), action, false, s)
-            }
+    struct Keyframe<T> {
+        time: f64,
+        value: T,
+        // easing of the segment that ends at this keyframe
+        ease: Easing,
+    }
 
-            fn undo_barrier(&self, undo_barrier_type: UndoBarrier, s: Slock<impl ThreadMarker>) {
-                self.inner_ref().borrow_mut(s)
-                    .dispatcher_mut()
-                    .undo_barrier(undo_barrier_type, s.to_general_slock());
-            }
+    /// A keyframed animation of a single value.
+    /// Before the first keyframe and after the last keyframe,
+    /// the value is held constant
+    pub struct Track<T> where T: Stateful + Lerp + Clone {
+        keyframes: Vec<Keyframe<T>>,
+    }
 
-            fn action_listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>) where G: Send + FnMut(&F::Target, &<<F as StateFilter>::Target as Stateful>::Action, Slock) -> bool + 'static {
-                self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::ActionListener(Box::new(listener)));
+    impl<T> Track<T> where T: Stateful + Lerp + Clone {
+        /// Starts the track with `initial` at time 0
+        pub fn new(initial: T) -> Self {
+            Track {
+                keyframes: vec![Keyframe {
+                    time: 0.0,
+                    value: initial,
+                    ease: Box::new(|t| t),
+                }]
             }
-            type WeakBinding = GeneralWeakBinding<F, R>;
+        }
 
-            fn downgrade(&self) -> Self::WeakBinding {
-                GeneralWeakBinding {
-                    weak: Arc::downgrade(self.inner_ref()),
-                    phantom: PhantomData
-                }
-            }
+        /// Linearly interpolates towards `value` at `time` seconds
+        pub fn keyframe(self, time: f64, value: T) -> Self {
+            self.keyframe_eased(time, value, |t| t)
         }
-    }
 
-    /* MARK: utilities */
-    mod action_inverter {
-        use std::marker::PhantomData;
-        use std::sync::Weak;
+        /// Interpolates towards `value` at `time` seconds, where `ease` maps
+        /// the progress in the segment (from 0 to 1) to the interpolation factor
+        pub fn keyframe_eased(mut self, time: f64, value: T, ease: impl Fn(f64) -> f64 + Send + Sync + 'static) -> Self {
+            assert!(time >= 0.0, "keyframes must have non negative time");
 
-        use crate::core::MSlock;
-        use crate::state::listener::DirectlyInvertible;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::{StateFilter, Stateful};
+            // keyframes at the same time are kept in insertion order
+            let index = self.keyframes.partition_point(|k| k.time <= time);
+            self.keyframes.insert(index, Keyframe {
+                time,
+                value,
+                ease: Box::new(ease),
+            });
+            self
+        }
 
-        pub(super) struct ActionInverter<F: StateFilter, I> where I: RawStore<F> {
-            action: Option<<F::Target as Stateful>::Action>,
-            state: Weak<SlockCell<I>>,
-            phantom: PhantomData<F>
+        /// Time of the last keyframe
+        pub fn duration(&self) -> f64 {
+            self.keyframes.last().unwrap().time
         }
 
-        impl<F, I> ActionInverter<F, I> where F: StateFilter, I: RawStore<F> {
-            pub(super) fn new(action: <F::Target as Stateful>::Action, weak: Weak<SlockCell<I>>) -> Self {
-                ActionInverter {
-                    action: Some(action),
-                    state: weak,
-                    phantom: PhantomData,
-                }
+        pub fn value_at(&self, time: f64) -> T {
+            let next = self.keyframes.partition_point(|k| k.time <= time);
+            if next == 0 {
+                return self.keyframes[0].value.clone();
+            }
+            else if next == self.keyframes.len() {
+                return self.keyframes[next - 1].value.clone();
             }
+
+            let (prev, next) = (&self.keyframes[next - 1], &self.keyframes[next]);
+            let alpha = (time - prev.time) / (next.time - prev.time);
+            T::lerp(prev.value.clone(), (next.ease)(alpha), next.value.clone())
         }
+    }
 
-        impl<F, I> DirectlyInvertible for ActionInverter<F, I> where F: StateFilter, I: RawStore<F> {
-            fn invert(&mut self, s: MSlock) {
-                let Some(state) = self.state.upgrade() else {
-                    return;
+    struct TimelineInner {
+        position: Store<f64>,
+        duration: Store<f64>,
+        playing: Store<bool>,
+        looping: Store<bool>,
+        rate: Store<f64>,
+        // invalidates clocks of previous play sessions
+        clock_generation: AtomicUsize,
+        // invalidates animated seeks once the playhead is moved otherwise
+        seek_generation: AtomicUsize,
+    }
+
+    /// Sequences keyframed tracks along a shared position (in seconds).
+    /// While playing, the position is advanced by a clock signal
+    /// according to the playback rate (which may be negative).
+    /// The position is an ordinary store, so it may be scrubbed
+    /// by binding it to a slider, or seeked by setting it directly.
+    ///
+    /// Playback is driven by [`clock_signal`], which is itself a
+    /// [`CapacitatedSignal`](crate::state::CapacitatedSignal), and
+    /// [`seek_animated`](Timeline::seek_animated) moves the playhead
+    /// through an arbitrary capacitor. Track values are a pure function of the
+    /// position rather than capacitated signals, so that scrubbing and
+    /// reversing are exact; the signals returned by [`insert`](Timeline::insert)
+    /// may still be given a capacitor with `with_capacitor` to smooth out seeks.
+    #[derive(Clone)]
+    pub struct Timeline {
+        inner: Arc<TimelineInner>
+    }
+
+    impl Timeline {
+        pub fn new(s: Slock<impl ThreadMarker>) -> Self {
+            Timeline::with_position(Store::new(0.0), s)
+        }
+
+        /// Uses `position` as the playhead of the timeline
+        pub fn with_position(position: Store<f64>, s: Slock<impl ThreadMarker>) -> Self {
+            let inner = Arc::new(TimelineInner {
+                position,
+                duration: Store::new(0.0),
+                playing: Store::new(false),
+                looping: Store::new(false),
+                rate: Store::new(1.0),
+                clock_generation: AtomicUsize::new(0),
+                seek_generation: AtomicUsize::new(0),
+            });
+
+            let weak = Arc::downgrade(&inner);
+            inner.playing.listen(move |playing, s| {
+                let Some(inner) = weak.upgrade() else {
+                    return false;
                 };
 
-                I::apply(&state, self.action.take().unwrap(), true, s);
-            }
+                if *playing {
+                    Timeline::start_clock(&inner, weak.clone(), s);
+                }
+                true
+            }, s);
 
-            unsafe fn right_multiply(&mut self, mut by: Box<dyn DirectlyInvertible>, s: MSlock) {
-                /* we are free to assume by is of type Self, allowing us to do this conversion */
-                let ptr = by.action_pointer(s) as *const <F::Target as Stateful>::Action;
-                self.action = Some(self.action.take().unwrap() * std::ptr::read(ptr));
-                /* we have implicitly moved the other's action, now we must tell it to forget to
-                   avoid double free
-                 */
-                by.forget_action(s);
+            Timeline {
+                inner
             }
+        }
 
-            unsafe fn action_pointer(&self, _s: MSlock) -> *const () {
-                self.action.as_ref().unwrap() as *const <F::Target as Stateful>::Action as *const ()
-            }
+        fn start_clock(inner: &TimelineInner, weak: Weak<TimelineInner>, s: Slock) {
+            let generation = inner.clock_generation.fetch_add(1, Ordering::SeqCst) + 1;
+            inner.seek_generation.fetch_add(1, Ordering::SeqCst);
 
-            unsafe fn forget_action(&mut self, _s: MSlock) {
-                std::mem::forget(self.action.take());
+            // rewind if starting from the end
+            let position = *inner.position.borrow(s);
+            let duration = *inner.duration.borrow(s);
+            let rate = *inner.rate.borrow(s);
+            if rate > 0.0 && position >= duration {
+                inner.position.apply(SetAction::Set(0.0), s);
             }
-
-            fn id(&self) -> usize {
-                self.state.as_ptr() as usize
+            else if rate < 0.0 && position <= 0.0 {
+                inner.position.apply(SetAction::Set(duration), s);
             }
-        }
-    }
 
-    mod state_ref {
-        use std::cell::Ref;
-        use std::marker::PhantomData;
-        use std::ops::Deref;
+            let mut last = 0.0;
+            clock_signal(s).listen(move |&now, s| {
+                let Some(inner) = weak.upgrade() else {
+                    return false;
+                };
 
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::StateFilter;
+                if inner.clock_generation.load(Ordering::SeqCst) != generation || !*inner.playing.borrow(s) {
+                    return false;
+                }
 
-        pub(super) struct StateRef<'a, F, I> where F: StateFilter, I: RawStore<F> {
-            pub(super) main_ref: Ref<'a, I>,
-            pub(super) phantom: PhantomData<F>
+                let delta = (now - last) * *inner.rate.borrow(s);
+                last = now;
+
+                let duration = *inner.duration.borrow(s);
+                let mut next = *inner.position.borrow(s) + delta;
+                if *inner.looping.borrow(s) && duration > 0.0 {
+                    next = next.rem_euclid(duration);
+                }
+                else if (delta > 0.0 && next >= duration) || (delta < 0.0 && next <= 0.0) {
+                    next = next.clamp(0.0, duration);
+                    inner.playing.apply(SetAction::Set(false), s);
+                }
+
+                inner.position.apply(SetAction::Set(next), s);
+                true
+            }, s);
         }
 
-        impl<'a, F, I> Deref for StateRef<'a, F, I>
-            where F: StateFilter, I: RawStore<F> {
-            type Target = F::Target;
-            fn deref(&self) -> &F::Target {
-                self.main_ref.dispatcher().data()
+        /// Adds `track` starting at `offset` seconds into the timeline.
+        /// Tracks may freely overlap.
+        /// Returns the value of the track at the current position
+        pub fn insert<T>(&self, offset: f64, track: Track<T>, s: Slock<impl ThreadMarker>) -> GeneralSignal<T>
+            where T: Stateful + Lerp + Clone
+        {
+            let end = offset + track.duration();
+            if end > *self.inner.duration.borrow(s) {
+                self.inner.duration.apply(SetAction::Set(end), s);
             }
+
+            self.inner.position.map(move |position| track.value_at(position - offset), s)
         }
-    }
 
-    mod inverse_listener_holder {
-        use crate::core::Slock;
-        use crate::state::{DirectlyInvertible, InverseListener, UndoBarrier};
-        use crate::view::undo_manager::UndoBucket;
+        /// Adds `track` after all current tracks have finished
+        pub fn append<T>(&self, track: Track<T>, s: Slock<impl ThreadMarker>) -> GeneralSignal<T>
+            where T: Stateful + Lerp + Clone
+        {
+            let offset = *self.inner.duration.borrow(s);
+            self.insert(offset, track, s)
+        }
 
-        pub(super) trait InverseListenerHolder {
-            fn new() -> Self;
-            fn set_listener(&mut self, listener: Box<dyn InverseListener>);
+        /// Adds the tracks so that the ith track starts at `offset + i * interval`
+        pub fn stagger<T>(&self, offset: f64, interval: f64, tracks: impl IntoIterator<Item=Track<T>>, s: Slock<impl ThreadMarker>) -> Vec<GeneralSignal<T>>
+            where T: Stateful + Lerp + Clone
+        {
+            tracks.into_iter()
+                .enumerate()
+                .map(|(i, track)| self.insert(offset + i as f64 * interval, track, s))
+                .collect()
+        }
 
-            fn set_undo_bucket(&mut self, ub: UndoBucket);
+        pub fn play(&self, s: Slock<impl ThreadMarker>) {
+            self.inner.playing.apply(SetAction::Set(true), s);
+        }
 
-            fn invoke_listener(&mut self, action: impl FnOnce() -> Box<dyn DirectlyInvertible>, s: Slock);
+        pub fn pause(&self, s: Slock<impl ThreadMarker>) {
+            self.inner.playing.apply(SetAction::Set(false), s);
+        }
 
-            fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock);
+        pub fn seek(&self, position: f64, s: Slock<impl ThreadMarker>) {
+            self.inner.seek_generation.fetch_add(1, Ordering::SeqCst);
+            self.inner.position.apply(SetAction::Set(position), s);
         }
 
-        pub(super) struct NullInverseListenerHolder;
+        /// Pauses playback and moves the playhead to `position`
+        /// as driven by `capacitor` (e.g. a `SpringCapacitor`), rather than jumping there.
+        /// A later seek or play interrupts the animation
+        pub fn seek_animated<C>(&self, position: f64, capacitor: C, s: Slock<impl ThreadMarker>)
+            where C: Capacitor<Target=f64>
+        {
+            self.pause(s);
+            let generation = self.inner.seek_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+            let target = Store::new(*self.inner.position.borrow(s));
+            let weak = Arc::downgrade(&self.inner);
+            target.with_capacitor(capacitor, s).listen(move |&at, s| {
+                let Some(inner) = weak.upgrade() else {
+                    return false;
+                };
 
-        impl InverseListenerHolder for NullInverseListenerHolder {
-            fn new() -> Self {
-                NullInverseListenerHolder
-            }
+                if inner.seek_generation.load(Ordering::SeqCst) != generation {
+                    return false;
+                }
 
-            fn set_listener(&mut self, _listener: Box<dyn InverseListener>) {
+                inner.position.apply(SetAction::Set(at), s);
+                true
+            }, s);
 
-            }
+            // the capacitor is kept alive by its worker until it settles
+            target.apply(SetAction::Set(position), s);
+        }
 
-            fn set_undo_bucket(&mut self, _ub: UndoBucket) {
+        /// Playhead, in seconds
+        pub fn position(&self) -> impl Binding<Filterless<f64>> + Clone {
+            self.inner.position.binding()
+        }
 
-            }
+        /// End of the last track, in seconds
+        pub fn duration(&self) -> impl Signal<Target=f64> + Clone {
+            self.inner.duration.binding()
+        }
 
-            fn invoke_listener(&mut self, _action: impl FnOnce() -> Box<dyn DirectlyInvertible>, _s: Slock) {
-            }
+        pub fn playing(&self) -> impl Binding<Filterless<bool>> + Clone {
+            self.inner.playing.binding()
+        }
 
-            fn undo_barrier(&mut self, _ubt: UndoBarrier, _s: Slock) {
+        /// If set, playback wraps around rather than
+        /// pausing at the end of the timeline
+        pub fn looping(&self) -> impl Binding<Filterless<bool>> + Clone {
+            self.inner.looping.binding()
+        }
 
-            }
+        /// Playback speed multiplier, negative rates play in reverse
+        pub fn rate(&self) -> impl Binding<Filterless<f64>> + Clone {
+            self.inner.rate.binding()
         }
+    }
+}
 
-        pub(super) struct InverseListenerHolderImpl(Option<Box<dyn InverseListener>>, UndoBucket);
+mod store {
+    use std::cell::Cell;
+    use std::hash::Hash;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
-        impl InverseListenerHolder for InverseListenerHolderImpl {
-            fn new() -> Self {
-                InverseListenerHolderImpl(None, UndoBucket::default())
-            }
+    pub use derived_store::*;
+    pub use general_binding::*;
+    pub use lens_binding::*;
+    pub use shared_store_container::*;
+    pub use store::*;
+    #[cfg(feature = "futures")]
+    pub use stream_binding::*;
+    pub use token_store::*;
+    pub use transaction::*;
+    #[cfg(feature = "serde")]
+    pub use journal::*;
 
-            fn set_listener(&mut self, listener: Box<dyn InverseListener>) {
-                self.0 = Some(listener);
-            }
+    use crate::core::Slock;
+    use crate::state::listener::{GeneralListener, InverseListener};
+    use crate::state::{keyed_diff, removal_runs, IntoAction, SetAction, Signal, StateFilter, Stateful, UndoBarrier, VecActionBasis, Word};
+    use crate::util::marker::ThreadMarker;
+    use crate::view::undo_manager::{history_label, UndoBucket};
 
-            fn set_undo_bucket(&mut self, ub: UndoBucket) {
-                self.1 = ub;
-            }
+    static UNDO_BUCKET_COUNTER: AtomicUsize = AtomicUsize::new(1);
 
-            fn invoke_listener(&mut self, action: impl FnOnce() -> Box<dyn DirectlyInvertible>, s: Slock) {
-                if let Some(ref mut func) = self.0 {
-                    if !func.handle_inverse(action(), self.1, s) {
-                        self.0 = None;
-                    }
-                }
-            }
+    thread_local! {
+        static EXECUTING_INVERSE: Cell<bool> = Cell::new(false);
+    }
 
-            fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock) {
-                if let Some(ref mut func) = self.0 {
-                    func.undo_barrier(ubt, s);
-                }
-            }
+    /// It is the implementors job to guarantee that subtree_listener
+    /// and relatives do not get into call cycles
+    pub trait StoreContainer: Send + Sync + Sized + 'static {
+        fn subtree_general_listener<F: GeneralListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>);
+
+        fn subtree_inverse_listener<F: InverseListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>);
+
+        fn subtree_undo_bucket(&self, bucket: UndoBucket, s: Slock<impl ThreadMarker>);
+
+        /// Places all stores into a fresh bucket, which is returned
+        fn group_undos(&self, s: Slock<impl ThreadMarker>) -> UndoBucket {
+            let bucket = UndoBucket::new(UNDO_BUCKET_COUNTER.fetch_add(1, Ordering::SeqCst));
+            self.subtree_undo_bucket(bucket, s);
+            bucket
         }
     }
 
-    mod store_dispatcher {
-        use crate::core::Slock;
-        use crate::state::listener::StateListener;
-        use crate::state::store::inverse_listener_holder::InverseListenerHolder;
-        use crate::state::{DirectlyInvertible, GeneralListener, GroupBasis, IntoAction, InverseListener, StateFilter, Stateful, UndoBarrier};
-        use crate::util::marker::ThreadMarker;
-        use crate::util::test_util::QuarveAllocTag;
-        use crate::view::undo_manager::UndoBucket;
+    // FIXME at some point, this and RawStore[SharedOwner] should
+    // have F as associated type rather than input parameter
+    pub trait Filterable<S: Stateful> {
+        fn action_filter<G>(&self, filter: G, s: Slock<impl ThreadMarker>)
+            where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static;
+    }
 
-        pub(crate) struct StoreDispatcher<S, F, I>
-            where S: Stateful, F: StateFilter, I: InverseListenerHolder
-        {
-            _quarve_tag: QuarveAllocTag,
-            data: S,
-            listeners: Vec<StateListener<S>>,
-            inverse_listener: I,
-            filter: F,
-        }
 
-        impl<S, F, I> StoreDispatcher<S, F, I>
-            where S: Stateful, F: StateFilter<Target=S>, I: InverseListenerHolder {
+    // Like with signal, I believe it makes more sense for
+    pub trait Binding<F>: Signal<Target=F::Target> + Sized + Send + 'static where F: StateFilter {
+        fn address(&self) -> usize;
+        fn ptr_eq(&self, other: &Self) -> bool;
 
-            #[inline]
-            pub(super) fn new(data: S) -> Self {
-                StoreDispatcher {
-                    _quarve_tag: QuarveAllocTag::new(),
-                    data,
-                    listeners: Vec::new(),
-                    inverse_listener: I::new(),
-                    filter: F::new(),
-                }
-            }
+        fn is_applying(&self, s: Slock<impl ThreadMarker>) -> bool;
+        fn is_borrowed(&self, s: Slock<impl ThreadMarker>) -> bool;
 
-            #[inline]
-            pub fn data(&self) -> &S {
-                &self.data
+        fn apply(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>);
+        fn apply_coupled(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>) {
+            if !self.is_applying(s) {
+                self.apply(action, s);
             }
+        }
 
-            #[inline]
-            pub fn apply_post_filter(
-                &mut self,
-                into_action: impl IntoAction<S::Action, S>,
-                make_inverter: impl FnOnce(S::Action) -> Box<dyn DirectlyInvertible>,
-                post_filter: impl FnOnce(&S, S::Action) -> S::Action,
-                skip_filters: bool,
-                s: Slock<impl ThreadMarker>
-            ) {
-                let transaction = into_action.into_action(&self.data);
-
-                let filtered_action = if skip_filters {
-                    transaction
-                }
-                else {
-                    post_filter(&self.data, self.filter.filter(&self.data, transaction, s))
-                };
+        /// Applies the action, titling the resulting undo (and redo)
+        /// with `label` (e.g. "Rename Layer")
+        fn apply_labeled(&self, label: impl Into<String>, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>) {
+            history_label(label, || self.apply(action, s));
+        }
 
-                // tell action listeners
-                self.listeners.retain_mut(
-                    |listener| match listener {
-                        StateListener::ActionListener(listener) => listener(&self.data, &filtered_action, s.to_general_slock()),
-                        _ => true
-                    }
-                );
+        fn undo_barrier(&self, undo_barrier_type: UndoBarrier, s: Slock<impl ThreadMarker>);
 
-                // apply action
-                let inverse = filtered_action.apply(&mut self.data);
+        fn action_listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>)
+            where G: Send + FnMut(&F::Target, &<<F as StateFilter>::Target as Stateful>::Action, Slock) -> bool + 'static;
 
-                // tell signal and general listeners
-                let data = &self.data;
-                self.listeners.retain_mut(
-                    |listener| match listener {
-                        StateListener::GeneralListener(action) => {
-                            action(s.to_general_slock())
-                        },
-                        StateListener::SignalListener(action) => {
-                            action(data, s.to_general_slock())
-                        },
-                        _ => true
-                    }
-                );
+        type WeakBinding: WeakBinding<F>;
+        fn downgrade(&self) -> Self::WeakBinding;
 
-                // tell inverse listener
-                self.inverse_listener.invoke_listener(move || make_inverter(inverse), s.to_general_slock());
+        /// Creates a binding onto a projection of this binding.
+        /// `getter` computes the projected value whenever the source changes.
+        /// `translator` converts an action on the projected value into
+        /// an action on the source; returning `None` indicates
+        /// the action could not be translated (for instance, a parse failure)
+        /// and nothing is applied.
+        /// `projector` converts an action that was applied to the source
+        /// (from anywhere, after filtering) into an action on the projected value,
+        /// which is what action listeners of the lens receive; returning `None`
+        /// indicates that the projected value is unaffected.
+        /// Actions are applied through this binding, so filters, undo,
+        /// and action listeners of the source continue to work.
+        #[track_caller]
+        fn lens<U, G, A, R, P>(&self, getter: G, translator: A, projector: P, s: Slock<impl ThreadMarker>) -> LensBinding<F, Self, U>
+            where Self: Clone,
+                  U: Stateful,
+                  G: Fn(&F::Target) -> U + Send + 'static,
+                  A: Fn(&F::Target, &U::Action) -> Option<R> + Send + Sync + 'static,
+                  R: IntoAction<<F::Target as Stateful>::Action, F::Target>,
+                  P: Fn(&F::Target, &<F::Target as Stateful>::Action) -> Option<U::Action> + Send + Sync + 'static
+        {
+            LensBinding::new(self.clone(), getter, translator, projector, s)
+        }
+
+        /// Replaces the contents of a vector with `new` using a minimal
+        /// set of edits, so that views and undo history update incrementally.
+        /// Elements are matched by `key_fn`: a current element whose key
+        /// also appears in `new` is kept (and moved if necessary)
+        /// as long as `same_fn(current, new)` is true, in which case the
+        /// element of `new` with that key is dropped. Otherwise, the
+        /// current element is replaced by the new one.
+        /// Nothing is applied if the vector already matches `new`
+        fn apply_diff<T, K, G, E>(&self, new: Vec<T>, key_fn: G, same_fn: E, s: Slock<impl ThreadMarker>)
+            where F: StateFilter<Target=Vec<T>>,
+                  T: StoreContainer,
+                  K: Hash + Eq,
+                  G: Fn(&T, Slock) -> K,
+                  E: Fn(&T, &T, Slock) -> bool
+        {
+            let gs = s.to_general_slock();
+            let edits = {
+                let current = self.borrow(s);
+                let old: Vec<K> = current.iter()
+                    .map(|elem| key_fn(elem, gs))
+                    .collect();
+                let new = new.into_iter()
+                    .map(|elem| (key_fn(&elem, gs), elem))
+                    .collect();
+
+                keyed_diff(&old, new, |i, elem| same_fn(&current[i], elem, gs))
+            };
+            if !edits.is_empty() {
+                self.apply(Word::new(edits.into_iter().rev().collect()), s);
             }
+        }
 
-            pub fn apply(
-                &mut self,
-                into_action: impl IntoAction<S::Action, S>,
-                make_inverter: impl FnOnce(S::Action) -> Box<dyn DirectlyInvertible>,
-                skip_filters: bool,
-                s: Slock<impl ThreadMarker>
-            ) {
-                self.apply_post_filter(into_action, make_inverter, |_, f| f, skip_filters, s);
-            }
+        /// Removes every element for which `f` returns false
+        /// as a single action (runs of removed elements are batched)
+        fn retain<T, G>(&self, mut f: G, s: Slock<impl ThreadMarker>)
+            where F: StateFilter<Target=Vec<T>>,
+                  T: StoreContainer,
+                  G: FnMut(&T, Slock) -> bool
+        {
+            let gs = s.to_general_slock();
+            let keep: Vec<bool> = self.borrow(s).iter()
+                .map(|elem| f(elem, gs))
+                .collect();
 
-            pub fn add_listener(&mut self, listener: StateListener<S>) {
-                debug_assert!(! matches!(listener, StateListener::GeneralListener(_)),
-                              "Should be set via set_general_listener"
-                );
-                self.listeners.push(listener);
+            let removals = removal_runs(&keep);
+            if !removals.is_empty() {
+                self.apply(Word::new(removals.into_iter().rev().collect()), s);
             }
+        }
 
-            pub fn action_filter<G>(&mut self, filter: G, _s: Slock<impl ThreadMarker>)
-                where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static {
-                self.filter.add_filter(filter);
-            }
+        /// Stably sorts the vector by `key_fn` as a single
+        /// permutation, so that existing elements (and their views) are kept
+        fn sort_by_key<T, K, G>(&self, key_fn: G, s: Slock<impl ThreadMarker>)
+            where F: StateFilter<Target=Vec<T>>,
+                  T: StoreContainer,
+                  K: Ord,
+                  G: Fn(&T, Slock) -> K
+        {
+            let gs = s.to_general_slock();
+            let keys: Vec<K> = self.borrow(s).iter()
+                .map(|elem| key_fn(elem, gs))
+                .collect();
 
-            pub fn set_general_listener(&mut self, f: impl GeneralListener + Clone, s: Slock) {
-                self.listeners.retain_mut(|l| !matches!(l, StateListener::GeneralListener(_)));
-                self.listeners.push(StateListener::GeneralListener(Box::new(f.clone())));
+            let mut permutation: Vec<usize> = (0..keys.len()).collect();
+            permutation.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+            if permutation.iter().enumerate().any(|(i, &j)| i != j) {
+                self.apply(VecActionBasis::Permute(permutation), s);
+            }
+        }
 
-                if let Some(action) = self.data.subtree_general_listener(f, s) {
-                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
+        /// Lens between two values that are only ever set as a whole
+        /// `setter` returns `None` if the projected value has no
+        /// corresponding source value (e.g. unparsable text)
+        fn bimap<U, G, H>(&self, getter: G, setter: H, s: Slock<impl ThreadMarker>) -> LensBinding<F, Self, U>
+            where Self: Clone,
+                  F::Target: Stateful<Action=SetAction<F::Target>>,
+                  U: Stateful<Action=SetAction<U>>,
+                  G: Fn(&F::Target) -> U + Send + Sync + 'static,
+                  H: Fn(&U) -> Option<F::Target> + Send + Sync + 'static
+        {
+            let getter = Arc::new(getter);
+            let project = getter.clone();
+            self.lens(move |val| getter(val), move |_, action: &SetAction<U>| {
+                match action {
+                    SetAction::Set(val) => setter(val).map(SetAction::Set),
+                    SetAction::Identity => Some(SetAction::Identity),
                 }
-            }
+            }, move |_, action: &SetAction<F::Target>| {
+                match action {
+                    SetAction::Set(val) => Some(SetAction::Set(project(val))),
+                    SetAction::Identity => None,
+                }
+            }, s)
+        }
+    }
 
-            pub fn set_inverse_listener(&mut self, f: impl InverseListener + Clone, s: Slock) {
-                self.inverse_listener.set_listener(Box::new(f.clone()));
+    pub trait WeakBinding<F>: Clone where F: StateFilter {
+        type Binding: Binding<F>;
+        fn upgrade(&self) -> Option<Self::Binding>;
+    }
 
-                if let Some(action) = self.data.subtree_inverse_listener(f, s) {
-                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
-                }
-            }
+    pub trait Bindable<F> where F: StateFilter {
+        type Binding: Binding<F> + Clone;
+        type WeakBinding: WeakBinding<F>;
 
-            pub fn set_undo_bucket(&mut self, ub: UndoBucket, s: Slock) {
-                self.inverse_listener.set_undo_bucket(ub);
+        fn binding(&self) -> Self::Binding;
+        fn weak_binding(&self) -> Self::WeakBinding;
+    }
 
-                if let Some(action) = self.data.subtree_undo_bucket(ub, s) {
-                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
+    mod shared_store_container {
+        use std::ops::Deref;
+        use std::sync::Arc;
+
+        use crate::core::Slock;
+        use crate::state::{GeneralListener, InverseListener, StoreContainer};
+        use crate::util::marker::ThreadMarker;
+        use crate::view::undo_manager::UndoBucket;
+
+        pub struct StoreContainerSource<S> where S: StoreContainer {
+            source: Arc<S>
+        }
+
+        impl<S> StoreContainerSource<S> where S: StoreContainer {
+            pub fn new(source: S) -> Self {
+                Self {
+                    source: Arc::new(source),
                 }
             }
 
-            pub fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock) {
-                self.inverse_listener.undo_barrier(ubt, s);
+            pub fn view(&self) -> StoreContainerView<S> {
+                StoreContainerView {
+                    source: self.source.clone()
+                }
             }
         }
-    }
 
-    mod macros {
-        macro_rules! impl_store_container_inner {
-            () => {
-                fn subtree_general_listener<Q>(&self, f: Q, s: Slock<impl ThreadMarker>)
-                    where Q: GeneralListener + Clone {
-                    self.inner.borrow_mut(s).dispatcher_mut().set_general_listener(f, s.to_general_slock());
-                }
+        impl<S> StoreContainer for StoreContainerSource<S> where S: StoreContainer {
+            fn subtree_general_listener<F: GeneralListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>) {
+                self.source.subtree_general_listener(f, s);
+            }
 
-                fn subtree_inverse_listener<Q>(&self, f: Q, s: Slock<impl ThreadMarker>)
-                    where Q: InverseListener + Clone {
-                    self.inner.borrow_mut(s).dispatcher_mut().set_inverse_listener(f, s.to_general_slock());
-                }
+            fn subtree_inverse_listener<F: InverseListener + Clone>(&self, f: F, s: Slock<impl ThreadMarker>) {
+                self.source.subtree_inverse_listener(f, s);
+            }
 
-                fn subtree_undo_bucket(&self, ub: UndoBucket, s: Slock<impl ThreadMarker>) {
-                    self.inner.borrow_mut(s).dispatcher_mut().set_undo_bucket(ub, s.to_general_slock());
-                }
+            fn subtree_undo_bucket(&self, bucket: UndoBucket, s: Slock<impl ThreadMarker>) {
+                self.source.subtree_undo_bucket(bucket, s);
             }
         }
 
-        macro_rules! impl_bindable_inner {
-            ($s:ty, $f:ty) => {
-                type Binding = GeneralBinding<$f, Self>;
-                type WeakBinding = GeneralWeakBinding<$f, Self>;
+        impl<S> Deref for StoreContainerSource<S> where S: StoreContainer {
+            type Target = S;
 
-                fn binding(&self) -> Self::Binding {
-                    <Self as RawStoreSharedOwner<$f>>::Inner::strong_count_increment(self.inner_ref());
+            fn deref(&self) -> &Self::Target {
+                self.source.deref()
+            }
+        }
 
-                    GeneralBinding {
-                        inner: self.arc_clone(),
-                        phantom: PhantomData,
-                    }
-                }
+        pub struct StoreContainerView<S> where S: StoreContainer {
+            source: Arc<S>
+        }
 
-                fn weak_binding(&self) -> Self::WeakBinding {
-                    let ret: GeneralWeakBinding<$f, Self> = GeneralWeakBinding {
-                        weak: Arc::downgrade(self.inner_ref()),
-                        phantom: PhantomData
-                    };
-                    ret
+        impl<S> Clone for StoreContainerView<S> where S: StoreContainer {
+            fn clone(&self) -> Self {
+                Self {
+                    source: self.source.clone(),
                 }
-
             }
         }
 
-        macro_rules! impl_adhoc_inner {
-            ($s:ty, $f:ty) => {
-                pub fn signal(&self) -> impl Signal<Target=$s> + Clone {
-                    <Self as RawStoreSharedOwner<$f>>::Inner::strong_count_increment(self.inner_ref());
+        impl<S> Deref for StoreContainerView<S> where S: StoreContainer {
+            type Target = S;
 
-                    GeneralBinding {
-                        inner: self.arc_clone(),
-                        phantom: PhantomData,
-                    }
-                }
+            fn deref(&self) -> &Self::Target {
+                self.source.deref()
             }
         }
-
-        macro_rules! impl_signal_inner {
-            ($s:ty) => {
-                type Target=$s;
-
-                fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=$s> + 'a {
-                    StateRef {
-                        main_ref: self.inner_ref().borrow(s),
-                        phantom: PhantomData
-                    }
-                }
-
-                fn listen<Q>(&self, listener: Q, s: Slock<impl ThreadMarker>)
-                    where Q: FnMut(&$s, Slock) -> bool + Send + 'static {
-                    self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(Box::new(listener)));
-                }
-
-                // non trait method so it's fine to just return impl Signal
-                type MappedOutput<U: Send + 'static> = GeneralSignal<U>;
-                fn map<U, Q>(&self, map: Q, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<U>
-                    where U: Send + 'static, Q: Send + 'static + Fn(&$s) -> U {
-                    GeneralSignal::from(self, self, map, |this, listener, s| {
-                        this.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(listener))
-                    }, s)
-                }
-            };
-        }
-
-        pub(super) use {impl_store_container_inner, impl_signal_inner, impl_bindable_inner, impl_adhoc_inner};
     }
 
-    /* MARK: Stores */
-    mod store {
-        use std::marker::PhantomData;
-        use std::ops::{Deref, DerefMut};
+    mod raw_store {
         use std::sync::Arc;
 
-        use crate::state::listener::{GeneralListener, InverseListener};
+        use crate::core::Slock;
         use crate::state::slock_cell::SlockCell;
-        use crate::state::store::action_inverter::ActionInverter;
-        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
-        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
-        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
-        use crate::state::store::state_ref::StateRef;
         use crate::state::store::store_dispatcher::StoreDispatcher;
-        use crate::state::store::EXECUTING_INVERSE;
-        use crate::state::Filter;
-        use crate::state::{Bindable, StateListener};
+        use crate::state::{IntoAction, StateFilter, Stateful};
         use crate::util::marker::ThreadMarker;
-        use crate::view::undo_manager::UndoBucket;
-        use crate::{
-            core::Slock,
-            state::{Filterless, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer},
-        };
 
-        pub(super) struct InnerStore<S: Stateful, F: StateFilter<Target=S>> {
-            dispatcher: StoreDispatcher<S, F, InverseListenerHolderImpl>
-        }
+        pub(super) trait RawStore<F: StateFilter>: Sized + Send + 'static {
+            type InverseListenerHolder: super::inverse_listener_holder::InverseListenerHolder;
 
-        impl<S, F> RawStore<F> for InnerStore<S, F>
-            where S: Stateful, F: StateFilter<Target=S>
-        {
-            type InverseListenerHolder = InverseListenerHolderImpl;
+            fn dispatcher(&self) -> &StoreDispatcher<F::Target, F, Self::InverseListenerHolder>;
 
-            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &self.dispatcher
-            }
+            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<F::Target, F, Self::InverseListenerHolder>;
 
-            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &mut self.dispatcher
-            }
+            // may introduce some additional behavior that the dispatcher does not handle
+            fn apply(inner: &Arc<SlockCell<Self>>, action: impl IntoAction<<F::Target as Stateful>::Action, F::Target>, is_inverted_action: bool, s: Slock<impl ThreadMarker>);
 
-            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
-                if EXECUTING_INVERSE.get() {
-                    // do not execute while inverse is acting
-                    return;
-                }
-                else if is_inverse {
-                    EXECUTING_INVERSE.set(true);
-                }
+            // Must be careful with these two methods
+            // since generally not called with the state lock
+            fn strong_count_decrement(_this: &Arc<SlockCell<Self>>) {
 
-                let mut borrow = arc.borrow_mut(s);
-                let inner = borrow.deref_mut();
+            }
 
-                inner.dispatcher.apply(alt_action, |action| {
-                    Box::new(ActionInverter::new(action, Arc::downgrade(&arc)))
-                }, is_inverse, s);
+            fn strong_count_increment(_this: &Arc<SlockCell<Self>>) {
 
-                if is_inverse {
-                    EXECUTING_INVERSE.set(false);
-                }
             }
         }
+    }
 
-        pub struct Store<S: Stateful, F: StateFilter<Target=S>=Filterless<S>>
-        {
-            pub(super) inner: Arc<SlockCell<InnerStore<S, F>>>
-        }
+    pub(super) mod raw_store_shared_owner {
+        use std::marker::PhantomData;
+        use std::sync::Arc;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::check_listener_cycle;
 
-        impl<S> Store<S, Filterless<S>>
-            where S: Stateful
-        {
-            pub fn new(initial: S) -> Self {
-                Store {
-                    inner: Arc::new(SlockCell::new(InnerStore {
-                        dispatcher: StoreDispatcher::new(initial)
-                    }))
-                }
-            }
-        }
+        use crate::core::Slock;
+        use crate::state::listener::StateListener;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::general_binding::GeneralWeakBinding;
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::{Binding, Filter, Filterable, IntoAction, Signal, StateFilter, Stateful, UndoBarrier};
+        use crate::util::marker::ThreadMarker;
 
-        impl<S> Store<S, Filter<S>>
-            where S: Stateful
-        {
-            pub fn new_with_filter(initial: S) -> Self {
-                Store {
-                    inner: Arc::new(SlockCell::new(InnerStore {
-                        dispatcher: StoreDispatcher::new(initial)
-                    }))
-                }
-            }
+        pub(super) trait RawStoreSharedOwner<F: StateFilter> : Signal<Target=F::Target> + Sync {
+            type Inner: RawStore<F>;
+
+            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self;
+
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>>;
+
+            // guaranteed to only be used for creating the binding
+            // This does not need to call strong_count_increment
+            // caller is expected to do so
+            fn arc_clone(&self) -> Self;
         }
 
-        impl<S> Default for Store<S, Filterless<S>>
-            where S: Stateful + Default
-        {
-            fn default() -> Self {
-                Self::new(S::default())
+        impl<I, S: Stateful> Filterable<S> for I where I: RawStoreSharedOwner<Filter<S>> {
+
+            fn action_filter<G>(&self, filter: G, s: Slock<impl ThreadMarker>)
+                where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static {
+                self.inner_ref().borrow_mut(s).dispatcher_mut().action_filter(filter, s);
             }
         }
 
-        impl<S, M> StoreContainer for Store<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_store_container_inner!();
-        }
+        // Unfortunately can't do this for signal as well
+        // Since FixedSignal 'might' implement RawStoreSharedOwnerBase
+        // It's therefore done as macros
+        impl<F, R> Binding<F> for R where F: StateFilter, R: RawStoreSharedOwner<F> {
+            fn address(&self) -> usize {
+                Arc::as_ptr(&self.inner_ref()) as usize
+            }
 
-        impl<S, M> Bindable<M> for Store<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_bindable_inner!(S, M);
-        }
+            fn ptr_eq(&self, other: &Self) -> bool {
+                Arc::ptr_eq(self.inner_ref(), other.inner_ref())
+            }
 
-        impl<S, M> Store<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_adhoc_inner!(S, M);
-        }
+            fn is_applying(&self, s: Slock<impl ThreadMarker>) -> bool {
+                self.inner_ref().is_borrowed_mut(s)
+            }
 
-        impl<S, M> Signal for Store<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_signal_inner!(S);
-        }
+            fn is_borrowed(&self, s: Slock<impl ThreadMarker>) -> bool {
+                self.inner_ref().is_borrowed(s)
+            }
 
-        impl<S, F> RawStoreSharedOwner<F> for Store<S, F>
-            where S: Stateful, F: StateFilter<Target=S>
-        {
-            type Inner = InnerStore<S, F>;
+            fn apply(&self, action: impl IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target>, s: Slock<impl ThreadMarker>) {
+                R::Inner::apply(self.inner_ref(), action, false, s)
+            }
 
-            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
-                Store {
-                    inner: arc,
-                }
+            fn undo_barrier(&self, undo_barrier_type: UndoBarrier, s: Slock<impl ThreadMarker>) {
+                self.inner_ref().borrow_mut(s)
+                    .dispatcher_mut()
+                    .undo_barrier(undo_barrier_type, s.to_general_slock());
             }
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
-                &self.inner
+            #[track_caller]
+            fn action_listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>) where G: Send + FnMut(&F::Target, &<<F as StateFilter>::Target as Stateful>::Action, Slock) -> bool + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(self.inner_ref(), listener);
+                    move |state: &F::Target, action: &<F::Target as Stateful>::Action, s: Slock| listener.invoke(|listener| listener(state, action, s))
+                };
+                self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::ActionListener(Box::new(listener)));
             }
+            type WeakBinding = GeneralWeakBinding<F, R>;
 
-            fn arc_clone(&self) -> Self {
-                Store {
-                    inner: Arc::clone(&self.inner)
+            fn downgrade(&self) -> Self::WeakBinding {
+                GeneralWeakBinding {
+                    weak: Arc::downgrade(self.inner_ref()),
+                    phantom: PhantomData
                 }
             }
         }
     }
 
-    mod token_store {
-        use std::collections::HashMap;
-        use std::hash::Hash;
+    /* MARK: utilities */
+    mod action_inverter {
         use std::marker::PhantomData;
-        use std::ops::{Deref, DerefMut};
-        use std::sync::Arc;
+        use std::sync::Weak;
 
-        use crate::core::Slock;
+        use crate::core::{MSlock, Slock};
+        use crate::state::listener::DirectlyInvertible;
         use crate::state::slock_cell::SlockCell;
-        use crate::state::store::action_inverter::ActionInverter;
-        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
-        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
-        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
         use crate::state::store::raw_store::RawStore;
-        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
-        use crate::state::store::state_ref::StateRef;
-        use crate::state::store::store_dispatcher::StoreDispatcher;
-        use crate::state::store::EXECUTING_INVERSE;
-        use crate::state::InverseListener;
-        use crate::state::{Bindable, StateListener};
-        use crate::state::{Filter, Filterless, GeneralListener, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer};
-        use crate::util::marker::ThreadMarker;
-        use crate::view::undo_manager::UndoBucket;
+        use crate::state::{GroupBasis, StateFilter, Stateful};
+        use crate::view::undo_manager::{current_history_label, with_history_label};
 
-        pub(super) struct InnerTokenStore<S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S>> {
-            dispatcher: StoreDispatcher<S, F, InverseListenerHolderImpl>,
-            equal_listeners: HashMap<S, Vec<Box<dyn FnMut(&S, Slock) -> bool + Send>>>,
+        pub(super) struct ActionInverter<F: StateFilter, I> where I: RawStore<F> {
+            action: Option<<F::Target as Stateful>::Action>,
+            state: Weak<SlockCell<I>>,
+            label: Option<String>,
+            // (forward, backward) descriptions, kept once the action is consumed
+            descriptions: Option<(String, String)>,
+            phantom: PhantomData<F>
         }
-        impl<S, F> RawStore<F> for InnerTokenStore<S, F>
-            where S: Stateful + Copy + Hash + Eq,
-                  F: StateFilter<Target=S>
-        {
-            type InverseListenerHolder = InverseListenerHolderImpl;
 
-            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &self.dispatcher
+        impl<F, I> ActionInverter<F, I> where F: StateFilter, I: RawStore<F> {
+            pub(super) fn new(action: <F::Target as Stateful>::Action, weak: Weak<SlockCell<I>>) -> Self {
+                ActionInverter {
+                    action: Some(action),
+                    state: weak,
+                    label: current_history_label(),
+                    descriptions: None,
+                    phantom: PhantomData,
+                }
             }
 
-            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &mut self.dispatcher
+            fn descriptions(&self) -> (String, String) {
+                if let Some(descriptions) = &self.descriptions {
+                    return descriptions.clone();
+                }
+
+                match (&self.label, &self.action) {
+                    (Some(label), _) => (label.clone(), label.clone()),
+                    (None, Some(action)) => (action.forward_description().into(), action.backward_description().into()),
+                    (None, None) => ("Change".to_string(), "Change".to_string())
+                }
             }
+        }
 
-            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
-                if EXECUTING_INVERSE.get() {
-                    // do not execute while inverse is acting
+        impl<F, I> DirectlyInvertible for ActionInverter<F, I> where F: StateFilter, I: RawStore<F> {
+            fn invert(&mut self, s: MSlock) {
+                self.rollback(s.to_general_slock());
+            }
+
+            fn rollback(&mut self, s: Slock) {
+                let Some(state) = self.state.upgrade() else {
                     return;
-                }
-                else if is_inverse {
-                    EXECUTING_INVERSE.set(true);
-                }
+                };
 
+                // the inverse of the inverse keeps the same label
+                self.descriptions = Some(self.descriptions());
+                let action = self.action.take().unwrap();
+                with_history_label(self.label.clone(), || {
+                    I::apply(&state, action, true, s);
+                });
+            }
 
-                let mut borrow = arc.borrow_mut(s);
-                let inner = borrow.deref_mut();
+            unsafe fn right_multiply(&mut self, mut by: Box<dyn DirectlyInvertible>, s: MSlock) {
+                /* we are free to assume by is of type Self, allowing us to do this conversion */
+                let ptr = by.action_pointer(s) as *const <F::Target as Stateful>::Action;
+                self.action = Some(self.action.take().unwrap() * std::ptr::read(ptr));
+                /* we have implicitly moved the other's action, now we must tell it to forget to
+                   avoid double free
+                 */
+                by.forget_action(s);
+            }
 
-                let old = *inner.dispatcher.data();
+            unsafe fn action_pointer(&self, _s: MSlock) -> *const () {
+                self.action.as_ref().unwrap() as *const <F::Target as Stateful>::Action as *const ()
+            }
 
-                inner.dispatcher.apply(alt_action, |action| {
-                    Box::new(ActionInverter::new(action, Arc::downgrade(&arc)))
-                }, is_inverse, s);
+            unsafe fn forget_action(&mut self, _s: MSlock) {
+                std::mem::forget(self.action.take());
+            }
 
-                // relevant equal listeners (old and new)
-                let new = *inner.dispatcher.data();
+            fn id(&self) -> usize {
+                self.state.as_ptr() as usize
+            }
 
-                if old != new {
-                    for class in [old, new] {
-                        inner.equal_listeners.entry(class)
-                            .and_modify(|l|
-                                l.retain_mut(|f| f(&new, s.to_general_slock()))
-                            );
-                    }
-                }
+            fn history_label(&self) -> Option<String> {
+                self.label.clone()
+            }
 
-                if is_inverse {
-                    EXECUTING_INVERSE.set(false);
-                }
+            fn forward_description(&self) -> String {
+                self.descriptions().0
+            }
+
+            fn backward_description(&self) -> String {
+                self.descriptions().1
             }
         }
+    }
 
-        pub struct TokenStore<S, F=Filterless<S>>
-            where S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S> {
-            inner: Arc<SlockCell<InnerTokenStore<S, F>>>
+    mod state_ref {
+        use std::cell::Ref;
+        use std::marker::PhantomData;
+        use std::ops::Deref;
+
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::StateFilter;
+
+        pub(super) struct StateRef<'a, F, I> where F: StateFilter, I: RawStore<F> {
+            pub(super) main_ref: Ref<'a, I>,
+            pub(super) phantom: PhantomData<F>
         }
 
-        impl<S, F> TokenStore<S, F> where S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S> {
-            pub fn equals(&self, target: S, s: Slock<impl ThreadMarker>) -> impl Signal<Target=bool> + Clone {
-                GeneralSignal::from(self, &self.signal(), move |u| *u == target,
-                    |this, listener, _s | {
-                        this.inner.borrow_mut(s).equal_listeners.entry(target)
-                            .or_insert(Vec::new())
-                            .push(listener);
-                    },
-                    s
-                )
+        impl<'a, F, I> Deref for StateRef<'a, F, I>
+            where F: StateFilter, I: RawStore<F> {
+            type Target = F::Target;
+            fn deref(&self) -> &F::Target {
+                self.main_ref.dispatcher().data()
             }
         }
+    }
 
-        impl<S> TokenStore<S, Filterless<S>>
-            where S: Stateful + Copy + Hash + Eq {
-            pub fn new(initial: S) -> Self {
-                TokenStore {
-                    inner: Arc::new(SlockCell::new(InnerTokenStore {
-                        dispatcher: StoreDispatcher::new(initial),
-                        equal_listeners: HashMap::new(),
-                    }))
-                }
-            }
+    mod inverse_listener_holder {
+        use std::sync::Arc;
+
+        use crate::core::Slock;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::transaction;
+        use crate::state::store::transaction::SharedInverseListener;
+        use crate::state::{DirectlyInvertible, InverseListener, UndoBarrier};
+        use crate::view::undo_manager::UndoBucket;
+
+        pub(super) trait InverseListenerHolder {
+            fn new() -> Self;
+            fn set_listener(&mut self, listener: Box<dyn InverseListener>);
+
+            fn set_undo_bucket(&mut self, ub: UndoBucket);
+
+            fn invoke_listener(&mut self, action: impl FnOnce() -> Box<dyn DirectlyInvertible>, s: Slock);
+
+            fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock);
         }
 
-        impl<S> TokenStore<S, Filter<S>>
-            where S: Stateful + Copy + Hash + Eq {
-            pub fn new_with_filter(initial: S) -> Self {
-                TokenStore {
-                    inner: Arc::new(SlockCell::new(InnerTokenStore {
-                        dispatcher: StoreDispatcher::new(initial),
-                        equal_listeners: HashMap::new(),
-                    }))
-                }
+        pub(super) struct NullInverseListenerHolder;
+
+        impl InverseListenerHolder for NullInverseListenerHolder {
+            fn new() -> Self {
+                NullInverseListenerHolder
             }
-        }
 
-        impl<S> Default for TokenStore<S, Filterless<S>>
-            where S: Default + Stateful + Copy + Hash + Eq {
-            fn default() -> Self {
-                Self::new(S::default())
+            fn set_listener(&mut self, _listener: Box<dyn InverseListener>) {
+
             }
-        }
 
-        impl<S> Default for TokenStore<S, Filter<S>>
-            where S: Default + Stateful + Copy + Hash + Eq {
-            fn default() -> Self {
-                Self::new_with_filter(S::default())
+            fn set_undo_bucket(&mut self, _ub: UndoBucket) {
+
             }
-        }
 
-        impl<S, M> StoreContainer for TokenStore<S, M>
-            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
-            impl_store_container_inner!();
-        }
+            fn invoke_listener(&mut self, _action: impl FnOnce() -> Box<dyn DirectlyInvertible>, _s: Slock) {
+            }
 
-        impl<S, M> Bindable<M> for TokenStore<S, M>
-            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
-            impl_bindable_inner!(S, M);
-        }
+            fn undo_barrier(&mut self, _ubt: UndoBarrier, _s: Slock) {
 
-        impl<S, M> TokenStore<S, M>
-            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
-            impl_adhoc_inner!(S, M);
+            }
         }
 
-        impl<S, M> Signal for TokenStore<S, M>
-            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
-            impl_signal_inner!(S);
-        }
+        // listener is shared so that transactions may deliver inverses later on
+        pub(super) struct InverseListenerHolderImpl(Option<SharedInverseListener>, UndoBucket);
 
-        impl<S, A> RawStoreSharedOwner<A> for TokenStore<S, A>
-            where S: Stateful + Copy + Hash + Eq, A: StateFilter<Target=S> {
-            type Inner = InnerTokenStore<S, A>;
+        impl InverseListenerHolder for InverseListenerHolderImpl {
+            fn new() -> Self {
+                InverseListenerHolderImpl(None, UndoBucket::default())
+            }
 
-            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
-                TokenStore {
-                    inner: arc,
-                }
+            fn set_listener(&mut self, listener: Box<dyn InverseListener>) {
+                self.0 = Some(Arc::new(SlockCell::new(Some(listener))));
             }
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
-                &self.inner
+            fn set_undo_bucket(&mut self, ub: UndoBucket) {
+                self.1 = ub;
             }
 
-            fn arc_clone(&self) -> Self {
-                TokenStore {
-                    inner: Arc::clone(&self.inner)
+            fn invoke_listener(&mut self, action: impl FnOnce() -> Box<dyn DirectlyInvertible>, s: Slock) {
+                if transaction::is_rolling_back() {
+                    return;
+                }
+                else if transaction::is_active() {
+                    transaction::record(action(), self.0.clone());
+                    return;
+                }
+
+                if let Some(ref func) = self.0 {
+                    let mut listener = func.borrow_mut(s);
+                    // the listener may also have been dropped by a transaction
+                    let alive = listener.as_mut()
+                        .is_some_and(|l| l.handle_inverse(action(), self.1, s));
+
+                    if !alive {
+                        *listener = None;
+                        drop(listener);
+                        self.0 = None;
+                    }
+                }
+            }
+
+            fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock) {
+                if let Some(ref func) = self.0 {
+                    if let Some(listener) = func.borrow_mut(s).as_mut() {
+                        listener.undo_barrier(ubt, s);
+                    }
                 }
             }
         }
     }
 
-    mod derived_store {
+    // Transactions defer the inverse listeners of all stores
+    // applied to within the transaction. On failure, the collected inverses
+    // are rolled back, on success they are delivered as a single undo group
+    mod transaction {
+        use std::cell::{Cell, RefCell};
         use std::marker::PhantomData;
-        use std::ops::{Deref, DerefMut};
         use std::sync::Arc;
+        use std::sync::atomic::Ordering;
 
-        use crate::state::listener::{GeneralListener, InverseListener};
+        use crate::core::Slock;
         use crate::state::slock_cell::SlockCell;
-        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
-        use crate::state::store::inverse_listener_holder::NullInverseListenerHolder;
-        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
-        use crate::state::store::state_ref::StateRef;
-        use crate::state::store::store_dispatcher::StoreDispatcher;
-        use crate::state::Filter;
-        use crate::state::{Bindable, StateListener};
+        use crate::state::store::UNDO_BUCKET_COUNTER;
+        use crate::state::{DirectlyInvertible, InverseListener, UndoBarrier};
         use crate::util::marker::ThreadMarker;
-        use crate::view::undo_manager::UndoBucket;
-        use crate::{
-            core::Slock,
-            state::{
-                Filterless, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer,
-            },
-        };
+        use crate::util::rust_util::PhantomUnsendUnsync;
+        use crate::view::undo_manager::{capture_history_context, HistoryContext, UndoBucket};
 
-        pub(super) struct InnerDerivedStore<S: Stateful, F: StateFilter<Target=S>> {
-            dispatcher: StoreDispatcher<S, F, NullInverseListenerHolder>
+        // None once the listener has asked to be removed
+        pub(in crate::state::store) type SharedInverseListener = Arc<SlockCell<Option<Box<dyn InverseListener>>>>;
+
+        enum TransactionEntry {
+            // the history context (elision and hooks) is that of the original action
+            Inverse(Box<dyn DirectlyInvertible>, Option<(SharedInverseListener, HistoryContext)>),
+            RollbackHook(Box<dyn FnOnce(Slock)>),
         }
 
-        impl<S, F> RawStore<F> for InnerDerivedStore<S, F>
-            where S: Stateful, F: StateFilter<Target=S>
-        {
-            type InverseListenerHolder = NullInverseListenerHolder;
+        thread_local! {
+            // one log per (nested) transaction
+            static TRANSACTIONS: RefCell<Vec<Vec<TransactionEntry>>> = const { RefCell::new(Vec::new()) };
+            static ROLLING_BACK: Cell<bool> = const { Cell::new(false) };
+        }
 
-            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &self.dispatcher
-            }
+        pub(in crate::state::store) fn is_active() -> bool {
+            TRANSACTIONS.with_borrow(|t| !t.is_empty())
+        }
 
-            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
-                &mut self.dispatcher
-            }
+        pub(in crate::state::store) fn is_rolling_back() -> bool {
+            ROLLING_BACK.get()
+        }
 
-            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
-                // note that derived store does not care whether or not the action is inverse or not
-                // (except in the case of filters)
+        pub(in crate::state::store) fn record(inverse: Box<dyn DirectlyInvertible>, listener: Option<SharedInverseListener>) {
+            let listener = listener.map(|l| (l, capture_history_context()));
+            TRANSACTIONS.with_borrow_mut(|t| {
+                t.last_mut().unwrap().push(TransactionEntry::Inverse(inverse, listener))
+            })
+        }
 
-                let mut borrow = arc.borrow_mut(s);
-                let inner = borrow.deref_mut();
+        // pops (and rolls back) the frame if the transaction body unwinds
+        struct FrameGuard<'a, M: ThreadMarker> {
+            s: Slock<'a, M>,
+            armed: bool,
+        }
 
-                inner.dispatcher.apply(alt_action, |_| unreachable!(), is_inverse, s);
+        impl<M: ThreadMarker> Drop for FrameGuard<'_, M> {
+            fn drop(&mut self) {
+                if self.armed {
+                    let log = TRANSACTIONS.with_borrow_mut(|t| t.pop().unwrap());
+                    rollback(log, self.s.to_general_slock());
+                }
             }
         }
 
-        pub struct DerivedStore<S: Stateful, F: StateFilter<Target=S>=Filterless<S>>
-        {
-            inner: Arc<SlockCell<InnerDerivedStore<S, F>>>
+        pub struct Transaction {
+            unsend_unsync: PhantomUnsendUnsync
         }
 
-        impl<S> DerivedStore<S, Filterless<S>>
-            where S: Stateful
-        {
-            pub fn new(initial: S) -> Self {
-                DerivedStore {
-                    inner: Arc::new(SlockCell::new(InnerDerivedStore {
-                        dispatcher: StoreDispatcher::new(initial)
-                    }))
-                }
+        impl Transaction {
+            /// Registers a callback to undo side effects that
+            /// are not tracked by stores. It is called if this transaction
+            /// (or an enclosing one) is rolled back, interleaved in reverse
+            /// order with the rollback of store actions
+            pub fn on_rollback(&mut self, f: impl FnOnce(Slock) + 'static) {
+                TRANSACTIONS.with_borrow_mut(|t| {
+                    t.last_mut().unwrap().push(TransactionEntry::RollbackHook(Box::new(f)))
+                })
             }
         }
 
-        impl<S> DerivedStore<S, Filter<S>>
-            where S: Stateful
+        pub(crate) fn run_transaction<'a, M, T, E>(f: impl FnOnce(&mut Transaction, Slock<'a, M>) -> Result<T, E>, s: Slock<'a, M>) -> Result<T, E>
+            where M: ThreadMarker
         {
-            pub fn new_with_filter(initial: S) -> Self {
-                DerivedStore {
-                    inner: Arc::new(SlockCell::new(InnerDerivedStore {
-                        dispatcher: StoreDispatcher::new(initial),
-                    }))
-                }
-            }
-        }
+            TRANSACTIONS.with_borrow_mut(|t| t.push(Vec::new()));
+            let mut tx = Transaction {
+                unsend_unsync: PhantomData
+            };
 
-        impl<S> Default for DerivedStore<S, Filterless<S>>
-            where S: Stateful + Default
-        {
-            fn default() -> Self {
-                Self::new(S::default())
-            }
-        }
+            let mut guard = FrameGuard { s, armed: true };
+            let result = f(&mut tx, s);
+            guard.armed = false;
 
-        impl<S> Default for DerivedStore<S, Filter<S>>
-            where S: Stateful + Default
-        {
-            fn default() -> Self {
-                Self::new_with_filter(S::default())
+            let log = TRANSACTIONS.with_borrow_mut(|t| t.pop().unwrap());
+            if result.is_ok() {
+                commit(log, s.to_general_slock());
+            }
+            else {
+                rollback(log, s.to_general_slock());
             }
-        }
 
-        impl<S, M> StoreContainer for DerivedStore<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_store_container_inner!();
+            result
         }
 
-        impl<S, M> Bindable<M> for DerivedStore<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_bindable_inner!(S, M);
-        }
+        fn commit(log: Vec<TransactionEntry>, s: Slock) {
+            // nested transactions are only committed with their parent
+            let Some(log) = TRANSACTIONS.with_borrow_mut(|t| {
+                if let Some(parent) = t.last_mut() {
+                    parent.extend(log);
+                    None
+                }
+                else {
+                    Some(log)
+                }
+            }) else {
+                return;
+            };
 
-        impl<S, M> DerivedStore<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_adhoc_inner!(S, M);
-        }
+            let inverses: Vec<_> = log.into_iter()
+                .filter_map(|entry| match entry {
+                    TransactionEntry::Inverse(inverse, Some(listener)) => Some((inverse, listener)),
+                    _ => None
+                })
+                .collect();
 
-        impl<S, M> Signal for DerivedStore<S, M>
-            where S: Stateful, M: StateFilter<Target=S>
-        {
-            impl_signal_inner!(S);
-        }
+            let barrier = |listener: &SharedInverseListener| {
+                if let Some(l) = listener.borrow_mut(s).as_mut() {
+                    l.undo_barrier(UndoBarrier::Strong, s);
+                }
+            };
 
-        impl<S, F> RawStoreSharedOwner<F> for DerivedStore<S, F>
-            where S: Stateful, F: StateFilter<Target=S>
-        {
-            type Inner = InnerDerivedStore<S, F>;
+            // every listener receives the entire batch within a single (fresh) bucket,
+            // closed off on either side
+            let bucket = UndoBucket::new(UNDO_BUCKET_COUNTER.fetch_add(1, Ordering::SeqCst));
+            for (_, (listener, _)) in &inverses {
+                barrier(listener);
+            }
 
-            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
-                DerivedStore {
-                    inner: arc
+            let listeners: Vec<_> = inverses.iter()
+                .map(|(_, (listener, _))| listener.clone())
+                .collect();
+
+            for (inverse, (listener, context)) in inverses {
+                let mut borrow = listener.borrow_mut(s);
+                let Some(l) = borrow.as_mut() else {
+                    continue;
+                };
+
+                if !context.enter(|| l.handle_inverse(inverse, bucket, s)) {
+                    *borrow = None;
                 }
             }
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
-                &self.inner
+            for listener in listeners {
+                barrier(&listener);
             }
+        }
 
-            fn arc_clone(&self) -> Self {
-                DerivedStore {
-                    inner: Arc::clone(&self.inner)
+        fn rollback(log: Vec<TransactionEntry>, s: Slock) {
+            let old = ROLLING_BACK.replace(true);
+            for entry in log.into_iter().rev() {
+                match entry {
+                    TransactionEntry::Inverse(mut inverse, _) => inverse.rollback(s),
+                    TransactionEntry::RollbackHook(hook) => hook(s),
                 }
             }
+            ROLLING_BACK.set(old);
         }
     }
 
-    mod general_binding {
-        use std::marker::PhantomData;
-        use std::ops::Deref;
-        use std::sync::{Arc, Weak};
-
+    mod store_dispatcher {
         use crate::core::Slock;
-        use crate::state::signal::GeneralSignal;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
-        use crate::state::store::state_ref::StateRef;
-        use crate::state::Signal;
-        use crate::state::{StateFilter, StateListener, WeakBinding};
+        use crate::state::listener::StateListener;
+        use crate::state::store::inverse_listener_holder::InverseListenerHolder;
+        use crate::state::{DirectlyInvertible, GeneralListener, GroupBasis, IntoAction, InverseListener, StateFilter, Stateful, UndoBarrier};
         use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+        use crate::view::undo_manager::UndoBucket;
 
-        // will find better solution in the future
-        // This really shouldn't even have to be public in first place
-        // it's just because of rust typing issues
-        #[allow(private_bounds)]
-        pub struct GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            pub(super) weak: Weak<SlockCell<I::Inner>>,
-            pub(super) phantom: PhantomData<(Weak<SlockCell<F>>, I)>
+        pub(crate) struct StoreDispatcher<S, F, I>
+            where S: Stateful, F: StateFilter, I: InverseListenerHolder
+        {
+            _quarve_tag: QuarveAllocTag,
+            data: S,
+            listeners: Vec<StateListener<S>>,
+            inverse_listener: I,
+            filter: F,
         }
 
-        impl<F, I> Clone for GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            fn clone(&self) -> Self {
-                GeneralWeakBinding {
-                    weak: self.weak.clone(),
-                    phantom: Default::default(),
+        impl<S, F, I> StoreDispatcher<S, F, I>
+            where S: Stateful, F: StateFilter<Target=S>, I: InverseListenerHolder {
+
+            #[inline]
+            #[track_caller]
+            pub(super) fn new(data: S) -> Self {
+                StoreDispatcher {
+                    _quarve_tag: QuarveAllocTag::new::<S>(AllocKind::Store),
+                    data,
+                    listeners: Vec::new(),
+                    inverse_listener: I::new(),
+                    filter: F::new(),
                 }
             }
-        }
 
-        #[allow(private_bounds)]
-        pub struct GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            pub(super) inner: I,
-            pub(super) phantom: PhantomData<Arc<SlockCell<F>>>,
-        }
+            #[inline]
+            pub fn data(&self) -> &S {
+                &self.data
+            }
 
-        impl<F, I> WeakBinding<F> for GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            type Binding = GeneralBinding<F, I>;
+            #[inline]
+            pub fn apply_post_filter(
+                &mut self,
+                into_action: impl IntoAction<S::Action, S>,
+                make_inverter: impl FnOnce(S::Action) -> Box<dyn DirectlyInvertible>,
+                post_filter: impl FnOnce(&S, S::Action) -> S::Action,
+                skip_filters: bool,
+                s: Slock<impl ThreadMarker>
+            ) {
+                let transaction = into_action.into_action(&self.data);
 
-            fn upgrade(&self) -> Option<Self::Binding> {
-                let Some(inner) = self.weak.upgrade() else {
-                    return None;
+                let filtered_action = if skip_filters {
+                    transaction
+                }
+                else {
+                    post_filter(&self.data, self.filter.filter(&self.data, transaction, s))
                 };
 
-                I::Inner::strong_count_increment(&inner);
+                // tell action listeners
+                self.listeners.retain_mut(
+                    |listener| match listener {
+                        StateListener::ActionListener(listener) => listener(&self.data, &filtered_action, s.to_general_slock()),
+                        _ => true
+                    }
+                );
 
-                Some(GeneralBinding {
-                    inner: I::from_ref(inner),
-                    phantom: Default::default(),
-                })
-            }
-        }
+                // apply action
+                let inverse = filtered_action.apply(&mut self.data);
 
-        impl<F, I> Clone for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            fn clone(&self) -> Self {
-                I::Inner::strong_count_increment(self.inner.inner_ref());
+                // tell signal and general listeners
+                let data = &self.data;
+                self.listeners.retain_mut(
+                    |listener| match listener {
+                        StateListener::GeneralListener(action) => {
+                            action(s.to_general_slock())
+                        },
+                        StateListener::SignalListener(action) => {
+                            action(data, s.to_general_slock())
+                        },
+                        _ => true
+                    }
+                );
 
-                GeneralBinding {
-                    inner: self.inner.arc_clone(),
-                    phantom: PhantomData
-                }
+                // tell inverse listener
+                self.inverse_listener.invoke_listener(move || make_inverter(inverse), s.to_general_slock());
             }
-        }
-
-        impl<F, I> Signal for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            type Target=F::Target;
 
-            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=F::Target> + 'a {
-                StateRef {
-                    main_ref: self.inner_ref().borrow(s),
-                    phantom: PhantomData
-                }
+            pub fn apply(
+                &mut self,
+                into_action: impl IntoAction<S::Action, S>,
+                make_inverter: impl FnOnce(S::Action) -> Box<dyn DirectlyInvertible>,
+                skip_filters: bool,
+                s: Slock<impl ThreadMarker>
+            ) {
+                self.apply_post_filter(into_action, make_inverter, |_, f| f, skip_filters, s);
             }
 
-            fn listen<Q>(&self, listener: Q, s: Slock<impl ThreadMarker>)
-                where Q: FnMut(&F::Target, Slock) -> bool + Send + 'static {
-                self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(Box::new(listener)));
+            #[track_caller]
+            pub fn add_listener(&mut self, listener: StateListener<S>) {
+                debug_assert!(! matches!(listener, StateListener::GeneralListener(_)),
+                              "Should be set via set_general_listener"
+                );
+                self.listeners.push(listener.tagged());
             }
 
-            type MappedOutput<U: Send + 'static> = GeneralSignal<U>;
-            fn map<U, Q>(&self, map: Q, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<U>
-                where U: Send + 'static, Q: Send + 'static + Fn(&F::Target) -> U {
-                GeneralSignal::from(self, self, map, |this, listener, s| {
-                    this.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(listener))
-                }, s)
+            pub fn action_filter<G>(&mut self, filter: G, _s: Slock<impl ThreadMarker>)
+                where G: Send + Fn(&S, S::Action, Slock) -> S::Action + 'static {
+                self.filter.add_filter(filter);
             }
-        }
 
-        impl<F, I> RawStoreSharedOwner<F> for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            type Inner = I::Inner;
+            pub fn set_general_listener(&mut self, f: impl GeneralListener + Clone, s: Slock) {
+                self.listeners.retain_mut(|l| !matches!(l, StateListener::GeneralListener(_)));
+                self.listeners.push(StateListener::GeneralListener(Box::new(f.clone())));
 
-            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
-                GeneralBinding {
-                    inner: I::from_ref(arc),
-                    phantom: Default::default(),
+                if let Some(action) = self.data.subtree_general_listener(f, s) {
+                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
                 }
             }
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
-                self.inner.inner_ref()
-            }
+            pub fn set_inverse_listener(&mut self, f: impl InverseListener + Clone, s: Slock) {
+                self.inverse_listener.set_listener(Box::new(f.clone()));
 
-            fn arc_clone(&self) -> Self {
-                GeneralBinding {
-                    inner: self.inner.arc_clone(),
-                    phantom: PhantomData
+                if let Some(action) = self.data.subtree_inverse_listener(f, s) {
+                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
                 }
             }
-        }
 
-        #[allow(private_bounds)]
-        impl<F, I> GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            pub fn store(&self) -> &I {
-                &self.inner
+            pub fn set_undo_bucket(&mut self, ub: UndoBucket, s: Slock) {
+                self.inverse_listener.set_undo_bucket(ub);
+
+                if let Some(action) = self.data.subtree_undo_bucket(ub, s) {
+                    self.listeners.push(StateListener::ActionListener(Box::new(action)));
+                }
             }
-        }
 
-        impl<F, I> Drop for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
-            fn drop(&mut self) {
-                I::Inner::strong_count_decrement(self.inner.inner_ref());
+            pub fn undo_barrier(&mut self, ubt: UndoBarrier, s: Slock) {
+                self.inverse_listener.undo_barrier(ubt, s);
             }
         }
     }
 
-    // only to make rust happy
-    pub mod unreachable_binding {
-        use std::marker::PhantomData;
-        use std::ops::Deref;
-        use std::sync::Arc;
-
-        use crate::core::Slock;
-        use crate::state::slock_cell::{MainSlockCell, SlockCell};
-        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
-        use crate::state::store::raw_store::RawStore;
-        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
-        use crate::state::store::store_dispatcher::StoreDispatcher;
-        use crate::state::{GeneralSignal, IntoAction, Signal, StateFilter, Stateful};
-        use crate::util::marker::ThreadMarker;
-
-        pub struct UnreachableBindingInner<F>(pub PhantomData<Arc<MainSlockCell<F>>>) where F: StateFilter;
+    mod macros {
+        macro_rules! impl_store_container_inner {
+            () => {
+                fn subtree_general_listener<Q>(&self, f: Q, s: Slock<impl ThreadMarker>)
+                    where Q: GeneralListener + Clone {
+                    self.inner.borrow_mut(s).dispatcher_mut().set_general_listener(f, s.to_general_slock());
+                }
 
-        impl<F> Deref for UnreachableBindingInner<F> where F: StateFilter {
-            type Target = F::Target;
+                fn subtree_inverse_listener<Q>(&self, f: Q, s: Slock<impl ThreadMarker>)
+                    where Q: InverseListener + Clone {
+                    self.inner.borrow_mut(s).dispatcher_mut().set_inverse_listener(f, s.to_general_slock());
+                }
 
-            fn deref(&self) -> &Self::Target {
-                unreachable!()
+                fn subtree_undo_bucket(&self, ub: UndoBucket, s: Slock<impl ThreadMarker>) {
+                    self.inner.borrow_mut(s).dispatcher_mut().set_undo_bucket(ub, s.to_general_slock());
+                }
             }
         }
 
-        impl<F> RawStore<F> for UnreachableBindingInner<F> where F: StateFilter {
-            type InverseListenerHolder = InverseListenerHolderImpl;
+        macro_rules! impl_bindable_inner {
+            ($s:ty, $f:ty) => {
+                type Binding = GeneralBinding<$f, Self>;
+                type WeakBinding = GeneralWeakBinding<$f, Self>;
 
-            fn dispatcher(&self) -> &StoreDispatcher<F::Target, F, Self::InverseListenerHolder> {
-                unreachable!()
-            }
+                fn binding(&self) -> Self::Binding {
+                    <Self as RawStoreSharedOwner<$f>>::Inner::strong_count_increment(self.inner_ref());
 
-            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<F::Target, F, Self::InverseListenerHolder> {
-                unreachable!()
-            }
+                    GeneralBinding {
+                        inner: self.arc_clone(),
+                        phantom: PhantomData,
+                    }
+                }
+
+                fn weak_binding(&self) -> Self::WeakBinding {
+                    let ret: GeneralWeakBinding<$f, Self> = GeneralWeakBinding {
+                        weak: Arc::downgrade(self.inner_ref()),
+                        phantom: PhantomData
+                    };
+                    ret
+                }
 
-            fn apply(_inner: &Arc<SlockCell<Self>>, _action: impl IntoAction<<F::Target as Stateful>::Action, F::Target>, _is_inverse: bool, _s: Slock<impl ThreadMarker>) {
-                unreachable!()
             }
         }
 
-        pub struct UnreachableBinding<F>(pub PhantomData<Arc<MainSlockCell<F>>>) where F: StateFilter;
-        impl<F> Signal for UnreachableBinding<F> where F: StateFilter {
-            type Target = F::Target;
+        macro_rules! impl_adhoc_inner {
+            ($s:ty, $f:ty) => {
+                pub fn signal(&self) -> impl Signal<Target=$s> + Clone {
+                    <Self as RawStoreSharedOwner<$f>>::Inner::strong_count_increment(self.inner_ref());
 
-            fn borrow<'a>(&'a self, _s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=Self::Target> + 'a {
-                UnreachableBindingInner::<F>(PhantomData)
+                    GeneralBinding {
+                        inner: self.arc_clone(),
+                        phantom: PhantomData,
+                    }
+                }
             }
+        }
 
-            fn listen<G>(&self, _listener: G, _s: Slock<impl ThreadMarker>) where G: FnMut(&Self::Target, Slock) -> bool + Send + 'static {
-                unreachable!()
-            }
+        macro_rules! impl_signal_inner {
+            ($s:ty) => {
+                type Target=$s;
 
-            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
-            fn map<S, G>(&self, _map: G, _s: Slock<impl ThreadMarker>) -> Self::MappedOutput<S> where S: Send + 'static, G: Send + 'static + Fn(&Self::Target) -> S {
-                unreachable!()
-            }
+                fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=$s> + 'a {
+                    StateRef {
+                        main_ref: self.inner_ref().borrow(s),
+                        phantom: PhantomData
+                    }
+                }
+
+                #[track_caller]
+                fn listen<Q>(&self, listener: Q, s: Slock<impl ThreadMarker>)
+                    where Q: FnMut(&$s, Slock) -> bool + Send + 'static {
+                    #[cfg(debug_assertions)]
+                    let listener = {
+                        let mut listener = crate::util::test_util::check_listener_cycle(self.inner_ref(), listener);
+                        move |val: &$s, s: Slock| listener.invoke(|listener| listener(val, s))
+                    };
+                    self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(Box::new(listener)));
+                }
+
+                // non trait method so it's fine to just return impl Signal
+                type MappedOutput<U: Send + 'static> = GeneralSignal<U>;
+                #[track_caller]
+                fn map<U, Q>(&self, map: Q, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<U>
+                    where U: Send + 'static, Q: Send + 'static + Fn(&$s) -> U {
+                    GeneralSignal::from(self, self, map, |this, listener, s| {
+                        this.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(listener))
+                    }, s)
+                }
+            };
         }
 
-        impl<F> RawStoreSharedOwner<F> for UnreachableBinding<F> where F: StateFilter {
-            type Inner = UnreachableBindingInner<F>;
+        pub(super) use {impl_store_container_inner, impl_signal_inner, impl_bindable_inner, impl_adhoc_inner};
+    }
 
-            fn from_ref(_arc: Arc<SlockCell<Self::Inner>>) -> Self {
-                unreachable!()
-            }
+    /* MARK: Stores */
+    mod store {
+        use std::marker::PhantomData;
+        use std::ops::{Deref, DerefMut};
+        use std::sync::Arc;
 
-            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
-                unreachable!()
-            }
+        use crate::state::listener::{GeneralListener, InverseListener};
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::action_inverter::ActionInverter;
+        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
+        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
+        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::store::state_ref::StateRef;
+        use crate::state::store::store_dispatcher::StoreDispatcher;
+        use crate::state::store::EXECUTING_INVERSE;
+        use crate::state::Filter;
+        use crate::state::{Bindable, StateListener};
+        use crate::util::marker::ThreadMarker;
+        use crate::view::undo_manager::UndoBucket;
+        use crate::{
+            core::Slock,
+            state::{Filterless, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer},
+        };
 
-            fn arc_clone(&self) -> Self {
-                unreachable!()
-            }
+        pub(super) struct InnerStore<S: Stateful, F: StateFilter<Target=S>> {
+            dispatcher: StoreDispatcher<S, F, InverseListenerHolderImpl>
         }
 
-        impl<F> Clone for UnreachableBinding<F> where F: StateFilter {
-            fn clone(&self) -> Self {
-                UnreachableBinding(PhantomData)
+        impl<S, F> RawStore<F> for InnerStore<S, F>
+            where S: Stateful, F: StateFilter<Target=S>
+        {
+            type InverseListenerHolder = InverseListenerHolderImpl;
+
+            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &self.dispatcher
             }
-        }
-    }
-}
 
-mod buffer {
-    use std::ops::{Deref, DerefMut};
-    use std::sync::{Arc, Weak};
+            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &mut self.dispatcher
+            }
 
-    use crate::core::Slock;
-    use crate::state::slock_cell::SlockCell;
-    use crate::util::marker::ThreadMarker;
-    use crate::util::test_util::QuarveAllocTag;
+            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
+                if EXECUTING_INVERSE.get() {
+                    // do not execute while inverse is acting
+                    return;
+                }
+                else if is_inverse {
+                    EXECUTING_INVERSE.set(true);
+                }
 
-    pub struct Buffer<T>(Arc<(SlockCell<T>, QuarveAllocTag)>) where T: Send;
+                let mut borrow = arc.borrow_mut(s);
+                let inner = borrow.deref_mut();
 
-    pub struct WeakBuffer<T>(Weak<(SlockCell<T>, QuarveAllocTag)>) where T: Send;
+                inner.dispatcher.apply(alt_action, |action| {
+                    Box::new(ActionInverter::new(action, Arc::downgrade(&arc)))
+                }, is_inverse, s);
 
-    impl<T> Clone for WeakBuffer<T> where T: Send {
-        fn clone(&self) -> Self {
-            WeakBuffer(self.0.clone())
+                if is_inverse {
+                    EXECUTING_INVERSE.set(false);
+                }
+            }
         }
-    }
 
-    impl<T> Buffer<T>
-        where T: Send
-    {
-        pub fn new(initial: T) -> Buffer<T> {
-            Buffer(Arc::new((SlockCell::new(initial), QuarveAllocTag::new())))
+        pub struct Store<S: Stateful, F: StateFilter<Target=S>=Filterless<S>>
+        {
+            pub(super) inner: Arc<SlockCell<InnerStore<S, F>>>
         }
 
-        pub fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
-            self.0.0.borrow(s)
+        impl<S> Store<S, Filterless<S>>
+            where S: Stateful
+        {
+            #[track_caller]
+            pub fn new(initial: S) -> Self {
+                Store {
+                    inner: Arc::new(SlockCell::new(InnerStore {
+                        dispatcher: StoreDispatcher::new(initial)
+                    }))
+                }
+            }
         }
 
-        pub fn borrow_mut<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl DerefMut<Target=T> + 'a  {
-            self.0.0.borrow_mut(s)
+        impl<S> Store<S, Filter<S>>
+            where S: Stateful
+        {
+            #[track_caller]
+            pub fn new_with_filter(initial: S) -> Self {
+                Store {
+                    inner: Arc::new(SlockCell::new(InnerStore {
+                        dispatcher: StoreDispatcher::new(initial)
+                    }))
+                }
+            }
         }
 
-        pub fn replace<'a>(&'a self, with: T, s: Slock<'a, impl ThreadMarker>) -> T {
-            std::mem::replace(self.borrow_mut(s).deref_mut(), with)
+        impl<S> Default for Store<S, Filterless<S>>
+            where S: Stateful + Default
+        {
+            #[track_caller]
+            fn default() -> Self {
+                Self::new(S::default())
+            }
         }
 
-        pub fn downgrade(&self) -> WeakBuffer<T> {
-            WeakBuffer(Arc::downgrade(&self.0))
+        impl<S, M> StoreContainer for Store<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_store_container_inner!();
         }
-    }
 
-    impl<T> Buffer<T> where T: Send + Default {
-        pub fn take(&self, s: Slock<impl ThreadMarker>) -> T {
-            std::mem::take(self.borrow_mut(s).deref_mut())
+        impl<S, M> Bindable<M> for Store<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_bindable_inner!(S, M);
         }
-    }
 
-    impl<T> WeakBuffer<T> where T: Send {
-        pub fn upgrade(&self) -> Option<Buffer<T>> {
-            self.0.upgrade().map(|arc| Buffer(arc))
+        impl<S, M> Store<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_adhoc_inner!(S, M);
         }
-    }
-}
-
-mod signal {
-    use std::ops::Deref;
-
-    pub use fixed_signal::*;
-    pub use general_signal::*;
-    pub use joined_signal::*;
-    use signal_audience::*;
-    pub use signal_or_value::*;
-    use signal_ref::*;
-    pub use timed_signal::*;
-
-    use crate::core::Slock;
-    use crate::util::marker::ThreadMarker;
 
-    pub trait Signal: Sized + Send + Sync + 'static {
-        type Target: Send + 'static;
+        impl<S, M> Signal for Store<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_signal_inner!(S);
+        }
 
-        /// Be careful about calling this method within an
-        /// action_listener or related fields. While not bad by itself
-        /// This can easily cause retain cycles
-        /// Instead, similar logic can usually be done by using JoinedSignals,
-        /// DerivedStores, or Buffers
-        fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=Self::Target> + 'a;
+        impl<S, F> RawStoreSharedOwner<F> for Store<S, F>
+            where S: Stateful, F: StateFilter<Target=S>
+        {
+            type Inner = InnerStore<S, F>;
 
-        fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
-            where F: (FnMut(&Self::Target, Slock) -> bool) + Send + 'static;
+            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
+                Store {
+                    inner: arc,
+                }
+            }
 
-        type MappedOutput<S: Send + 'static>: Signal<Target=S> + Clone;
-        fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<S>
-            where S: Send + 'static,
-                  F: Send + 'static + Fn(&Self::Target) -> S;
-    }
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
+                #[cfg(debug_assertions)]
+                crate::util::test_util::note_strong_handle(self, &self.inner);
+                &self.inner
+            }
 
-    pub trait ActualDiffSignal : Signal where Self::Target: Send + 'static + Clone + PartialEq {
-        fn diff_listen<F>(&self, mut listener: F, s: Slock<impl ThreadMarker>)
-            where F: (FnMut(&Self::Target, Slock) -> bool) + Send + 'static
-        {
-            let mut last_val = self.borrow(s).clone();
-            self.listen(move |val, s| {
-                if *val != last_val {
-                    last_val = val.clone();
-                    listener(val, s)
-                }
-                else {
-                    true
+            fn arc_clone(&self) -> Self {
+                Store {
+                    inner: Arc::clone(&self.inner)
                 }
-            }, s)
+            }
         }
     }
-    impl<S> ActualDiffSignal for S where S::Target: Send + Clone + PartialEq + 'static, S: Signal { }
 
-    trait InnerSignal {
-        type Target: Send + 'static;
-        fn borrow(&self) -> &Self::Target;
-    }
+    mod token_store {
+        use std::collections::HashMap;
+        use std::hash::Hash;
+        use std::marker::PhantomData;
+        use std::ops::{Deref, DerefMut};
+        use std::sync::Arc;
 
-    mod signal_audience {
         use crate::core::Slock;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::action_inverter::ActionInverter;
+        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
+        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
+        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::store::state_ref::StateRef;
+        use crate::state::store::store_dispatcher::StoreDispatcher;
+        use crate::state::store::EXECUTING_INVERSE;
+        use crate::state::InverseListener;
+        use crate::state::{Bindable, StateListener};
+        use crate::state::{Filter, Filterless, GeneralListener, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer};
         use crate::util::marker::ThreadMarker;
+        use crate::view::undo_manager::UndoBucket;
 
-        pub(super) struct SignalAudience<T> where T: Send + 'static {
-            listeners: Vec<Box<dyn FnMut(&T, Slock) -> bool + Send>>
+        pub(super) struct InnerTokenStore<S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S>> {
+            dispatcher: StoreDispatcher<S, F, InverseListenerHolderImpl>,
+            equal_listeners: HashMap<S, Vec<Box<dyn FnMut(&S, Slock) -> bool + Send>>>,
         }
+        impl<S, F> RawStore<F> for InnerTokenStore<S, F>
+            where S: Stateful + Copy + Hash + Eq,
+                  F: StateFilter<Target=S>
+        {
+            type InverseListenerHolder = InverseListenerHolderImpl;
 
-        impl<T> SignalAudience<T> where T: Send + 'static {
-            pub(super) fn new() -> SignalAudience<T> {
-                SignalAudience {
-                    listeners: Vec::new()
-                }
+            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &self.dispatcher
             }
 
-            pub(super) fn listen<F>(&mut self, listener: F, _s: Slock<impl ThreadMarker>)
-                where F: (FnMut(&T, Slock) -> bool) + Send + 'static {
-                self.listeners.push(Box::new(listener));
+            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &mut self.dispatcher
             }
 
-            pub(super) fn listen_box(
-                &mut self,
-                listener: Box<dyn (FnMut(&T, Slock) -> bool) + Send + 'static>,
-                _s: Slock<impl ThreadMarker>
-            ) {
-                self.listeners.push(listener);
-            }
+            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
+                if EXECUTING_INVERSE.get() {
+                    // do not execute while inverse is acting
+                    return;
+                }
+                else if is_inverse {
+                    EXECUTING_INVERSE.set(true);
+                }
 
-            pub(super) fn dispatch(&mut self, new_val: &T, s: Slock<impl ThreadMarker>) {
-                self.listeners
-                    .retain_mut(|listener| listener(new_val, s.to_general_slock()))
-            }
 
-            pub(super) fn is_empty(&self) -> bool {
-                self.listeners.is_empty()
+                let mut borrow = arc.borrow_mut(s);
+                let inner = borrow.deref_mut();
+
+                let old = *inner.dispatcher.data();
+
+                inner.dispatcher.apply(alt_action, |action| {
+                    Box::new(ActionInverter::new(action, Arc::downgrade(&arc)))
+                }, is_inverse, s);
+
+                // relevant equal listeners (old and new)
+                let new = *inner.dispatcher.data();
+
+                if old != new {
+                    for class in [old, new] {
+                        inner.equal_listeners.entry(class)
+                            .and_modify(|l|
+                                l.retain_mut(|f| f(&new, s.to_general_slock()))
+                            );
+                    }
+                }
+
+                if is_inverse {
+                    EXECUTING_INVERSE.set(false);
+                }
             }
         }
-    }
 
-    mod signal_ref {
-        use std::cell::Ref;
-        use std::ops::Deref;
+        pub struct TokenStore<S, F=Filterless<S>>
+            where S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S> {
+            inner: Arc<SlockCell<InnerTokenStore<S, F>>>
+        }
 
-        use super::InnerSignal;
+        impl<S, F> TokenStore<S, F> where S: Stateful + Copy + Hash + Eq, F: StateFilter<Target=S> {
+            pub fn equals(&self, target: S, s: Slock<impl ThreadMarker>) -> impl Signal<Target=bool> + Clone {
+                GeneralSignal::from(self, &self.signal(), move |u| *u == target,
+                    |this, listener, _s | {
+                        this.inner.borrow_mut(s).equal_listeners.entry(target)
+                            .or_insert(Vec::new())
+                            .push(listener);
+                    },
+                    s
+                )
+            }
+        }
 
-        pub(super) struct SignalRef<'a, U: InnerSignal> {
-            pub(super) src: Ref<'a, U>,
+        impl<S> TokenStore<S, Filterless<S>>
+            where S: Stateful + Copy + Hash + Eq {
+            #[track_caller]
+            pub fn new(initial: S) -> Self {
+                TokenStore {
+                    inner: Arc::new(SlockCell::new(InnerTokenStore {
+                        dispatcher: StoreDispatcher::new(initial),
+                        equal_listeners: HashMap::new(),
+                    }))
+                }
+            }
         }
 
-        impl<'a, U> Deref for SignalRef<'a, U> where U: InnerSignal {
-            type Target = U::Target;
+        impl<S> TokenStore<S, Filter<S>>
+            where S: Stateful + Copy + Hash + Eq {
+            #[track_caller]
+            pub fn new_with_filter(initial: S) -> Self {
+                TokenStore {
+                    inner: Arc::new(SlockCell::new(InnerTokenStore {
+                        dispatcher: StoreDispatcher::new(initial),
+                        equal_listeners: HashMap::new(),
+                    }))
+                }
+            }
+        }
 
-            fn deref(&self) -> &U::Target {
-                self.src.borrow()
+        impl<S> Default for TokenStore<S, Filterless<S>>
+            where S: Default + Stateful + Copy + Hash + Eq {
+            #[track_caller]
+            fn default() -> Self {
+                Self::new(S::default())
             }
         }
-    }
 
-    mod fixed_signal {
-        use std::ops::Deref;
-        use std::sync::Arc;
+        impl<S> Default for TokenStore<S, Filter<S>>
+            where S: Default + Stateful + Copy + Hash + Eq {
+            fn default() -> Self {
+                Self::new_with_filter(S::default())
+            }
+        }
 
-        use crate::core::Slock;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::Signal;
-        use crate::util::marker::ThreadMarker;
-        use crate::util::test_util::QuarveAllocTag;
+        impl<S, M> StoreContainer for TokenStore<S, M>
+            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
+            impl_store_container_inner!();
+        }
 
-        use super::InnerSignal;
-        use super::SignalRef;
+        impl<S, M> Bindable<M> for TokenStore<S, M>
+            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
+            impl_bindable_inner!(S, M);
+        }
 
-        struct InnerFixedSignal<T: Send + 'static>(QuarveAllocTag, T);
+        impl<S, M> TokenStore<S, M>
+            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
+            impl_adhoc_inner!(S, M);
+        }
 
-        impl<T> InnerSignal for InnerFixedSignal<T> where T: Send + 'static {
-            type Target = T;
+        impl<S, M> Signal for TokenStore<S, M>
+            where S: Stateful + Copy + Hash + Eq, M: StateFilter<Target=S> {
+            impl_signal_inner!(S);
+        }
 
-            fn borrow(&self) -> &Self::Target {
+        impl<S, A> RawStoreSharedOwner<A> for TokenStore<S, A>
+            where S: Stateful + Copy + Hash + Eq, A: StateFilter<Target=S> {
+            type Inner = InnerTokenStore<S, A>;
+
+            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
+                TokenStore {
+                    inner: arc,
+                }
+            }
+
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
+                #[cfg(debug_assertions)]
+                crate::util::test_util::note_strong_handle(self, &self.inner);
+                &self.inner
+            }
+
+            fn arc_clone(&self) -> Self {
+                TokenStore {
+                    inner: Arc::clone(&self.inner)
+                }
+            }
+        }
+    }
+
+    mod derived_store {
+        use std::marker::PhantomData;
+        use std::ops::{Deref, DerefMut};
+        use std::sync::Arc;
+
+        use crate::state::listener::{GeneralListener, InverseListener};
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::general_binding::{GeneralBinding, GeneralWeakBinding};
+        use crate::state::store::inverse_listener_holder::NullInverseListenerHolder;
+        use crate::state::store::macros::{impl_adhoc_inner, impl_bindable_inner, impl_signal_inner, impl_store_container_inner};
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::store::state_ref::StateRef;
+        use crate::state::store::store_dispatcher::StoreDispatcher;
+        use crate::state::Filter;
+        use crate::state::{Bindable, StateListener};
+        use crate::util::marker::ThreadMarker;
+        use crate::view::undo_manager::UndoBucket;
+        use crate::{
+            core::Slock,
+            state::{
+                Filterless, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, StoreContainer,
+            },
+        };
+
+        pub(super) struct InnerDerivedStore<S: Stateful, F: StateFilter<Target=S>> {
+            dispatcher: StoreDispatcher<S, F, NullInverseListenerHolder>
+        }
+
+        impl<S, F> RawStore<F> for InnerDerivedStore<S, F>
+            where S: Stateful, F: StateFilter<Target=S>
+        {
+            type InverseListenerHolder = NullInverseListenerHolder;
+
+            fn dispatcher(&self) -> &StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &self.dispatcher
+            }
+
+            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<S, F, Self::InverseListenerHolder> {
+                &mut self.dispatcher
+            }
+
+            fn apply(arc: &Arc<SlockCell<Self>>, alt_action: impl IntoAction<S::Action, S>, is_inverse: bool, s: Slock<impl ThreadMarker>) {
+                // note that derived store does not care whether or not the action is inverse or not
+                // (except in the case of filters)
+
+                let mut borrow = arc.borrow_mut(s);
+                let inner = borrow.deref_mut();
+
+                inner.dispatcher.apply(alt_action, |_| unreachable!(), is_inverse, s);
+            }
+        }
+
+        pub struct DerivedStore<S: Stateful, F: StateFilter<Target=S>=Filterless<S>>
+        {
+            inner: Arc<SlockCell<InnerDerivedStore<S, F>>>
+        }
+
+        impl<S> DerivedStore<S, Filterless<S>>
+            where S: Stateful
+        {
+            #[track_caller]
+            pub fn new(initial: S) -> Self {
+                DerivedStore {
+                    inner: Arc::new(SlockCell::new(InnerDerivedStore {
+                        dispatcher: StoreDispatcher::new(initial)
+                    }))
+                }
+            }
+        }
+
+        impl<S> DerivedStore<S, Filter<S>>
+            where S: Stateful
+        {
+            #[track_caller]
+            pub fn new_with_filter(initial: S) -> Self {
+                DerivedStore {
+                    inner: Arc::new(SlockCell::new(InnerDerivedStore {
+                        dispatcher: StoreDispatcher::new(initial),
+                    }))
+                }
+            }
+        }
+
+        impl<S> Default for DerivedStore<S, Filterless<S>>
+            where S: Stateful + Default
+        {
+            fn default() -> Self {
+                Self::new(S::default())
+            }
+        }
+
+        impl<S> Default for DerivedStore<S, Filter<S>>
+            where S: Stateful + Default
+        {
+            fn default() -> Self {
+                Self::new_with_filter(S::default())
+            }
+        }
+
+        impl<S, M> StoreContainer for DerivedStore<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_store_container_inner!();
+        }
+
+        impl<S, M> Bindable<M> for DerivedStore<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_bindable_inner!(S, M);
+        }
+
+        impl<S, M> DerivedStore<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_adhoc_inner!(S, M);
+        }
+
+        impl<S, M> Signal for DerivedStore<S, M>
+            where S: Stateful, M: StateFilter<Target=S>
+        {
+            impl_signal_inner!(S);
+        }
+
+        impl<S, F> RawStoreSharedOwner<F> for DerivedStore<S, F>
+            where S: Stateful, F: StateFilter<Target=S>
+        {
+            type Inner = InnerDerivedStore<S, F>;
+
+            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
+                DerivedStore {
+                    inner: arc
+                }
+            }
+
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
+                #[cfg(debug_assertions)]
+                crate::util::test_util::note_strong_handle(self, &self.inner);
+                &self.inner
+            }
+
+            fn arc_clone(&self) -> Self {
+                DerivedStore {
+                    inner: Arc::clone(&self.inner)
+                }
+            }
+        }
+    }
+
+    // Stores serialize their current value. Deserializing always produces a fresh store,
+    // so a loaded model has no listeners (and in particular, no undo history)
+    // until it is mounted
+    #[cfg(feature = "serde")]
+    mod store_serde {
+        use std::hash::Hash;
+
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use crate::core::slock_is_held;
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::{DerivedStore, Filter, Filterless, StateFilter, Stateful, Store, TokenStore};
+
+        fn serialize_raw<F, R, Z>(owner: &R, serializer: Z) -> Result<Z::Ok, Z::Error>
+            where F: StateFilter, F::Target: Serialize, R: RawStoreSharedOwner<F>, Z: Serializer
+        {
+            assert!(slock_is_held(), "Stores can only be serialized by the thread that owns the slock");
+
+            // safety: we just checked that the current thread owns the state lock
+            let inner = unsafe { owner.inner_ref().borrow_unchecked() };
+            inner.dispatcher().data().serialize(serializer)
+        }
+
+        impl<S, F> Serialize for Store<S, F>
+            where S: Stateful + Serialize, F: StateFilter<Target=S>
+        {
+            fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+                serialize_raw(self, serializer)
+            }
+        }
+
+        impl<S, F> Serialize for TokenStore<S, F>
+            where S: Stateful + Copy + Hash + Eq + Serialize, F: StateFilter<Target=S>
+        {
+            fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+                serialize_raw(self, serializer)
+            }
+        }
+
+        impl<S, F> Serialize for DerivedStore<S, F>
+            where S: Stateful + Serialize, F: StateFilter<Target=S>
+        {
+            fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+                serialize_raw(self, serializer)
+            }
+        }
+
+        macro_rules! impl_deserialize {
+            ($store: ident, $filter: ident, $constructor: ident $(, $bound: path)*) => {
+                impl<'de, S> Deserialize<'de> for $store<S, $filter<S>>
+                    where S: Stateful + Deserialize<'de> $(+ $bound)*
+                {
+                    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        S::deserialize(deserializer).map(Self::$constructor)
+                    }
+                }
+            };
+        }
+
+        impl_deserialize!(Store, Filterless, new);
+        impl_deserialize!(Store, Filter, new_with_filter);
+        impl_deserialize!(TokenStore, Filterless, new, Copy, Hash, Eq);
+        impl_deserialize!(TokenStore, Filter, new_with_filter, Copy, Hash, Eq);
+        impl_deserialize!(DerivedStore, Filterless, new);
+        impl_deserialize!(DerivedStore, Filter, new_with_filter);
+    }
+
+    // Journals record every forward action applied to a store tree
+    // (including undo and redo) so that the exact sequence can be replayed
+    // against a fresh copy of the model
+    #[cfg(feature = "serde")]
+    mod journal {
+        use std::collections::{BTreeMap, HashMap};
+        use std::fmt::{Display, Formatter};
+        use std::hash::Hash;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::{Arc, Weak};
+        use std::time::{Duration, Instant};
+
+        use serde::de::DeserializeOwned;
+        use serde::{Deserialize, Serialize};
+
+        use crate::core::{MSlock, Slock};
+        use crate::state::listener::{DirectlyInvertible, StateListener};
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::{permute, Binding, DerivedStore, EditingRope, EditingString, MapActionBasis, Stateful, StateFilter, Store, StoreContainer, TokenStore, VecActionBasis, Word};
+        use crate::util::marker::ThreadMarker;
+        use crate::view::undo_manager::with_history_label;
+
+        pub type JournalValue = serde_json::Value;
+
+        const DETACHED: usize = usize::MAX;
+
+        type RawActionEncoder = unsafe fn(*const ()) -> Result<JournalValue, serde_json::Error>;
+        // checks that an encoded action can be decoded for the store's type
+        type RawActionDecoder = fn(JournalValue) -> Result<(), serde_json::Error>;
+
+        // applies an encoded action as an inverse
+        type InverseReplay = Arc<dyn Fn(JournalValue, Slock) -> Result<(), JournalError> + Send + Sync>;
+
+        #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+        pub enum PathSegment {
+            Field(String),
+            Index(usize),
+            Key(JournalValue),
+        }
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct JournalEntry {
+            pub path: Vec<PathSegment>,
+            // time since the journal was created
+            pub time: Duration,
+            pub action: JournalValue,
+        }
+
+        #[derive(Debug)]
+        pub enum JournalError {
+            InvalidPath(Vec<PathSegment>),
+            InvalidAction(serde_json::Error),
+            // an action (or map key) of the store at the path could not be encoded
+            Unencodable(Vec<PathSegment>, serde_json::Error),
+        }
+
+        impl Display for JournalError {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    JournalError::InvalidPath(path) => write!(f, "no store at journal path {:?}", path),
+                    JournalError::InvalidAction(err) => write!(f, "could not decode journaled action: {}", err),
+                    JournalError::Unencodable(path, err) => write!(f, "could not encode journaled action at {:?}: {}", path, err),
+                }
+            }
+        }
+
+        impl std::error::Error for JournalError {}
+
+        enum PathNode {
+            Field(&'static str),
+            // indices into vectors change as the vector is modified
+            // so that the containing vector keeps these up to date
+            Index(Arc<AtomicUsize>),
+            // false once removed from the map
+            Key(JournalValue, Arc<AtomicBool>),
+        }
+
+        /// Location of a store relative to the root container the journal
+        /// was attached to
+        #[derive(Clone, Default)]
+        pub struct JournalPath(Arc<Vec<PathNode>>);
+
+        impl JournalPath {
+            pub fn root() -> Self {
+                JournalPath::default()
+            }
+
+            pub fn field(&self, name: &'static str) -> Self {
+                self.child(PathNode::Field(name))
+            }
+
+            fn index(&self, index: Arc<AtomicUsize>) -> Self {
+                self.child(PathNode::Index(index))
+            }
+
+            fn key(&self, key: JournalValue, attached: Arc<AtomicBool>) -> Self {
+                self.child(PathNode::Key(key, attached))
+            }
+
+            fn child(&self, node: PathNode) -> Self {
+                let mut nodes: Vec<_> = self.0.iter()
+                    .map(|n| match n {
+                        PathNode::Field(f) => PathNode::Field(f),
+                        PathNode::Index(i) => PathNode::Index(i.clone()),
+                        PathNode::Key(k, a) => PathNode::Key(k.clone(), a.clone()),
+                    })
+                    .collect();
+                nodes.push(node);
+
+                JournalPath(Arc::new(nodes))
+            }
+
+            // None if the store has since been removed from its container
+            fn resolve(&self) -> Option<Vec<PathSegment>> {
+                self.0.iter()
+                    .map(|n| match n {
+                        PathNode::Field(f) => Some(PathSegment::Field(f.to_string())),
+                        PathNode::Index(i) => {
+                            let i = i.load(Ordering::Relaxed);
+                            (i != DETACHED).then_some(PathSegment::Index(i))
+                        }
+                        PathNode::Key(k, a) => {
+                            a.load(Ordering::Relaxed).then(|| PathSegment::Key(k.clone()))
+                        }
+                    })
+                    .collect()
+            }
+        }
+
+        // a store that has been attached to the journal,
+        // used to locate the inverses it produces
+        struct IndexedStore {
+            alive: Weak<dyn Send + Sync>,
+            path: JournalPath,
+            encode: RawActionEncoder,
+            decode: RawActionDecoder,
+            replay: InverseReplay,
+        }
+
+        struct JournalInner {
+            start: Instant,
+            entries: Vec<JournalEntry>,
+            // actions that could not be encoded (and so are missing from entries)
+            failures: Vec<JournalError>,
+            // index only journals do not keep entries
+            log: bool,
+            stores: HashMap<usize, IndexedStore>,
+        }
+
+        /// An append only log of actions
+        #[derive(Clone)]
+        pub struct Journal {
+            inner: Arc<SlockCell<JournalInner>>
+        }
+
+        #[derive(Clone)]
+        pub struct WeakJournal {
+            inner: Weak<SlockCell<JournalInner>>
+        }
+
+        impl Journal {
+            pub fn new() -> Self {
+                Self::with_log(true)
+            }
+
+            /// A journal that only tracks the location of stores
+            /// (so that their inverses may be persisted)
+            /// but does not record any entries
+            pub fn index_only() -> Self {
+                Self::with_log(false)
+            }
+
+            fn with_log(log: bool) -> Self {
+                Journal {
+                    inner: Arc::new(SlockCell::new(JournalInner {
+                        start: Instant::now(),
+                        entries: Vec::new(),
+                        failures: Vec::new(),
+                        log,
+                        stores: HashMap::new(),
+                    }))
+                }
+            }
+
+            pub fn len(&self, s: Slock<impl ThreadMarker>) -> usize {
+                self.inner.borrow(s).entries.len()
+            }
+
+            pub fn is_empty(&self, s: Slock<impl ThreadMarker>) -> bool {
+                self.len(s) == 0
+            }
+
+            /// A copy of the log that can be serialized or replayed
+            pub fn entries(&self, s: Slock<impl ThreadMarker>) -> Vec<JournalEntry> {
+                self.inner.borrow(s).entries.clone()
+            }
+
+            /// Errors for every action that could not be encoded since the last call.
+            /// These actions are missing from the log, so that replaying the entries
+            /// will not reproduce the model
+            pub fn take_failures(&self, s: Slock<impl ThreadMarker>) -> Vec<JournalError> {
+                std::mem::take(&mut self.inner.borrow_mut(s).failures)
+            }
+
+            pub fn downgrade(&self) -> WeakJournal {
+                WeakJournal {
+                    inner: Arc::downgrade(&self.inner)
+                }
+            }
+
+            /// Finds where the store that produced `inverse` currently
+            /// lives in the journaled tree. None if the inverse was not
+            /// produced by a journaled store (or the store has since been removed)
+            pub fn locate_inverse(&self, inverse: &dyn DirectlyInvertible, s: Slock<impl ThreadMarker>) -> Option<InverseLocation> {
+                let id = inverse.id();
+                if id == 0 {
+                    return None;
+                }
+
+                let inner = self.inner.borrow(s);
+                let store = inner.stores.get(&id)?;
+                if store.alive.strong_count() == 0 {
+                    return None;
+                }
+
+                Some(InverseLocation {
+                    id,
+                    path: store.path.resolve()?,
+                    encode: store.encode,
+                })
+            }
+
+            /// Applies an inverse to the store currently at `path`
+            /// (relative to the root container of the journal)
+            pub fn replay_inverse(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                let replay = self.inner.borrow(s).stores.values()
+                    .find(|store| store.alive.strong_count() > 0 && store.path.resolve().is_some_and(|p| p == path))
+                    .map(|store| store.replay.clone())
+                    .ok_or_else(|| JournalError::InvalidPath(path.to_vec()))?;
+
+                // the journal must not be borrowed as the action may attach new stores
+                replay(action, s.to_general_slock())
+            }
+
+            /// Checks that an inverse persisted at `path` can be restored against
+            /// the tree this journal is attached to. The nearest store along the path must
+            /// currently exist and, if it is the target itself, must be able to decode `action`.
+            /// Anything past that store (e.g. an element that only exists at another point
+            /// in the history) cannot be checked until the inverse is applied
+            pub fn validate_inverse(&self, path: &[PathSegment], action: &JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                let inner = self.inner.borrow(s);
+                let nearest = inner.stores.values()
+                    .filter(|store| store.alive.strong_count() > 0)
+                    .filter_map(|store| store.path.resolve().map(|p| (p, store)))
+                    .filter(|(p, _)| path.starts_with(p))
+                    .max_by_key(|(p, _)| p.len());
+
+                match nearest {
+                    None => Err(JournalError::InvalidPath(path.to_vec())),
+                    Some((p, store)) if p.len() == path.len() => {
+                        (store.decode)(action.clone())
+                            .map_err(JournalError::InvalidAction)
+                    }
+                    Some(_) => Ok(())
+                }
+            }
+
+            fn index_store(&self, id: usize, store: IndexedStore, s: Slock) {
+                let mut inner = self.inner.borrow_mut(s);
+                inner.stores.retain(|_, store| store.alive.strong_count() > 0);
+                inner.stores.insert(id, store);
+            }
+
+            fn logs(&self, s: Slock) -> bool {
+                self.inner.borrow(s).log
+            }
+
+            fn record_failure(&self, path: Vec<PathSegment>, err: serde_json::Error, s: Slock) {
+                self.inner.borrow_mut(s).failures.push(JournalError::Unencodable(path, err));
+            }
+
+            fn record(&self, path: Vec<PathSegment>, action: JournalValue, s: Slock) {
+                let mut inner = self.inner.borrow_mut(s);
+                let time = inner.start.elapsed();
+                inner.entries.push(JournalEntry {
+                    path,
+                    time,
+                    action,
+                });
+            }
+        }
+
+        impl Default for Journal {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl WeakJournal {
+            pub fn upgrade(&self) -> Option<Journal> {
+                self.inner.upgrade()
+                    .map(|inner| Journal { inner })
+            }
+        }
+
+        /// Stateful values whose actions can be journaled
+        pub trait JournaledState: Stateful {
+            fn encode_action(action: &Self::Action) -> Result<JournalValue, serde_json::Error>;
+
+            fn decode_action(value: JournalValue) -> Result<Self::Action, serde_json::Error>;
+
+            // Analogous to the subtree listeners of Stateful
+            // returns an action listener to be applied on the surrounding container
+            #[allow(unused_variables)]
+            fn subtree_journal(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>)
+                -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static> {
+                None::<fn(&Self, &Self::Action, Slock) -> bool>
+            }
+
+            // path is relative to this value
+            #[allow(unused_variables)]
+            fn replay_subtree(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                Err(JournalError::InvalidPath(path.to_vec()))
+            }
+        }
+
+        pub trait Journaled: StoreContainer {
+            fn subtree_journal_at(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>);
+
+            fn replay_at(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError>;
+
+            /// Records all actions applied to this container (and its children)
+            /// for as long as the journal is alive
+            fn subtree_journal(&self, journal: &Journal, s: Slock<impl ThreadMarker>) {
+                self.subtree_journal_at(&JournalPath::root(), journal, s);
+            }
+
+            /// Applies all entries in order. Paths are relative to this container
+            fn replay(&self, entries: &[JournalEntry], s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                for entry in entries {
+                    self.replay_at(&entry.path, entry.action.clone(), s)
+                        .map_err(|err| match err {
+                            JournalError::InvalidPath(_) => JournalError::InvalidPath(entry.path.clone()),
+                            err => err
+                        })?;
+                }
+
+                Ok(())
+            }
+        }
+
+        fn journal_raw_store<F, R>(owner: &R, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>)
+            where F: StateFilter, F::Target: JournaledState, R: RawStoreSharedOwner<F>
+        {
+            let id = Arc::as_ptr(owner.inner_ref()) as usize;
+            let weak = Arc::downgrade(owner.inner_ref());
+            journal.index_store(id, IndexedStore {
+                alive: weak.clone() as Weak<dyn Send + Sync>,
+                path: path.clone(),
+                encode: encode_raw_action::<F::Target>,
+                decode: |action| <F::Target as JournaledState>::decode_action(action).map(|_| ()),
+                replay: Arc::new(move |action, s| {
+                    let action = <F::Target as JournaledState>::decode_action(action)
+                        .map_err(JournalError::InvalidAction)?;
+                    if let Some(inner) = weak.upgrade() {
+                        R::Inner::apply(&inner, action, true, s);
+                    }
+                    Ok(())
+                }),
+            }, s.to_general_slock());
+
+            let mut inner = owner.inner_ref().borrow_mut(s);
+            if journal.logs(s.to_general_slock()) {
+                let weak = journal.downgrade();
+                let own_path = path.clone();
+                inner.dispatcher_mut().add_listener(StateListener::ActionListener(Box::new(move |_, action, s| {
+                    let Some(journal) = weak.upgrade() else {
+                        return false;
+                    };
+
+                    // removed from the parent container
+                    // if it is ever reinserted, a new listener is made
+                    let Some(resolved) = own_path.resolve() else {
+                        return false;
+                    };
+
+                    // panicking here would leave the transaction half applied
+                    match <F::Target as JournaledState>::encode_action(action) {
+                        Ok(encoded) => journal.record(resolved, encoded, s),
+                        Err(err) => journal.record_failure(resolved, err, s),
+                    }
+
+                    true
+                })));
+            }
+
+            let subtree = inner.dispatcher().data().subtree_journal(path, journal, s);
+            if let Some(listener) = subtree {
+                inner.dispatcher_mut().add_listener(StateListener::ActionListener(Box::new(listener)));
+            }
+        }
+
+        fn replay_raw_store<F, R>(owner: &R, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError>
+            where F: StateFilter, F::Target: JournaledState, R: RawStoreSharedOwner<F>
+        {
+            if path.is_empty() {
+                let action = <F::Target as JournaledState>::decode_action(action)
+                    .map_err(JournalError::InvalidAction)?;
+                owner.apply(action, s);
+                Ok(())
+            }
+            else {
+                owner.inner_ref().borrow(s).dispatcher().data()
+                    .replay_subtree(path, action, s)
+            }
+        }
+
+        // attaches the value stored under key in a map at path
+        // None if the key cannot be encoded, in which case the value is not journaled
+        fn journal_map_value<K, V>(key: &K, store: &V, path: &JournalPath, journal: &Journal, s: Slock) -> Option<Arc<AtomicBool>>
+            where K: Serialize, V: Journaled
+        {
+            match serde_json::to_value(key) {
+                Ok(encoded) => {
+                    let attached = Arc::new(AtomicBool::new(true));
+                    store.subtree_journal_at(&path.key(encoded, attached.clone()), journal, s);
+                    Some(attached)
+                }
+                Err(err) => {
+                    if let Some(resolved) = path.resolve() {
+                        journal.record_failure(resolved, err, s);
+                    }
+                    None
+                }
+            }
+        }
+
+        // the caller must guarantee that action points to an S::Action
+        unsafe fn encode_raw_action<S: JournaledState>(action: *const ()) -> Result<JournalValue, serde_json::Error> {
+            S::encode_action(&*(action as *const S::Action))
+        }
+
+        /// Where an inverse is to be applied, relative to the root
+        /// container of the journal
+        pub struct InverseLocation {
+            id: usize,
+            path: Vec<PathSegment>,
+            encode: RawActionEncoder,
+        }
+
+        impl InverseLocation {
+            pub fn path(&self) -> &[PathSegment] {
+                &self.path
+            }
+
+            /// Serializes the current action of `inverse`.
+            /// The inverse must be the one this location was made from,
+            /// and must not have been inverted yet
+            pub fn persist(&self, inverse: &dyn DirectlyInvertible, s: MSlock) -> Option<PersistedInverse> {
+                if inverse.id() != self.id {
+                    return None;
+                }
+
+                // safety: an inverse with a matching id holds a weak reference
+                // to the exact store that was indexed, so that the address cannot
+                // have been reused by a store of another type
+                let action = unsafe {
+                    (self.encode)(inverse.action_pointer(s))
+                }.ok()?;
+
+                Some(PersistedInverse {
+                    path: self.path.clone(),
+                    action,
+                    label: inverse.history_label(),
+                    forward_description: inverse.forward_description(),
+                    backward_description: inverse.backward_description(),
+                })
+            }
+        }
+
+        /// An inverse action that can be saved alongside its document
+        /// and later be restored against a fresh copy of the model
+        #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+        pub struct PersistedInverse {
+            pub path: Vec<PathSegment>,
+            pub action: JournalValue,
+            pub label: Option<String>,
+            pub forward_description: String,
+            pub backward_description: String,
+        }
+
+        impl PersistedInverse {
+            /// Inverting the result applies the action to the store
+            /// at `path` within the tree that `journal` is attached to
+            pub fn restore(self, journal: &Journal) -> Box<dyn DirectlyInvertible> {
+                Box::new(RestoredInverse {
+                    inverse: Some(self),
+                    journal: journal.clone(),
+                })
+            }
+        }
+
+        struct RestoredInverse {
+            inverse: Option<PersistedInverse>,
+            journal: Journal,
+        }
+
+        impl DirectlyInvertible for RestoredInverse {
+            fn invert(&mut self, s: MSlock) {
+                self.rollback(s.to_general_slock());
+            }
+
+            fn rollback(&mut self, s: Slock) {
+                let inverse = self.inverse.take().unwrap();
+                with_history_label(inverse.label, || {
+                    if let Err(err) = self.journal.replay_inverse(&inverse.path, inverse.action, s) {
+                        panic!("Persisted inverse does not match the model: {}", err);
+                    }
+                });
+            }
+
+            unsafe fn right_multiply(&mut self, _by: Box<dyn DirectlyInvertible>, _s: MSlock) {
+                unreachable!()
+            }
+
+            unsafe fn action_pointer(&self, _s: MSlock) -> *const () {
+                unreachable!()
+            }
+
+            unsafe fn forget_action(&mut self, _s: MSlock) {
+                unreachable!()
+            }
+
+            fn id(&self) -> usize {
+                0
+            }
+
+            fn history_label(&self) -> Option<String> {
+                self.inverse.as_ref().unwrap().label.clone()
+            }
+
+            fn forward_description(&self) -> String {
+                self.inverse.as_ref().unwrap().forward_description.clone()
+            }
+
+            fn backward_description(&self) -> String {
+                self.inverse.as_ref().unwrap().backward_description.clone()
+            }
+        }
+
+        impl<S, F> Journaled for Store<S, F>
+            where S: JournaledState, F: StateFilter<Target=S>
+        {
+            fn subtree_journal_at(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>) {
+                journal_raw_store(self, path, journal, s);
+            }
+
+            fn replay_at(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                replay_raw_store(self, path, action, s)
+            }
+        }
+
+        impl<S, F> Journaled for TokenStore<S, F>
+            where S: JournaledState + Copy + Hash + Eq, F: StateFilter<Target=S>
+        {
+            fn subtree_journal_at(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>) {
+                journal_raw_store(self, path, journal, s);
+            }
+
+            fn replay_at(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                replay_raw_store(self, path, action, s)
+            }
+        }
+
+        // derived stores are updated as a consequence of other stores
+        // so that replaying their actions would apply them twice
+        impl<S, F> Journaled for DerivedStore<S, F>
+            where S: Stateful, F: StateFilter<Target=S>
+        {
+            fn subtree_journal_at(&self, _path: &JournalPath, _journal: &Journal, _s: Slock<impl ThreadMarker>) {
+
+            }
+
+            fn replay_at(&self, path: &[PathSegment], _action: JournalValue, _s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                Err(JournalError::InvalidPath(path.to_vec()))
+            }
+        }
+
+        macro_rules! impl_serde_journaled_state {
+            ($($t:ty), *) => {
+                $(
+                    impl JournaledState for $t {
+                        fn encode_action(action: &Self::Action) -> Result<JournalValue, serde_json::Error> {
+                            serde_json::to_value(action)
+                        }
+
+                        fn decode_action(value: JournalValue) -> Result<Self::Action, serde_json::Error> {
+                            serde_json::from_value(value)
+                        }
+                    }
+                )*
+            };
+        }
+
+        impl_serde_journaled_state!(
+            i8, u8,
+            i16, u16,
+            i32, u32,
+            i64, u64,
+            i128, u128,
+            isize, usize,
+            f32, f64,
+            bool, String,
+            Option<i8>, Option<u8>,
+            Option<i16>, Option<u16>,
+            Option<i32>, Option<u32>,
+            Option<i64>, Option<u64>,
+            Option<i128>, Option<u128>,
+            Option<isize>, Option<usize>,
+            Option<f32>, Option<f64>,
+            Option<bool>, Option<String>,
+            EditingString, EditingRope
+        );
+
+        impl<T> JournaledState for Vec<T>
+            where T: Journaled + Serialize + DeserializeOwned
+        {
+            // inserted elements are serialized by value
+            fn encode_action(action: &Self::Action) -> Result<JournalValue, serde_json::Error> {
+                serde_json::to_value(action)
+            }
+
+            fn decode_action(value: JournalValue) -> Result<Self::Action, serde_json::Error> {
+                serde_json::from_value(value)
+            }
+
+            fn subtree_journal(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>)
+                -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static> {
+                let mut indices: Vec<Arc<AtomicUsize>> = Vec::with_capacity(self.len());
+                for (i, store) in self.iter().enumerate() {
+                    let index = Arc::new(AtomicUsize::new(i));
+                    store.subtree_journal_at(&path.index(index.clone()), journal, s);
+                    indices.push(index);
+                }
+
+                let weak = journal.downgrade();
+                let path = path.clone();
+                Some(move |_v: &Vec<T>, w: &Word<VecActionBasis<T>>, s: Slock| {
+                    let Some(journal) = weak.upgrade() else {
+                        return false;
+                    };
+
+                    let attach = |store: &T, at: usize, indices: &mut Vec<Arc<AtomicUsize>>| {
+                        let index = Arc::new(AtomicUsize::new(at));
+                        store.subtree_journal_at(&path.index(index.clone()), &journal, s);
+                        indices.insert(at, index);
+                    };
+
+                    for a in w.iter() {
+                        match a {
+                            VecActionBasis::Insert(store, at) => {
+                                attach(store, *at, &mut indices);
+                            }
+                            VecActionBasis::InsertMany(stores, at) => {
+                                for (i, store) in stores.iter().enumerate() {
+                                    attach(store, at + i, &mut indices);
+                                }
+                            }
+                            VecActionBasis::Remove(at) => {
+                                indices.remove(*at).store(DETACHED, Ordering::Relaxed);
+                            }
+                            VecActionBasis::RemoveMany(range) => {
+                                indices.drain(range.clone())
+                                    .for_each(|index| index.store(DETACHED, Ordering::Relaxed));
+                            }
+                            VecActionBasis::Swap(a, b) => {
+                                indices.swap(*a, *b);
+                            }
+                            VecActionBasis::Move(from, at) => {
+                                let index = indices.remove(*from);
+                                indices.insert(*at, index);
+                            }
+                            VecActionBasis::Replace(at, store) => {
+                                indices.remove(*at).store(DETACHED, Ordering::Relaxed);
+                                attach(store, *at, &mut indices);
+                            }
+                            VecActionBasis::Permute(permutation) => {
+                                permute(&mut indices, permutation);
+                            }
+                        }
+                    }
+
+                    for (i, index) in indices.iter().enumerate() {
+                        index.store(i, Ordering::Relaxed);
+                    }
+
+                    true
+                })
+            }
+
+            fn replay_subtree(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                match path.split_first() {
+                    Some((PathSegment::Index(i), rest)) if *i < self.len() => {
+                        self[*i].replay_at(rest, action, s)
+                    }
+                    _ => Err(JournalError::InvalidPath(path.to_vec()))
+                }
+            }
+        }
+
+        macro_rules! impl_map_journaled_state {
+            ($map: ident, $($bound: path),*) => {
+                impl<K, V> JournaledState for $map<K, V>
+                    where K: Clone + Send + Serialize + DeserializeOwned + 'static $(+ $bound)*,
+                          V: Journaled + Serialize + DeserializeOwned
+                {
+                    fn encode_action(action: &Self::Action) -> Result<JournalValue, serde_json::Error> {
+                        serde_json::to_value(action)
+                    }
+
+                    fn decode_action(value: JournalValue) -> Result<Self::Action, serde_json::Error> {
+                        serde_json::from_value(value)
+                    }
+
+                    fn subtree_journal(&self, path: &JournalPath, journal: &Journal, s: Slock<impl ThreadMarker>)
+                        -> Option<impl Send + FnMut(&Self, &Self::Action, Slock) -> bool + 'static> {
+                        let mut attached: $map<K, Arc<AtomicBool>> = self.iter()
+                            .filter_map(|(key, store)| {
+                                journal_map_value(key, store, path, journal, s.to_general_slock())
+                                    .map(|flag| (key.clone(), flag))
+                            })
+                            .collect();
+
+                        let weak = journal.downgrade();
+                        let path = path.clone();
+                        Some(move |_m: &$map<K, V>, w: &Word<MapActionBasis<K, V>>, s: Slock| {
+                            let Some(journal) = weak.upgrade() else {
+                                return false;
+                            };
+
+                            for a in w.iter() {
+                                let (key, store) = match a {
+                                    MapActionBasis::Insert(key, store) | MapActionBasis::Replace(key, store) => (key, Some(store)),
+                                    MapActionBasis::Remove(key) => (key, None),
+                                };
+
+                                if let Some(old) = attached.remove(key) {
+                                    old.store(false, Ordering::Relaxed);
+                                }
+
+                                if let Some(flag) = store.and_then(|store| journal_map_value(key, store, &path, &journal, s)) {
+                                    attached.insert(key.clone(), flag);
+                                }
+                            }
+
+                            true
+                        })
+                    }
+
+                    fn replay_subtree(&self, path: &[PathSegment], action: JournalValue, s: Slock<impl ThreadMarker>) -> Result<(), JournalError> {
+                        let Some((PathSegment::Key(key), rest)) = path.split_first() else {
+                            return Err(JournalError::InvalidPath(path.to_vec()));
+                        };
+
+                        let key: K = serde_json::from_value(key.clone())
+                            .map_err(JournalError::InvalidAction)?;
+                        match self.get(&key) {
+                            Some(store) => store.replay_at(rest, action, s),
+                            None => Err(JournalError::InvalidPath(path.to_vec()))
+                        }
+                    }
+                }
+            };
+        }
+
+        impl_map_journaled_state!(HashMap, Hash, Eq);
+        impl_map_journaled_state!(BTreeMap, Ord);
+    }
+
+    // Drives a binding from an async source.
+    // The slock is acquired per action and released before the next poll
+    // so that the stream may be driven from any executor
+    #[cfg(feature = "futures")]
+    mod stream_binding {
+        use std::future::Future;
+        use std::pin::pin;
+
+        use futures::{Stream, StreamExt};
+
+        use crate::core::slock_owner;
+        use crate::state::{Binding, IntoAction, StateFilter, Stateful, WeakBinding};
+
+        pub trait StreamBindingExt<F>: Binding<F> where F: StateFilter {
+            /// Applies every action yielded by `stream` to this binding.
+            /// The returned future completes once either the stream
+            /// terminates or the underlying store is freed.
+            /// Note that the future must not be polled by a thread that
+            /// currently holds the slock
+            fn apply_stream<S>(&self, stream: S) -> impl Future<Output=()> + Send + 'static
+                where S: Stream + Send + 'static,
+                      S::Item: IntoAction<<<F as StateFilter>::Target as Stateful>::Action, <F as StateFilter>::Target> + Send,
+                      Self::WeakBinding: Send + 'static
+            {
+                let weak = self.downgrade();
+                async move {
+                    let mut stream = pin!(stream);
+                    while let Some(action) = stream.next().await {
+                        let s = slock_owner();
+                        let Some(binding) = weak.upgrade() else {
+                            break;
+                        };
+                        binding.apply(action, s.marker());
+                    }
+                }
+            }
+        }
+
+        impl<F, B> StreamBindingExt<F> for B where F: StateFilter, B: Binding<F> { }
+    }
+
+    mod general_binding {
+        use std::marker::PhantomData;
+        use std::ops::Deref;
+        use std::sync::{Arc, Weak};
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::check_listener_cycle;
+
+        use crate::core::Slock;
+        use crate::state::signal::GeneralSignal;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::store::state_ref::StateRef;
+        use crate::state::Signal;
+        use crate::state::{StateFilter, StateListener, WeakBinding};
+        use crate::util::marker::ThreadMarker;
+
+        // will find better solution in the future
+        // This really shouldn't even have to be public in first place
+        // it's just because of rust typing issues
+        #[allow(private_bounds)]
+        pub struct GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            pub(super) weak: Weak<SlockCell<I::Inner>>,
+            pub(super) phantom: PhantomData<(Weak<SlockCell<F>>, I)>
+        }
+
+        impl<F, I> Clone for GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            fn clone(&self) -> Self {
+                GeneralWeakBinding {
+                    weak: self.weak.clone(),
+                    phantom: Default::default(),
+                }
+            }
+        }
+
+        #[allow(private_bounds)]
+        pub struct GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            pub(super) inner: I,
+            pub(super) phantom: PhantomData<Arc<SlockCell<F>>>,
+        }
+
+        impl<F, I> WeakBinding<F> for GeneralWeakBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            type Binding = GeneralBinding<F, I>;
+
+            fn upgrade(&self) -> Option<Self::Binding> {
+                let Some(inner) = self.weak.upgrade() else {
+                    return None;
+                };
+
+                I::Inner::strong_count_increment(&inner);
+
+                Some(GeneralBinding {
+                    inner: I::from_ref(inner),
+                    phantom: Default::default(),
+                })
+            }
+        }
+
+        impl<F, I> Clone for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            fn clone(&self) -> Self {
+                I::Inner::strong_count_increment(self.inner.inner_ref());
+
+                GeneralBinding {
+                    inner: self.inner.arc_clone(),
+                    phantom: PhantomData
+                }
+            }
+        }
+
+        impl<F, I> Signal for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            type Target=F::Target;
+
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=F::Target> + 'a {
+                StateRef {
+                    main_ref: self.inner_ref().borrow(s),
+                    phantom: PhantomData
+                }
+            }
+
+            #[track_caller]
+            fn listen<Q>(&self, listener: Q, s: Slock<impl ThreadMarker>)
+                where Q: FnMut(&F::Target, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(self.inner_ref(), listener);
+                    move |val: &F::Target, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
+                self.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(Box::new(listener)));
+            }
+
+            type MappedOutput<U: Send + 'static> = GeneralSignal<U>;
+            #[track_caller]
+            fn map<U, Q>(&self, map: Q, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<U>
+                where U: Send + 'static, Q: Send + 'static + Fn(&F::Target) -> U {
+                GeneralSignal::from(self, self, map, |this, listener, s| {
+                    this.inner_ref().borrow_mut(s).dispatcher_mut().add_listener(StateListener::SignalListener(listener))
+                }, s)
+            }
+        }
+
+        impl<F, I> RawStoreSharedOwner<F> for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            type Inner = I::Inner;
+
+            fn from_ref(arc: Arc<SlockCell<Self::Inner>>) -> Self {
+                GeneralBinding {
+                    inner: I::from_ref(arc),
+                    phantom: Default::default(),
+                }
+            }
+
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
+                self.inner.inner_ref()
+            }
+
+            fn arc_clone(&self) -> Self {
+                GeneralBinding {
+                    inner: self.inner.arc_clone(),
+                    phantom: PhantomData
+                }
+            }
+        }
+
+        #[allow(private_bounds)]
+        impl<F, I> GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            pub fn store(&self) -> &I {
+                &self.inner
+            }
+        }
+
+        impl<F, I> Drop for GeneralBinding<F, I> where F: StateFilter, I: RawStoreSharedOwner<F> {
+            fn drop(&mut self) {
+                I::Inner::strong_count_decrement(self.inner.inner_ref());
+            }
+        }
+    }
+
+    mod lens_binding {
+        use std::marker::PhantomData;
+        use std::ops::Deref;
+        use std::sync::{Arc, Weak};
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
+
+        use crate::core::Slock;
+        use crate::state::{Binding, Filterless, GeneralSignal, IntoAction, Signal, StateFilter, Stateful, UndoBarrier, WeakBinding};
+        use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        type Translator<T, U> = Box<dyn Fn(&T, &<U as Stateful>::Action) -> Option<<T as Stateful>::Action> + Send + Sync>;
+        type Projector<T, U> = Box<dyn Fn(&T, &<T as Stateful>::Action) -> Option<<U as Stateful>::Action> + Send + Sync>;
+        type FailureHandler<T, U> = Arc<dyn Fn(&T, &<U as Stateful>::Action, Slock) + Send + Sync>;
+
+        struct LensInner<T, U> where T: Stateful, U: Stateful {
+            _quarve_tag: QuarveAllocTag,
+            // cached projection of the source
+            projected: GeneralSignal<U>,
+            translator: Translator<T, U>,
+            projector: Projector<T, U>,
+        }
+
+        /// A binding onto a projection of another binding.
+        /// See [`Binding::lens`]
+        pub struct LensBinding<F, B, U> where F: StateFilter, B: Binding<F>, U: Stateful {
+            source: B,
+            inner: Arc<LensInner<F::Target, U>>,
+            on_failure: Option<FailureHandler<F::Target, U>>,
+            phantom: PhantomData<fn() -> F>
+        }
+
+        pub struct LensWeakBinding<F, W, U> where F: StateFilter, W: WeakBinding<F>, U: Stateful {
+            source: W,
+            inner: Weak<LensInner<F::Target, U>>,
+            on_failure: Option<FailureHandler<F::Target, U>>,
+            phantom: PhantomData<fn() -> F>
+        }
+
+        impl<F, B, U> LensBinding<F, B, U> where F: StateFilter, B: Binding<F>, U: Stateful {
+            #[track_caller]
+            pub fn new<G, A, R, P>(source: B, getter: G, translator: A, projector: P, s: Slock<impl ThreadMarker>) -> Self
+                where G: Fn(&F::Target) -> U + Send + 'static,
+                      A: Fn(&F::Target, &U::Action) -> Option<R> + Send + Sync + 'static,
+                      R: IntoAction<<F::Target as Stateful>::Action, F::Target>,
+                      P: Fn(&F::Target, &<F::Target as Stateful>::Action) -> Option<U::Action> + Send + Sync + 'static
+            {
+                let projected = GeneralSignal::from(&source, &source, getter, |source, listener, s| {
+                    source.listen(listener, s)
+                }, s);
+
+                LensBinding {
+                    source,
+                    inner: Arc::new(LensInner {
+                        _quarve_tag: QuarveAllocTag::new::<U>(AllocKind::Store),
+                        projected,
+                        translator: Box::new(move |target, action| {
+                            translator(target, action)
+                                .map(|action| action.into_action(target))
+                        }),
+                        projector: Box::new(projector),
+                    }),
+                    on_failure: None,
+                    phantom: PhantomData,
+                }
+            }
+
+            /// Called with the current source value and the rejected action
+            /// whenever an action cannot be translated
+            pub fn on_parse_failure(mut self, f: impl Fn(&F::Target, &U::Action, Slock) + Send + Sync + 'static) -> Self {
+                self.on_failure = Some(Arc::new(f));
+                self
+            }
+
+            pub fn source(&self) -> &B {
+                &self.source
+            }
+        }
+
+        impl<F, B, U> Clone for LensBinding<F, B, U> where F: StateFilter, B: Binding<F> + Clone, U: Stateful {
+            fn clone(&self) -> Self {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
+                LensBinding {
+                    source: self.source.clone(),
+                    inner: self.inner.clone(),
+                    on_failure: self.on_failure.clone(),
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<F, B, U> Signal for LensBinding<F, B, U> where F: StateFilter, B: Binding<F>, U: Stateful {
+            type Target = U;
+
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=U> + 'a {
+                self.inner.projected.borrow(s)
+            }
+
+            #[track_caller]
+            fn listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>)
+                where G: FnMut(&U, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &U, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
+                self.inner.projected.listen(listener, s)
+            }
+
+            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
+            #[track_caller]
+            fn map<S, G>(&self, map: G, s: Slock<impl ThreadMarker>) -> GeneralSignal<S>
+                where S: Send + 'static, G: Send + 'static + Fn(&U) -> S {
+                self.inner.projected.map(map, s)
+            }
+        }
+
+        impl<F, B, U> Binding<Filterless<U>> for LensBinding<F, B, U> where F: StateFilter, B: Binding<F>, U: Stateful {
+            fn address(&self) -> usize {
+                Arc::as_ptr(&self.inner) as usize
+            }
+
+            fn ptr_eq(&self, other: &Self) -> bool {
+                Arc::ptr_eq(&self.inner, &other.inner)
+            }
+
+            fn is_applying(&self, s: Slock<impl ThreadMarker>) -> bool {
+                self.source.is_applying(s)
+            }
+
+            fn is_borrowed(&self, s: Slock<impl ThreadMarker>) -> bool {
+                self.source.is_borrowed(s)
+            }
+
+            fn apply(&self, action: impl IntoAction<U::Action, U>, s: Slock<impl ThreadMarker>) {
+                let action = {
+                    let current = self.inner.projected.borrow(s);
+                    action.into_action(&*current)
+                };
+
+                let translated = {
+                    let source = self.source.borrow(s);
+                    let translated = (self.inner.translator)(&*source, &action);
+
+                    if translated.is_none() {
+                        if let Some(ref on_failure) = self.on_failure {
+                            on_failure(&*source, &action, s.to_general_slock());
+                        }
+                    }
+
+                    translated
+                };
+
+                let Some(translated) = translated else {
+                    return;
+                };
+
+                self.source.apply(translated, s);
+            }
+
+            fn undo_barrier(&self, undo_barrier_type: UndoBarrier, s: Slock<impl ThreadMarker>) {
+                self.source.undo_barrier(undo_barrier_type, s);
+            }
+
+            /// Every action applied to the source is reported
+            /// (after filtering), as converted by the projector
+            #[track_caller]
+            fn action_listen<G>(&self, listener: G, s: Slock<impl ThreadMarker>)
+                where G: Send + FnMut(&U, &U::Action, Slock) -> bool + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &U, action: &U::Action, s: Slock| listener.invoke(|listener| listener(val, action, s))
+                };
+                let mut listener = listener;
+
+                let inner = Arc::downgrade(&self.inner);
+                self.source.action_listen(move |source, action, s| {
+                    let Some(inner) = inner.upgrade() else {
+                        return false;
+                    };
+                    let Some(projected) = (inner.projector)(source, action) else {
+                        return true;
+                    };
+
+                    // signal listeners have not yet run, so this is the value before the action
+                    let current = inner.projected.borrow(s);
+                    listener(&current, &projected, s)
+                }, s);
+            }
+
+            type WeakBinding = LensWeakBinding<F, B::WeakBinding, U>;
+
+            fn downgrade(&self) -> Self::WeakBinding {
+                LensWeakBinding {
+                    source: self.source.downgrade(),
+                    inner: Arc::downgrade(&self.inner),
+                    on_failure: self.on_failure.clone(),
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<F, W, U> Clone for LensWeakBinding<F, W, U> where F: StateFilter, W: WeakBinding<F>, U: Stateful {
+            fn clone(&self) -> Self {
+                LensWeakBinding {
+                    source: self.source.clone(),
+                    inner: self.inner.clone(),
+                    on_failure: self.on_failure.clone(),
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<F, W, U> WeakBinding<Filterless<U>> for LensWeakBinding<F, W, U> where F: StateFilter, W: WeakBinding<F>, U: Stateful {
+            type Binding = LensBinding<F, W::Binding, U>;
+
+            fn upgrade(&self) -> Option<Self::Binding> {
+                let source = self.source.upgrade()?;
+                let inner = self.inner.upgrade()?;
+
+                Some(LensBinding {
+                    source,
+                    inner,
+                    on_failure: self.on_failure.clone(),
+                    phantom: PhantomData,
+                })
+            }
+        }
+    }
+
+    // only to make rust happy
+    pub mod unreachable_binding {
+        use std::marker::PhantomData;
+        use std::ops::Deref;
+        use std::sync::Arc;
+
+        use crate::core::Slock;
+        use crate::state::slock_cell::{MainSlockCell, SlockCell};
+        use crate::state::store::inverse_listener_holder::InverseListenerHolderImpl;
+        use crate::state::store::raw_store::RawStore;
+        use crate::state::store::raw_store_shared_owner::RawStoreSharedOwner;
+        use crate::state::store::store_dispatcher::StoreDispatcher;
+        use crate::state::{GeneralSignal, IntoAction, Signal, StateFilter, Stateful};
+        use crate::util::marker::ThreadMarker;
+
+        pub struct UnreachableBindingInner<F>(pub PhantomData<Arc<MainSlockCell<F>>>) where F: StateFilter;
+
+        impl<F> Deref for UnreachableBindingInner<F> where F: StateFilter {
+            type Target = F::Target;
+
+            fn deref(&self) -> &Self::Target {
+                unreachable!()
+            }
+        }
+
+        impl<F> RawStore<F> for UnreachableBindingInner<F> where F: StateFilter {
+            type InverseListenerHolder = InverseListenerHolderImpl;
+
+            fn dispatcher(&self) -> &StoreDispatcher<F::Target, F, Self::InverseListenerHolder> {
+                unreachable!()
+            }
+
+            fn dispatcher_mut(&mut self) -> &mut StoreDispatcher<F::Target, F, Self::InverseListenerHolder> {
+                unreachable!()
+            }
+
+            fn apply(_inner: &Arc<SlockCell<Self>>, _action: impl IntoAction<<F::Target as Stateful>::Action, F::Target>, _is_inverse: bool, _s: Slock<impl ThreadMarker>) {
+                unreachable!()
+            }
+        }
+
+        pub struct UnreachableBinding<F>(pub PhantomData<Arc<MainSlockCell<F>>>) where F: StateFilter;
+        impl<F> Signal for UnreachableBinding<F> where F: StateFilter {
+            type Target = F::Target;
+
+            fn borrow<'a>(&'a self, _s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=Self::Target> + 'a {
+                UnreachableBindingInner::<F>(PhantomData)
+            }
+
+            fn listen<G>(&self, _listener: G, _s: Slock<impl ThreadMarker>) where G: FnMut(&Self::Target, Slock) -> bool + Send + 'static {
+                unreachable!()
+            }
+
+            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
+            fn map<S, G>(&self, _map: G, _s: Slock<impl ThreadMarker>) -> Self::MappedOutput<S> where S: Send + 'static, G: Send + 'static + Fn(&Self::Target) -> S {
+                unreachable!()
+            }
+        }
+
+        impl<F> RawStoreSharedOwner<F> for UnreachableBinding<F> where F: StateFilter {
+            type Inner = UnreachableBindingInner<F>;
+
+            fn from_ref(_arc: Arc<SlockCell<Self::Inner>>) -> Self {
+                unreachable!()
+            }
+
+            fn inner_ref(&self) -> &Arc<SlockCell<Self::Inner>> {
+                unreachable!()
+            }
+
+            fn arc_clone(&self) -> Self {
+                unreachable!()
+            }
+        }
+
+        impl<F> Clone for UnreachableBinding<F> where F: StateFilter {
+            fn clone(&self) -> Self {
+                UnreachableBinding(PhantomData)
+            }
+        }
+    }
+}
+
+mod buffer {
+    use std::ops::{Deref, DerefMut};
+    use std::sync::{Arc, Weak};
+
+    use crate::core::Slock;
+    use crate::state::slock_cell::SlockCell;
+    use crate::util::marker::ThreadMarker;
+    use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+    pub struct Buffer<T>(Arc<(SlockCell<T>, QuarveAllocTag)>) where T: Send;
+
+    pub struct WeakBuffer<T>(Weak<(SlockCell<T>, QuarveAllocTag)>) where T: Send;
+
+    impl<T> Clone for WeakBuffer<T> where T: Send {
+        fn clone(&self) -> Self {
+            WeakBuffer(self.0.clone())
+        }
+    }
+
+    impl<T> Buffer<T>
+        where T: Send
+    {
+        #[track_caller]
+        pub fn new(initial: T) -> Buffer<T> {
+            Buffer(Arc::new((SlockCell::new(initial), QuarveAllocTag::new::<T>(AllocKind::Buffer))))
+        }
+
+        pub fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
+            self.0.0.borrow(s)
+        }
+
+        pub fn borrow_mut<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl DerefMut<Target=T> + 'a  {
+            self.0.0.borrow_mut(s)
+        }
+
+        pub fn replace<'a>(&'a self, with: T, s: Slock<'a, impl ThreadMarker>) -> T {
+            std::mem::replace(self.borrow_mut(s).deref_mut(), with)
+        }
+
+        pub fn downgrade(&self) -> WeakBuffer<T> {
+            WeakBuffer(Arc::downgrade(&self.0))
+        }
+    }
+
+    impl<T> Buffer<T> where T: Send + Default {
+        pub fn take(&self, s: Slock<impl ThreadMarker>) -> T {
+            std::mem::take(self.borrow_mut(s).deref_mut())
+        }
+    }
+
+    impl<T> WeakBuffer<T> where T: Send {
+        pub fn upgrade(&self) -> Option<Buffer<T>> {
+            self.0.upgrade().map(|arc| Buffer(arc))
+        }
+    }
+}
+
+mod signal {
+    use std::ops::Deref;
+
+    pub use computed_signal::*;
+    pub use fixed_signal::*;
+    pub use general_signal::*;
+    pub use joined_signal::*;
+    pub use operator_signal::*;
+    use signal_audience::*;
+    pub use signal_or_value::*;
+    use signal_ref::*;
+    #[cfg(feature = "futures")]
+    pub use signal_stream::*;
+    pub use timed_signal::*;
+
+    use crate::core::Slock;
+    use crate::util::marker::ThreadMarker;
+
+    pub trait Signal: Sized + Send + Sync + 'static {
+        type Target: Send + 'static;
+
+        /// Be careful about calling this method within an
+        /// action_listener or related fields. While not bad by itself
+        /// This can easily cause retain cycles
+        /// Instead, similar logic can usually be done by using JoinedSignals,
+        /// DerivedStores, or Buffers
+        fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=Self::Target> + 'a;
+
+        fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
+            where F: (FnMut(&Self::Target, Slock) -> bool) + Send + 'static;
+
+        type MappedOutput<S: Send + 'static>: Signal<Target=S> + Clone;
+        fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> Self::MappedOutput<S>
+            where S: Send + 'static,
+                  F: Send + 'static + Fn(&Self::Target) -> S;
+    }
+
+    pub trait ActualDiffSignal : Signal where Self::Target: Send + 'static + Clone + PartialEq {
+        fn diff_listen<F>(&self, mut listener: F, s: Slock<impl ThreadMarker>)
+            where F: (FnMut(&Self::Target, Slock) -> bool) + Send + 'static
+        {
+            let mut last_val = self.borrow(s).clone();
+            self.listen(move |val, s| {
+                if *val != last_val {
+                    last_val = val.clone();
+                    listener(val, s)
+                }
+                else {
+                    true
+                }
+            }, s)
+        }
+    }
+    impl<S> ActualDiffSignal for S where S::Target: Send + Clone + PartialEq + 'static, S: Signal { }
+
+    trait InnerSignal {
+        type Target: Send + 'static;
+        fn borrow(&self) -> &Self::Target;
+    }
+
+    mod signal_audience {
+        use crate::core::Slock;
+        use crate::util::marker::ThreadMarker;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        pub(super) struct SignalAudience<T> where T: Send + 'static {
+            listeners: Vec<Box<dyn FnMut(&T, Slock) -> bool + Send>>
+        }
+
+        impl<T> SignalAudience<T> where T: Send + 'static {
+            pub(super) fn new() -> SignalAudience<T> {
+                SignalAudience {
+                    listeners: Vec::new()
+                }
+            }
+
+            #[track_caller]
+            pub(super) fn listen<F>(&mut self, listener: F, s: Slock<impl ThreadMarker>)
+                where F: (FnMut(&T, Slock) -> bool) + Send + 'static {
+                self.listen_box(Box::new(listener), s);
+            }
+
+            #[track_caller]
+            pub(super) fn listen_box(
+                &mut self,
+                listener: Box<dyn (FnMut(&T, Slock) -> bool) + Send + 'static>,
+                _s: Slock<impl ThreadMarker>
+            ) {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = listener;
+                    let tag = QuarveAllocTag::new::<T>(AllocKind::Listener);
+                    Box::new(move |val: &T, s: Slock| {
+                        let _tag = &tag;
+                        listener(val, s)
+                    })
+                };
+
+                self.listeners.push(listener);
+            }
+
+            pub(super) fn dispatch(&mut self, new_val: &T, s: Slock<impl ThreadMarker>) {
+                self.listeners
+                    .retain_mut(|listener| listener(new_val, s.to_general_slock()))
+            }
+
+            pub(super) fn is_empty(&self) -> bool {
+                self.listeners.is_empty()
+            }
+        }
+    }
+
+    mod signal_ref {
+        use std::cell::Ref;
+        use std::ops::Deref;
+
+        use super::InnerSignal;
+
+        pub(super) struct SignalRef<'a, U: InnerSignal> {
+            pub(super) src: Ref<'a, U>,
+        }
+
+        impl<'a, U> Deref for SignalRef<'a, U> where U: InnerSignal {
+            type Target = U::Target;
+
+            fn deref(&self) -> &U::Target {
+                self.src.borrow()
+            }
+        }
+    }
+
+    mod fixed_signal {
+        use std::ops::Deref;
+        use std::sync::Arc;
+
+        use crate::core::Slock;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::Signal;
+        use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        use super::InnerSignal;
+        use super::SignalRef;
+
+        struct InnerFixedSignal<T: Send + 'static>(#[allow(dead_code)] QuarveAllocTag, T);
+
+        impl<T> InnerSignal for InnerFixedSignal<T> where T: Send + 'static {
+            type Target = T;
+
+            fn borrow(&self) -> &Self::Target {
                 &self.1
             }
         }
 
-        pub struct FixedSignal<T> where T: Send + 'static {
-            inner: Arc<SlockCell<InnerFixedSignal<T>>>
+        pub struct FixedSignal<T> where T: Send + 'static {
+            inner: Arc<SlockCell<InnerFixedSignal<T>>>
+        }
+
+        impl<T> FixedSignal<T> where T: Send + 'static {
+            #[track_caller]
+            pub fn new(val: T) -> FixedSignal<T> {
+                FixedSignal {
+                    inner: Arc::new(SlockCell::new(InnerFixedSignal(QuarveAllocTag::new::<T>(AllocKind::Signal), val)))
+                }
+            }
+        }
+
+        impl<T> Clone for FixedSignal<T> where T: Send + 'static {
+            fn clone(&self) -> Self {
+                FixedSignal {
+                    inner: self.inner.clone()
+                }
+            }
+        }
+
+        impl<T> Signal for FixedSignal<T> where T: Send + 'static {
+            type Target = T;
+
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
+                SignalRef {
+                    src: self.inner.borrow(s),
+                }
+            }
+
+            fn listen<F>(&self, _listener: F, _s: Slock<impl ThreadMarker>)
+                where F: FnMut(&T, Slock) -> bool + Send {
+                /* no op */
+            }
+
+            type MappedOutput<S: Send + 'static> = FixedSignal<S>;
+            #[track_caller]
+            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> FixedSignal<S>
+                where S: Send + 'static,
+                      F: Send + 'static + Fn(&T) -> S
+            {
+                let inner = self.inner.borrow(s);
+                let data = map(&inner.1);
+
+                FixedSignal {
+                    inner: Arc::new(SlockCell::new(InnerFixedSignal(QuarveAllocTag::new::<S>(AllocKind::Signal), data)))
+                }
+            }
+        }
+    }
+
+    mod general_signal {
+        use std::ops::{Deref, DerefMut};
+        use std::sync::Arc;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
+
+        use crate::core::Slock;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::Signal;
+        use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        use super::SignalRef;
+        use super::{InnerSignal, SignalAudience};
+
+        struct GeneralInnerSignal<T> where T: Send + 'static {
+            _quarve_tag: QuarveAllocTag,
+            val: T,
+            audience: SignalAudience<T>
+        }
+
+        impl<T> InnerSignal for GeneralInnerSignal<T> where T: Send + 'static {
+            type Target = T;
+
+            fn borrow(&self) -> &T {
+                &self.val
+            }
+        }
+
+        pub struct GeneralSignal<T> where T: Send + 'static {
+            inner: Arc<SlockCell<GeneralInnerSignal<T>>>
+        }
+
+        impl<T> Clone for GeneralSignal<T> where T: Send + 'static {
+            fn clone(&self) -> Self {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
+                GeneralSignal {
+                    inner: self.inner.clone()
+                }
+            }
+        }
+
+        impl<T> GeneralSignal<T> where T: Send + 'static {
+            /// add listener is a function to help out generally handling
+            /// TokenStore. Otherwise, .listen is used
+            #[track_caller]
+            pub(crate) fn from<H, S, F, G>(dispatcher: &H, signal: &S, map: F, add_listener: G, s: Slock<impl ThreadMarker>)
+                                           -> GeneralSignal<T>
+                where S: Signal,
+                      F: Send + 'static + Fn(&S::Target) -> T,
+                      G: FnOnce(&H, Box<dyn FnMut(&S::Target, Slock) -> bool + Send>, Slock)
+            {
+                let inner;
+                {
+                    let val = signal.borrow(s);
+                    inner = GeneralInnerSignal {
+                        _quarve_tag: QuarveAllocTag::new::<T>(AllocKind::Signal),
+                        val: map(&*val),
+                        audience: SignalAudience::new(),
+                    };
+                }
+
+                let arc = Arc::new(SlockCell::new(inner));
+                let pseudo_weak = arc.clone();
+                add_listener(dispatcher, Box::new(move |val, s| {
+                    let mut binding = pseudo_weak.borrow_mut(s);
+                    let inner = binding.deref_mut();
+
+                    // no longer any point
+                    inner.val = map(val);
+                    inner.audience.dispatch(&inner.val, s);
+
+                    // races don't matter too much since it'll just mean late drop
+                    // but nothing unsound
+                    !inner.audience.is_empty() || Arc::strong_count(&pseudo_weak) > 1
+                }), s.to_general_slock());
+
+                GeneralSignal {
+                    inner: arc
+                }
+            }
+        }
+
+        impl<T> Signal for GeneralSignal<T> where T: Send + 'static {
+            type Target = T;
+
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
+                SignalRef {
+                    src: self.inner.borrow(s),
+                }
+            }
+
+            #[track_caller]
+            fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
+                where F: FnMut(&T, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &T, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
+                self.inner.borrow_mut(s).audience.listen(listener, s);
+            }
+
+            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
+            #[track_caller]
+            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> GeneralSignal<S>
+                where S: Send + 'static, F: Fn(&T) -> S + Send + 'static {
+                GeneralSignal::from(self, self, map, |this, listener, s| {
+                    this.inner.borrow_mut(s).audience.listen_box(listener, s);
+                }, s)
+            }
+        }
+    }
+
+    mod joined_signal {
+        use std::ops::{Deref, DerefMut};
+        use std::sync::atomic::AtomicU8;
+        use std::sync::atomic::Ordering::SeqCst;
+        use std::sync::Arc;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
+
+        use crate::core::Slock;
+        use crate::state::signal::signal_audience::SignalAudience;
+        use crate::state::signal::signal_ref::SignalRef;
+        use crate::state::signal::InnerSignal;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::{GeneralSignal, Signal};
+        use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        struct JoinedInnerSignal<T, U, V>
+            where T: Send + 'static,
+                  U: Send + 'static,
+                  V: Send + 'static
+        {
+            _quarve_tag: QuarveAllocTag,
+            t: T,
+            u: U,
+            ours: V,
+            audience: SignalAudience<V>,
+        }
+
+        impl<T, U, V> InnerSignal for JoinedInnerSignal<T, U, V>
+            where T: Send + 'static,
+                  U: Send + 'static,
+                  V: Send + 'static
+        {
+            type Target = V;
+
+            fn borrow(&self) -> &V {
+                &self.ours
+            }
+        }
+
+        pub struct JoinedSignal<T, U, V>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static
+        {
+            // first field is the number of parents (i.e. lhs, rhs) owning it
+            inner: Arc<(AtomicU8, SlockCell<JoinedInnerSignal<T, U, V>>)>
+        }
+
+        impl<T, U, V> Clone for JoinedSignal<T, U, V>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static
+        {
+            fn clone(&self) -> Self {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
+                JoinedSignal {
+                    inner: self.inner.clone()
+                }
+            }
+        }
+
+        struct ParentOwner<T, U, V>(Arc<(AtomicU8, SlockCell<JoinedInnerSignal<T, U, V>>)>)
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static;
+
+        impl<T, U, V> Drop for ParentOwner<T, U, V>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static
+        {
+            fn drop(&mut self) {
+                // it's important that this is subtracted at a time
+                // strictly before the ARC strong counter
+                // so that we do not falsely free early
+                self.0.0.fetch_sub(1, SeqCst);
+            }
+        }
+
+
+        impl<T, U> JoinedSignal<T, U, (T, U)>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+        {
+            #[track_caller]
+            pub fn join(lhs: &impl Signal<Target=T>, rhs: &impl Signal<Target=U>, s: Slock<impl ThreadMarker>) -> Self
+            {
+                JoinedSignal::join_map(lhs, rhs, |t, u| (t.clone(), u.clone()), s)
+            }
+        }
+
+        impl<T, U, V> JoinedSignal<T, U, V>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static
+        {
+
+            #[track_caller]
+            pub fn join_map<F>(lhs: &impl Signal<Target=T>, rhs: &impl Signal<Target=U>, map: F, s: Slock<impl ThreadMarker>)
+                               -> JoinedSignal<T, U, V>
+                where F: Send + Clone + 'static + Fn(&T, &U) -> V
+            {
+                let inner = {
+                    let l = lhs.borrow(s);
+                    let r = rhs.borrow(s);
+
+                    JoinedInnerSignal {
+                        _quarve_tag: QuarveAllocTag::new::<V>(AllocKind::Signal),
+                        t: l.clone(),
+                        u: r.clone(),
+                        ours: map(&*l, &*r),
+                        audience: SignalAudience::new(),
+                    }
+                };
+
+                // initially, there are two parents owning this
+                let arc = Arc::new((AtomicU8::new(2), SlockCell::new(inner)));
+
+                let pseudo_weak = ParentOwner(arc.clone());
+                let lhs_map = map.clone();
+                lhs.listen(move |lhs, s| {
+                    let ParentOwner(pseudo_weak) = &pseudo_weak;
+
+                    let mut binding = pseudo_weak.1.borrow_mut(s);
+                    let inner = binding.deref_mut();
+                    inner.t = lhs.clone();
+                    inner.ours = lhs_map(&inner.t, &inner.u);
+                    inner.audience.dispatch(&inner.ours, s);
+
+                    // certainly this can change, but we do not particular care
+                    // since this is just an early exit, not necessarily the final
+
+                    !inner.audience.is_empty() ||
+                        Arc::strong_count(&pseudo_weak) > pseudo_weak.0.load(SeqCst) as usize
+                }, s);
+
+                let pseudo_weak = ParentOwner(arc.clone());
+                rhs.listen(move |rhs, s| {
+                    let ParentOwner(pseudo_weak) = &pseudo_weak;
+
+                    let mut binding = pseudo_weak.1.borrow_mut(s);
+                    let inner = binding.deref_mut();
+                    inner.u = rhs.clone();
+                    inner.ours = map(&inner.t, &inner.u);
+                    inner.audience.dispatch(&inner.ours, s);
+
+                    !inner.audience.is_empty() ||
+                        Arc::strong_count(&pseudo_weak) > pseudo_weak.0.load(SeqCst) as usize
+                }, s);
+
+                JoinedSignal {
+                    inner: arc
+                }
+            }
+        }
+
+        impl<T, U, V> Signal for JoinedSignal<T, U, V>
+            where T: Send + Clone + 'static,
+                  U: Send + Clone + 'static,
+                  V: Send + 'static
+        {
+            type Target = V;
+
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=V> + 'a {
+                SignalRef {
+                    src: self.inner.1.borrow(s),
+                }
+            }
+
+            #[track_caller]
+            fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
+                where F: FnMut(&V, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &V, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
+                self.inner.1.borrow_mut(s).audience.listen(listener, s);
+            }
+
+            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
+            #[track_caller]
+            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> GeneralSignal<S>
+                where S: Send + 'static,
+                      F: Send + 'static + Fn(&V) -> S
+            {
+                GeneralSignal::from(self, self, map, |this, listener, s| {
+                    this.inner.1.borrow_mut(s).audience.listen_box(listener, s);
+                }, s)
+            }
+        }
+    }
+
+    mod computed_signal {
+        use std::any::{Any, TypeId};
+        use std::ops::{Deref, DerefMut};
+        use std::sync::atomic::AtomicBool;
+        use std::sync::atomic::Ordering::SeqCst;
+        use std::sync::{Arc, Weak};
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
+
+        use crate::core::Slock;
+        use crate::state::signal::signal_audience::SignalAudience;
+        use crate::state::signal::signal_ref::SignalRef;
+        use crate::state::signal::InnerSignal;
+        use crate::state::slock_cell::SlockCell;
+        use crate::state::Signal;
+        use crate::util::marker::ThreadMarker;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
+
+        type Notifier = Arc<dyn Fn(Slock) -> bool + Send + Sync>;
+        type Compute<T> = Box<dyn FnMut(&mut ComputeContext, Slock) -> T + Send>;
+
+        struct Dependency {
+            // address and type of the signal handle
+            key: (usize, TypeId),
+            // Arc<SlockCell<S::Target>>, the latest value seen by our listener
+            // (the source may be mid dispatch when we recompute, so it cannot be borrowed)
+            cache: Arc<dyn Any + Send + Sync>,
+            // once cleared, the listener removes itself on the next dispatch
+            active: Arc<AtomicBool>,
         }
 
-        impl<T> FixedSignal<T> where T: Send + 'static {
-            pub fn new(val: T) -> FixedSignal<T> {
-                FixedSignal {
-                    inner: Arc::new(SlockCell::new(InnerFixedSignal(QuarveAllocTag::new(), val)))
-                }
+        impl Drop for Dependency {
+            fn drop(&mut self) {
+                self.active.store(false, SeqCst);
             }
         }
 
-        impl<T> Clone for FixedSignal<T> where T: Send + 'static {
-            fn clone(&self) -> Self {
-                FixedSignal {
-                    inner: self.inner.clone()
-                }
-            }
+        /// Records the signals read by a computed signal
+        pub struct ComputeContext {
+            notify: Notifier,
+            old: Vec<Dependency>,
+            new: Vec<Dependency>,
         }
 
-        impl<T> Signal for FixedSignal<T> where T: Send + 'static {
-            type Target = T;
+        impl ComputeContext {
+            /// Reads the current value of a signal, subscribing to it if
+            /// it was not read during the previous evaluation.
+            /// Dependencies are identified by the address of the handle,
+            /// so signals should be owned by the closure rather than
+            /// created during evaluation.
+            pub fn get<S>(&mut self, signal: &S, s: Slock<impl ThreadMarker>) -> S::Target
+                where S: Signal, S::Target: Clone
+            {
+                let key = (signal as *const S as usize, TypeId::of::<S>());
 
-            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
-                SignalRef {
-                    src: self.inner.borrow(s),
+                if let Some(dep) = self.new.iter().find(|dep| dep.key == key) {
+                    return Self::cached(dep, s);
+                }
+                else if let Some(index) = self.old.iter().position(|dep| dep.key == key) {
+                    let dep = self.old.swap_remove(index);
+                    let ret = Self::cached(&dep, s);
+                    self.new.push(dep);
+                    return ret;
                 }
-            }
 
-            fn listen<F>(&self, _listener: F, _s: Slock<impl ThreadMarker>)
-                where F: FnMut(&T, Slock) -> bool + Send {
-                /* no op */
-            }
+                let val = signal.borrow(s).clone();
+                let cache = Arc::new(SlockCell::new(val.clone()));
+                let active = Arc::new(AtomicBool::new(true));
 
-            type MappedOutput<S: Send + 'static> = FixedSignal<S>;
-            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> FixedSignal<S>
-                where S: Send + 'static,
-                      F: Send + 'static + Fn(&T) -> S
-            {
-                let inner = self.inner.borrow(s);
-                let data = map(&inner.1);
+                let listener_cache = cache.clone();
+                let listener_active = active.clone();
+                let notify = self.notify.clone();
+                signal.listen(move |val, s| {
+                    if !listener_active.load(SeqCst) {
+                        return false;
+                    }
 
-                FixedSignal {
-                    inner: Arc::new(SlockCell::new(InnerFixedSignal(QuarveAllocTag::new(), data)))
-                }
-            }
-        }
-    }
+                    *listener_cache.borrow_mut(s) = val.clone();
+                    notify(s)
+                }, s);
 
-    mod general_signal {
-        use std::ops::{Deref, DerefMut};
-        use std::sync::Arc;
+                self.new.push(Dependency {
+                    key,
+                    cache,
+                    active,
+                });
 
-        use crate::core::Slock;
-        use crate::state::slock_cell::SlockCell;
-        use crate::state::Signal;
-        use crate::util::marker::ThreadMarker;
-        use crate::util::test_util::QuarveAllocTag;
+                val
+            }
 
-        use super::SignalRef;
-        use super::{InnerSignal, SignalAudience};
+            fn cached<T>(dep: &Dependency, s: Slock<impl ThreadMarker>) -> T where T: Clone + Send + 'static {
+                dep.cache.downcast_ref::<SlockCell<T>>()
+                    .expect("Signal dependency changed type")
+                    .borrow(s)
+                    .clone()
+            }
+        }
 
-        struct GeneralInnerSignal<T> where T: Send + 'static {
-            _quarve_tag: QuarveAllocTag,
+        enum Evaluator<T> {
+            Compute(Compute<T>),
+            // updated directly by the source's audience
+            // only retained to keep the source alive
+            Mapped {
+                _source: Box<dyn Send + Sync>
+            }
+        }
+
+        struct ComputedInnerSignal<T> where T: Send + 'static {
             val: T,
-            audience: SignalAudience<T>
+            audience: SignalAudience<T>,
+            dependencies: Vec<Dependency>,
         }
 
-        impl<T> InnerSignal for GeneralInnerSignal<T> where T: Send + 'static {
+        impl<T> InnerSignal for ComputedInnerSignal<T> where T: Send + 'static {
             type Target = T;
 
             fn borrow(&self) -> &T {
@@ -2725,152 +6215,269 @@ mod signal {
             }
         }
 
-        pub struct GeneralSignal<T> where T: Send + 'static {
-            inner: Arc<SlockCell<GeneralInnerSignal<T>>>
+        struct ComputedInner<T> where T: Send + 'static {
+            _quarve_tag: QuarveAllocTag,
+            notify: Notifier,
+            evaluator: SlockCell<Evaluator<T>>,
+            signal: SlockCell<ComputedInnerSignal<T>>,
         }
 
-        impl<T> Clone for GeneralSignal<T> where T: Send + 'static {
+        impl<T> ComputedInner<T> where T: Send + 'static {
+            fn notifier(weak: Weak<ComputedInner<T>>) -> Notifier {
+                Arc::new(move |s| {
+                    let Some(inner) = weak.upgrade() else {
+                        return false;
+                    };
+
+                    inner.recompute(s);
+                    true
+                })
+            }
+
+            fn recompute(&self, s: Slock) {
+                let old = std::mem::take(&mut self.signal.borrow_mut(s).dependencies);
+                let mut ctx = ComputeContext {
+                    notify: self.notify.clone(),
+                    old,
+                    new: Vec::new(),
+                };
+
+                let val = match self.evaluator.borrow_mut(s).deref_mut() {
+                    Evaluator::Compute(compute) => compute(&mut ctx, s),
+                    Evaluator::Mapped { .. } => unreachable!("mapped signals do not have dependencies"),
+                };
+
+                let mut binding = self.signal.borrow_mut(s);
+                let inner = binding.deref_mut();
+                // unused dependencies of ctx.old are released when ctx is dropped
+                inner.dependencies = std::mem::take(&mut ctx.new);
+                inner.val = val;
+                inner.audience.dispatch(&inner.val, s);
+            }
+        }
+
+        /// A signal whose value is computed from other signals.
+        /// It subscribes to exactly the signals read through the
+        /// [`ComputeContext`] during the most recent evaluation.
+        ///
+        /// The closure owns its dependencies, while dependencies only
+        /// hold weak references back. Therefore a computed signal lives only
+        /// as long as some handle to it (or a signal mapped from it) exists.
+        pub struct ComputedSignal<T> where T: Send + 'static {
+            inner: Arc<ComputedInner<T>>
+        }
+
+        impl<T> Clone for ComputedSignal<T> where T: Send + 'static {
             fn clone(&self) -> Self {
-                GeneralSignal {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
+                ComputedSignal {
                     inner: self.inner.clone()
                 }
             }
         }
 
-        impl<T> GeneralSignal<T> where T: Send + 'static {
-            /// add listener is a function to help out generally handling
-            /// TokenStore. Otherwise, .listen is used
-            pub(crate) fn from<H, S, F, G>(dispatcher: &H, signal: &S, map: F, add_listener: G, s: Slock<impl ThreadMarker>)
-                                           -> GeneralSignal<T>
-                where S: Signal,
-                      F: Send + 'static + Fn(&S::Target) -> T,
-                      G: FnOnce(&H, Box<dyn FnMut(&S::Target, Slock) -> bool + Send>, Slock)
+        impl<T> ComputedSignal<T> where T: Send + 'static {
+            #[track_caller]
+            pub fn new<F>(compute: F, s: Slock<impl ThreadMarker>) -> Self
+                where F: FnMut(&mut ComputeContext, Slock) -> T + Send + 'static
             {
-                let inner;
-                {
-                    let val = signal.borrow(s);
-                    inner = GeneralInnerSignal {
-                        _quarve_tag: QuarveAllocTag::new(),
-                        val: map(&*val),
-                        audience: SignalAudience::new(),
+                // boxed before the first evaluation so that the
+                // addresses of captured signals remain stable
+                let mut compute: Compute<T> = Box::new(compute);
+                let inner = Arc::new_cyclic(|weak| {
+                    let notify = ComputedInner::notifier(weak.clone());
+                    let mut ctx = ComputeContext {
+                        notify: notify.clone(),
+                        old: Vec::new(),
+                        new: Vec::new(),
                     };
-                }
-
-                let arc = Arc::new(SlockCell::new(inner));
-                let pseudo_weak = arc.clone();
-                add_listener(dispatcher, Box::new(move |val, s| {
-                    let mut binding = pseudo_weak.borrow_mut(s);
-                    let inner = binding.deref_mut();
-
-                    // no longer any point
-                    inner.val = map(val);
-                    inner.audience.dispatch(&inner.val, s);
-
-                    // races don't matter too much since it'll just mean late drop
-                    // but nothing unsound
-                    !inner.audience.is_empty() || Arc::strong_count(&pseudo_weak) > 1
-                }), s.to_general_slock());
+                    let val = compute(&mut ctx, s.to_general_slock());
+
+                    ComputedInner {
+                        _quarve_tag: QuarveAllocTag::new::<T>(AllocKind::Signal),
+                        notify,
+                        evaluator: SlockCell::new(Evaluator::Compute(compute)),
+                        signal: SlockCell::new(ComputedInnerSignal {
+                            val,
+                            audience: SignalAudience::new(),
+                            dependencies: std::mem::take(&mut ctx.new),
+                        }),
+                    }
+                });
 
-                GeneralSignal {
-                    inner: arc
+                ComputedSignal {
+                    inner
                 }
             }
         }
 
-        impl<T> Signal for GeneralSignal<T> where T: Send + 'static {
+        /// Alias for [`ComputedSignal::new`]
+        #[track_caller]
+        pub fn computed<T, F>(compute: F, s: Slock<impl ThreadMarker>) -> ComputedSignal<T>
+            where T: Send + 'static,
+                  F: FnMut(&mut ComputeContext, Slock) -> T + Send + 'static
+        {
+            ComputedSignal::new(compute, s)
+        }
+
+        impl<T> Signal for ComputedSignal<T> where T: Send + 'static {
             type Target = T;
 
             fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
                 SignalRef {
-                    src: self.inner.borrow(s),
+                    src: self.inner.signal.borrow(s),
                 }
             }
 
+            #[track_caller]
             fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
                 where F: FnMut(&T, Slock) -> bool + Send + 'static {
-                self.inner.borrow_mut(s).audience.listen(listener, s);
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &T, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
+                self.inner.signal.borrow_mut(s).audience.listen(listener, s);
             }
 
-            type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
-            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> GeneralSignal<S>
-                where S: Send + 'static, F: Fn(&T) -> S + Send + 'static {
-                GeneralSignal::from(self, self, map, |this, listener, s| {
-                    this.inner.borrow_mut(s).audience.listen_box(listener, s);
-                }, s)
+            type MappedOutput<S: Send + 'static> = ComputedSignal<S>;
+            #[track_caller]
+            fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> ComputedSignal<S>
+                where S: Send + 'static,
+                      F: Send + 'static + Fn(&T) -> S
+            {
+                let val = map(&*self.borrow(s));
+                let inner = Arc::new(ComputedInner {
+                    _quarve_tag: QuarveAllocTag::new::<S>(AllocKind::Signal),
+                    notify: Arc::new(|_s| false),
+                    evaluator: SlockCell::new(Evaluator::Mapped {
+                        _source: Box::new(self.clone())
+                    }),
+                    signal: SlockCell::new(ComputedInnerSignal {
+                        val,
+                        audience: SignalAudience::new(),
+                        dependencies: Vec::new(),
+                    }),
+                });
+
+                let weak = Arc::downgrade(&inner);
+                self.listen(move |val, s| {
+                    let Some(inner) = weak.upgrade() else {
+                        return false;
+                    };
+
+                    let mut binding = inner.signal.borrow_mut(s);
+                    let inner = binding.deref_mut();
+                    inner.val = map(val);
+                    inner.audience.dispatch(&inner.val, s);
+                    true
+                }, s);
+
+                ComputedSignal {
+                    inner
+                }
             }
         }
     }
 
-    mod joined_signal {
+    mod operator_signal {
         use std::ops::{Deref, DerefMut};
         use std::sync::atomic::AtomicU8;
         use std::sync::atomic::Ordering::SeqCst;
         use std::sync::Arc;
+        use std::time::Duration;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
 
-        use crate::core::Slock;
+        use crate::core::{timed_worker, Slock};
         use crate::state::signal::signal_audience::SignalAudience;
         use crate::state::signal::signal_ref::SignalRef;
         use crate::state::signal::InnerSignal;
         use crate::state::slock_cell::SlockCell;
-        use crate::state::{GeneralSignal, Signal};
+        use crate::state::{ActualDiffSignal, GeneralSignal, Signal};
         use crate::util::marker::ThreadMarker;
-        use crate::util::test_util::QuarveAllocTag;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
 
-        struct JoinedInnerSignal<T, U, V>
-            where T: Send + 'static,
-                  U: Send + 'static,
-                  V: Send + 'static
-        {
+        pub trait SignalOperators: Signal where Self::Target: Clone {
+            /// Emits the latest value of the source once it has
+            /// not changed for `duration`
+            fn debounce(&self, duration: Duration, s: Slock<impl ThreadMarker>) -> OperatorSignal<Self::Target>;
+
+            /// Emits the first change immediately, and afterwards
+            /// at most one value (the latest) per `duration`
+            fn throttle(&self, duration: Duration, s: Slock<impl ThreadMarker>) -> OperatorSignal<Self::Target>;
+
+            /// Only forwards values accepted by `predicate`, retaining the last
+            /// accepted value otherwise. The initial value is always accepted
+            fn filter<F>(&self, predicate: F, s: Slock<impl ThreadMarker>) -> OperatorSignal<Self::Target>
+                where F: Fn(&Self::Target) -> bool + Send + 'static;
+
+            /// Only forwards values that differ from the previous one
+            fn distinct(&self, s: Slock<impl ThreadMarker>) -> OperatorSignal<Self::Target>
+                where Self::Target: PartialEq;
+
+            /// Emits the latest value of the source whenever `clock` changes,
+            /// provided the source has changed since the last emission
+            fn sample(&self, clock: &impl Signal, s: Slock<impl ThreadMarker>) -> OperatorSignal<Self::Target>;
+        }
+
+        struct OperatorInnerSignal<T> where T: Send + 'static {
             _quarve_tag: QuarveAllocTag,
-            t: T,
-            u: U,
-            ours: V,
-            audience: SignalAudience<V>,
+            val: T,
+            // latest value of the source that has yet to be emitted
+            pending: Option<T>,
+            worker_active: bool,
+            // set whenever the source changes while the worker is active
+            changed: bool,
+            audience: SignalAudience<T>,
         }
 
-        impl<T, U, V> InnerSignal for JoinedInnerSignal<T, U, V>
-            where T: Send + 'static,
-                  U: Send + 'static,
-                  V: Send + 'static
-        {
-            type Target = V;
+        impl<T> OperatorInnerSignal<T> where T: Send + 'static {
+            fn emit(&mut self, val: T, s: Slock) {
+                self.val = val;
+                self.audience.dispatch(&self.val, s);
+            }
+        }
 
-            fn borrow(&self) -> &V {
-                &self.ours
+        impl<T> InnerSignal for OperatorInnerSignal<T> where T: Send + 'static {
+            type Target = T;
+
+            fn borrow(&self) -> &T {
+                &self.val
             }
         }
 
-        pub struct JoinedSignal<T, U, V>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static
-        {
-            // first field is the number of parents (i.e. lhs, rhs) owning it
-            inner: Arc<(AtomicU8, SlockCell<JoinedInnerSignal<T, U, V>>)>
+        // first field is parent retain count
+        // i.e. worker thread or source signals
+        type OperatorArc<T> = Arc<(AtomicU8, SlockCell<OperatorInnerSignal<T>>)>;
+
+        pub struct OperatorSignal<T> where T: Send + 'static {
+            inner: OperatorArc<T>
         }
 
-        impl<T, U, V> Clone for JoinedSignal<T, U, V>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static
-        {
+        impl<T> Clone for OperatorSignal<T> where T: Send + 'static {
             fn clone(&self) -> Self {
-                JoinedSignal {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
+                OperatorSignal {
                     inner: self.inner.clone()
                 }
             }
         }
 
-        struct ParentOwner<T, U, V>(Arc<(AtomicU8, SlockCell<JoinedInnerSignal<T, U, V>>)>)
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static;
+        struct ParentOwner<T>(OperatorArc<T>) where T: Send + 'static;
 
-        impl<T, U, V> Drop for ParentOwner<T, U, V>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static
-        {
+        impl<T> ParentOwner<T> where T: Send + 'static {
+            fn retained(&self, inner: &OperatorInnerSignal<T>) -> bool {
+                // races don't matter too much since it'll just mean late drop
+                // but nothing unsound (since the parent count will always be decremented first)
+                !inner.audience.is_empty() ||
+                    Arc::strong_count(&self.0) > self.0.0.load(SeqCst) as usize
+            }
+        }
+
+        impl<T> Drop for ParentOwner<T> where T: Send + 'static {
             fn drop(&mut self) {
                 // it's important that this is subtracted at a time
                 // strictly before the ARC strong counter
@@ -2879,103 +6486,208 @@ mod signal {
             }
         }
 
+        impl<T> OperatorSignal<T> where T: Send + 'static {
+            #[track_caller]
+            fn new_arc(initial: T, parents: u8) -> OperatorArc<T> {
+                Arc::new((AtomicU8::new(parents), SlockCell::new(OperatorInnerSignal {
+                    _quarve_tag: QuarveAllocTag::new::<T>(AllocKind::Signal),
+                    val: initial,
+                    pending: None,
+                    worker_active: false,
+                    changed: false,
+                    audience: SignalAudience::new(),
+                })))
+            }
 
-        impl<T, U> JoinedSignal<T, U, (T, U)>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-        {
-            pub fn join(lhs: &impl Signal<Target=T>, rhs: &impl Signal<Target=U>, s: Slock<impl ThreadMarker>) -> Self
+            // tick receives the time since the worker was spawned
+            // and returns whether the worker should continue
+            fn spawn_worker<F>(arc: &OperatorArc<T>, mut tick: F)
+                where F: FnMut(Duration, &mut OperatorInnerSignal<T>, Slock) -> bool + Send + 'static
             {
-                JoinedSignal::join_map(lhs, rhs, |t, u| (t.clone(), u.clone()), s)
+                arc.0.fetch_add(1, SeqCst);
+
+                let worker_arc = ParentOwner(arc.clone());
+                timed_worker(move |duration, s| {
+                    let mut borrow = worker_arc.0.1.borrow_mut(s);
+                    let inner = borrow.deref_mut();
+
+                    let cont = tick(duration, inner, s) && worker_arc.retained(inner);
+                    if !cont {
+                        inner.worker_active = false;
+                    }
+
+                    cont
+                })
+            }
+
+            #[track_caller]
+            fn from_source<S, F>(source: &S, mut on_change: F, s: Slock<impl ThreadMarker>) -> Self
+                where S: Signal<Target=T>,
+                      T: Clone,
+                      F: FnMut(&OperatorArc<T>, &mut OperatorInnerSignal<T>, &T, Slock) + Send + 'static
+            {
+                let arc = OperatorSignal::new_arc(source.borrow(s).clone(), 1);
+
+                let parent_arc = ParentOwner(arc.clone());
+                source.listen(move |val, s| {
+                    let mut borrow = parent_arc.0.1.borrow_mut(s);
+                    let inner = borrow.deref_mut();
+                    on_change(&parent_arc.0, inner, val, s);
+
+                    parent_arc.retained(inner)
+                }, s);
+
+                OperatorSignal {
+                    inner: arc
+                }
             }
         }
 
-        impl<T, U, V> JoinedSignal<T, U, V>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static
-        {
+        impl<S> SignalOperators for S where S: Signal, S::Target: Clone {
+            #[track_caller]
+            fn debounce(&self, duration: Duration, s: Slock<impl ThreadMarker>) -> OperatorSignal<S::Target> {
+                OperatorSignal::from_source(self, move |arc, inner, val, _s| {
+                    inner.pending = Some(val.clone());
 
-            pub fn join_map<F>(lhs: &impl Signal<Target=T>, rhs: &impl Signal<Target=U>, map: F, s: Slock<impl ThreadMarker>)
-                               -> JoinedSignal<T, U, V>
-                where F: Send + Clone + 'static + Fn(&T, &U) -> V
+                    if inner.worker_active {
+                        inner.changed = true;
+                        return;
+                    }
+
+                    inner.worker_active = true;
+                    let mut last_change = Duration::ZERO;
+                    OperatorSignal::spawn_worker(arc, move |elapsed, inner, s| {
+                        if std::mem::take(&mut inner.changed) {
+                            last_change = elapsed;
+                        }
+
+                        if elapsed.saturating_sub(last_change) < duration {
+                            return true;
+                        }
+
+                        if let Some(val) = inner.pending.take() {
+                            inner.emit(val, s);
+                        }
+                        false
+                    });
+                }, s)
+            }
+
+            #[track_caller]
+            fn throttle(&self, duration: Duration, s: Slock<impl ThreadMarker>) -> OperatorSignal<S::Target> {
+                OperatorSignal::from_source(self, move |arc, inner, val, s| {
+                    if inner.worker_active {
+                        inner.pending = Some(val.clone());
+                        return;
+                    }
+
+                    inner.emit(val.clone(), s);
+
+                    inner.worker_active = true;
+                    let mut last_emit = Duration::ZERO;
+                    OperatorSignal::spawn_worker(arc, move |elapsed, inner, s| {
+                        if elapsed.saturating_sub(last_emit) < duration {
+                            return true;
+                        }
+
+                        // close the window if nothing arrived during it
+                        let Some(val) = inner.pending.take() else {
+                            return false;
+                        };
+
+                        inner.emit(val, s);
+                        last_emit = elapsed;
+                        true
+                    });
+                }, s)
+            }
+
+            #[track_caller]
+            fn filter<F>(&self, predicate: F, s: Slock<impl ThreadMarker>) -> OperatorSignal<S::Target>
+                where F: Fn(&S::Target) -> bool + Send + 'static
+            {
+                OperatorSignal::from_source(self, move |_arc, inner, val, s| {
+                    if predicate(val) {
+                        inner.emit(val.clone(), s);
+                    }
+                }, s)
+            }
+
+            #[track_caller]
+            fn distinct(&self, s: Slock<impl ThreadMarker>) -> OperatorSignal<S::Target>
+                where S::Target: PartialEq
             {
-                let inner = {
-                    let l = lhs.borrow(s);
-                    let r = rhs.borrow(s);
+                let arc = OperatorSignal::new_arc(self.borrow(s).clone(), 1);
 
-                    JoinedInnerSignal {
-                        _quarve_tag: QuarveAllocTag::new(),
-                        t: l.clone(),
-                        u: r.clone(),
-                        ours: map(&*l, &*r),
-                        audience: SignalAudience::new(),
-                    }
-                };
+                let parent_arc = ParentOwner(arc.clone());
+                self.diff_listen(move |val, s| {
+                    let mut borrow = parent_arc.0.1.borrow_mut(s);
+                    let inner = borrow.deref_mut();
+                    inner.emit(val.clone(), s);
 
-                // initially, there are two parents owning this
-                let arc = Arc::new((AtomicU8::new(2), SlockCell::new(inner)));
+                    parent_arc.retained(inner)
+                }, s);
 
-                let pseudo_weak = ParentOwner(arc.clone());
-                let lhs_map = map.clone();
-                lhs.listen(move |lhs, s| {
-                    let ParentOwner(pseudo_weak) = &pseudo_weak;
+                OperatorSignal {
+                    inner: arc
+                }
+            }
 
-                    let mut binding = pseudo_weak.1.borrow_mut(s);
-                    let inner = binding.deref_mut();
-                    inner.t = lhs.clone();
-                    inner.ours = lhs_map(&inner.t, &inner.u);
-                    inner.audience.dispatch(&inner.ours, s);
+            #[track_caller]
+            fn sample(&self, clock: &impl Signal, s: Slock<impl ThreadMarker>) -> OperatorSignal<S::Target> {
+                let arc = OperatorSignal::new_arc(self.borrow(s).clone(), 2);
 
-                    // certainly this can change, but we do not particular care
-                    // since this is just an early exit, not necessarily the final
+                let parent_arc = ParentOwner(arc.clone());
+                self.listen(move |val, s| {
+                    let mut borrow = parent_arc.0.1.borrow_mut(s);
+                    let inner = borrow.deref_mut();
+                    inner.pending = Some(val.clone());
 
-                    !inner.audience.is_empty() ||
-                        Arc::strong_count(&pseudo_weak) > pseudo_weak.0.load(SeqCst) as usize
+                    parent_arc.retained(inner)
                 }, s);
 
-                let pseudo_weak = ParentOwner(arc.clone());
-                rhs.listen(move |rhs, s| {
-                    let ParentOwner(pseudo_weak) = &pseudo_weak;
-
-                    let mut binding = pseudo_weak.1.borrow_mut(s);
-                    let inner = binding.deref_mut();
-                    inner.u = rhs.clone();
-                    inner.ours = map(&inner.t, &inner.u);
-                    inner.audience.dispatch(&inner.ours, s);
+                let parent_arc = ParentOwner(arc.clone());
+                clock.listen(move |_, s| {
+                    let mut borrow = parent_arc.0.1.borrow_mut(s);
+                    let inner = borrow.deref_mut();
+                    if let Some(val) = inner.pending.take() {
+                        inner.emit(val, s);
+                    }
 
-                    !inner.audience.is_empty() ||
-                        Arc::strong_count(&pseudo_weak) > pseudo_weak.0.load(SeqCst) as usize
+                    parent_arc.retained(inner)
                 }, s);
 
-                JoinedSignal {
+                OperatorSignal {
                     inner: arc
                 }
             }
         }
 
-        impl<T, U, V> Signal for JoinedSignal<T, U, V>
-            where T: Send + Clone + 'static,
-                  U: Send + Clone + 'static,
-                  V: Send + 'static
-        {
-            type Target = V;
+        impl<T> Signal for OperatorSignal<T> where T: Send + 'static {
+            type Target = T;
 
-            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=V> + 'a {
+            fn borrow<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=T> + 'a {
                 SignalRef {
                     src: self.inner.1.borrow(s),
                 }
             }
 
+            #[track_caller]
             fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
-                where F: FnMut(&V, Slock) -> bool + Send + 'static {
+                where F: FnMut(&T, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &T, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
                 self.inner.1.borrow_mut(s).audience.listen(listener, s);
             }
 
             type MappedOutput<S: Send + 'static> = GeneralSignal<S>;
+            #[track_caller]
             fn map<S, F>(&self, map: F, s: Slock<impl ThreadMarker>) -> GeneralSignal<S>
                 where S: Send + 'static,
-                      F: Send + 'static + Fn(&V) -> S
+                      F: Send + 'static + Fn(&T) -> S
             {
                 GeneralSignal::from(self, self, map, |this, listener, s| {
                     this.inner.1.borrow_mut(s).audience.listen_box(listener, s);
@@ -2984,14 +6696,117 @@ mod signal {
         }
     }
 
+    // bridges signals into the async world
+    // the stream only ever holds cloned values, so the slock
+    // is never needed (nor held) while polling
+    #[cfg(feature = "futures")]
+    mod signal_stream {
+        use std::collections::VecDeque;
+        use std::pin::Pin;
+        use std::sync::{Arc, Mutex};
+        use std::task::{Context, Poll, Waker};
+
+        use futures::Stream;
+
+        use crate::core::Slock;
+        use crate::state::Signal;
+        use crate::util::marker::ThreadMarker;
+
+        struct StreamState<T> {
+            queue: VecDeque<T>,
+            waker: Option<Waker>,
+            closed: bool,
+        }
+
+        type SharedStreamState<T> = Arc<Mutex<StreamState<T>>>;
+
+        // owned by the source listener, so that the stream
+        // terminates once the source signal is freed
+        struct StreamSender<T> {
+            state: SharedStreamState<T>
+        }
+
+        impl<T> Drop for StreamSender<T> {
+            fn drop(&mut self) {
+                let mut state = self.state.lock().unwrap();
+                state.closed = true;
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+
+        pub trait SignalStreamExt: Signal where Self::Target: Clone {
+            /// Yields a clone of every subsequent value of the signal.
+            /// The stream terminates once the signal is dropped
+            fn stream(&self, s: Slock<impl ThreadMarker>) -> SignalStream<Self::Target>;
+        }
+
+        impl<S> SignalStreamExt for S where S: Signal, S::Target: Clone {
+            fn stream(&self, s: Slock<impl ThreadMarker>) -> SignalStream<S::Target> {
+                let state = Arc::new(Mutex::new(StreamState {
+                    queue: VecDeque::new(),
+                    waker: None,
+                    closed: false,
+                }));
+
+                let sender = StreamSender {
+                    state: state.clone()
+                };
+                self.listen(move |val, _s| {
+                    // stream was dropped
+                    if Arc::strong_count(&sender.state) == 1 {
+                        return false;
+                    }
+
+                    let mut state = sender.state.lock().unwrap();
+                    state.queue.push_back(val.clone());
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+
+                    true
+                }, s);
+
+                SignalStream {
+                    state
+                }
+            }
+        }
+
+        pub struct SignalStream<T> {
+            state: SharedStreamState<T>
+        }
+
+        impl<T> Stream for SignalStream<T> {
+            type Item = T;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+                let mut state = self.state.lock().unwrap();
+                if let Some(val) = state.queue.pop_front() {
+                    Poll::Ready(Some(val))
+                }
+                else if state.closed {
+                    Poll::Ready(None)
+                }
+                else {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
     mod timed_signal {
         use std::ops::{Deref, DerefMut};
         use std::sync::atomic::AtomicU8;
         use std::sync::atomic::Ordering::SeqCst;
         use std::sync::Arc;
         use std::time::Duration;
+        #[cfg(debug_assertions)]
+        use crate::util::test_util::{check_listener_cycle, note_strong_handle};
 
-        use crate::core::{timed_worker, Slock};
+        use crate::core::{reduced_motion, timed_worker, Slock};
         use crate::state::capacitor::Capacitor;
         use crate::state::signal::signal_audience::SignalAudience;
         use crate::state::signal::signal_ref::SignalRef;
@@ -2999,7 +6814,7 @@ mod signal {
         use crate::state::slock_cell::SlockCell;
         use crate::state::{GeneralSignal, Signal};
         use crate::util::marker::ThreadMarker;
-        use crate::util::test_util::QuarveAllocTag;
+        use crate::util::test_util::{AllocKind, QuarveAllocTag};
 
         pub trait WithCapacitor {
             type Target: Send;
@@ -3010,6 +6825,7 @@ mod signal {
         impl<S> WithCapacitor for S where S: Signal {
             type Target = S::Target;
 
+            #[track_caller]
             fn with_capacitor<C>(&self, capacitor: C, s: Slock<impl ThreadMarker>)
                 -> CapacitatedSignal<C> where C: Capacitor<Target=S::Target> {
                 CapacitatedSignal::from(self, capacitor, s)
@@ -3029,6 +6845,21 @@ mod signal {
                 self.curr = to;
                 self.audience.dispatch(&self.curr, s);
             }
+
+            // with reduced motion, jumps to the target rather than animating
+            // returns whether it was settled
+            // (any active worker is left to stop itself on its next tick)
+            fn try_settle(&mut self, s: Slock) -> bool {
+                if !reduced_motion() {
+                    return false;
+                }
+
+                let Some(target) = self.capacitor.settle() else {
+                    return false;
+                };
+                self.set_curr(target, s);
+                true
+            }
         }
 
         impl<C> InnerSignal for CapacitatedInnerSignal<C> where C: Capacitor {
@@ -3047,6 +6878,8 @@ mod signal {
 
         impl<C> Clone for CapacitatedSignal<C> where C: Capacitor {
             fn clone(&self) -> Self {
+                #[cfg(debug_assertions)]
+                note_strong_handle(self, &self.inner);
                 CapacitatedSignal {
                     inner: self.inner.clone()
                 }
@@ -3082,6 +6915,12 @@ mod signal {
                         let mut borrow = worker_arc.1.borrow_mut(s);
                         let mut_ref = borrow.deref_mut();
 
+                        // reduced motion may have been enabled mid animation
+                        if mut_ref.try_settle(s) {
+                            mut_ref.time_active = None;
+                            return false;
+                        }
+
                         let (sample, cont) = mut_ref.capacitor.sample(duration);
                         mut_ref.set_curr(sample, s);
 
@@ -3104,6 +6943,7 @@ mod signal {
         }
 
         impl<C> CapacitatedSignal<C> where C: Capacitor {
+            #[track_caller]
             pub fn from(source: &impl Signal<Target=C::Target>, mut capacitor: C, s: Slock<impl ThreadMarker>) -> Self {
                 capacitor.target_set(&*source.borrow(s), None);
                 let (curr, initial_thread) = capacitor.sample(Duration::from_secs(0));
@@ -3111,7 +6951,7 @@ mod signal {
                 // initially, there is only one parent (the source signal)
                 // hence the first field
                 let arc = Arc::new((AtomicU8::new(1), SlockCell::new(CapacitatedInnerSignal {
-                    _quarve_tag: QuarveAllocTag::new(),
+                    _quarve_tag: QuarveAllocTag::new::<C::Target>(AllocKind::Signal),
                     curr,
                     capacitor,
                     time_active: None,
@@ -3125,17 +6965,19 @@ mod signal {
                 // which we can argue via retain count, only then can we cancel
                 let parent_arc = ParentOwner(arc.clone());
                 source.listen(move |curr, s| {
-                    let ParentOwner(parent_arc) = &parent_arc;
+                    let ParentOwner(owner) = &parent_arc;
 
-                    let mut borrow = parent_arc.1.borrow_mut(s);
+                    let mut borrow = owner.1.borrow_mut(s);
                     let mut_ref = borrow.deref_mut();
                     mut_ref.capacitor.target_set(curr, mut_ref.time_active);
-                    CapacitatedSignal::update_active(&parent_arc, mut_ref, s);
+                    if !mut_ref.try_settle(s) {
+                        CapacitatedSignal::update_active(owner, mut_ref, s);
+                    }
 
                     // races don't matter too much since it'll just mean late drop
                     // but nothing unsounds
                     !mut_ref.audience.is_empty() ||
-                        Arc::strong_count(&parent_arc) > parent_arc.0.load(SeqCst) as usize
+                        Arc::strong_count(owner) > owner.0.load(SeqCst) as usize
                 }, s);
 
                 // start thread if necessary
@@ -3158,8 +7000,14 @@ mod signal {
                 }
             }
 
+            #[track_caller]
             fn listen<F>(&self, listener: F, s: Slock<impl ThreadMarker>)
                 where F: FnMut(&C::Target, Slock) -> bool + Send + 'static {
+                #[cfg(debug_assertions)]
+                let listener = {
+                    let mut listener = check_listener_cycle(&self.inner, listener);
+                    move |val: &C::Target, s: Slock| listener.invoke(|listener| listener(val, s))
+                };
                 self.inner.1.borrow_mut(s).audience.listen(listener, s);
             }
 
@@ -3216,23 +7064,25 @@ mod signal {
 #[cfg(test)]
 mod test {
     use std::sync::{Arc, Mutex};
-    use std::thread;
-    use std::thread::sleep;
     use std::time::Duration;
 
     use rand::Rng;
 
-    use crate::core::{clock_signal, setup_timing_thread, slock_main_owner, slock_owner, timed_worker, Slock, SlockOwner};
+    use crate::core::{clock_signal, set_reduced_motion, slock_main_owner, slock_owner, MSlock, Slock, SlockOwner, TestClock};
     use crate::native::global::mark_thread_main;
-    use crate::state::capacitor::{ConstantSpeedCapacitor, ConstantTimeCapacitor, SmoothCapacitor};
+    use crate::state::capacitor::{Capacitor, ConstantSpeedCapacitor, ConstantTimeCapacitor, CubicBezierCapacitor, DecayCapacitor, SmoothCapacitor, SpringCapacitor};
+    use crate::state::timeline::{Timeline, Track};
     use crate::state::SetAction::{Identity, Set};
-    use crate::state::VecActionBasis::{Insert, Remove, Swap};
-    use crate::state::{Bindable, Binding, Buffer, DerivedStore, DirectlyInvertible, EditingString, Filterable, FixedSignal, GroupAction, InverseListener, JoinedSignal, NumericAction, SetAction, Signal, Store, StoreContainer, StringActionBasis, TokenStore, UndoBarrier, WeakBinding, WithCapacitor, Word};
-    use crate::util::marker::MainThreadMarker;
-    use crate::util::numeric::Norm;
+    use crate::state::VecActionBasis::{Insert, Move, Permute, Remove, Replace, Swap};
+    use crate::state::{computed, keyed_diff, Bindable, Binding, Buffer, ComponentAction, DerivedStore, DirectlyInvertible, EditingRope, EditingString, Filterable, FixedSignal, GroupAction, GroupBasis, InverseListener, JoinedSignal, NumericAction, RopeActionBasis, SetAction, Signal, SignalOperators, Store, StoreContainer, StringActionBasis, TextIndex, TokenStore, UndoBarrier, WeakBinding, WithCapacitor, Word};
+    use crate::util::geo::{Inset, Point, Rect, Size};
+    use crate::util::marker::{MainThreadMarker, ThreadMarker};
+    use crate::util::numeric::{Lerp, Norm};
     use crate::util::test_util::HeapChecker;
     use crate::util::Vector;
-    use crate::view::undo_manager::UndoBucket;
+    use crate::view::undo_manager::{history_label, UndoBucket};
+    use crate::view::text::{AttributeSet, CharAttribute, Page, PageAttribute, RunAttribute};
+    use crate::view::util::{Color, LinearColor};
 
     // basic Undo Manager
     #[derive(Clone)]
@@ -3655,6 +7505,127 @@ mod test {
         h.assert_diff(2);
     }
 
+    #[test]
+    fn test_computed() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let use_left = Store::new(true);
+        let left = Store::new(1);
+        let right = Store::new(10);
+
+        let evaluations = Arc::new(Mutex::new(0));
+        let c = evaluations.clone();
+        let (flag, l, r) = (use_left.binding(), left.binding(), right.binding());
+        let result = computed(move |ctx, s| {
+            *c.lock().unwrap() += 1;
+            if ctx.get(&flag, s) {
+                ctx.get(&l, s)
+            }
+            else {
+                ctx.get(&r, s) * 2
+            }
+        }, s.marker());
+
+        let changes = Buffer::new(Vec::new());
+        let weak = changes.downgrade();
+        result.listen(move |val, s| {
+            let Some(changes) = weak.upgrade() else {
+                return false;
+            };
+
+            changes.borrow_mut(s).push(*val);
+            true
+        }, s.marker());
+        assert_eq!(*result.borrow(s.marker()), 1);
+
+        left.apply(Set(2), s.marker());
+        assert_eq!(*result.borrow(s.marker()), 2);
+        // not yet a dependency
+        right.apply(Set(20), s.marker());
+        assert_eq!(*evaluations.lock().unwrap(), 2);
+
+        use_left.apply(Set(false), s.marker());
+        assert_eq!(*result.borrow(s.marker()), 40);
+        // no longer a dependency
+        left.apply(Set(3), s.marker());
+        assert_eq!(*evaluations.lock().unwrap(), 3);
+
+        right.apply(Set(5), s.marker());
+        assert_eq!(*result.borrow(s.marker()), 10);
+        assert_eq!(*changes.borrow(s.marker()), vec![2, 40, 10]);
+        assert_eq!(*evaluations.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_computed_no_early_freeing() {
+        // even if the computed signal is dropped
+        // signals mapped from it remain unaffected
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let store = Store::new(0);
+        let binding = store.binding();
+        let middle = computed(move |ctx, s| ctx.get(&binding, s), s.marker());
+        let bottom = middle.map(|x| *x, s.marker());
+        let changes = Buffer::new(0);
+        let binding = changes.downgrade();
+        bottom.listen(move |_a, s| {
+            let Some(binding) = binding.upgrade() else {
+                return false;
+            };
+
+            *binding.borrow_mut(s) += 1;
+            true
+        }, s.marker());
+
+        store.apply(Set(1), s.marker());
+        drop(middle);
+        store.apply(Set(-1), s.marker());
+        assert_eq!(*bottom.borrow(s.marker()), -1);
+        drop(bottom);
+
+        assert_eq!(*changes.borrow(s.marker()), 2);
+    }
+
+    #[test]
+    fn test_computed_early_freeing() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let store = Store::new(0);
+        {
+            let _h = HeapChecker::new();
+            let binding = store.binding();
+            let middle = computed(move |ctx, s| ctx.get(&binding, s), s.marker());
+            drop(middle);
+            // freed immediately, the stale listener is removed here
+            store.apply(Set(1), s.marker());
+        }
+    }
+
+    #[test]
+    fn test_computed_inputs_dropped() {
+        let h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let left = Store::new(1);
+        let right = Store::new(2);
+        let (l, r) = (left.binding(), right.binding());
+        let middle = computed(move |ctx, s| ctx.get(&l, s) + ctx.get(&r, s), s.marker());
+        let bottom = middle.map(|x| *x * 2, s.marker());
+        bottom.listen(|_, _| true, s.marker());
+
+        left.apply(Set(3), s.marker());
+        assert_eq!(*bottom.borrow(s.marker()), 10);
+
+        // inputs are retained by the closure
+        drop(left);
+        drop(right);
+        h.assert_diff(4);
+
+        drop(middle);
+        h.assert_diff(4);
+        drop(bottom);
+    }
+
     #[test]
     fn test_string() {
         let _h = HeapChecker::new();
@@ -3798,6 +7769,91 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_map() {
+        use std::collections::{BTreeMap, HashMap};
+
+        use crate::state::MapActionBasis;
+
+        fn snapshot(store: &Store<BTreeMap<u32, Store<i32>>>, s: Slock<impl ThreadMarker>) -> Vec<(u32, i32)> {
+            store.borrow(s).iter()
+                .map(|(k, v)| (*k, *v.borrow(s)))
+                .collect()
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let actions: Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>> = Arc::new(Mutex::new(Vec::new()));
+        let store: Store<BTreeMap<u32, Store<i32>>> = Store::new(BTreeMap::from([(1, Store::new(10))]));
+        let a = Arc::downgrade(&actions);
+        store.subtree_inverse_listener(um(move |invertible, _s| {
+            let Some(a) = a.upgrade() else {
+                return false;
+            };
+            a.lock().unwrap().push(invertible);
+
+            true
+        }), s.marker());
+
+        let mut items = Vec::new();
+        for i in 0..127u32 {
+            let curr = snapshot(&store, s.marker());
+            let key = rand::thread_rng().gen_range(0..8u32);
+            let v = rand::thread_rng().gen_range(-100000..100000);
+            let present = curr.iter().any(|(k, _)| *k == key);
+
+            let act = match (present, i % 3) {
+                (false, _) => MapActionBasis::Insert(key, Store::new(v)),
+                (true, 0) => MapActionBasis::Remove(key),
+                (true, 1) => MapActionBasis::Replace(key, Store::new(v)),
+                (true, _) => {
+                    // inner stores are listened to as well
+                    items.push(curr);
+                    store.borrow(s.marker())[&key].apply(Set(v), s.marker());
+                    continue;
+                }
+            };
+            items.push(curr);
+            store.apply(act, s.marker());
+        }
+
+        let mut actions_ = std::mem::take(&mut *actions.lock().unwrap());
+        actions_.reverse();
+        assert_eq!(actions_.len(), items.len());
+
+        for (i, mut action) in actions_.into_iter().enumerate() {
+            action.invert(s.marker());
+            assert_eq!(snapshot(&store, s.marker()), items[items.len() - 1 - i]);
+        }
+
+        // hash maps share the implementation
+        let hash: Store<HashMap<String, Store<i32>>> = Store::new(HashMap::new());
+        let count = Arc::new(Mutex::new(0));
+        let c = count.clone();
+        hash.subtree_general_listener(move |_s| {
+            *c.lock().unwrap() += 1;
+            true
+        }, s.marker());
+        hash.apply(MapActionBasis::Insert("a".to_string(), Store::new(1)), s.marker());
+        hash.borrow(s.marker())["a"].apply(Set(2), s.marker());
+        hash.apply([MapActionBasis::Remove("a".to_string())], s.marker());
+        assert!(hash.borrow(s.marker()).is_empty());
+        // structural changes notify twice (see test_subtree_general_listener)
+        assert_eq!(*count.lock().unwrap(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "already present")]
+    fn test_map_insert_present_key() {
+        use std::collections::BTreeMap;
+
+        use crate::state::MapActionBasis;
+
+        let s = mslock_owner();
+        let store: Store<BTreeMap<u32, Store<i32>>> = Store::new(BTreeMap::from([(1, Store::new(10))]));
+        store.apply(MapActionBasis::Insert(1, Store::new(20)), s.marker());
+    }
+
     #[test]
     fn test_vec_collapsed() {
         let _h = HeapChecker::new();
@@ -3857,54 +7913,319 @@ mod test {
         drop(l);
         res.invert(s.marker());
 
-        assert_eq!(store.borrow(s.marker()).len(), 1);
+        assert_eq!(store.borrow(s.marker()).len(), 1);
+    }
+
+    #[test]
+    fn test_subtree_general_listener() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let store = Store::new(vec![Store::new(1)]);
+        let count = Arc::new(Mutex::new(0));
+        let c = count.clone();
+        store.subtree_general_listener(move |_s| {
+            *c.lock().unwrap() += 1;
+            true
+        }, s.marker());
+        store.apply(Insert(Store::new(2), 0), s.marker());
+        store.borrow(s.marker())[0].apply(Set(1), s.marker());
+
+        // 3 because an extra call is made to check
+        // if it's still relevant
+        assert_eq!(*count.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_clock_signal() {
+        let test_clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let clock = {
+            let s = slock_owner();
+            clock_signal(s.marker())
+        };
+
+        test_clock.advance(Duration::from_millis(800));
+
+        {
+            let s = slock_owner();
+            assert!((*clock.borrow(s.marker()) - 0.8).abs() < 1e-9);
+        }
+
+        // another tick frees the clock from the scheduler
+        drop(clock);
+        test_clock.settle(Duration::from_secs(1));
     }
 
     #[test]
-    fn test_subtree_general_listener() {
+    fn test_signal_operators() {
         let _h = HeapChecker::new();
         let s = mslock_owner();
-        let store = Store::new(vec![Store::new(1)]);
-        let count = Arc::new(Mutex::new(0));
-        let c = count.clone();
-        store.subtree_general_listener(move |_s| {
-            *c.lock().unwrap() += 1;
+        let store = Store::new(0);
+        let clock = Store::new(0);
+
+        let evens = store.filter(|x| *x % 2 == 0, s.marker());
+        let distinct = store.map(|x| *x / 10, s.marker())
+            .distinct(s.marker());
+        let sampled = store.sample(&clock, s.marker());
+
+        let changes = Buffer::new(0);
+        let weak = changes.downgrade();
+        distinct.listen(move |_, s| {
+            let Some(changes) = weak.upgrade() else {
+                return false;
+            };
+
+            *changes.borrow_mut(s) += 1;
             true
         }, s.marker());
-        store.apply(Insert(Store::new(2), 0), s.marker());
-        store.borrow(s.marker())[0].apply(Set(1), s.marker());
 
-        // 3 because an extra call is made to check
-        // if it's still relevant
-        assert_eq!(*count.lock().unwrap(), 3);
+        for i in [1, 2, 3, 12, 15, 21] {
+            store.apply(Set(i), s.marker());
+        }
+        assert_eq!(*evens.borrow(s.marker()), 12);
+        assert_eq!(*distinct.borrow(s.marker()), 2);
+        assert_eq!(*changes.borrow(s.marker()), 2);
+        assert_eq!(*sampled.borrow(s.marker()), 0);
+
+        clock.apply(Set(1), s.marker());
+        assert_eq!(*sampled.borrow(s.marker()), 21);
+
+        store.apply(Set(22), s.marker());
+        store.apply(Set(23), s.marker());
+        assert_eq!(*sampled.borrow(s.marker()), 21);
+        clock.apply(Set(2), s.marker());
+        assert_eq!(*sampled.borrow(s.marker()), 23);
     }
 
     #[test]
-    fn test_clock_signal() {
-        setup_timing_thread();
+    fn test_debounce_throttle() {
+        let clock = TestClock::install();
 
         let _h = HeapChecker::new();
-        let clock = {
+        let store = Store::new(0);
+        let (debounced, throttled, emissions) = {
             let s = slock_owner();
-            clock_signal(s.marker())
+            let debounced = store.debounce(Duration::from_millis(200), s.marker());
+            let throttled = store.throttle(Duration::from_millis(200), s.marker());
+
+            let emissions = Buffer::new(Vec::new());
+            let weak = emissions.downgrade();
+            throttled.listen(move |val, s| {
+                let Some(emissions) = weak.upgrade() else {
+                    return false;
+                };
+
+                emissions.borrow_mut(s).push(*val);
+                true
+            }, s.marker());
+
+            for i in 1..=3 {
+                store.apply(Set(i), s.marker());
+            }
+
+            assert_eq!(*debounced.borrow(s.marker()), 0);
+            assert_eq!(*throttled.borrow(s.marker()), 1);
+
+            (debounced, throttled, emissions)
         };
 
-        sleep(Duration::from_millis(800));
+        clock.advance(Duration::from_millis(100));
+        {
+            let s = slock_owner();
+            // resets the debounce period
+            store.apply(Set(4), s.marker());
+            assert_eq!(*debounced.borrow(s.marker()), 0);
+        }
+
+        clock.advance(Duration::from_millis(150));
+        {
+            let s = slock_owner();
+            // the throttle period has passed, but not the debounce period
+            assert_eq!(*debounced.borrow(s.marker()), 0);
+            assert_eq!(*throttled.borrow(s.marker()), 4);
+        }
 
+        clock.advance(Duration::from_millis(100));
         {
             let s = slock_owner();
-            assert!((*clock.borrow(s.marker()) - 0.8).abs() < 0.16);
+            assert_eq!(*debounced.borrow(s.marker()), 4);
+            assert_eq!(*emissions.borrow(s.marker()), vec![1, 4]);
         }
 
-        // wait for another tick to make sure clock is
-        // freed from timer thread
-        drop(clock);
-        sleep(Duration::from_millis(100));
+        // another tick frees the signals from the scheduler
+        drop(debounced);
+        drop(throttled);
+        clock.settle(Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_transaction() {
+        type Recorded = Arc<Mutex<Vec<(Box<dyn DirectlyInvertible>, UndoBucket)>>>;
+
+        #[derive(Clone)]
+        struct Recorder(Recorded, Arc<Mutex<usize>>);
+        impl InverseListener for Recorder {
+            fn handle_inverse(&mut self, inverse_action: Box<dyn DirectlyInvertible>, bucket: UndoBucket, _s: Slock) -> bool {
+                self.0.lock().unwrap().push((inverse_action, bucket));
+                true
+            }
+
+            fn undo_barrier(&mut self, _undo_barrier_type: UndoBarrier, _s: Slock) {
+                *self.1.lock().unwrap() += 1;
+            }
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let recorder = Recorder(Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(0)));
+
+        let a = Store::new(0);
+        let b = Store::new(0);
+        a.subtree_inverse_listener(recorder.clone(), s.marker());
+        b.subtree_inverse_listener(recorder.clone(), s.marker());
+
+        // failure reverts everything, and nothing reaches the listener
+        let rolled_back = Arc::new(Mutex::new(false));
+        let rb = rolled_back.clone();
+        let res: Result<(), &str> = s.marker().transaction(|tx, s| {
+            a.apply(Set(1), s);
+            tx.on_rollback(move |_s| *rb.lock().unwrap() = true);
+            b.apply(Set(2), s);
+            if *b.borrow(s) > 1 {
+                return Err("validation");
+            }
+            Ok(())
+        });
+        assert_eq!(res, Err("validation"));
+        assert_eq!(*a.borrow(s.marker()), 0);
+        assert_eq!(*b.borrow(s.marker()), 0);
+        assert!(*rolled_back.lock().unwrap());
+        assert!(recorder.0.lock().unwrap().is_empty());
+        assert_eq!(*recorder.1.lock().unwrap(), 0);
+
+        // nested failure only reverts the inner transaction
+        let res: Result<i32, ()> = s.marker().transaction(|_tx, s| {
+            a.apply(Set(5), s);
+            let inner: Result<(), ()> = s.transaction(|_tx, s| {
+                b.apply(Set(100), s);
+                Err(())
+            });
+            assert!(inner.is_err());
+            assert_eq!(*b.borrow(s), 0);
+            b.apply(Set(6), s);
+
+            Ok(*a.borrow(s) + *b.borrow(s))
+        });
+        assert_eq!(res, Ok(11));
+        assert_eq!(*a.borrow(s.marker()), 5);
+        assert_eq!(*b.borrow(s.marker()), 6);
+
+        // success delivers a single group (with surrounding barriers)
+        let recorded: Vec<_> = recorder.0.lock().unwrap().drain(..).collect();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].1, recorded[1].1);
+        assert_ne!(recorded[0].1, UndoBucket::GLOBAL);
+        assert_eq!(*recorder.1.lock().unwrap(), 4);
+
+        for (mut inverse, _) in recorded.into_iter().rev() {
+            inverse.invert(s.marker());
+        }
+        assert_eq!(*a.borrow(s.marker()), 0);
+        assert_eq!(*b.borrow(s.marker()), 0);
+        // redo actions are delivered directly outside of transactions
+        assert_eq!(recorder.0.lock().unwrap().len(), 2);
+        recorder.0.lock().unwrap().clear();
+
+        // a panicking body is rolled back, and later actions are no longer deferred
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _: Result<(), ()> = s.marker().transaction(|_tx, s| {
+                a.apply(Set(7), s);
+                panic!("transaction body");
+            });
+        }));
+        assert!(res.is_err());
+        assert_eq!(*a.borrow(s.marker()), 0);
+        a.apply(Set(8), s.marker());
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+        recorder.0.lock().unwrap().clear();
+
+        // listeners that ask to be removed during a commit are detached
+        #[derive(Clone)]
+        struct Detaching(Arc<Mutex<usize>>);
+        impl InverseListener for Detaching {
+            fn handle_inverse(&mut self, _inverse_action: Box<dyn DirectlyInvertible>, _bucket: UndoBucket, _s: Slock) -> bool {
+                *self.0.lock().unwrap() += 1;
+                false
+            }
+
+            fn undo_barrier(&mut self, _undo_barrier_type: UndoBarrier, _s: Slock) {
+
+            }
+        }
+
+        let calls = Arc::new(Mutex::new(0));
+        let detached = Store::new(0);
+        detached.subtree_inverse_listener(Detaching(calls.clone()), s.marker());
+        let _: Result<(), ()> = s.marker().transaction(|_tx, s| {
+            detached.apply(Set(1), s);
+            detached.apply(Set(2), s);
+            Ok(())
+        });
+        detached.apply(Set(3), s.marker());
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_action_labels() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let a = actions.clone();
+        let listener = um(move |inv, _s| {
+            a.lock().unwrap().push(inv);
+            true
+        });
+
+        let text = Store::new(EditingString("ab".to_string()));
+        let list = Store::new(vec![Store::new(1), Store::new(2), Store::new(3)]);
+        let name = Store::new("layer".to_string());
+        text.subtree_inverse_listener(listener.clone(), s.marker());
+        list.subtree_inverse_listener(listener.clone(), s.marker());
+        name.subtree_inverse_listener(listener, s.marker());
+
+        text.apply(Word::new(vec![StringActionBasis::ReplaceSubrange(2..2, "c".to_string())]), s.marker());
+        list.apply([Remove(0)], s.marker());
+        name.apply_labeled("Rename Layer", Set("background".to_string()), s.marker());
+        history_label("Cleanup", || {
+            list.apply([Insert(Store::new(4), 2)], s.marker());
+        });
+        name.apply(Set("foreground".to_string()), s.marker());
+
+        let mut recorded: Vec<_> = actions.lock().unwrap().drain(..).collect();
+        let descriptions: Vec<_> = recorded.iter()
+            .map(|a| a.backward_description())
+            .collect();
+        assert_eq!(descriptions, vec!["Typing", "Delete", "Rename Layer", "Cleanup", "Change"]);
+
+        // redo entries keep the same title
+        recorded[2].invert(s.marker());
+        recorded[1].invert(s.marker());
+        let redo: Vec<_> = actions.lock().unwrap().drain(..).collect();
+        // consumed entries still describe themselves
+        assert_eq!(recorded[2].backward_description(), "Rename Layer");
+        assert_eq!(recorded[1].backward_description(), "Delete");
+        assert_eq!(redo[0].forward_description(), "Rename Layer");
+        assert_eq!(redo[1].forward_description(), "Delete");
+        assert_eq!(*name.borrow(s.marker()), "layer");
+        assert_eq!(list.borrow(s.marker()).len(), 4);
+        assert_eq!(*list.borrow(s.marker())[0].borrow(s.marker()), 1);
     }
 
     #[test]
     fn test_constant_time_capacitor() {
-        setup_timing_thread();
+        let clock = TestClock::install();
 
         let _h = HeapChecker::new();
         let store = Store::new(0.0);
@@ -3916,14 +8237,14 @@ mod test {
             ret
         };
 
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
 
         {
             let s = slock_owner();
             assert!((*capacitated.borrow(s.marker()) - 0.15) < 0.05);
         }
 
-        sleep(Duration::from_millis(1000));
+        clock.advance(Duration::from_millis(1000));
 
         {
             let s = slock_owner();
@@ -3931,7 +8252,7 @@ mod test {
             store.apply(Set(2.0), s.marker());
         }
 
-        sleep(Duration::from_millis(400));
+        clock.advance(Duration::from_millis(400));
 
         {
             let s = slock_owner();
@@ -3944,7 +8265,7 @@ mod test {
             assert!((*capacitated.borrow(s.marker()) - 2.0) < 0.05);
         }
 
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
 
         {
             let s = slock_owner();
@@ -3952,159 +8273,853 @@ mod test {
             store.apply(Set(3.0), s.marker());
         }
 
-        sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
 
         {
             let s = slock_owner();
             assert!((*capacitated.borrow(s.marker()) - 2.82) < 0.05);
         }
 
-        sleep(Duration::from_millis(900));
+        clock.advance(Duration::from_millis(900));
+
+        {
+            let s = slock_owner();
+            assert!((*capacitated.borrow(s.marker()) - 3.0) < 0.05);
+        }
+
+        clock.advance(Duration::from_millis(900));
+
+        {
+            let s = slock_owner();
+            assert!((*capacitated.borrow(s.marker()) - 3.0) < 0.05);
+        }
+
+        // run another frame to make sure the signal is
+        // freed from the scheduler
+        drop(capacitated);
+        clock.advance(Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_virtual_clock() {
+        let clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let store = Store::new(0);
+        let (time, debounced) = {
+            let s = slock_owner();
+            let time = clock_signal(s.marker());
+            let debounced = store.debounce(Duration::from_millis(200), s.marker());
+            store.apply(Set(1), s.marker());
+
+            (time, debounced)
+        };
+
+        clock.advance(Duration::from_millis(150));
+        {
+            let s = slock_owner();
+            assert_eq!(*time.borrow(s.marker()), 0.15);
+            assert_eq!(*debounced.borrow(s.marker()), 0);
+        }
+
+        clock.advance(Duration::from_millis(100));
+        {
+            let s = slock_owner();
+            assert_eq!(*time.borrow(s.marker()), 0.25);
+            assert_eq!(*debounced.borrow(s.marker()), 1);
+        }
+        assert_eq!(clock.now(), Duration::from_millis(250));
+
+        drop(time);
+        drop(debounced);
+        clock.settle(Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_constant_speed_capacitor() {
+        let clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let store = Store::new(Vector([0.0, 0.0]));
+        let capacitated = {
+            let s = slock_owner();
+            store.with_capacitor(ConstantSpeedCapacitor::new(2.0), s.marker())
+        };
+
+        let set = |u, v| {
+            let s = slock_owner();
+            store.apply([Set(u), Set(v)], s.marker());
+        };
+        let close_to = |u, v| {
+            let s = slock_owner();
+            let ret = (*capacitated.borrow(s.marker()) - Vector([u, v])).norm() < 1e-3;
+            ret
+        };
+
+        set(1.0, 0.0);
+        clock.advance(Duration::from_millis(250));
+        assert!(close_to(0.5, 0.0));
+
+        // reaches (1, 0) after half a second and then rests
+        clock.advance(Duration::from_millis(750));
+        assert!(close_to(1.0, 0.0));
+        set(2.0, 3.0);
+
+        clock.advance(Duration::from_millis(250));
+        let first = Vector([1.0, 0.0]) + Vector([1.0, 3.0]) * (0.5 / 10f64.sqrt());
+        assert!(close_to(first.0[0], first.0[1]));
+
+        // retargeted mid flight, continuing from the current position
+        set(2.0, 1.0);
+        clock.advance(Duration::from_millis(250));
+        let remaining = Vector([2.0, 1.0]) - first;
+        let second = first + remaining * (0.5 / remaining.norm());
+        assert!(close_to(second.0[0], second.0[1]));
+
+        drop(capacitated);
+        clock.settle(Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_smooth_capacitor() {
+        let clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let store = Store::new(0.0);
+        let c = {
+            let s = slock_owner();
+            store.with_capacitor(SmoothCapacitor::new(|t| {
+                3.0 * t * t - 2.0 * t * t * t
+            }, 1.5), s.marker())
+        };
+
+        // (time, target) of each retarget, sampled every half second
+        let targets = [(0.0, 10.0), (1.0, 30.0), (1.5, 3.0), (2.5, 100.0)];
+        let vals: [f64; 10] = [
+            2.5925925925925926,
+            7.407407407407408,
+            15.185185185185185,
+            17.814814814814817,
+            10.0,
+            28.148148148148145,
+            74.85185185185186,
+            100.0,
+            100.0,
+            100.0
+        ];
+
+        store.apply(Set(targets[0].1), slock_owner().marker());
+        for (i, val) in vals.into_iter().enumerate() {
+            clock.advance(Duration::from_millis(500));
+
+            let s = slock_owner();
+            assert!((*c.borrow(s.marker()) - val).abs() < 1e-6);
+
+            let time = (i + 1) as f64 * 0.5;
+            if let Some(&(_, target)) = targets.iter().find(|(t, _)| *t == time) {
+                store.apply(Set(target), s.marker());
+            }
+        }
+
+        drop(c);
+        clock.settle(Duration::from_secs(5));
+    }
+
+    // asserts that a retarget at `at` keeps both position and velocity continuous
+    fn assert_smooth_retarget<C: Capacitor<Target=f64>>(capacitor: &mut C, at: f64, target: f64) {
+        let h = 1e-5;
+        let before = capacitor.sample(Duration::from_secs_f64(at - h)).0;
+        let curr = capacitor.sample(Duration::from_secs_f64(at)).0;
+        capacitor.target_set(&target, Some(Duration::from_secs_f64(at)));
+
+        assert!((capacitor.sample(Duration::from_secs_f64(at)).0 - curr).abs() < 1e-9);
+        let after = capacitor.sample(Duration::from_secs_f64(at + h)).0;
+        // a jump in velocity would show up as a first order term
+        assert!((after - 2.0 * curr + before).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_spring_capacitor() {
+        let secs = Duration::from_secs_f64;
+
+        let mut spring: SpringCapacitor<f64> = SpringCapacitor::new(100.0, 10.0, 1.0);
+        spring.target_set(&0.0, None);
+        assert_eq!(spring.sample(secs(0.0)), (0.0, false));
+
+        // underdamped (zeta = 0.5)
+        spring.target_set(&10.0, None);
+        let omega_d = 75f64.sqrt();
+        for t in [0.0f64, 0.05, 0.1, 0.3] {
+            let expected = 10.0 - 10.0 * (-5.0 * t).exp() * ((omega_d * t).cos() + 5.0 / omega_d * (omega_d * t).sin());
+            let (val, cont) = spring.sample(secs(t));
+            assert!((val - expected).abs() < 1e-9);
+            assert!(cont);
+        }
+
+        assert_smooth_retarget(&mut spring, 0.2, -5.0);
+        assert_eq!(spring.sample(secs(10.0)), (-5.0, false));
+
+        // critically damped
+        let mut spring: SpringCapacitor<f64> = SpringCapacitor::critically_damped(100.0);
+        spring.target_set(&0.0, None);
+        spring.target_set(&10.0, None);
+        for t in [0.05f64, 0.1, 0.3] {
+            let expected = 10.0 - 10.0 * (-10.0 * t).exp() * (1.0 + 10.0 * t);
+            assert!((spring.sample(secs(t)).0 - expected).abs() < 1e-9);
+        }
+        assert_smooth_retarget(&mut spring, 0.15, 0.0);
+
+        // overdamped never overshoots
+        let mut spring: SpringCapacitor<f64> = SpringCapacitor::new(100.0, 50.0, 1.0);
+        spring.target_set(&0.0, None);
+        spring.target_set(&10.0, None);
+        let mut last = 0.0;
+        for i in 1..100 {
+            let (val, _) = spring.sample(secs(i as f64 * 0.05));
+            assert!(val >= last && val <= 10.0);
+            last = val;
+        }
+        assert_smooth_retarget(&mut spring, 0.5, 20.0);
+        assert_eq!(spring.sample(secs(60.0)), (20.0, false));
+    }
+
+    #[test]
+    fn test_decay_capacitor() {
+        let secs = Duration::from_secs_f64;
+
+        let mut decay: DecayCapacitor<f64> = DecayCapacitor::new(0.25);
+        decay.target_set(&0.0, None);
+        assert_eq!(decay.sample(secs(0.0)), (0.0, false));
+
+        decay.target_set(&10.0, None);
+        for t in [0.0f64, 0.1, 0.25, 0.5] {
+            let expected = 10.0 * (1.0 - (1.0 + t / 0.25) * (-t / 0.25).exp());
+            let (val, cont) = decay.sample(secs(t));
+            assert!((val - expected).abs() < 1e-9);
+            assert!(cont);
+        }
+
+        // reversing direction carries momentum past the current position
+        let curr = decay.sample(secs(0.3)).0;
+        assert_smooth_retarget(&mut decay, 0.3, 0.0);
+        assert!(decay.sample(secs(0.35)).0 > curr);
+        assert_eq!(decay.sample(secs(10.0)), (0.0, false));
+
+        // resting again
+        decay.target_set(&5.0, None);
+        assert_eq!(decay.sample(secs(0.0)), (0.0, true));
+    }
+
+    #[test]
+    fn test_cubic_bezier_capacitor() {
+        let secs = Duration::from_secs_f64;
+
+        let mut linear: CubicBezierCapacitor<f64> = CubicBezierCapacitor::new(0.0, 0.0, 1.0, 1.0, 1.0);
+        linear.target_set(&0.0, None);
+        assert_eq!(linear.sample(secs(0.0)), (0.0, false));
+
+        linear.target_set(&10.0, None);
+        for t in [0.0, 0.25, 0.5, 0.75] {
+            let (val, cont) = linear.sample(secs(t));
+            assert!((val - 10.0 * t).abs() < 1e-6);
+            assert!(cont);
+        }
+        assert_eq!(linear.sample(secs(1.0)), (10.0, false));
+
+        // css `ease` at half time
+        let mut ease: CubicBezierCapacitor<f64> = CubicBezierCapacitor::ease(2.0);
+        ease.target_set(&0.0, None);
+        ease.target_set(&1.0, None);
+        assert!((ease.sample(secs(1.0)).0 - 0.8024).abs() < 1e-3);
+
+        assert_smooth_retarget(&mut ease, 0.5, -1.0);
+        assert!(ease.sample(secs(2.4)).1);
+        assert_eq!(ease.sample(secs(2.5)), (-1.0, false));
+    }
+
+    #[test]
+    fn test_timeline_tracks() {
+        let _h = HeapChecker::new();
+        let s = slock_owner();
+
+        let track = Track::new(0.0)
+            .keyframe(1.0, 10.0)
+            .keyframe_eased(2.0, 20.0, |t| t * t);
+        assert_eq!(track.duration(), 2.0);
+        assert_eq!(track.value_at(-1.0), 0.0);
+        assert_eq!(track.value_at(0.5), 5.0);
+        assert_eq!(track.value_at(1.5), 12.5);
+        assert_eq!(track.value_at(3.0), 20.0);
+
+        let position = Store::new(0.0);
+        let timeline = Timeline::with_position(position, s.marker());
+        let first = timeline.append(Track::new(0.0).keyframe(1.0, 1.0), s.marker());
+        let second = timeline.append(Track::new(0.0).keyframe(1.0, 1.0), s.marker());
+        // overlaps the second half of the first track
+        let overlap = timeline.insert(0.5, Track::new(0).keyframe(1.0, 100), s.marker());
+        let staggered = timeline.stagger(2.0, 0.5, [
+            Track::new(0.0).keyframe(1.0, 1.0),
+            Track::new(0.0).keyframe(1.0, 1.0),
+        ], s.marker());
+        assert_eq!(*timeline.duration().borrow(s.marker()), 3.5);
+
+        // scrub
+        timeline.position().apply(Set(0.75), s.marker());
+        assert_eq!(*first.borrow(s.marker()), 0.75);
+        assert_eq!(*second.borrow(s.marker()), 0.0);
+        assert_eq!(*overlap.borrow(s.marker()), 25);
+
+        timeline.seek(2.75, s.marker());
+        assert_eq!(*first.borrow(s.marker()), 1.0);
+        assert_eq!(*second.borrow(s.marker()), 1.0);
+        assert_eq!(*staggered[0].borrow(s.marker()), 0.75);
+        assert_eq!(*staggered[1].borrow(s.marker()), 0.25);
+    }
+
+    #[test]
+    fn test_reduced_motion_settle() {
+        let clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let store = Store::new(0.0);
+        let (spring, time) = {
+            let s = slock_owner();
+            let spring = store.with_capacitor(SpringCapacitor::critically_damped(100.0), s.marker());
+            store.apply(Set(10.0), s.marker());
+            (spring, clock_signal(s.marker()))
+        };
+
+        clock.advance(Duration::from_millis(50));
+        {
+            let s = slock_owner();
+            let curr = *spring.borrow(s.marker());
+            assert!(curr > 0.0 && curr < 10.0);
+        }
+
+        // enabled mid animation, the spring settles on the next frame
+        set_reduced_motion(true);
+        clock.advance(Duration::from_millis(20));
+        {
+            let s = slock_owner();
+            assert_eq!(*spring.borrow(s.marker()), 10.0);
+
+            // later targets are adopted immediately
+            store.apply(Set(-3.0), s.marker());
+            assert_eq!(*spring.borrow(s.marker()), -3.0);
+        }
+
+        // clocks are not motion, so they keep running
+        clock.advance(Duration::from_millis(100));
+        {
+            let s = slock_owner();
+            assert!(*time.borrow(s.marker()) >= 0.1);
+        }
+        set_reduced_motion(false);
+
+        // the spring starts from rest at its settled value
+        {
+            let s = slock_owner();
+            store.apply(Set(0.0), s.marker());
+        }
+        clock.advance(Duration::from_millis(20));
+        {
+            let s = slock_owner();
+            let curr = *spring.borrow(s.marker());
+            assert!(curr > -3.0 && curr < 0.0);
+        }
+
+        drop((spring, time));
+        clock.settle(Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_timeline_seek_animated() {
+        let clock = TestClock::install();
+
+        let _h = HeapChecker::new();
+        let (timeline, value) = {
+            let s = slock_owner();
+            let timeline = Timeline::new(s.marker());
+            let value = timeline.append(Track::new(0.0f64).keyframe(2.0, 20.0), s.marker());
+            timeline.play(s.marker());
+            timeline.seek_animated(2.0, ConstantTimeCapacitor::new(1.0), s.marker());
+            (timeline, value)
+        };
+
+        clock.advance(Duration::from_millis(500));
+        {
+            let s = slock_owner();
+            assert!(!*timeline.playing().borrow(s.marker()));
+            assert!((*timeline.position().borrow(s.marker()) - 1.0).abs() < 0.05);
+            assert!((*value.borrow(s.marker()) - 10.0).abs() < 0.5);
+
+            // a direct seek interrupts the animation
+            timeline.seek(0.5, s.marker());
+        }
+
+        clock.advance(Duration::from_millis(1000));
+        {
+            let s = slock_owner();
+            assert_eq!(*timeline.position().borrow(s.marker()), 0.5);
+            assert_eq!(*value.borrow(s.marker()), 5.0);
+        }
+    }
+
+    #[test]
+    fn test_timeline_playback() {
+        let clock = TestClock::install();
+
+        let timeline = Timeline::new(slock_owner().marker());
+        let value = timeline.insert(0.0, Track::new(0.0f64).keyframe(1.0, 1.0), slock_owner().marker());
+        {
+            let s = slock_owner();
+            timeline.rate().apply(Set(2.0), s.marker());
+            timeline.play(s.marker());
+        }
+
+        clock.advance(Duration::from_millis(250));
+        {
+            let s = slock_owner();
+            assert!((*value.borrow(s.marker()) - 0.5).abs() < 1e-9);
+            timeline.pause(s.marker());
+        }
+
+        clock.advance(Duration::from_millis(100));
+        let paused = *timeline.position().borrow(slock_owner().marker());
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(*timeline.position().borrow(slock_owner().marker()), paused);
+
+        // runs until the end
+        timeline.play(slock_owner().marker());
+        clock.advance(Duration::from_millis(500));
+        {
+            let s = slock_owner();
+            assert!(!*timeline.playing().borrow(s.marker()));
+            assert_eq!(*value.borrow(s.marker()), 1.0);
+
+            // reverse and loop
+            timeline.rate().apply(Set(-1.0), s.marker());
+            timeline.looping().apply(Set(true), s.marker());
+            timeline.play(s.marker());
+        }
+
+        clock.advance(Duration::from_millis(1500));
+        {
+            let s = slock_owner();
+            assert!(*timeline.playing().borrow(s.marker()));
+            assert!((*value.borrow(s.marker()) - 0.5).abs() < 1e-9);
+            timeline.pause(s.marker());
+        }
+
+        drop(value);
+        drop(timeline);
+        // let the clock observe the pause
+        clock.settle(Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_vector_action() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let actions: Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>> = Arc::new(Mutex::new(Vec::new()));
+        let store = Store::new(Vector([1, 2]));
+        let weak = Arc::downgrade(&actions);
+        store.subtree_inverse_listener(um(move |invertible, _s| {
+            let Some(strong) = weak.upgrade() else {
+                return false;
+            };
+            strong.lock().unwrap().push(invertible);
+            true
+        }), s.marker());
+        store.apply([Set(2), Identity], s.marker());
+        assert_eq!(*store.borrow(s.marker()).x(), 2);
+        assert_eq!(*store.borrow(s.marker()).y(), 2);
+        store.apply([Set(3), Set(1)], s.marker());
+        assert_eq!(*store.borrow(s.marker()).x(), 3);
+        assert_eq!(*store.borrow(s.marker()).y(), 1);
+
+        let mut action = actions.lock().unwrap().pop().unwrap();
+        let mut action2 = actions.lock().unwrap().pop().unwrap();
+
+        action.invert(s.marker());
+        assert_eq!(*store.borrow(s.marker()).x(), 2);
+        assert_eq!(*store.borrow(s.marker()).y(), 2);
+
+        action2.invert(s.marker());
+        assert_eq!(*store.borrow(s.marker()).x(), 1);
+        assert_eq!(*store.borrow(s.marker()).y(), 2);
+    }
 
-        {
-            let s = slock_owner();
-            assert!((*capacitated.borrow(s.marker()) - 3.0) < 0.05);
-        }
+    #[test]
+    fn test_vec_diff() {
+        let apply = |old: &[i32], new: &[i32]| {
+            let edits = keyed_diff(old, new.iter().map(|&x| (x, x)).collect(), |_, _| true);
+            let count = edits.len();
+            let mut result = old.to_vec();
+            let _ = Word::new(edits.into_iter().rev().collect()).apply(&mut result);
+            assert_eq!(result, new);
+            count
+        };
 
-        sleep(Duration::from_millis(900));
+        assert_eq!(apply(&[1, 2, 3], &[1, 2, 3]), 0);
+        // one removal run, one insertion run
+        assert_eq!(apply(&[1, 2, 3, 4, 5], &[1, 4, 6, 7, 5]), 2);
+        // a single transposition
+        assert_eq!(apply(&[1, 2, 3, 4], &[1, 4, 3, 2]), 1);
+        assert_eq!(apply(&[1, 2, 3], &[]), 1);
+        assert_eq!(apply(&[], &[1, 2, 3]), 1);
+
+        // duplicate keys are matched in order
+        let edits = keyed_diff(&[1, 1, 2], vec![(1, 'a'), (2, 'b'), (1, 'c')], |_, _| true);
+        assert!(edits.iter().all(|e| matches!(e, Swap(..))));
+
+        // changed elements are replaced at their final index, after any reordering
+        let old = [(1, 'a'), (2, 'b'), (3, 'c')];
+        let new = vec![(3, 'c'), (4, 'd'), (1, 'x')];
+        let keys: Vec<i32> = old.iter().map(|&(k, _)| k).collect();
+        let edits = keyed_diff(&keys, new.clone(), |i, &v| old[i].1 == v);
+        assert!(matches!(edits.last(), Some(Replace(2, 'x'))));
+        let mut result: Vec<char> = old.iter().map(|&(_, v)| v).collect();
+        let _ = Word::new(edits.into_iter().rev().collect()).apply(&mut result);
+        assert_eq!(result, new.iter().map(|&(_, v)| v).collect::<Vec<_>>());
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let old: Vec<i32> = (0..20).filter(|_| rng.gen_bool(0.7)).collect();
+            let mut new: Vec<i32> = (0..30).filter(|_| rng.gen_bool(0.5)).collect();
+            for i in (1..new.len()).rev() {
+                new.swap(i, rng.gen_range(0..=i));
+            }
+            apply(&old, &new);
+        }
+
+        // invalid permutations are rejected before anything is moved
+        let mut items = vec![1, 2, 3];
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = Permute(vec![0, 0, 1]).apply(&mut items);
+        }));
+        assert!(res.is_err());
+        assert_eq!(items, vec![1, 2, 3]);
+
+        // stores of kept elements are preserved, and the diff is one undo step
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let store = Store::new((1..=4).map(Store::new).collect::<Vec<_>>());
+        let original: Vec<usize> = store.borrow(s.marker()).iter()
+            .map(|item| item.binding().address())
+            .collect();
+
+        let inverses = Arc::new(Mutex::new(Vec::new()));
+        let c = inverses.clone();
+        store.subtree_inverse_listener(um(move |inv, _s| {
+            c.lock().unwrap().push(inv);
+            true
+        }), s.marker());
 
+        let values = |s: Slock| store.borrow(s).iter()
+            .map(|item| *item.borrow(s))
+            .collect::<Vec<_>>();
+        let fresh = [4, 2, 5, 1].into_iter().map(Store::new).collect();
+        store.apply_diff(fresh, |item, s| *item.borrow(s), |_, _, _| true, s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![4, 2, 5, 1]);
         {
-            let s = slock_owner();
-            assert!((*capacitated.borrow(s.marker()) - 3.0) < 0.05);
+            let items = store.borrow(s.marker());
+            assert_eq!(items[0].binding().address(), original[3]);
+            assert_eq!(items[1].binding().address(), original[1]);
+            assert_eq!(items[3].binding().address(), original[0]);
         }
 
-        // wait for another tick to make sure clock is
-        // freed from timer thread
-        drop(capacitated);
-        sleep(Duration::from_millis(100));
+        let mut last = {
+            let mut inverses = inverses.lock().unwrap();
+            assert_eq!(inverses.len(), 1);
+            inverses.pop().unwrap()
+        };
+        last.invert(s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![1, 2, 3, 4]);
+        inverses.lock().unwrap().clear();
+
+        // a kept element whose value changed is replaced, still as one undo step
+        let fresh = vec![Store::new(12), Store::new(3)];
+        store.apply_diff(fresh, |item, s| *item.borrow(s) % 10, |a, b, s| *a.borrow(s) == *b.borrow(s), s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![12, 3]);
+        assert_eq!(store.borrow(s.marker())[1].binding().address(), original[2]);
+
+        let mut last = {
+            let mut inverses = inverses.lock().unwrap();
+            assert_eq!(inverses.len(), 1);
+            inverses.pop().unwrap()
+        };
+        last.invert(s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![1, 2, 3, 4]);
+        inverses.lock().unwrap().clear();
     }
 
     #[test]
-    fn test_constant_speed_capacitor() {
-        setup_timing_thread();
-
+    fn test_vec_reorder_actions() {
         let _h = HeapChecker::new();
-        let store = Store::new(Vector([0.0, 0.0]));
-        let capacitated = {
-            let s = slock_owner();
-            let ret = store.with_capacitor(ConstantSpeedCapacitor::new(2.0), s.marker());
+        let s = mslock_owner();
+        let store = Store::new((1..=5).map(Store::new).collect::<Vec<_>>());
+        let inverses = Arc::new(Mutex::new(Vec::new()));
+        let c = inverses.clone();
+        store.subtree_inverse_listener(um(move |inv, _s| {
+            c.lock().unwrap().push(inv);
+            true
+        }), s.marker());
 
-            ret
+        let values = |s: Slock| store.borrow(s).iter()
+            .map(|item| *item.borrow(s))
+            .collect::<Vec<_>>();
+        let address = |i: usize| store.borrow(s.marker())[i].binding().address();
+        let undo_all = || {
+            let all: Vec<_> = inverses.lock().unwrap().drain(..).collect();
+            for mut inv in all.into_iter().rev() {
+                inv.invert(s.marker());
+            }
+            inverses.lock().unwrap().clear();
         };
 
-        let first = thread::spawn(move || {
-            let set = |u, v| {
-                let s = slock_owner();
-                store.apply([Set(u), Set(v)], s.marker());
-            };
-
-            set(1.0, 0.0);
-
-            sleep(Duration::from_millis(1000));
-
-            set(2.0, 3.0);
+        let first = address(0);
+        store.apply(Move(0, 3), s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![2, 3, 4, 1, 5]);
+        assert_eq!(address(3), first);
+
+        store.apply(Permute(vec![4, 3, 2, 1, 0]), s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![5, 1, 4, 3, 2]);
+
+        // replaced elements receive the subtree listeners
+        store.apply(Replace(2, Store::new(40)), s.marker());
+        store.borrow(s.marker())[2].apply(Set(41), s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![5, 1, 41, 3, 2]);
+        assert_eq!(inverses.lock().unwrap().len(), 4);
+
+        undo_all();
+        assert_eq!(values(s.marker().to_general_slock()), vec![1, 2, 3, 4, 5]);
+        assert_eq!(address(0), first);
+
+        // stable
+        store.sort_by_key(|item, s| *item.borrow(s) % 2, s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![2, 4, 1, 3, 5]);
+        store.retain(|item, s| *item.borrow(s) != 4 && *item.borrow(s) != 1, s.marker());
+        assert_eq!(values(s.marker().to_general_slock()), vec![2, 3, 5]);
+        // sorting an already sorted vector is not recorded
+        store.sort_by_key(|item, s| *item.borrow(s), s.marker());
+        assert_eq!(inverses.lock().unwrap().len(), 2);
+
+        undo_all();
+        assert_eq!(values(s.marker().to_general_slock()), vec![1, 2, 3, 4, 5]);
+    }
 
-            sleep(Duration::from_millis(250));
+    #[test]
+    fn test_editing_rope() {
+        let text = "héllo\nwörld 🎉\n\nend";
+        let string = EditingString(text.to_string());
+        let rope = EditingRope::new(text);
+        assert_eq!(rope.len_lines(), 4);
+        assert_eq!(rope.line(1), "wörld 🎉");
+        for (a, b) in [(&string as &dyn TextIndex, &rope as &dyn TextIndex)] {
+            assert_eq!(a.len_bytes(), b.len_bytes());
+            assert_eq!(a.len_chars(), b.len_chars());
+            assert_eq!(a.len_lines(), b.len_lines());
+            for c in 0..=a.len_chars() {
+                assert_eq!(a.char_to_byte(c), b.char_to_byte(c));
+                assert_eq!(a.char_to_line(c), b.char_to_line(c));
+                assert_eq!(a.char_to_utf16(c), b.char_to_utf16(c));
+                assert_eq!(a.utf16_to_char(a.char_to_utf16(c)), c);
+                assert_eq!(b.byte_to_char(b.char_to_byte(c)), c);
+            }
+            for l in 0..=a.len_lines() {
+                assert_eq!(a.line_to_byte(l), b.line_to_byte(l));
+                assert_eq!(a.line_to_char(l), b.line_to_char(l));
+            }
+            for l in 0..a.len_lines() {
+                assert_eq!(a.line(l), b.line(l));
+            }
+        }
+
+        // same actions (and inverses) as editing strings
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let store = Store::new(rope);
+        let inverses = Arc::new(Mutex::new(Vec::new()));
+        let c = inverses.clone();
+        store.subtree_inverse_listener(um(move |inv, _s| {
+            c.lock().unwrap().push(inv);
+            true
+        }), s.marker());
 
-            set(2.0,  1.0);
-        });
+        store.apply(RopeActionBasis::ReplaceSubrange(1..3, "e".to_string()), s.marker());
+        store.apply(RopeActionBasis::from(StringActionBasis::ReplaceSubrange(13..17, "🎈".to_string())), s.marker());
+        assert_eq!(store.borrow(s.marker()).to_string(), "hello\nwörld 🎈\n\nend");
 
-        let second = thread::spawn(move || {
-            let close_to = |u, v| {
-                let s = slock_owner();
-                let ret = (*capacitated.borrow(s.marker()) - Vector([u, v])).norm() < 0.1;
-                ret
-            };
+        let all: Vec<_> = inverses.lock().unwrap().drain(..).collect();
+        for mut inv in all.into_iter().rev() {
+            inv.invert(s.marker());
+        }
+        inverses.lock().unwrap().clear();
+        assert_eq!(store.borrow(s.marker()).to_string(), text);
+    }
 
-            sleep(Duration::from_millis(250));
+    #[test]
+    fn test_page_content_rope() {
+        struct Attributes;
+        impl AttributeSet for Attributes {
+            type CharAttribute = CharAttribute;
+            type RunAttribute = RunAttribute;
+            type PageAttribute = PageAttribute;
+        }
 
-            assert!(close_to(0.5, 0.0));
+        let s = mslock_owner();
+        let page: Page<Attributes, Attributes> = Page::new(s.marker());
+        let joined = |s: MSlock| page.runs(s).iter()
+            .map(|run| run.content(s).to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // pages place undo barriers
+        #[derive(Clone)]
+        struct Recorder(Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>>);
+        impl InverseListener for Recorder {
+            fn handle_inverse(&mut self, inverse_action: Box<dyn DirectlyInvertible>, _bucket: UndoBucket, _s: Slock) -> bool {
+                self.0.lock().unwrap().push(inverse_action);
+                true
+            }
 
-            sleep(Duration::from_millis(1000));
+            fn undo_barrier(&mut self, _undo_barrier_type: UndoBarrier, _s: Slock) { }
+        }
 
-            assert!(close_to(1.15, 0.474341649));
+        let inverses = Arc::new(Mutex::new(Vec::new()));
+        page.subtree_inverse_listener(Recorder(inverses.clone()), s.marker());
 
-            sleep(Duration::from_millis(250));
-            assert!(close_to(1.575, 0.737));
+        let mut rng = rand::thread_rng();
+        let pieces = ["a", "bc", "\n", "x\ny", "é", "\n\nz", ""];
+        for _ in 0..200 {
+            let runs = page.num_runs(s.marker());
+            let start_run = rng.gen_range(0..runs);
+            let end_run = rng.gen_range(start_run..runs.min(start_run + 3));
+            let char_boundary = |run: usize, rng: &mut rand::rngs::ThreadRng| {
+                let content = page.run(run, s.marker()).content(s.marker()).to_string();
+                let bounds: Vec<_> = content.char_indices().map(|(i, _)| i).chain([content.len()]).collect();
+                bounds[rng.gen_range(0..bounds.len())]
+            };
+            let mut start_char = char_boundary(start_run, &mut rng);
+            let mut end_char = char_boundary(end_run, &mut rng);
+            if start_run == end_run && end_char < start_char {
+                std::mem::swap(&mut start_char, &mut end_char);
+            }
+            let with = pieces[rng.gen_range(0..pieces.len())];
 
-        });
-        first.join().unwrap();
-        second.join().unwrap();
+            page.replace_range(start_run, start_char, end_run, end_char, with, s.marker());
+            assert_eq!(page.content(s.marker()).to_string(), joined(s.marker()));
+            assert_eq!(page.content(s.marker()).len_lines(), page.num_runs(s.marker()));
+        }
 
-        // wait for another tick to make sure clock is
-        // freed from timer thread
-        sleep(Duration::from_millis(1000));
+        // undos are mirrored as well
+        let all: Vec<_> = inverses.lock().unwrap().drain(..).collect();
+        for mut inv in all.into_iter().rev() {
+            inv.invert(s.marker());
+            assert_eq!(page.content(s.marker()).to_string(), joined(s.marker()));
+        }
+        inverses.lock().unwrap().clear();
+        assert_eq!(page.build_full_content(s.marker()), "");
     }
 
     #[test]
-    fn test_smooth_capacitor() {
-        setup_timing_thread();
+    fn test_geometry_and_color_stateful() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let actions: Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>> = Arc::new(Mutex::new(Vec::new()));
+        let store = Store::new(Rect::new(0.0, 0.0, 10.0, 10.0));
+        let weak = Arc::downgrade(&actions);
+        store.subtree_inverse_listener(um(move |invertible, _s| {
+            let Some(strong) = weak.upgrade() else {
+                return false;
+            };
+            strong.lock().unwrap().push(invertible);
+            true
+        }), s.marker());
 
-        let store = Store::new(0.0);
-        let c = {
-            let s = slock_owner();
-            store.with_capacitor(SmoothCapacitor::new(|t| {
-                3.0 * t * t - 2.0 * t * t * t
-            }, 1.5), s.marker())
-        };
-        let u = Arc::new(Mutex::new(vec![]));
-        let v = Arc::new(Mutex::new(vec![]));
+        store.apply([Identity, Set(5.0), Identity, Set(20.0)], s.marker());
+        assert_eq!(*store.borrow(s.marker()), Rect::new(0.0, 5.0, 10.0, 20.0));
+        store.apply(ComponentAction::component(2, 1.0), s.marker());
+        assert_eq!(*store.borrow(s.marker()), Rect::new(0.0, 5.0, 1.0, 20.0));
+        store.apply(Set(Rect::new(1.0, 2.0, 3.0, 4.0)), s.marker());
+        assert_eq!(*store.borrow(s.marker()), Rect::new(1.0, 2.0, 3.0, 4.0));
 
-        let up = u.clone();
-        let vp = v.clone();
+        let inverses: Vec<_> = actions.lock().unwrap().drain(..).rev().collect();
+        let expected = [Rect::new(0.0, 5.0, 1.0, 20.0), Rect::new(0.0, 5.0, 10.0, 20.0), Rect::new(0.0, 0.0, 10.0, 10.0)];
+        for (mut action, expected) in inverses.into_iter().zip(expected) {
+            action.invert(s.marker());
+            assert_eq!(*store.borrow(s.marker()), expected);
+        }
+
+        assert_eq!(Point::lerp(Point::new(0.0, 2.0), 0.25, Point::new(4.0, 6.0)), Point::new(1.0, 3.0));
+        assert_eq!(Size::new(3.0, 4.0).norm(), 5.0);
+        assert_eq!(Inset::default().norm(), 0.0);
+
+        let color = Store::new(Color::black());
+        color.apply([Set(255), Identity, Identity, Identity], s.marker());
+        assert_eq!(*color.borrow(s.marker()), Color::rgb(255, 0, 0));
+
+        let (red, blue) = (Color::rgb(255, 0, 0), Color::rgb(0, 0, 255));
+        for lerp in [Color::lerp, Color::lerp_srgb, Color::lerp_oklab] {
+            assert_eq!(lerp(red, 0.0, blue), red);
+            assert_eq!(lerp(red, 1.0, blue), blue);
+        }
+        assert_eq!(Color::lerp_srgb(red, 0.5, blue), Color::rgb(128, 0, 128));
+        // linear rgb keeps the midpoint brighter than naive srgb
+        assert_eq!(Color::lerp(red, 0.5, blue), Color::rgb(188, 0, 188));
+        // fading to clear does not darken the color
+        assert_eq!(Color::lerp(red, 0.5, Color::clear()), Color::rgba(255, 0, 0, 128));
+        assert_eq!(Color::white().norm(), 2.0);
+
+        // linear colors support physically based capacitors
+        for color in [red, blue, Color::rgba(12, 200, 99, 40)] {
+            assert_eq!(Color::from(LinearColor::from(color)), color);
+        }
+        let mut spring: SpringCapacitor<LinearColor> = SpringCapacitor::new(100.0, 10.0, 1.0);
+        spring.target_set(&red.into(), None);
+        spring.target_set(&blue.into(), None);
+        let (mid, cont) = spring.sample(Duration::from_secs_f64(0.05));
+        assert!(cont);
+        assert!(mid.r < 1.0 && mid.r > 0.0 && mid.b > 0.0 && mid.b < 1.0);
+        let (end, _) = spring.sample(Duration::from_secs(5));
+        assert_eq!(Color::from(end), blue);
+
+        let linear = Store::new(LinearColor::from(red));
+        linear.apply(ComponentAction::component(3, 0.5), s.marker());
+        assert_eq!(Color::from(*linear.borrow(s.marker())), Color::rgba(255, 0, 0, 128));
+    }
 
-        let binding = store.binding();
-        let signal = c.clone();
-        timed_worker(move |t, s| {
-            up.lock().unwrap().push(*store.borrow(s));
-            vp.lock().unwrap().push(*c.borrow(s));
+    #[test]
+    fn test_derive_stateful() {
+        use quarve_derive::Stateful;
 
-            t < Duration::from_secs(5)
-        });
+        #[derive(Stateful, Clone, PartialEq, Debug)]
+        struct Layer {
+            name: String,
+            opacity: f64,
+            is_visible: bool,
+        }
 
-        thread::spawn(move || {
-            let set = |targ| {
-                let s = slock_owner();
-                binding.apply(Set(targ), s.marker());
-            };
-            set(10.0);
-            sleep(Duration::from_millis(1000));
-            set(30.0);
-            sleep(Duration::from_millis(500));
-            set(3.0);
-            sleep(Duration::from_millis(1000));
-            set(100.0);
-        });
+        #[derive(Stateful, PartialEq, Debug)]
+        struct Pair<T>(T, T) where T: Send + 'static;
 
-        thread::spawn(move || {
-            let vals: [f64; 10] = [
-                2.5165922397962865,
-                7.358935059160058,
-                15.27119632650832,
-                17.815625429884776,
-                9.614400059097994,
-                29.786713432638333,
-                75.9109455527237,
-                99.95274972998922,
-                99.95274972998922,
-                99.95274972998922
-            ];
-            for i in 0..10 {
-                sleep(Duration::from_millis(500));
-                let s = slock_owner();
-                // relatively high tolerance since
-                // pretty steep
-                assert!((*signal.borrow(s.marker()) / vals[i] - 1.0).abs() < 0.25);
-            }
-        }).join().unwrap();
-    }
+        #[derive(Stateful, PartialEq, Debug)]
+        enum Shape {
+            Circle { radius: f64 },
+            Rect(f64, f64),
+            Empty,
+        }
 
-    #[test]
-    fn test_vector_action() {
         let _h = HeapChecker::new();
         let s = mslock_owner();
         let actions: Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>> = Arc::new(Mutex::new(Vec::new()));
-        let store = Store::new(Vector([1, 2]));
+        let layer = Layer {
+            name: "Background".to_string(),
+            opacity: 1.0,
+            is_visible: true,
+        };
+        let store = Store::new(layer.clone());
         let weak = Arc::downgrade(&actions);
         store.subtree_inverse_listener(um(move |invertible, _s| {
             let Some(strong) = weak.upgrade() else {
@@ -4113,23 +9128,54 @@ mod test {
             strong.lock().unwrap().push(invertible);
             true
         }), s.marker());
-        store.apply([Set(2), Identity], s.marker());
-        assert_eq!(*store.borrow(s.marker()).x(), 2);
-        assert_eq!(*store.borrow(s.marker()).y(), 2);
-        store.apply([Set(3), Set(1)], s.marker());
-        assert_eq!(*store.borrow(s.marker()).x(), 3);
-        assert_eq!(*store.borrow(s.marker()).y(), 1);
 
-        let mut action = actions.lock().unwrap().pop().unwrap();
-        let mut action2 = actions.lock().unwrap().pop().unwrap();
+        store.apply(LayerActionBasis::Opacity(Set(0.5)), s.marker());
+        store.apply([
+            LayerActionBasis::IsVisible(Set(false)),
+            LayerActionBasis::Name(Set("New Background".to_string()))
+        ], s.marker());
+        assert_eq!(*store.borrow(s.marker()), Layer {
+            name: "New Background".to_string(),
+            opacity: 0.5,
+            is_visible: false,
+        });
 
-        action.invert(s.marker());
-        assert_eq!(*store.borrow(s.marker()).x(), 2);
-        assert_eq!(*store.borrow(s.marker()).y(), 2);
+        store.apply(LayerActionBasis::Set(Layer {
+            name: "Foreground".to_string(),
+            opacity: 0.25,
+            is_visible: true,
+        }), s.marker());
+        assert_eq!(store.borrow(s.marker()).name, "Foreground");
 
-        action2.invert(s.marker());
-        assert_eq!(*store.borrow(s.marker()).x(), 1);
-        assert_eq!(*store.borrow(s.marker()).y(), 2);
+        let mut set = actions.lock().unwrap().pop().unwrap();
+        let mut fields = actions.lock().unwrap().pop().unwrap();
+        let mut opacity = actions.lock().unwrap().pop().unwrap();
+
+        set.invert(s.marker());
+        assert_eq!(store.borrow(s.marker()).name, "New Background");
+
+        fields.invert(s.marker());
+        assert_eq!(*store.borrow(s.marker()), Layer {
+            name: "Background".to_string(),
+            opacity: 0.5,
+            is_visible: true,
+        });
+
+        opacity.invert(s.marker());
+        assert_eq!(*store.borrow(s.marker()), layer);
+
+        let pair = Store::new(Pair(1, 2));
+        pair.apply(PairActionBasis::Field1(Set(3)), s.marker());
+        assert_eq!(*pair.borrow(s.marker()), Pair(1, 3));
+
+        let shape = Store::new(Shape::Circle { radius: 1.0 });
+        shape.apply(ShapeActionBasis::CircleRadius(Set(2.0)), s.marker());
+        assert_eq!(*shape.borrow(s.marker()), Shape::Circle { radius: 2.0 });
+        shape.apply(ShapeActionBasis::Set(Shape::Rect(1.0, 2.0)), s.marker());
+        shape.apply(ShapeActionBasis::Rect0(Set(3.0)), s.marker());
+        assert_eq!(*shape.borrow(s.marker()), Shape::Rect(3.0, 2.0));
+        shape.apply(ShapeActionBasis::Set(Shape::Empty), s.marker());
+        assert_eq!(*shape.borrow(s.marker()), Shape::Empty);
     }
 
     #[test]
@@ -4252,6 +9298,121 @@ mod test {
         assert_eq!(*state.borrow(s.marker()), 0);
     }
 
+    #[test]
+    fn test_lens() {
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+        let actions: Arc<Mutex<Vec<Box<dyn DirectlyInvertible>>>> = Arc::new(Mutex::new(Vec::new()));
+        let store = Store::new_with_filter(1.0);
+        store.action_filter(|_curr, action, _s| {
+            match action {
+                Set(val) => Set(f64::max(val, 0.0)),
+                Identity => Identity
+            }
+        }, s.marker());
+        let weak = Arc::downgrade(&actions);
+        store.subtree_inverse_listener(um(move |invertible, _s| {
+            let Some(strong) = weak.upgrade() else {
+                return false;
+            };
+            strong.lock().unwrap().push(invertible);
+            true
+        }), s.marker());
+
+        let source_actions = Arc::new(Mutex::new(0));
+        let c = source_actions.clone();
+        store.action_listen(move |_, _, _| {
+            *c.lock().unwrap() += 1;
+            true
+        }, s.marker());
+
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let c = failures.clone();
+        let text = store.binding()
+            .bimap(|val| val.to_string(), |text: &String| text.parse().ok(), s.marker())
+            .on_parse_failure(move |_, action, _| {
+                if let Set(text) = action {
+                    c.lock().unwrap().push(text.clone());
+                }
+            });
+        assert_eq!(*text.borrow(s.marker()), "1");
+
+        let text_actions = Arc::new(Mutex::new(Vec::new()));
+        let c = text_actions.clone();
+        text.action_listen(move |_, action, _| {
+            if let Set(text) = action {
+                c.lock().unwrap().push(text.clone());
+            }
+            true
+        }, s.marker());
+
+        text.apply(Set("2.5".to_string()), s.marker());
+        assert_eq!(*store.borrow(s.marker()), 2.5);
+        assert_eq!(*text.borrow(s.marker()), "2.5");
+
+        // source filter still applies
+        text.apply(Set("-3".to_string()), s.marker());
+        assert_eq!(*store.borrow(s.marker()), 0.0);
+        assert_eq!(*text.borrow(s.marker()), "0");
+
+        text.apply(Set("abc".to_string()), s.marker());
+        assert_eq!(*store.borrow(s.marker()), 0.0);
+        assert_eq!(*failures.lock().unwrap(), vec!["abc".to_string()]);
+
+        store.apply(Set(4.0), s.marker());
+        assert_eq!(*text.borrow(s.marker()), "4");
+        assert_eq!(*source_actions.lock().unwrap(), 3);
+        // the filtered actions are reported, including those applied to the source directly
+        assert_eq!(*text_actions.lock().unwrap(), vec!["2.5", "0", "4"]);
+
+        let mut last = actions.lock().unwrap().pop().unwrap();
+        let mut second_last = actions.lock().unwrap().pop().unwrap();
+        last.invert(s.marker());
+        assert_eq!(*text.borrow(s.marker()), "0");
+        second_last.invert(s.marker());
+        assert_eq!(*text.borrow(s.marker()), "2.5");
+        drop(last);
+        drop(second_last);
+        actions.lock().unwrap().clear();
+
+        let vector = Store::new(Vector([1.0, 2.0]));
+        let y = vector.binding()
+            .lens(
+                |v| *v.y(),
+                |_, action: &SetAction<f64>| Some([Identity, action.clone()]),
+                |_, action| match &action.actions()[1] {
+                    Identity => None,
+                    set => Some(set.clone()),
+                },
+                s.marker()
+            );
+        let lens_actions = Arc::new(Mutex::new(Vec::new()));
+        let c = lens_actions.clone();
+        y.action_listen(move |curr, action, _| {
+            let Set(val) = action else {
+                unreachable!()
+            };
+            c.lock().unwrap().push((*curr, *val));
+            true
+        }, s.marker());
+
+        y.apply(Set(5.0), s.marker());
+        assert_eq!(*vector.borrow(s.marker()).x(), 1.0);
+        assert_eq!(*vector.borrow(s.marker()).y(), 5.0);
+        assert_eq!(*y.borrow(s.marker()), 5.0);
+
+        // changes made to the source directly are reported
+        // (unless they do not affect the projection)
+        vector.apply([Set(3.0), Identity], s.marker());
+        vector.apply([Identity, Set(7.0)], s.marker());
+        assert_eq!(*lens_actions.lock().unwrap(), vec![(2.0, 5.0), (5.0, 7.0)]);
+
+        let weak_y = y.downgrade();
+        assert!(weak_y.upgrade().is_some());
+        drop(y);
+        assert!(weak_y.upgrade().is_none());
+    }
+
     #[test]
     fn test_buffer() {
         let _h = HeapChecker::new();
@@ -4374,4 +9535,449 @@ mod test {
         assert_eq!(*s1.borrow(s), -4);
         assert_eq!(*s2.borrow(s), 4);
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use quarve_derive::StoreContainer;
+
+        use crate::state::Stateful;
+
+        #[derive(StoreContainer)]
+        #[quarve(serde)]
+        struct Model<T> where T: Stateful {
+            name: Store<String>,
+            token: TokenStore<i32>,
+            derived: DerivedStore<f64>,
+            items: Store<Vec<Store<T>>>,
+            #[quarve(ignore)]
+            cache: usize,
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let model = Model {
+            name: Store::new("layer".to_string()),
+            token: TokenStore::new(3),
+            derived: DerivedStore::new(1.5),
+            items: Store::new(vec![Store::new(1), Store::new(2)]),
+            cache: 7,
+        };
+
+        let json = serde_json::to_string(&model).unwrap();
+        assert_eq!(json, r#"{"name":"layer","token":3,"derived":1.5,"items":[1,2]}"#);
+
+        let inverse_count = Arc::new(Mutex::new(0));
+        let c = inverse_count.clone();
+        model.subtree_inverse_listener(um(move |_inv, _s| {
+            *c.lock().unwrap() += 1;
+            true
+        }), s.marker());
+
+        let restored: Model<i32> = serde_json::from_str(&json).unwrap();
+        let restored_count = Arc::new(Mutex::new(0));
+        let c = restored_count.clone();
+        restored.subtree_inverse_listener(um(move |_inv, _s| {
+            *c.lock().unwrap() += 1;
+            true
+        }), s.marker());
+
+        assert_eq!(*restored.name.borrow(s.marker()), "layer");
+        assert_eq!(*restored.token.borrow(s.marker()), 3);
+        assert_eq!(*restored.derived.borrow(s.marker()), 1.5);
+        assert_eq!(restored.cache, 0);
+        let items: Vec<_> = restored.items.borrow(s.marker()).iter()
+            .map(|item| *item.borrow(s.marker()))
+            .collect();
+        assert_eq!(items, vec![1, 2]);
+
+        // restored model is fully independent
+        restored.name.apply(Set("other".to_string()), s.marker());
+        restored.items.borrow(s.marker())[1].apply(Set(5), s.marker());
+        assert_eq!(*model.name.borrow(s.marker()), "layer");
+        assert_eq!(*inverse_count.lock().unwrap(), 0);
+        assert_eq!(*restored_count.lock().unwrap(), 2);
+        assert_eq!(serde_json::to_string(&restored).unwrap(), r#"{"name":"other","token":3,"derived":1.5,"items":[1,5]}"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_journal_replay() {
+        use quarve_derive::StoreContainer;
+
+        use std::collections::BTreeMap;
+
+        use crate::state::{Journal, JournalEntry, Journaled, MapActionBasis, PathSegment};
+
+        #[derive(StoreContainer)]
+        #[quarve(serde, journal)]
+        struct Item {
+            value: Store<i32>,
+        }
+
+        #[derive(StoreContainer)]
+        #[quarve(serde, journal)]
+        struct Model {
+            title: Store<EditingString>,
+            items: Store<Vec<Item>>,
+            tags: Store<BTreeMap<String, Item>>,
+        }
+
+        fn item(value: i32) -> Item {
+            Item { value: Store::new(value) }
+        }
+
+        fn values(model: &Model, s: Slock<impl ThreadMarker>) -> Vec<i32> {
+            model.items.borrow(s).iter()
+                .map(|item| *item.value.borrow(s))
+                .collect()
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let model = Model {
+            title: Store::new(EditingString("untitled".to_string())),
+            items: Store::new(vec![item(1)]),
+            tags: Store::new(BTreeMap::new()),
+        };
+        let journal = Journal::new();
+        model.subtree_journal(&journal, s.marker());
+
+        let inverses = Arc::new(Mutex::new(Vec::new()));
+        let c = inverses.clone();
+        model.subtree_inverse_listener(um(move |inv, _s| {
+            c.lock().unwrap().push(inv);
+            true
+        }), s.marker());
+
+        model.title.apply(StringActionBasis::ReplaceSubrange(0..8, "doc".to_string()), s.marker());
+        // words apply right to left
+        model.items.apply([Insert(item(3), 2), Insert(item(2), 1)], s.marker());
+        model.items.borrow(s.marker())[2].value.apply(Set(30), s.marker());
+        model.items.apply(Remove(0), s.marker());
+        model.items.apply(Swap(0, 1), s.marker());
+        // indices must be tracked through the remove and swap
+        model.items.borrow(s.marker())[1].value.apply(Set(20), s.marker());
+
+        // undos are journaled as well
+        // the removed item's inverse would otherwise form a cycle with the listener
+        let mut last = {
+            let mut inverses = inverses.lock().unwrap();
+            let last = inverses.pop().unwrap();
+            inverses.clear();
+            last
+        };
+        last.invert(s.marker());
+
+        model.tags.apply(MapActionBasis::Insert("x".to_string(), item(5)), s.marker());
+        model.tags.borrow(s.marker())["x"].value.apply(Set(6), s.marker());
+
+        assert_eq!(*model.title.borrow(s.marker()), EditingString("doc".to_string()));
+        assert_eq!(values(&model, s.marker()), vec![30, 2]);
+
+        let entries = journal.entries(s.marker());
+        assert_eq!(entries.len(), 9);
+        assert_eq!(entries[5].path, vec![
+            PathSegment::Field("items".to_string()),
+            PathSegment::Index(1),
+            PathSegment::Field("value".to_string()),
+        ]);
+        assert!(entries.windows(2).all(|w| w[0].time <= w[1].time));
+
+        let json = serde_json::to_string(&entries).unwrap();
+        let decoded: Vec<JournalEntry> = serde_json::from_str(&json).unwrap();
+
+        let fresh = Model {
+            title: Store::new(EditingString("untitled".to_string())),
+            items: Store::new(vec![item(1)]),
+            tags: Store::new(BTreeMap::new()),
+        };
+        fresh.replay(&decoded, s.marker()).unwrap();
+        assert_eq!(*fresh.title.borrow(s.marker()), EditingString("doc".to_string()));
+        assert_eq!(values(&fresh, s.marker()), vec![30, 2]);
+        assert_eq!(*fresh.tags.borrow(s.marker())["x"].value.borrow(s.marker()), 6);
+
+        let invalid = [JournalEntry {
+            path: vec![PathSegment::Field("missing".to_string())],
+            ..decoded[0].clone()
+        }];
+        assert!(fresh.replay(&invalid, s.marker()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_journal_unencodable() {
+        use std::collections::BTreeMap;
+
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        use crate::state::{Journal, JournalError, Journaled, MapActionBasis};
+
+        // keys that fail to serialize
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Opaque(u32);
+
+        impl Serialize for Opaque {
+            fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("opaque key"))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Opaque {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                u32::deserialize(deserializer).map(Opaque)
+            }
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let store: Store<BTreeMap<Opaque, Store<i32>>> = Store::new(BTreeMap::from([(Opaque(1), Store::new(1))]));
+        let plain = Store::new(0);
+        let journal = Journal::new();
+        store.subtree_journal(&journal, s.marker());
+        plain.subtree_journal(&journal, s.marker());
+
+        // the existing key could not be attached
+        let failures = journal.take_failures(s.marker());
+        assert!(matches!(failures.as_slice(), [JournalError::Unencodable(path, _)] if path.is_empty()));
+
+        // both the action and the inserted key fail, without interrupting the transaction
+        store.apply(MapActionBasis::Insert(Opaque(2), Store::new(2)), s.marker());
+        store.borrow(s.marker())[&Opaque(2)].apply(Set(3), s.marker());
+        plain.apply(Set(4), s.marker());
+
+        assert_eq!(*store.borrow(s.marker())[&Opaque(2)].borrow(s.marker()), 3);
+        assert_eq!(journal.len(s.marker()), 1);
+        assert_eq!(journal.take_failures(s.marker()).len(), 2);
+        assert!(journal.take_failures(s.marker()).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_persisted_inverse() {
+        use quarve_derive::StoreContainer;
+
+        use crate::state::{Journal, Journaled, PathSegment, PersistedInverse};
+
+        #[derive(StoreContainer)]
+        #[quarve(serde, journal)]
+        struct Model {
+            title: Store<EditingString>,
+            items: Store<Vec<Store<i32>>>,
+        }
+
+        fn state(model: &Model, s: Slock<impl ThreadMarker>) -> (String, Vec<i32>) {
+            let items = model.items.borrow(s).iter()
+                .map(|item| *item.borrow(s))
+                .collect();
+            (model.title.borrow(s).0.clone(), items)
+        }
+
+        let _h = HeapChecker::new();
+        let s = mslock_owner();
+
+        let model = Model {
+            title: Store::new(EditingString("untitled".to_string())),
+            items: Store::new(vec![Store::new(1), Store::new(2)]),
+        };
+        let index = Journal::index_only();
+        model.subtree_journal(&index, s.marker());
+
+        // inverses must be located when they are made
+        // (later actions may move the store)
+        let located = Arc::new(Mutex::new(Vec::new()));
+        let c = located.clone();
+        let i = index.clone();
+        model.subtree_inverse_listener(um(move |inv, s| {
+            let location = i.locate_inverse(&*inv, s)
+                .expect("Journaled store must be indexed");
+            c.lock().unwrap().push((inv, location));
+            true
+        }), s.marker());
+
+        history_label("Rename", || {
+            model.title.apply(StringActionBasis::ReplaceSubrange(0..8, "doc".to_string()), s.marker());
+        });
+        model.items.borrow(s.marker())[1].apply(Set(20), s.marker());
+        model.items.apply(Insert(Store::new(0), 0), s.marker());
+        model.items.borrow(s.marker())[2].apply(Set(30), s.marker());
+
+        // index only journals do not log
+        assert!(index.is_empty(s.marker()));
+
+        let persisted: Vec<PersistedInverse> = std::mem::take(&mut *located.lock().unwrap())
+            .into_iter()
+            .map(|(inv, location)| location.persist(&*inv, s.marker()).unwrap())
+            .collect();
+        assert_eq!(persisted.len(), 4);
+        assert_eq!(persisted[0].label, Some("Rename".to_string()));
+        assert_eq!(persisted[0].backward_description, "Rename");
+        assert_eq!(persisted[1].path, vec![PathSegment::Field("items".to_string()), PathSegment::Index(1)]);
+        assert_eq!(persisted[3].path, vec![PathSegment::Field("items".to_string()), PathSegment::Index(2)]);
+
+        let json = serde_json::to_string(&persisted).unwrap();
+        let decoded: Vec<PersistedInverse> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, persisted);
+
+        // as if after a restart
+        let fresh: Model = serde_json::from_str(&serde_json::to_string(&model).unwrap()).unwrap();
+        assert_eq!(state(&fresh, s.marker()), ("doc".to_string(), vec![0, 1, 30]));
+
+        let fresh_index = Journal::index_only();
+        fresh.subtree_journal(&fresh_index, s.marker());
+
+        let redos = Arc::new(Mutex::new(Vec::new()));
+        let c = redos.clone();
+        fresh.subtree_inverse_listener(um(move |inv, _s| {
+            c.lock().unwrap().push(inv);
+            true
+        }), s.marker());
+
+        for inverse in decoded.into_iter().rev() {
+            let mut restored = inverse.restore(&fresh_index);
+            assert_eq!(restored.id(), 0);
+            restored.invert(s.marker());
+        }
+        assert_eq!(state(&fresh, s.marker()), ("untitled".to_string(), vec![1, 2]));
+
+        // and the inverses of restored actions behave like any other
+        let redos: Vec<_> = std::mem::take(&mut *redos.lock().unwrap());
+        assert_eq!(redos.len(), 4);
+        assert_eq!(redos[3].forward_description(), "Rename");
+        for mut redo in redos.into_iter().rev() {
+            redo.invert(s.marker());
+        }
+        assert_eq!(state(&fresh, s.marker()), ("doc".to_string(), vec![0, 1, 30]));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_futures_bridge() {
+        use std::sync::mpsc::channel;
+        use std::thread;
+
+        use futures::executor::block_on;
+        use futures::StreamExt;
+
+        use crate::core::spawn_task;
+        use crate::state::{SignalStreamExt, StreamBindingExt};
+
+        let _h = HeapChecker::new();
+        let store = Store::new(0);
+        let mut stream = {
+            let s = slock_owner();
+            let stream = store.stream(s.marker());
+            store.apply(Set(1), s.marker());
+            store.apply(Set(2), s.marker());
+            stream
+        };
+        assert_eq!(block_on(stream.next()), Some(1));
+        assert_eq!(block_on(stream.next()), Some(2));
+
+        // actions from a stream (slock is not held while polling)
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        tx.unbounded_send(Set(3)).unwrap();
+        tx.unbounded_send(Set(4)).unwrap();
+        drop(tx);
+        block_on(store.apply_stream(rx));
+        assert_eq!(block_on(stream.by_ref().take(2).collect::<Vec<_>>()), vec![3, 4]);
+        assert_eq!(*store.borrow(slock_owner().marker()), 4);
+
+        // stream terminates once the source is freed
+        drop(store);
+        assert_eq!(block_on(stream.next()), None);
+
+        // future-aware tasks deliver under the slock
+        let result = Store::new(0);
+        let weak = result.downgrade();
+        let (done_tx, done_rx) = channel();
+        spawn_task(|task| {
+            thread::spawn(move || block_on(task));
+        }, async { 6 * 7 }, move |val, s| {
+            weak.upgrade().unwrap().apply(Set(val), s);
+            done_tx.send(()).unwrap();
+        });
+        done_rx.recv().unwrap();
+        assert_eq!(*result.borrow(slock_owner().marker()), 42);
+    }
+
+    #[test]
+    fn test_leak_diagnostics() {
+        use crate::diagnostics::{live_allocations, retain_cycles, AllocKind};
+
+        // other tests run concurrently, so only look at sites in this test
+        let at_line = |kind: AllocKind, line: u32| live_allocations()
+            .into_iter()
+            .filter(|a| a.kind == kind && a.site.file() == file!() && a.site.line() == line)
+            .map(|a| a.count)
+            .sum::<usize>();
+        let cycle_at = |line: u32| retain_cycles()
+            .into_iter()
+            .any(|c| c.site.file() == file!() && c.site.line() == line);
+
+        let _h = HeapChecker::new();
+        let s = slock_owner();
+
+        let store_line = line!(); let store = Store::new(0);
+        assert_eq!(at_line(AllocKind::Store, store_line), 1);
+        assert_eq!(live_allocations().iter()
+            .find(|a| a.site.line() == store_line && a.site.file() == file!())
+            .unwrap()
+            .type_name, "i32");
+
+        let signal_line = line!(); let signal = store.map(|x| x + 1, s.marker());
+        assert_eq!(at_line(AllocKind::Signal, signal_line), 1);
+
+        // strong capture of its own source
+        let strong = store.binding();
+        let strong_line = line!(); store.listen(move |val, _| { let _ = strong.clone(); *val < 5 }, s.marker());
+        assert_eq!(at_line(AllocKind::Listener, strong_line), 1);
+
+        // weak capture is fine
+        let weak = store.downgrade();
+        let weak_line = line!(); store.listen(move |_, _| weak.upgrade().is_some(), s.marker());
+        assert_eq!(at_line(AllocKind::Listener, weak_line), 1);
+
+        // listening to a different source is fine
+        let other = store.binding();
+        let other_line = line!(); signal.listen(move |val, _| { let _ = other.clone(); *val < 3 }, s.marker());
+
+        // the listener holds its source, but the store is still reachable
+        store.apply(Set(1), s.marker());
+        assert!(!cycle_at(strong_line));
+
+        // a strong listener that is released did not leak
+        let released = store.binding();
+        let released_line = line!(); store.listen(move |val, _| { let _ = released.clone(); *val < 2 }, s.marker());
+        store.apply(Set(2), s.marker());
+        assert_eq!(at_line(AllocKind::Listener, released_line), 0);
+        assert_eq!(at_line(AllocKind::Listener, other_line), 0);
+
+        // the listener is now the only thing keeping the store alive
+        let store_weak = store.downgrade();
+        drop(signal);
+        drop(store);
+        assert_eq!(at_line(AllocKind::Store, store_line), 1);
+        assert!(cycle_at(strong_line));
+        assert!(!cycle_at(weak_line));
+        assert!(!cycle_at(other_line));
+        assert!(!cycle_at(released_line));
+
+        // an outside handle makes the store reachable again
+        let revived = store_weak.upgrade().unwrap();
+        assert!(!cycle_at(strong_line));
+
+        // breaking the cycle frees everything
+        revived.apply(Set(5), s.marker());
+        drop(revived);
+        assert!(!cycle_at(strong_line));
+        assert_eq!(at_line(AllocKind::Store, store_line), 0);
+        assert_eq!(at_line(AllocKind::Signal, signal_line), 0);
+        assert_eq!(at_line(AllocKind::Listener, strong_line), 0);
+        assert_eq!(at_line(AllocKind::Listener, weak_line), 0);
+        assert!(_h.leaked().is_empty());
+    }
+}
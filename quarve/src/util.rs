@@ -58,24 +58,100 @@ pub(crate) mod rust_util {
 pub(crate) mod test_util {
     use std::marker::PhantomData;
     #[cfg(debug_assertions)]
+    use std::collections::HashMap;
+    #[cfg(debug_assertions)]
+    use std::panic::Location;
+    #[cfg(debug_assertions)]
     use std::sync::Mutex;
+    #[cfg(debug_assertions)]
+    use std::cell::RefCell;
+    #[cfg(debug_assertions)]
+    use std::collections::HashSet;
+    #[cfg(debug_assertions)]
+    use std::ops::Range;
+    #[cfg(debug_assertions)]
+    use std::sync::{Arc, Weak};
 
     #[cfg(debug_assertions)]
     static UNBALANCED_ALLOCS: Mutex<usize> = Mutex::new(0);
 
+    #[cfg(debug_assertions)]
+    type AllocKey = (AllocKind, &'static str, &'static Location<'static>);
+
+    #[cfg(debug_assertions)]
+    static LIVE_ALLOCS: Mutex<Option<HashMap<AllocKey, usize>>> = Mutex::new(None);
+    #[cfg(debug_assertions)]
+    static LISTENERS: Mutex<Vec<Weak<ListenerRecord>>> = Mutex::new(Vec::new());
+
+    #[cfg(debug_assertions)]
+    thread_local! {
+        // listeners currently being invoked on this thread
+        static INVOKING: RefCell<Vec<(Range<usize>, Arc<ListenerRecord>)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    pub enum AllocKind {
+        Store,
+        Signal,
+        Buffer,
+        Listener,
+    }
+
+    /// Number of live allocations of a kind, grouped
+    /// by the value type and the site that created them
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct LiveAllocation {
+        pub kind: AllocKind,
+        pub type_name: &'static str,
+        pub site: &'static std::panic::Location<'static>,
+        pub count: usize,
+    }
+
+    /// A registered listener that holds strong references to the source
+    /// it listens to, while no other strong references to the source remain.
+    /// Neither can be freed until the listener is released
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct RetainCycle {
+        pub source_type: &'static str,
+        pub site: &'static std::panic::Location<'static>,
+    }
+
     pub struct QuarveAllocTag {
+        #[cfg(debug_assertions)]
+        key: AllocKey,
         private: PhantomData<i32>
     }
 
     impl QuarveAllocTag {
-        pub fn new() -> QuarveAllocTag {
+        /// `T` is the type of the value held by the allocation
+        #[track_caller]
+        pub fn new<T: ?Sized>(kind: AllocKind) -> QuarveAllocTag {
             #[cfg(debug_assertions)]
             {
-                *UNBALANCED_ALLOCS.lock().unwrap() += 1;
+                // listeners are only counted by the registry
+                // since many legitimately outlive a heap checker
+                if kind != AllocKind::Listener {
+                    *UNBALANCED_ALLOCS.lock().unwrap() += 1;
+                }
+
+                let key = (kind, std::any::type_name::<T>(), Location::caller());
+                *LIVE_ALLOCS.lock().unwrap()
+                    .get_or_insert_with(HashMap::new)
+                    .entry(key)
+                    .or_insert(0) += 1;
+
+                QuarveAllocTag {
+                    key,
+                    private: PhantomData
+                }
             }
 
-            QuarveAllocTag {
-                private: PhantomData
+            #[cfg(not(debug_assertions))]
+            {
+                let _ = kind;
+                QuarveAllocTag {
+                    private: PhantomData
+                }
             }
         }
     }
@@ -83,13 +159,167 @@ pub(crate) mod test_util {
     #[cfg(debug_assertions)]
     impl Drop for QuarveAllocTag {
         fn drop(&mut self) {
-            *UNBALANCED_ALLOCS.lock().unwrap() -= 1;
+            if self.key.0 != AllocKind::Listener {
+                *UNBALANCED_ALLOCS.lock().unwrap() -= 1;
+            }
+
+            let mut live = LIVE_ALLOCS.lock().unwrap();
+            let live = live.as_mut().unwrap();
+            let count = live.get_mut(&self.key).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                live.remove(&self.key);
+            }
+        }
+    }
+
+    pub fn live_allocations() -> Vec<LiveAllocation> {
+        #[cfg(debug_assertions)]
+        {
+            let mut ret: Vec<_> = LIVE_ALLOCS.lock().unwrap()
+                .iter()
+                .flatten()
+                .map(|(&(kind, type_name, site), &count)| LiveAllocation {
+                    kind,
+                    type_name,
+                    site,
+                    count
+                })
+                .collect();
+            ret.sort_by_key(|alloc| (alloc.site.file(), alloc.site.line(), alloc.site.column()));
+            ret
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            Vec::new()
+        }
+    }
+
+    pub fn retain_cycles() -> Vec<RetainCycle> {
+        #[cfg(debug_assertions)]
+        {
+            let live: Vec<_> = {
+                let mut listeners = LISTENERS.lock().unwrap();
+                listeners.retain(|l| l.strong_count() > 0);
+                listeners.iter().filter_map(Weak::upgrade).collect()
+            };
+
+            // strong references to each source held from within its own listeners
+            let mut held: HashMap<usize, usize> = HashMap::new();
+            for record in &live {
+                *held.entry(record.source).or_insert(0) += record.handles.lock().unwrap().len();
+            }
+
+            live.iter()
+                .filter(|record| !record.handles.lock().unwrap().is_empty())
+                .filter(|record| {
+                    let strong = (record.strong_count)();
+                    strong > 0 && strong <= held[&record.source]
+                })
+                .map(|record| RetainCycle {
+                    source_type: record.source_type,
+                    site: record.site,
+                })
+                .collect()
+        }
+
+        #[cfg(not(debug_assertions))]
+        {
+            Vec::new()
         }
     }
 
+    #[cfg(debug_assertions)]
+    struct ListenerRecord {
+        source_type: &'static str,
+        site: &'static Location<'static>,
+        // address of the source, shared by all of its handles
+        source: usize,
+        strong_count: Box<dyn Fn() -> usize + Send + Sync>,
+        // addresses of strong handles to the source that live inside the listener
+        handles: Mutex<HashSet<usize>>,
+    }
+
+    /// Owns a listener on behalf of the source it is registered on.
+    /// Whenever the listener is invoked, strong handles to the source that
+    /// are used from inside the listener's own storage are recorded
+    /// (see [`note_strong_handle`]). Once these account for every remaining
+    /// strong reference, the listener is reported by [`retain_cycles`].
+    /// Handles that are never used while the listener runs are not seen
+    #[cfg(debug_assertions)]
+    pub(crate) struct ListenerCycleCheck<L> {
+        record: Arc<ListenerRecord>,
+        listener: L,
+    }
+
+    #[cfg(debug_assertions)]
+    impl<L> ListenerCycleCheck<L> {
+        pub(crate) fn invoke<R>(&mut self, f: impl FnOnce(&mut L) -> R) -> R {
+            struct Frame;
+
+            impl Drop for Frame {
+                fn drop(&mut self) {
+                    INVOKING.with_borrow_mut(|frames| frames.pop());
+                }
+            }
+
+            let start = &self.listener as *const L as usize;
+            let range = start..start + std::mem::size_of::<L>();
+            INVOKING.with_borrow_mut(|frames| frames.push((range, self.record.clone())));
+            let _frame = Frame;
+
+            f(&mut self.listener)
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    pub(crate) fn check_listener_cycle<T, L>(source: &Arc<T>, listener: L) -> ListenerCycleCheck<L>
+        where T: Send + Sync + 'static
+    {
+        let weak = Arc::downgrade(source);
+        let record = Arc::new(ListenerRecord {
+            source_type: std::any::type_name::<T>(),
+            site: Location::caller(),
+            source: Arc::as_ptr(source) as *const () as usize,
+            strong_count: Box::new(move || weak.strong_count()),
+            handles: Mutex::new(HashSet::new()),
+        });
+        LISTENERS.lock().unwrap().push(Arc::downgrade(&record));
+
+        ListenerCycleCheck {
+            record,
+            listener,
+        }
+    }
+
+    /// Called whenever `handle`, a strong handle to `source`, is used.
+    /// If this happens while a listener of `source` whose storage
+    /// contains `handle` runs, the listener is holding its own source
+    #[cfg(debug_assertions)]
+    pub(crate) fn note_strong_handle<H, T>(handle: &H, source: &Arc<T>) {
+        // handles may be dropped while thread locals are being destroyed
+        let _ = INVOKING.try_with(|frames| {
+            let frames = frames.borrow();
+            if frames.is_empty() {
+                return;
+            }
+
+            let addr = handle as *const H as usize;
+            let source = Arc::as_ptr(source) as *const () as usize;
+            for (range, record) in frames.iter() {
+                if record.source == source && range.contains(&addr) {
+                    record.handles.lock().unwrap().insert(addr);
+                }
+            }
+        });
+    }
+
     #[cfg(debug_assertions)]
     pub struct HeapChecker {
-        org_diff: usize
+        org_diff: usize,
+        org_live: Vec<LiveAllocation>,
     }
 
     #[cfg(debug_assertions)]
@@ -97,7 +327,8 @@ pub(crate) mod test_util {
         #[allow(unused)]
         pub fn new() -> Self {
             HeapChecker {
-                org_diff: *UNBALANCED_ALLOCS.lock().unwrap()
+                org_diff: *UNBALANCED_ALLOCS.lock().unwrap(),
+                org_live: live_allocations(),
             }
         }
 
@@ -106,13 +337,40 @@ pub(crate) mod test_util {
             let curr = *UNBALANCED_ALLOCS.lock().unwrap();
             assert_eq!(curr - self.org_diff, diff);
         }
+
+        /// Allocations (including listeners) that have
+        /// been created since this checker and are still alive
+        #[allow(unused)]
+        pub fn leaked(&self) -> Vec<LiveAllocation> {
+            live_allocations()
+                .into_iter()
+                .filter_map(|mut alloc| {
+                    let original = self.org_live.iter()
+                        .find(|o| o.kind == alloc.kind && o.type_name == alloc.type_name && o.site == alloc.site)
+                        .map(|o| o.count)
+                        .unwrap_or(0);
+
+                    alloc.count = alloc.count.checked_sub(original)?;
+                    (alloc.count > 0).then_some(alloc)
+                })
+                .collect()
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    impl Default for HeapChecker {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
     #[cfg(debug_assertions)]
     impl Drop for HeapChecker {
         fn drop(&mut self) {
             let curr = *UNBALANCED_ALLOCS.lock().unwrap();
-            debug_assert_eq!(curr, self.org_diff, "Introduced Memory Leak")
+            if curr != self.org_diff && !std::thread::panicking() {
+                panic!("Introduced Memory Leak: {:#?}", self.leaked());
+            }
         }
     }
 }
@@ -380,6 +638,8 @@ pub mod marker {
 pub mod geo {
     use std::ops::{Add, Neg, Sub};
 
+    use crate::util::numeric::{Lerp, Norm};
+
     pub type ScreenUnit = f64;
     pub const UNBOUNDED: f64 = 1e7;
     // if a ui element is this large, in some cases
@@ -521,6 +781,59 @@ pub mod geo {
         }
     }
 
+    // geometry is interpolated and measured component-wise
+    // so that it may be animated the same way as a Vector
+    macro_rules! impl_componentwise {
+        ($t:ident, $($f:ident), *) => {
+            impl Lerp for $t {
+                fn lerp(lhs: Self, factor: f64, rhs: Self) -> Self {
+                    $t {
+                        $($f: Lerp::lerp(lhs.$f, factor, rhs.$f)),*
+                    }
+                }
+            }
+
+            impl Norm for $t {
+                fn norm(&self) -> f64 {
+                    (0.0 $(+ self.$f * self.$f)*).sqrt()
+                }
+            }
+        };
+    }
+
+    macro_rules! impl_componentwise_arithmetic {
+        ($t:ident, $($f:ident), *) => {
+            impl Add for $t {
+                type Output = Self;
+
+                fn add(self, rhs: Self) -> Self::Output {
+                    $t {
+                        $($f: self.$f + rhs.$f),*
+                    }
+                }
+            }
+
+            impl Sub for $t {
+                type Output = Self;
+
+                fn sub(self, rhs: Self) -> Self::Output {
+                    $t {
+                        $($f: self.$f - rhs.$f),*
+                    }
+                }
+            }
+        };
+    }
+
+    impl_componentwise!(Point, x, y);
+    impl_componentwise!(Size, w, h);
+    impl_componentwise!(Rect, x, y, w, h);
+    impl_componentwise!(Inset, l, r, b, t);
+
+    impl_componentwise_arithmetic!(Size, w, h);
+    impl_componentwise_arithmetic!(Rect, x, y, w, h);
+    impl_componentwise_arithmetic!(Inset, l, r, b, t);
+
     #[derive(Copy, Clone, PartialEq, Eq, Debug)]
     pub enum Alignment {
         TopLeading,
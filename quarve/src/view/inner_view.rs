@@ -879,6 +879,23 @@ impl<'a, E> Subtree<'a, E> where E: Environment {
         self.insert_subview(subview, self.graph.subviews.len(), env, s);
     }
 
+    // unlike removing and reinserting, the subview remains mounted
+    // (and so keeps its backing and state)
+    pub fn move_subview(&mut self, from: usize, to: usize, env: &mut EnvRef<E>, s: MSlock) {
+        if from == to {
+            return;
+        }
+
+        let subview = self.graph.subviews.remove(from);
+        if !self.graph.native_view.backing.is_null() {
+            view_remove_child(self.graph.native_view.backing, from, s);
+            view_add_child_at(self.graph.native_view.backing, subview.borrow_main(s).native_view(), to, s);
+        }
+        self.graph.subviews.insert(to, subview);
+
+        self.ensure_subtree_has_layout_up_done(env, s);
+    }
+
     /* positional operations */
 
     // precondition: all subviews explicitly had their layout_down method
@@ -561,6 +561,7 @@ mod split {
 
 mod vec_layout {
     pub use binding_layout::*;
+    pub use map_binding_layout::*;
     pub use flex::*;
     pub use hetero_layout::*;
     pub use hstack::*;
@@ -816,6 +817,71 @@ mod vec_layout {
         }
         pub use impl_binding_layout_extension;
 
+        #[macro_export]
+        macro_rules! impl_map_binding_layout_extension {
+            (__declare_trait $t: ty, $trait_name: ident, $method_name: ident, $method_name_options: ident) => {
+                pub trait $trait_name<T, F, S, E> where T: StatefulMap, F: StateFilter<Target=T>, S: Binding<F>, E: Environment {
+                    fn $method_name<P>(self, map: impl FnMut(&T::Key, &T::Value, MSlock) -> P + 'static)
+                        -> impl IntoViewProvider<E,
+                                        DownContext=<$t as VecLayoutProvider<E>>::DownContext,
+                                        UpContext=<$t as VecLayoutProvider<E>>::UpContext>
+                        where P: IntoViewProvider<E,
+                                        DownContext=<$t as VecLayoutProvider<E>>::SubviewDownContext,
+                                        UpContext=<$t as VecLayoutProvider<E>>::SubviewUpContext>;
+                fn $method_name_options<P>(self, map: impl FnMut(&T::Key, &T::Value, MSlock) -> P + 'static, options: <$t as FromOptions>::Options)
+                        -> impl IntoViewProvider<E,
+                                        DownContext=<$t as VecLayoutProvider<E>>::DownContext,
+                                        UpContext=<$t as VecLayoutProvider<E>>::UpContext>
+                        where P: IntoViewProvider<E,
+                                        DownContext=<$t as VecLayoutProvider<E>>::SubviewDownContext,
+                                        UpContext=<$t as VecLayoutProvider<E>>::SubviewUpContext>;
+                }
+            };
+            (__impl_trait $t: ty, $trait_name: ident, $method_name: ident, $method_name_options: ident) => {
+                fn $method_name<P>(self, map: impl FnMut(&T::Key, &T::Value, MSlock) -> P + 'static)
+                    -> impl IntoViewProvider<E,
+                                    DownContext=<$t as VecLayoutProvider<E>>::DownContext,
+                                    UpContext=<$t as VecLayoutProvider<E>>::UpContext>
+                    where P: IntoViewProvider<E,
+                                    DownContext=<$t as VecLayoutProvider<E>>::SubviewDownContext,
+                                    UpContext=<$t as VecLayoutProvider<E>>::SubviewUpContext>
+                {
+                    MapBindingLayout::new(self, map, <$t as FromOptions>::from_options(<$t as FromOptions>::Options::default()))
+                }
+                fn $method_name_options<P>(self, map: impl FnMut(&T::Key, &T::Value, MSlock) -> P + 'static, options: <$t as FromOptions>::Options)
+                    -> impl IntoViewProvider<E,
+                                    DownContext=<$t as VecLayoutProvider<E>>::DownContext,
+                                    UpContext=<$t as VecLayoutProvider<E>>::UpContext>
+                    where P: IntoViewProvider<E,
+                                    DownContext=<$t as VecLayoutProvider<E>>::SubviewDownContext,
+                                    UpContext=<$t as VecLayoutProvider<E>>::SubviewUpContext>
+                {
+                    MapBindingLayout::new(self, map, <$t as FromOptions>::from_options(options))
+                }
+            };
+
+            ($t: ty, $trait_name: ident, $method_name: ident, $method_name_options: ident, where E: $env: path) => {
+                impl_map_binding_layout_extension!(__declare_trait  $t, $trait_name, $method_name, $method_name_options);
+
+                impl<E, F, T, S> $trait_name<T, F, S, E> for S where T: StatefulMap, F: StateFilter<Target=T>, S: Binding<F>, E: $env
+                {
+                    impl_map_binding_layout_extension!(__impl_trait $t, $trait_name, $method_name, $method_name_options);
+                }
+            };
+            ($t: ty, $trait_name: ident, $method_name: ident, $method_name_options: ident, where E = $env: ty) => {
+                mod {
+                    type E = $env;
+                    impl_map_binding_layout_extension!(__declare_trait  $t, $trait_name, $method_name, $method_name_options);
+
+                    impl<F, T, S> $trait_name<T, F, S, E> for S where T: StatefulMap, F: StateFilter<Target=T>, S: Binding<F>
+                    {
+                        impl_map_binding_layout_extension!(__impl_trait $t, $trait_name, $method_name, $method_name_options);
+                    }
+                }
+            }
+        }
+        pub use impl_map_binding_layout_extension;
+
         #[macro_export]
         macro_rules! impl_iterator_layout_extension {
             (__declare_trait $t: ty, $trait_name: ident, $method_name: ident, $method_name_options: ident) => {
@@ -1176,7 +1242,7 @@ mod vec_layout {
         use std::marker::PhantomData;
 
         use crate::core::{Environment, MSlock};
-        use crate::state::{Binding, Buffer, GroupAction, GroupBasis, StateFilter, StoreContainer, VecActionBasis, Word};
+        use crate::state::{permute, Binding, Buffer, GroupAction, GroupBasis, StateFilter, StoreContainer, VecActionBasis, Word};
         use crate::util::geo::{Rect, Size};
         use crate::view::{EnvRef, IntoViewProvider, NativeView, Subtree, UpContextAdapter, View, ViewProvider, WeakInvalidator};
         use crate::view::layout::vec_layout::into_view_provider;
@@ -1342,6 +1408,15 @@ mod vec_layout {
                                 VecActionBasis::Swap(u, v) => {
                                     VecActionBasis::Swap(*u, *v)
                                 }
+                                VecActionBasis::Move(from, to) => {
+                                    VecActionBasis::Move(*from, *to)
+                                }
+                                VecActionBasis::Replace(at, _) => {
+                                    VecActionBasis::Replace(*at, None)
+                                }
+                                VecActionBasis::Permute(permutation) => {
+                                    VecActionBasis::Permute(permutation.clone())
+                                }
                             }
                         })
                         .rev()
@@ -1430,6 +1505,54 @@ mod vec_layout {
                         }
                     }
                 }
+                else if action.iter()
+                    .all(|a| {
+                        matches!(a, VecActionBasis::Swap(..) | VecActionBasis::Move(..) | VecActionBasis::Permute(_))
+                    }) {
+                    // reorders move the existing subviews
+                    for act in action {
+                        match act {
+                            VecActionBasis::Swap(u, v) => {
+                                let (u, v) = (u.min(v), u.max(v));
+                                if u != v {
+                                    subtree.move_subview(v, u, env, s);
+                                    subtree.move_subview(u + 1, v, env, s);
+                                    self.subviews.swap(u, v);
+                                }
+                            }
+                            VecActionBasis::Move(from, to) => {
+                                subtree.move_subview(from, to, env, s);
+                                let view = self.subviews.remove(from);
+                                self.subviews.insert(to, view);
+                            }
+                            VecActionBasis::Permute(permutation) => {
+                                // again n^2, but only in the number of subviews
+                                let mut current: Vec<usize> = (0..permutation.len()).collect();
+                                for (i, &target) in permutation.iter().enumerate() {
+                                    let j = current.iter()
+                                        .position(|&c| c == target)
+                                        .unwrap();
+                                    subtree.move_subview(j, i, env, s);
+                                    let moved = current.remove(j);
+                                    current.insert(i, moved);
+                                }
+                                permute(&mut self.subviews, &permutation);
+                            }
+                            _ => unreachable!()
+                        }
+                    }
+                }
+                else if action.len() == 1 &&
+                    matches!(action.iter().next().unwrap(), VecActionBasis::Replace(..)) {
+                    let Some(VecActionBasis::Replace(at, _)) = action.into_iter().next() else {
+                        unreachable!()
+                    };
+
+                    let mapped = (self.map)(&self.binding.borrow(s)[at], env.const_env(), s).into_view(s);
+                    subtree.remove_subview_at(at, env, s);
+                    subtree.insert_subview(&mapped, at, env, s);
+                    self.subviews[at] = mapped;
+                }
                 else {
                     // multiple insertions, or mixed with perms and removals
                     // are non-trivial so we basically recalculate everything
@@ -1471,6 +1594,253 @@ mod vec_layout {
         }
     }
 
+    // keyed counterpart of VecBindingLayout
+    // subviews are shown in the iteration order of the map
+    // (so generally BTreeMap is preferable to HashMap)
+    mod map_binding_layout {
+        use std::marker::PhantomData;
+
+        use crate::core::{Environment, MSlock};
+        use crate::state::{Binding, Buffer, MapActionBasis, StateFilter, StatefulMap};
+        use crate::util::geo::{Rect, Size};
+        use crate::view::{EnvRef, IntoViewProvider, NativeView, Subtree, UpContextAdapter, View, ViewProvider, WeakInvalidator};
+        use crate::view::layout::vec_layout::into_view_provider;
+        use crate::view::layout::VecLayoutProvider;
+
+        pub struct MapBindingLayout<E, S, F, B, M, U, P, L>
+            where E: Environment,
+                  S: StatefulMap,
+                  F: StateFilter<Target=S>,
+                  B: Binding<F>,
+                  M: FnMut(&S::Key, &S::Value, MSlock) -> P + 'static,
+                  U: Into<L::SubviewUpContext> + 'static,
+                  P: IntoViewProvider<E,
+                      DownContext=L::SubviewDownContext,
+                      UpContext=U
+                  >,
+                  L: VecLayoutProvider<E>
+        {
+            binding: B,
+            layout: L,
+            map: M,
+            phantom: PhantomData<fn(&U, F, E) -> P>,
+        }
+
+        impl<E, S, F, B, M, U, P, L> MapBindingLayout<E, S, F, B, M, U, P, L>
+            where E: Environment,
+                  S: StatefulMap,
+                  F: StateFilter<Target=S>,
+                  B: Binding<F>,
+                  M: FnMut(&S::Key, &S::Value, MSlock) -> P + 'static,
+                  U: Into<L::SubviewUpContext>,
+                  P: IntoViewProvider<E,
+                      DownContext=L::SubviewDownContext,
+                      UpContext=U
+                  >,
+                  L: VecLayoutProvider<E> {
+            pub fn new(binding: B, map: M, layout: L) -> Self {
+                MapBindingLayout {
+                    binding,
+                    layout,
+                    map,
+                    phantom: Default::default(),
+                }
+            }
+        }
+
+        struct MapBindingViewProvider<E, S, F, B, M, P, L>
+            where E: Environment,
+                  S: StatefulMap,
+                  F: StateFilter<Target=S>,
+                  B: Binding<F>,
+                  M: FnMut(&S::Key, &S::Value, &E::Const, MSlock) -> P + 'static,
+                  P: ViewProvider<E,
+                      DownContext=L::SubviewDownContext,
+                      UpContext=L::SubviewUpContext
+                  >,
+                  L: VecLayoutProvider<E>
+        {
+            binding: B,
+            layout: L,
+            map: M,
+            // None if there are no pending changes
+            // otherwise, the keys whose values have been inserted or replaced
+            dirty_keys: Buffer<Option<Vec<S::Key>>>,
+            keys: Vec<S::Key>,
+            subviews: Vec<View<E, P>>,
+            phantom: PhantomData<fn(F, E) -> P>,
+        }
+
+        impl<E, S, F, B, M, U, P, L> IntoViewProvider<E> for MapBindingLayout<E, S, F, B, M, U, P, L>
+            where E: Environment,
+                  S: StatefulMap,
+                  F: StateFilter<Target=S>,
+                  B: Binding<F>,
+                  M: FnMut(&S::Key, &S::Value, MSlock) -> P + 'static,
+                  U: Into<L::SubviewUpContext> + 'static,
+                  P: IntoViewProvider<E,
+                      DownContext=L::SubviewDownContext,
+                      UpContext=U
+                  >,
+                  L: VecLayoutProvider<E> {
+            type UpContext = L::UpContext;
+            type DownContext = L::DownContext;
+
+            fn into_view_provider(mut self, _env: &E::Const, _s: MSlock) -> impl ViewProvider<E, UpContext=Self::UpContext, DownContext=Self::DownContext> {
+                MapBindingViewProvider {
+                    binding: self.binding,
+                    layout: self.layout,
+                    map: move |key, value, env, s| {
+                        UpContextAdapter::new(into_view_provider((self.map)(key, value, s), env, s))
+                    },
+                    dirty_keys: Buffer::new(None),
+                    keys: vec![],
+                    subviews: vec![],
+                    phantom: Default::default(),
+                }
+            }
+        }
+
+        impl<E, S, F, B, M, P, L> ViewProvider<E> for MapBindingViewProvider<E, S, F, B, M, P, L>
+            where E: Environment,
+                  S: StatefulMap,
+                  F: StateFilter<Target=S>,
+                  B: Binding<F>,
+                  M: FnMut(&S::Key, &S::Value, &E::Const, MSlock) -> P + 'static,
+                  P: ViewProvider<E,
+                      DownContext=L::SubviewDownContext,
+                      UpContext=L::SubviewUpContext
+                  >,
+                  L: VecLayoutProvider<E> {
+            type UpContext = L::UpContext;
+            type DownContext = L::DownContext;
+
+            fn intrinsic_size(&mut self, s: MSlock) -> Size {
+                self.layout.intrinsic_size(s)
+            }
+
+            fn xsquished_size(&mut self, s: MSlock) -> Size {
+                self.layout.xsquished_size(s)
+            }
+
+            fn xstretched_size(&mut self, s: MSlock) -> Size {
+                self.layout.xstretched_size(s)
+            }
+
+            fn ysquished_size(&mut self, s: MSlock) -> Size {
+                self.layout.ysquished_size(s)
+            }
+
+            fn ystretched_size(&mut self, s: MSlock) -> Size {
+                self.layout.ystretched_size(s)
+            }
+
+            fn up_context(&mut self, s: MSlock) -> Self::UpContext {
+                self.layout.up_context(s)
+            }
+
+            fn init_backing(&mut self, invalidator: WeakInvalidator<E>, subtree: &mut Subtree<E>, backing_source: Option<(NativeView, Self)>, env: &mut EnvRef<E>, s: MSlock) -> NativeView {
+                self.layout.init(invalidator.clone(), s);
+
+                let buffer = self.dirty_keys.downgrade();
+                self.binding.action_listen(move |_, a, s| {
+                    let (Some(invalidator), Some(buffer)) = (invalidator.upgrade(), buffer.upgrade()) else {
+                        return false;
+                    };
+
+                    let mut dirty = buffer.take(s).unwrap_or_default();
+                    for basis in a.iter() {
+                        match basis {
+                            MapActionBasis::Insert(key, _) | MapActionBasis::Replace(key, _) => {
+                                dirty.push(key.clone());
+                            }
+                            MapActionBasis::Remove(_) => { }
+                        }
+                    }
+
+                    buffer.replace(Some(dirty), s);
+                    invalidator.invalidate(s);
+                    true
+                }, s);
+
+                let binding = self.binding.borrow(s);
+                (self.keys, self.subviews) = binding.entries()
+                    .map(|(key, value)| {
+                        (key.clone(), (self.map)(key, value, env.const_env(), s).into_view(s))
+                    })
+                    .unzip();
+                drop(binding);
+
+                if let Some((native, provider)) = backing_source {
+                    for (dst, src) in std::iter::zip(self.subviews.iter(), provider.subviews) {
+                        dst.take_backing(src, env, s);
+                    }
+
+                    self.subviews.iter()
+                        .for_each(|sv| subtree.push_subview(sv, env, s));
+
+                    native
+                }
+                else {
+                    self.subviews.iter()
+                        .for_each(|sv| subtree.push_subview(sv, env, s));
+
+                    NativeView::layout_view(s)
+                }
+            }
+
+            fn layout_up(&mut self, subtree: &mut Subtree<E>, env: &mut EnvRef<E>, s: MSlock) -> bool {
+                if let Some(dirty) = self.dirty_keys.take(s) {
+                    subtree.clear_subviews(s);
+
+                    let old_keys = std::mem::take(&mut self.keys);
+                    let mut old_views: Vec<_> = std::mem::take(&mut self.subviews)
+                        .into_iter()
+                        .map(Some)
+                        .collect();
+
+                    let binding = self.binding.borrow(s);
+                    let mut cursor = 0;
+                    for (key, value) in binding.entries() {
+                        // orders are generally stable, so check the next old view first
+                        let old = if old_keys.get(cursor) == Some(key) {
+                            Some(cursor)
+                        }
+                        else {
+                            old_keys.iter().position(|k| k == key)
+                        };
+
+                        let reused = old
+                            .filter(|_| !dirty.contains(key))
+                            .and_then(|i| {
+                                cursor = i + 1;
+                                old_views[i].take()
+                            });
+
+                        let view = reused.unwrap_or_else(|| {
+                            (self.map)(key, value, env.const_env(), s).into_view(s)
+                        });
+
+                        self.keys.push(key.clone());
+                        self.subviews.push(view);
+                    }
+                    drop(binding);
+
+                    self.subviews.iter().for_each(|sv| subtree.push_subview(sv, env, s));
+                }
+
+                self.layout
+                    .layout_up(self.subviews.iter(), env, s)
+            }
+
+            fn layout_down(&mut self, _subtree: &Subtree<E>, frame: Size, layout_context: &Self::DownContext, env: &mut EnvRef<E>, s: MSlock) -> (Rect, Rect) {
+                let used = self.layout
+                    .layout_down(self.subviews.iter(), frame, layout_context, env, s);
+                (used, used)
+            }
+        }
+    }
+
     mod signal_layout {
         use std::marker::PhantomData;
 
@@ -2687,28 +3057,32 @@ mod vec_layout {
     }
 
     mod impls {
-        use crate::{impl_binding_layout_extension, impl_hetero_layout, impl_iterator_layout_extension, impl_signal_layout_extension};
+        use crate::{impl_binding_layout_extension, impl_hetero_layout, impl_iterator_layout_extension, impl_map_binding_layout_extension, impl_signal_layout_extension};
         use crate::core::Environment;
         use crate::core::MSlock;
-        use crate::state::{Binding, FixedSignal, Signal, StateFilter, StoreContainer};
+        use crate::state::{Binding, FixedSignal, Signal, StateFilter, StatefulMap, StoreContainer};
         use crate::util::FromOptions;
         use crate::view::IntoViewProvider;
-        use crate::view::layout::{FlexStack, HStack, VecBindingLayout, VecLayoutProvider, VecSignalLayout, VStack, ZStack};
+        use crate::view::layout::{FlexStack, HStack, MapBindingLayout, VecBindingLayout, VecLayoutProvider, VecSignalLayout, VStack, ZStack};
 
         impl_signal_layout_extension!(VStack, SignalVMap, sig_vmap, sig_vmap_options, where E: Environment);
         impl_binding_layout_extension!(VStack, BindingVMap, binding_vmap, binding_vmap_options, where E: Environment);
+        impl_map_binding_layout_extension!(VStack, BindingKeyedVMap, binding_keyed_vmap, binding_keyed_vmap_options, where E: Environment);
         impl_iterator_layout_extension!(VStack, IteratorVMap, vmap, vmap_options, where E: Environment);
 
         impl_signal_layout_extension!(HStack, SignalHMap, sig_hmap, sig_hmap_options, where E: Environment);
         impl_binding_layout_extension!(HStack, BindingHMap, binding_hmap, binding_hmap_options, where E: Environment);
+        impl_map_binding_layout_extension!(HStack, BindingKeyedHMap, binding_keyed_hmap, binding_keyed_hmap_options, where E: Environment);
         impl_iterator_layout_extension!(HStack, IteratorHMap, hmap, hmap_options, where E: Environment);
 
         impl_signal_layout_extension!(ZStack, SignalZMap, sig_zmap, sig_zmap_options, where E: Environment);
         impl_binding_layout_extension!(ZStack, BindingZMap, binding_zmap, binding_zmap_options, where E: Environment);
+        impl_map_binding_layout_extension!(ZStack, BindingKeyedZMap, binding_keyed_zmap, binding_keyed_zmap_options, where E: Environment);
         impl_iterator_layout_extension!(ZStack, IteratorZMap, zmap, zmap_options, where E: Environment);
 
         impl_signal_layout_extension!(FlexStack, SignalFlexMap, sig_flexmap, sig_flexmap_options, where E: Environment);
         impl_binding_layout_extension!(FlexStack, BindingFlexMap, binding_flexmap, binding_flexmap_options, where E: Environment);
+        impl_map_binding_layout_extension!(FlexStack, BindingKeyedFlexMap, binding_keyed_flexmap, binding_keyed_flexmap_options, where E: Environment);
         impl_iterator_layout_extension!(FlexStack, IteratorFlexMap, flexmap, flexmap_options, where E: Environment);
 
         impl_hetero_layout!(VStack, vstack);
@@ -2724,3 +3098,163 @@ mod vec_layout {
         pub use flexstack;
     }
 }
+
+#[cfg(all(test, feature = "headless_backend"))]
+mod test {
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    use crate::core::{slock_main_owner, Application, ApplicationProvider, Environment, MSlock, StandardConstEnv, StandardVarEnv, WindowProvider};
+    use crate::headless::{Headless, HeadlessView, HeadlessViewKind, ViewId};
+    use crate::prelude::*;
+    use crate::state::{FixedSignal, MapActionBasis, Signal, Store};
+    use crate::state::SetAction::Set;
+    use crate::util::geo::Size;
+    use crate::view::text::Text;
+    use crate::view::ViewProvider;
+
+    struct Env(StandardConstEnv, StandardVarEnv);
+
+    impl Environment for Env {
+        type Const = StandardConstEnv;
+        type Variable = StandardVarEnv;
+
+        fn root_environment() -> Self {
+            Env(StandardConstEnv::new(), StandardVarEnv::new())
+        }
+
+        fn const_env(&self) -> &Self::Const {
+            &self.0
+        }
+
+        fn variable_env(&self) -> &Self::Variable {
+            &self.1
+        }
+
+        fn variable_env_mut(&mut self) -> &mut Self::Variable {
+            &mut self.1
+        }
+    }
+
+    type Rows = Store<BTreeMap<u32, Store<i32>>>;
+
+    struct App(Rc<Rows>);
+
+    impl ApplicationProvider for App {
+        fn name(&self) -> &str {
+            "Map Binding Layout Test"
+        }
+
+        fn will_spawn(&self, app: &Application, s: MSlock) {
+            app.spawn_window(MainWindow(self.0.clone()), s);
+        }
+    }
+
+    struct MainWindow(Rc<Rows>);
+
+    impl WindowProvider for MainWindow {
+        type Environment = Env;
+
+        fn title(&self, _env: &StandardConstEnv, _s: MSlock) -> impl Signal<Target=String> {
+            FixedSignal::new("Map Binding Layout".to_string())
+        }
+
+        fn size(&self, _env: &StandardConstEnv, _s: MSlock) -> (Size, Size, Size) {
+            (Size::new(100.0, 100.0), Size::new(200.0, 200.0), Size::new(400.0, 400.0))
+        }
+
+        fn root(&self, env: &StandardConstEnv, s: MSlock) -> impl ViewProvider<Env, DownContext=()> {
+            self.0.binding()
+                .binding_keyed_vmap(|key, value, s| {
+                    Text::new(format!("{}={}", key, *value.borrow(s)))
+                })
+                .into_view_provider(env, s)
+        }
+
+        fn menu(&self, env: &StandardConstEnv, s: MSlock) -> WindowMenu {
+            WindowMenu::standard(env, Menu::new("File"), Menu::new("Edit"), Menu::new("View"), Menu::new("Help"), s)
+        }
+    }
+
+    // text and id of every row, in display order
+    fn rows(h: &Headless) -> Vec<(String, ViewId)> {
+        let root = h.windows().remove(0).root.unwrap();
+        let mut rows = vec![];
+        collect_rows(&root, &mut rows);
+        rows
+    }
+
+    fn collect_rows(view: &HeadlessView, rows: &mut Vec<(String, ViewId)>) {
+        if matches!(view.kind, HeadlessViewKind::Text { .. }) {
+            rows.push((view.text.clone().unwrap(), view.id));
+        }
+        for child in &view.children {
+            collect_rows(child, rows);
+        }
+    }
+
+    fn apply(store: &Rows, action: MapActionBasis<u32, Store<i32>>, h: &Headless) {
+        {
+            let s = slock_main_owner();
+            store.apply(action, s.marker());
+        }
+        h.settle();
+    }
+
+    #[test]
+    fn test_map_binding_layout() {
+        let store: Rc<Rows> = Rc::new(Store::new(BTreeMap::from([
+            (1, Store::new(10)),
+            (3, Store::new(30)),
+            (5, Store::new(50)),
+        ])));
+
+        let m = store.clone();
+        crate::headless::run(App(store), move |h: &Headless| {
+            let initial = rows(h);
+            let texts: Vec<_> = initial.iter().map(|(t, _)| t.as_str()).collect();
+            assert_eq!(texts, ["1=10", "3=30", "5=50"]);
+            let (id1, id3, id5) = (initial[0].1, initial[1].1, initial[2].1);
+
+            // inserted rows are built in key order, others are kept
+            apply(&m, MapActionBasis::Insert(2, Store::new(20)), h);
+            apply(&m, MapActionBasis::Insert(6, Store::new(60)), h);
+            let after_insert = rows(h);
+            let texts: Vec<_> = after_insert.iter().map(|(t, _)| t.as_str()).collect();
+            assert_eq!(texts, ["1=10", "2=20", "3=30", "5=50", "6=60"]);
+            let ids: Vec<_> = after_insert.iter().map(|(_, id)| *id).collect();
+            assert_eq!([ids[0], ids[2], ids[3]], [id1, id3, id5]);
+            assert!(![id1, id3, id5].contains(&ids[1]));
+            assert!(![id1, id3, id5].contains(&ids[4]));
+            let (id2, id6) = (ids[1], ids[4]);
+
+            // replaced rows are rebuilt
+            apply(&m, MapActionBasis::Replace(3, Store::new(31)), h);
+            let after_replace = rows(h);
+            let texts: Vec<_> = after_replace.iter().map(|(t, _)| t.as_str()).collect();
+            assert_eq!(texts, ["1=10", "2=20", "3=31", "5=50", "6=60"]);
+            let ids: Vec<_> = after_replace.iter().map(|(_, id)| *id).collect();
+            assert_eq!([ids[0], ids[1], ids[3], ids[4]], [id1, id2, id5, id6]);
+            assert_ne!(ids[2], id3);
+            let id3 = ids[2];
+
+            // edits inside a value do not rebuild its row
+            {
+                let s = slock_main_owner();
+                m.borrow(s.marker())[&5].apply(Set(51), s.marker());
+            }
+            h.settle();
+            let ids: Vec<_> = rows(h).into_iter().map(|(_, id)| id).collect();
+            assert_eq!(ids, [id1, id2, id3, id5, id6]);
+
+            // removed rows disappear without disturbing the rest
+            apply(&m, MapActionBasis::Remove(2), h);
+            apply(&m, MapActionBasis::Remove(6), h);
+            let after_remove = rows(h);
+            let texts: Vec<_> = after_remove.iter().map(|(t, _)| t.as_str()).collect();
+            assert_eq!(texts, ["1=10", "3=31", "5=50"]);
+            let ids: Vec<_> = after_remove.iter().map(|(_, id)| *id).collect();
+            assert_eq!(ids, [id1, id3, id5]);
+        });
+    }
+}
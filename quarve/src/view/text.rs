@@ -916,7 +916,7 @@ mod text_view {
             use crate::view::text::text_view::state::AttributeSet;
             use crate::view::text::text_view::state::run_gui_info::RunGUIInfo;
             use crate::view::text::ToCharAttribute;
-            use crate::view::undo_manager::{history_elide, UndoBucket};
+            use crate::view::undo_manager::{history_elide_derived, UndoBucket};
 
             pub struct Run<I, D> where I: AttributeSet, D: AttributeSet {
                 content: Store<EditingString>,
@@ -1319,7 +1319,7 @@ mod text_view {
                     };
 
                     if !action.actions.is_empty() {
-                        history_elide(|| {
+                        history_elide_derived(|| {
                             self.char_derived_attribute.apply(action, s);
                         });
                         self.debug_assertions(s);
@@ -1554,12 +1554,13 @@ mod text_view {
 
         mod page {
             use std::ops::{Deref, Range};
-            use std::sync::atomic::{AtomicI32, Ordering};
+            use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+            use std::sync::Arc;
 
             use quarve_derive::StoreContainer;
 
             use crate::core::{MSlock, Slock};
-            use crate::state::{Bindable, Binding, DerivedStore, Filterless, SetAction, Signal, Stateful, Store, StoreContainerSource, StoreContainerView, UndoBarrier, VecActionBasis, Word};
+            use crate::state::{Bindable, Binding, Buffer, DerivedStore, EditingRope, Filterless, GroupBasis, RopeActionBasis, SetAction, Signal, Stateful, Store, StoreContainerSource, StoreContainerView, StringActionBasis, TextIndex, UndoBarrier, VecActionBasis, WeakBuffer, Word};
             use crate::state::SetAction::Set;
             use crate::util::geo::{Point, ScreenUnit};
             use crate::util::marker::{FalseMarker, ThreadMarker};
@@ -1570,6 +1571,8 @@ mod text_view {
             use crate::view::undo_manager::history_hook;
 
             static PAGE_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
+            // line index of a run that has been removed from its page
+            const DETACHED: usize = usize::MAX;
 
             #[derive(Copy, Clone)]
             pub(crate) struct PageGUIInfo {
@@ -1608,6 +1611,9 @@ mod text_view {
                 #[quarve(ignore)]
                 pub(crate) cursor: StoreContainerSource<CursorState>,
                 pub(crate) runs: Store<Vec<Run<I, D>>>,
+                // kept in sync with the runs (joined by new lines)
+                #[quarve(ignore)]
+                pub(crate) content: Buffer<EditingRope>,
                 pub(crate) page_intrinsic_attribute: Store<AttributeHolder<I::PageAttribute>>,
                 // keep derived attributes out of general listeners
                 #[quarve(ignore)]
@@ -1616,7 +1622,7 @@ mod text_view {
 
             impl<I, D> Page<I, D> where I: AttributeSet, D: AttributeSet {
                 pub fn new(s: Slock<impl ThreadMarker>) -> Self {
-                    Page {
+                    let page = Page {
                         id: PAGE_ID_COUNTER.fetch_add(1, Ordering::SeqCst),
                         gui_info: DerivedStore::new(PageGUIInfo {
                             added_text_view_listener: false,
@@ -1630,9 +1636,116 @@ mod text_view {
                         runs: Store::new(vec![
                             Run::new(s)
                         ]),
+                        content: Buffer::new(EditingRope::default()),
                         page_intrinsic_attribute: Store::default(),
                         page_derived_attribute: DerivedStore::default(),
+                    };
+                    page.mirror_content(s);
+                    page
+                }
+
+                // edits to individual runs are translated to edits of the full content
+                // (each run's listener knows its line through an index that is
+                // kept up to date as runs are inserted and removed)
+                fn mirror_content(&self, s: Slock<impl ThreadMarker>) {
+                    fn attach<I, D>(run: &Run<I, D>, line: Arc<AtomicUsize>, content: WeakBuffer<EditingRope>, s: Slock)
+                        where I: AttributeSet, D: AttributeSet
+                    {
+                        run.content_action_listen(move |_, action, s| {
+                            let line = line.load(Ordering::Relaxed);
+                            let Some(content) = content.upgrade().filter(|_| line != DETACHED) else {
+                                return false;
+                            };
+
+                            let mut content = content.borrow_mut(s);
+                            let offset = content.line_to_byte(line);
+                            for a in action.iter() {
+                                let StringActionBasis::ReplaceSubrange(range, with) = a;
+                                let _ = RopeActionBasis::ReplaceSubrange(offset + range.start..offset + range.end, with.clone())
+                                    .apply(&mut *content);
+                            }
+                            true
+                        }, s);
+                    }
+
+                    let mut lines = Vec::new();
+                    {
+                        let mut content = self.content.borrow_mut(s);
+                        for (i, run) in self.runs.borrow(s).iter().enumerate() {
+                            if i > 0 {
+                                let end = content.len_bytes();
+                                let _ = RopeActionBasis::ReplaceSubrange(end..end, "\n".to_string()).apply(&mut *content);
+                            }
+                            let end = content.len_bytes();
+                            let _ = RopeActionBasis::ReplaceSubrange(end..end, run.content(s).to_string()).apply(&mut *content);
+
+                            let line = Arc::new(AtomicUsize::new(i));
+                            attach(run, line.clone(), self.content.downgrade(), s.to_general_slock());
+                            lines.push(line);
+                        }
                     }
+
+                    let weak = self.content.downgrade();
+                    self.runs.action_listen(move |_, action, s| {
+                        let Some(content) = weak.upgrade() else {
+                            return false;
+                        };
+
+                        let insert = |run: &Run<I, D>, at: usize, lines: &mut Vec<Arc<AtomicUsize>>| {
+                            let text = run.content(s).to_string();
+                            {
+                                let mut content = content.borrow_mut(s);
+                                let edit = if at < lines.len() {
+                                    let start = content.line_to_byte(at);
+                                    RopeActionBasis::ReplaceSubrange(start..start, text + "\n")
+                                } else {
+                                    let end = content.len_bytes();
+                                    RopeActionBasis::ReplaceSubrange(end..end, "\n".to_string() + &text)
+                                };
+                                let _ = edit.apply(&mut *content);
+                            }
+
+                            let line = Arc::new(AtomicUsize::new(at));
+                            attach(run, line.clone(), weak.clone(), s);
+                            lines.insert(at, line);
+                        };
+
+                        let remove = |at: usize, lines: &mut Vec<Arc<AtomicUsize>>| {
+                            let mut content = content.borrow_mut(s);
+                            let range = if at + 1 < lines.len() {
+                                content.line_to_byte(at)..content.line_to_byte(at + 1)
+                            } else {
+                                // last line, so remove the preceding new line instead
+                                content.line_to_byte(at) - 1..content.len_bytes()
+                            };
+                            let _ = RopeActionBasis::ReplaceSubrange(range, String::new()).apply(&mut *content);
+
+                            lines.remove(at).store(DETACHED, Ordering::Relaxed);
+                        };
+
+                        for a in action.iter() {
+                            match a {
+                                VecActionBasis::Insert(run, at) => insert(run, *at, &mut lines),
+                                VecActionBasis::InsertMany(runs, at) => {
+                                    for (i, run) in runs.iter().enumerate() {
+                                        insert(run, at + i, &mut lines);
+                                    }
+                                }
+                                VecActionBasis::Remove(at) => remove(*at, &mut lines),
+                                VecActionBasis::RemoveMany(range) => {
+                                    range.clone().rev().for_each(|at| remove(at, &mut lines))
+                                }
+                                // runs are never permuted or replaced
+                                _ => unreachable!()
+                            }
+                        }
+
+                        for (i, line) in lines.iter().enumerate() {
+                            line.store(i, Ordering::Relaxed);
+                        }
+
+                        true
+                    }, s);
                 }
 
                 pub fn selection(&self) -> &CursorState {
@@ -1863,23 +1976,13 @@ mod text_view {
                         });
                 }
 
-                pub fn build_full_content(&self, s: Slock<impl ThreadMarker>) -> String {
-                    let runs = self.runs(s);
-                    let contents = runs.iter()
-                        .map(|run| run.content(s));
-                    let mut ret = String::new();
-
-                    for line in contents {
-                        ret.push_str(line.deref());
-                        ret.push('\n');
-                    }
-
-                    if !ret.is_empty() {
-                        // remove trailing new line
-                        ret.pop();
-                    }
+                /// The entire contents of the page, with runs separated by new lines
+                pub fn content<'a>(&'a self, s: Slock<'a, impl ThreadMarker>) -> impl Deref<Target=EditingRope> + 'a {
+                    self.content.borrow(s)
+                }
 
-                    ret
+                pub fn build_full_content(&self, s: Slock<impl ThreadMarker>) -> String {
+                    self.content.borrow(s).to_string()
                 }
 
                 // NOTE, currently a vector is used
@@ -1938,57 +2041,21 @@ mod text_view {
             impl<I, D> StoreContainerView<Page<I, D>>
                 where I: AttributeSet, D: AttributeSet
             {
-                pub(crate) fn utf16_to_position(&self, mut utf16_pos: usize, s: Slock) -> (usize, usize) {
-                    let len = self.runs.borrow(s).len();
-                    for (i, run) in self.runs.borrow(s).iter().enumerate() {
-                        let cu = run.gui_info.borrow(s).codeunits;
-                        if utf16_pos <= cu {
-                            let mut utf16_count = 0;
-                            if utf16_count == utf16_pos {
-                                // empty string edge case
-                                return (i, 0)
-                            }
-
-                            for (byte_idx, ch) in run.content(s).char_indices() {
-                                let ch_utf16_len = ch.len_utf16();
-                                if utf16_count + ch_utf16_len > utf16_pos {
-                                    return (i, byte_idx);
-                                }
-                                utf16_count += ch_utf16_len;
-                                if utf16_count == utf16_pos {
-                                    return (i, byte_idx + ch.len_utf8());
-                                }
-                            }
-
-                            unreachable!("bad utf16")
-                        }
+                pub(crate) fn utf16_to_position(&self, utf16_pos: usize, s: Slock) -> (usize, usize) {
+                    let content = self.content.borrow(s);
+                    let utf16_pos = utf16_pos.min(content.char_to_utf16(content.len_chars()));
+                    let char = content.utf16_to_char(utf16_pos);
+                    let line = content.char_to_line(char);
 
-                        if i == len - 1 {
-                            return (i, utf16_pos)
-                        }
-                        else {
-                            // dont forget new line
-                            utf16_pos -= cu + 1;
-                        }
-                    }
-
-                    unreachable!("Should always have at least one run")
+                    (line, content.char_to_byte(char) - content.line_to_byte(line))
                 }
 
                 pub(crate) fn position_to_utf16(&self, line: usize, char: usize, s: Slock) -> usize {
-                    let mut utf16 = 0;
-                    for (i, run) in self.runs(s).iter().take(line + 1).enumerate() {
-                        if i == line {
-                            utf16 += run.content(s)[0..char.min(run.content(s).len())]
-                                .encode_utf16().count()
-                        }
-                        else {
-                            // include new line
-                            utf16 += run.gui_info.borrow(s).codeunits + 1;
-                        }
-                    }
+                    let char = char.min(self.run(line, s).len(s));
+                    let content = self.content.borrow(s);
+                    let byte = content.line_to_byte(line) + char;
 
-                    utf16
+                    content.char_to_utf16(content.byte_to_char(byte))
                 }
             }
 
@@ -2949,7 +3016,8 @@ mod text_view {
                             }
                         }
                         VecActionBasis::RemoveMany(range) => range.rev().for_each(|at| handle_removal(&mut self.views, &mut self.view_displayed, env, at)),
-                        VecActionBasis::Swap(_, _) => unreachable!()
+                        VecActionBasis::Swap(..) | VecActionBasis::Move(..) |
+                        VecActionBasis::Replace(..) | VecActionBasis::Permute(_) => unreachable!()
                     }
                 }
 
@@ -3632,8 +3700,9 @@ mod text_view {
                                     replaced_range = pos..end;
                                 }
                             }
-                            VecActionBasis::Swap(_, _) => {
-                                // we dont use swaps
+                            VecActionBasis::Swap(..) | VecActionBasis::Move(..) |
+                            VecActionBasis::Replace(..) | VecActionBasis::Permute(_) => {
+                                // we dont use swaps or other permutations
                                 unreachable!()
                             }
                         }
@@ -1,22 +1,44 @@
 use std::cell::{Cell, RefCell};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::mem::take;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::core::{Environment, MSlock, run_main_async, Slock, slock_drop_listener, StandardConstEnv, StandardVarEnv};
+use crate::core::frame_scheduler::scheduler_now;
 use crate::event::{Event, EventResult};
-use crate::state::{DirectlyInvertible, InverseListener, StoreContainer, UndoBarrier};
+use crate::state::{Binding, DirectlyInvertible, InverseListener, Signal, StateFilter, Stateful, Store, StoreContainer, UndoBarrier};
+use crate::state::SetAction::Set;
 use crate::state::slock_cell::SlockCell;
+#[cfg(feature = "serde")]
+use crate::state::{InverseLocation, Journal, JournalError, Journaled, PersistedInverse};
 use crate::util::geo::{Rect, Size};
 use crate::view::{EnvRef, IntoViewProvider, NativeView, Subtree, ViewProvider, WeakInvalidator};
 use crate::view::menu::MenuChannel;
 use crate::view::undo_manager::GroupState::Closed;
 
 thread_local! {
-    static ELIDE_INVERSE: Cell<bool> = Cell::new(false);
-    static FORWARD_HOOKS: RefCell<Vec<Box<dyn FnMut(MSlock) + Send>>> = RefCell::new(vec![]);
-    static INVERSE_HOOKS: RefCell<Vec<Box<dyn FnMut(MSlock) + Send>>> = RefCell::new(vec![]);
+    static ELIDE_INVERSE: Cell<Elide> = const { Cell::new(Elide::None) };
+    static HISTORY_LABEL: RefCell<Option<String>> = const { RefCell::new(None) };
+    static FORWARD_HOOKS: RefCell<HookStack> = RefCell::new(vec![]);
+    static INVERSE_HOOKS: RefCell<HookStack> = RefCell::new(vec![]);
+}
+
+type HookStack = Vec<Box<dyn FnMut(MSlock) + Send>>;
+
+// why actions are currently kept out of the history
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Elide {
+    None,
+    // the document changes, but the edit cannot be undone
+    Edit,
+    // state that is derived from the rest of the document
+    // (and so does not make it dirty)
+    Derived,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -28,10 +50,32 @@ enum GroupState {
     Closed,
 }
 
+// how an inverse may be saved (if at all)
+#[cfg(feature = "serde")]
+enum Persist {
+    Located(InverseLocation),
+    Restored(PersistedInverse),
+}
+
+struct Inverse {
+    action: Box<dyn DirectlyInvertible>,
+    #[cfg(feature = "serde")]
+    persist: Option<Persist>,
+}
+
+impl Inverse {
+    #[cfg(feature = "serde")]
+    fn persist(&self, s: MSlock) -> Option<PersistedInverse> {
+        match self.persist.as_ref()? {
+            Persist::Located(location) => location.persist(&*self.action, s),
+            Persist::Restored(persisted) => Some(persisted.clone()),
+        }
+    }
+}
+
 struct History {
-    menu: Option<MenuChannel>,
-    callbacks: VecDeque<Box<dyn DirectlyInvertible>>,
-    grouped_actions: VecDeque<(UndoBucket, usize)>, // number of events in each undo group, as well as bucket
+    callbacks: VecDeque<Inverse>,
+    grouped_actions: VecDeque<(UndoBucket, usize, usize)>, // bucket, number of events, and id of each undo group
     last_group_state: GroupState,
     mem_limit: usize,
 }
@@ -39,7 +83,6 @@ struct History {
 impl History {
     fn new(limit: usize) -> History {
         History {
-            menu: None,
             callbacks: VecDeque::new(),
             grouped_actions: VecDeque::new(),
             last_group_state: Closed,
@@ -47,19 +90,29 @@ impl History {
         }
     }
 
-    fn register(&mut self, action: Box<dyn DirectlyInvertible>, bucket: UndoBucket) {
-        self.callbacks.push_back(action);
+    // coalesce overrides the default grouping if set
+    fn needs_new_group(&self, bucket: UndoBucket, coalesce: Option<bool>) -> bool {
+        if let Some(merge) = coalesce {
+            return !merge || self.grouped_actions.is_empty();
+        }
 
-        let needs_new = self.grouped_actions.is_empty() ||
+        self.grouped_actions.is_empty() ||
             self.last_group_state == Closed ||
             // if it's different group numbers but same slock, keep them in same transaction
             // likewise, if they're different and opened previous iteration, we'll need a new one
             (self.grouped_actions.back().unwrap().0 != bucket &&
-                self.last_group_state == GroupState::OpenPreviousIteration);
+                self.last_group_state == GroupState::OpenPreviousIteration)
+    }
+
+    // group is the id to use if a new group is needed
+    // returns the ids of groups that were evicted
+    fn register(&mut self, action: Inverse, bucket: UndoBucket, group: usize, coalesce: Option<bool>) -> Vec<usize> {
+        let needs_new = self.needs_new_group(bucket, coalesce);
+        self.callbacks.push_back(action);
 
         if needs_new {
             // push new group
-            self.grouped_actions.push_back((bucket, 1));
+            self.grouped_actions.push_back((bucket, 1, group));
         }
         else {
             // realistically if you have different groups in same transaction
@@ -74,12 +127,51 @@ impl History {
 
         self.last_group_state = GroupState::Open;
 
+        let mut evicted = Vec::new();
         while self.grouped_actions.len() > self.mem_limit {
-            let (_, drop_amount) = self.grouped_actions.pop_front().unwrap();
+            let (_, drop_amount, id) = self.grouped_actions.pop_front().unwrap();
             for _ in 0..drop_amount {
                 self.callbacks.pop_front();
             }
+            evicted.push(id);
         }
+
+        evicted
+    }
+
+    // pushes a closed group (whose callbacks are in registration order)
+    fn push_group(&mut self, bucket: UndoBucket, group: usize, callbacks: Vec<Inverse>) {
+        self.grouped_actions.push_back((bucket, callbacks.len(), group));
+        self.callbacks.extend(callbacks);
+        self.last_group_state = Closed;
+    }
+
+    fn pop_front_group(&mut self) -> Option<(UndoBucket, usize, Vec<Inverse>)> {
+        let (bucket, count, group) = self.grouped_actions.pop_front()?;
+        Some((bucket, group, self.callbacks.drain(..count).collect()))
+    }
+
+    fn top_group(&self) -> Option<usize> {
+        self.grouped_actions.back()
+            .map(|&(_, _, group)| group)
+    }
+
+    #[cfg(feature = "serde")]
+    fn persist_groups(&self, s: MSlock) -> Vec<Option<PersistedGroup>> {
+        let mut callbacks = self.callbacks.iter();
+        self.grouped_actions.iter()
+            .map(|(_, count, _)| {
+                // always consume the entire group
+                let group: Vec<_> = callbacks.by_ref()
+                    .take(*count)
+                    .map(|c| c.persist(s))
+                    .collect();
+
+                group.into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(PersistedGroup)
+            })
+            .collect()
     }
 
     fn clear(&mut self) {
@@ -89,11 +181,378 @@ impl History {
     }
 }
 
+/// Identifies a branch of the undo tree
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UndoBranchId(usize);
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UndoBranch {
+    pub id: UndoBranchId,
+    /// Number of undo groups from the oldest retained state to the tip of this branch
+    pub depth: usize,
+    /// Title of the most recent group of this branch
+    pub title: String,
+    /// Whether this branch contains the current state
+    pub active: bool,
+}
+
+// a group that is not on the active branch
+// the path from a root to any leaf is a previously abandoned redo stack
+struct BranchNode {
+    id: UndoBranchId,
+    bucket: UndoBucket,
+    group: usize,
+    // in registration order (i.e. the back is inverted first)
+    callbacks: Vec<Inverse>,
+    children: Vec<BranchNode>,
+}
+
+impl BranchNode {
+    fn title(&self) -> String {
+        self.callbacks.last()
+            .map(|c| c.action.forward_description())
+            .unwrap_or_default()
+    }
+
+    fn collect_leaves(nodes: &[BranchNode], depth: usize, into: &mut Vec<UndoBranch>) {
+        for node in nodes {
+            if node.children.is_empty() {
+                into.push(UndoBranch {
+                    id: node.id,
+                    depth: depth + 1,
+                    title: node.title(),
+                    active: false,
+                });
+            }
+            else {
+                BranchNode::collect_leaves(&node.children, depth + 1, into);
+            }
+        }
+    }
+
+    // returns the id of some leaf that descends from (or is) the node of the given group
+    fn find_group(nodes: &[BranchNode], group: usize) -> Option<UndoBranchId> {
+        nodes.iter()
+            .find_map(|node| {
+                if node.group == group {
+                    let mut leaf = node;
+                    while let Some(child) = leaf.children.first() {
+                        leaf = child;
+                    }
+                    Some(leaf.id)
+                }
+                else {
+                    BranchNode::find_group(&node.children, group)
+                }
+            })
+    }
+
+    fn find_leaf(nodes: &[BranchNode], id: UndoBranchId, path: &mut Vec<usize>) -> bool {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            if node.children.is_empty() && node.id == id ||
+                BranchNode::find_leaf(&node.children, id, path) {
+                return true;
+            }
+            path.pop();
+        }
+
+        false
+    }
+
+    // removes the leaf as well as any ancestors that are only used by it
+    fn prune_leaf(nodes: &mut Vec<BranchNode>, id: UndoBranchId) -> bool {
+        for i in 0..nodes.len() {
+            if nodes[i].children.is_empty() {
+                if nodes[i].id == id {
+                    nodes.remove(i);
+                    return true;
+                }
+            }
+            else if BranchNode::prune_leaf(&mut nodes[i].children, id) {
+                if nodes[i].children.is_empty() {
+                    nodes.remove(i);
+                }
+                return true;
+            }
+        }
+
+        false
+    }
+
+    #[cfg(feature = "serde")]
+    fn persist(&self, s: MSlock) -> Option<PersistedNode> {
+        let group = self.callbacks.iter()
+            .map(|c| c.persist(s))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(PersistedNode {
+            id: self.id,
+            group: PersistedGroup(group),
+            children: self.children.iter()
+                .filter_map(|c| c.persist(s))
+                .collect(),
+        })
+    }
+}
+
+struct UndoTree {
+    branching: bool,
+    active: UndoBranchId,
+    next_id: usize,
+    // forks[d] are the inactive children of the state
+    // that is d undo groups past the oldest retained state
+    // (along the active branch)
+    forks: VecDeque<Vec<BranchNode>>,
+}
+
+impl UndoTree {
+    fn new() -> Self {
+        UndoTree {
+            branching: false,
+            active: UndoBranchId(0),
+            next_id: 1,
+            forks: VecDeque::new(),
+        }
+    }
+
+    fn fresh_id(&mut self) -> UndoBranchId {
+        self.next_id += 1;
+        UndoBranchId(self.next_id - 1)
+    }
+
+    fn forks_at(&mut self, depth: usize) -> &mut Vec<BranchNode> {
+        while self.forks.len() <= depth {
+            self.forks.push_back(Vec::new());
+        }
+
+        &mut self.forks[depth]
+    }
+
+    // the oldest states have been forgotten
+    fn evict(&mut self, groups: usize) {
+        for _ in 0..groups {
+            self.forks.pop_front();
+        }
+    }
+
+    fn locate(&self, id: UndoBranchId) -> Option<(usize, Vec<usize>)> {
+        let mut path = Vec::new();
+        self.forks.iter()
+            .position(|roots| BranchNode::find_leaf(roots, id, &mut path))
+            .map(|depth| (depth, path))
+    }
+
+    // moves the redo stack (which starts at depth) into an inactive branch
+    // returns whether there was anything to move
+    fn stash(&mut self, redo: &mut History, depth: usize) -> bool {
+        let len = redo.grouped_actions.len();
+        let mut tip: Option<BranchNode> = None;
+
+        // the front of the redo stack is the furthest state
+        let mut i = 0;
+        while let Some((bucket, group, callbacks)) = redo.pop_front_group() {
+            let mut children = self.forks.get_mut(depth + len - i)
+                .map(take)
+                .unwrap_or_default();
+            if let Some(child) = tip.take() {
+                children.insert(0, child);
+            }
+
+            let id = if i == 0 {
+                self.active
+            }
+            else {
+                self.fresh_id()
+            };
+
+            tip = Some(BranchNode {
+                id,
+                bucket,
+                group,
+                callbacks,
+                children,
+            });
+            i += 1;
+        }
+
+        let Some(tip) = tip else {
+            return false;
+        };
+
+        self.forks.truncate(depth + 1);
+        self.forks_at(depth).push(tip);
+        true
+    }
+
+    // the inverse of stash: moves the branch starting at root
+    // and following path into the (empty) redo stack
+    fn unstash(&mut self, depth: usize, root: BranchNode, path: &[usize], redo: &mut History) {
+        let mut chain = Vec::with_capacity(path.len() + 1);
+        let mut node = root;
+        for &i in path {
+            let next = node.children.remove(i);
+            chain.push(node);
+            node = next;
+        }
+        chain.push(node);
+
+        self.forks.truncate(depth + 1);
+        self.active = chain.last().unwrap().id;
+        for (k, node) in chain.iter_mut().enumerate() {
+            *self.forks_at(depth + k + 1) = take(&mut node.children);
+        }
+
+        for node in chain.into_iter().rev() {
+            redo.push_group(node.bucket, node.group, node.callbacks);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PersistedGroup(Vec<PersistedInverse>);
+
+#[cfg(feature = "serde")]
+impl PersistedGroup {
+    fn validate(&self, index: &Journal, s: MSlock) -> Result<(), JournalError> {
+        self.0.iter()
+            .try_for_each(|persisted| index.validate_inverse(&persisted.path, &persisted.action, s))
+    }
+
+    fn restore(self, index: &Journal) -> Vec<Inverse> {
+        self.0.into_iter()
+            .map(|persisted| Inverse {
+                action: persisted.clone().restore(index),
+                persist: Some(Persist::Restored(persisted)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PersistedNode {
+    id: UndoBranchId,
+    group: PersistedGroup,
+    children: Vec<PersistedNode>,
+}
+
+#[cfg(feature = "serde")]
+impl PersistedNode {
+    fn validate(&self, index: &Journal, s: MSlock) -> Result<(), JournalError> {
+        self.group.validate(index, s)?;
+        self.children.iter()
+            .try_for_each(|c| c.validate(index, s))
+    }
+
+    fn restore(self, index: &Journal, fresh_group: &impl Fn() -> usize) -> BranchNode {
+        BranchNode {
+            id: self.id,
+            bucket: UndoBucket::GLOBAL,
+            group: fresh_group(),
+            callbacks: self.group.restore(index),
+            children: self.children.into_iter()
+                .map(|c| c.restore(index, fresh_group))
+                .collect(),
+        }
+    }
+}
+
+/// A serializable snapshot of the history of an undo manager
+/// (including inactive branches)
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UndoHistory {
+    // oldest group first
+    undo: Vec<PersistedGroup>,
+    // next group to redo last
+    redo: Vec<PersistedGroup>,
+    forks: Vec<(usize, PersistedNode)>,
+    active: UndoBranchId,
+    next_id: usize,
+}
+
+pub type CoalescePredicate = Box<dyn FnMut(&dyn DirectlyInvertible, Slock) -> bool + Send>;
+
+/// Determines whether an action is merged into the
+/// previous undo group of the same bucket
+pub enum CoalescePolicy {
+    /// Groups are delimited by undo barriers and slock iterations
+    Default,
+    /// Every action starts a new group
+    Never,
+    /// Merge if the previous group contains an action on the same store
+    /// and the previous action of the bucket was made within the given duration.
+    /// Time is measured by the frame scheduler, so a `TestClock` drives it
+    Window(Duration),
+    /// Merge as long as the predicate (given the inverse of the new action) holds.
+    /// See [`CoalescePolicy::while_action`] to inspect the action itself
+    While(CoalescePredicate),
+}
+
+impl CoalescePolicy {
+    /// Merge as long as `predicate` holds for each new action applied to `binding`.
+    /// The predicate sees the action before it is applied.
+    /// Actions on any other store in the bucket are never merged
+    pub fn while_action<F, B>(
+        binding: &B,
+        mut predicate: impl FnMut(&<F::Target as Stateful>::Action, Slock) -> bool + Send + 'static,
+        s: MSlock
+    ) -> Self
+        where F: StateFilter, B: Binding<F>
+    {
+        // decision for the most recent action on the binding
+        let decision = Arc::new(SlockCell::new(None::<bool>));
+        let weak = Arc::downgrade(&decision);
+        binding.action_listen(move |_, action, s| {
+            let Some(decision) = weak.upgrade() else {
+                return false;
+            };
+            *decision.borrow_mut(s) = Some(predicate(action, s));
+            true
+        }, s);
+
+        let id = binding.address();
+        CoalescePolicy::While(Box::new(move |action, s| {
+            action.id() == id && decision.borrow_mut(s).take().unwrap_or(false)
+        }))
+    }
+}
+
+// a state of the document, relative to the history
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Savepoint {
+    // before the oldest retained undo group
+    Base,
+    // right after the given group
+    After(usize),
+    // no longer reachable by undo or redo
+    Unreachable,
+}
+
 struct UndoManagerInner {
     is_undoing: Cell<bool>,
     is_redoing: Cell<bool>,
     undo: SlockCell<History>,
     redo: SlockCell<History>,
+    tree: SlockCell<UndoTree>,
+    next_group: Cell<usize>,
+    // the group currently being moved between the undo and redo stacks
+    moving_group: Cell<usize>,
+    clean: Cell<Savepoint>,
+    dirty: Store<bool>,
+    policies: SlockCell<HashMap<UndoBucket, CoalescePolicy>>,
+    // scheduler time of the most recent action in each bucket
+    last_actions: SlockCell<HashMap<UndoBucket, Duration>>,
+    // decision for the action currently being registered
+    coalesce: Cell<Option<bool>>,
+    // set once mounted
+    router: Option<UndoRouter>,
+    // tracks the location of stores so that their inverses can be persisted
+    #[cfg(feature = "serde")]
+    index: Option<Journal>,
 }
 
 impl UndoManagerInner {
@@ -102,55 +561,173 @@ impl UndoManagerInner {
             is_undoing: Cell::new(false),
             is_redoing: Cell::new(false),
             undo: SlockCell::new(History::new(undo_limit)),
-            redo: SlockCell::new(History::new(undo_limit))
+            redo: SlockCell::new(History::new(undo_limit)),
+            tree: SlockCell::new(UndoTree::new()),
+            next_group: Cell::new(0),
+            moving_group: Cell::new(0),
+            clean: Cell::new(Savepoint::Base),
+            dirty: Store::new(false),
+            policies: SlockCell::new(HashMap::new()),
+            last_actions: SlockCell::new(HashMap::new()),
+            coalesce: Cell::new(None),
+            router: None,
+            #[cfg(feature = "serde")]
+            index: None,
         }
     }
 
-    fn disable_menus(&mut self, s: MSlock) {
-        let mut undo = self.undo.borrow_mut(s);
-        if undo.menu.as_ref().unwrap().is_set(s) {
-            undo.menu.as_mut().unwrap().unset(s);
+    fn fresh_group(&self) -> usize {
+        self.next_group.replace(self.next_group.get() + 1)
+    }
+
+    fn position(&self, s: Slock) -> Savepoint {
+        self.undo.borrow(s).top_group()
+            .map_or(Savepoint::Base, Savepoint::After)
+    }
+
+    // the oldest groups have been forgotten
+    fn evict(&self, groups: Vec<usize>, s: Slock) {
+        self.tree.borrow_mut(s).evict(groups.len());
+
+        for group in groups {
+            self.clean.set(match self.clean.get() {
+                Savepoint::Base => Savepoint::Unreachable,
+                Savepoint::After(g) if g == group => Savepoint::Base,
+                other => other
+            });
         }
+    }
 
-        let mut redo = self.redo.borrow_mut(s);
-        if redo.menu.as_ref().unwrap().is_set(s) {
-            redo.menu.as_mut().unwrap().unset(s);
+    // decides whether the incoming action should be merged into the previous group
+    fn begin_action(&self, action: &dyn DirectlyInvertible, bucket: UndoBucket, s: Slock) {
+        self.coalesce.set(None);
+        if ELIDE_INVERSE.get() != Elide::None || self.is_undoing.get() || self.is_redoing.get() {
+            return;
         }
+
+        let now = scheduler_now();
+        let last = self.last_actions.borrow_mut(s).insert(bucket, now);
+
+        let mut policies = self.policies.borrow_mut(s);
+        let Some(policy) = policies.get_mut(&bucket) else {
+            return;
+        };
+
+        let undo = self.undo.borrow(s);
+        // never merge across an undo or the clean state
+        let continues = self.redo.borrow(s).grouped_actions.is_empty() &&
+            undo.grouped_actions.back()
+                .is_some_and(|&(b, _, group)| b == bucket && self.clean.get() != Savepoint::After(group));
+
+        let merge = continues && match policy {
+            CoalescePolicy::Default => {
+                return;
+            }
+            CoalescePolicy::Never => false,
+            CoalescePolicy::Window(window) => {
+                let &(_, count, _) = undo.grouped_actions.back().unwrap();
+                last.is_some_and(|last| now.saturating_sub(last) <= *window) &&
+                    action.id() != 0 &&
+                    undo.callbacks.iter().rev().take(count).any(|c| c.action.id() == action.id())
+            }
+            CoalescePolicy::While(predicate) => predicate(action, s),
+        };
+
+        self.coalesce.set(Some(merge));
     }
 
-    fn update_menus(&self, weak: Weak<SlockCell<UndoManagerInner>>, s: MSlock) {
-        {
-            let mut undo = self.undo.borrow_mut(s);
-            if undo.menu.as_ref().unwrap().is_set(s) {
-                undo.menu.as_mut().unwrap().unset(s);
+    fn refresh_dirty(&self, s: Slock) {
+        let dirty = self.position(s) != self.clean.get();
+        if *self.dirty.borrow(s) != dirty {
+            self.dirty.apply(Set(dirty), s);
+        }
+    }
+
+    fn mark_clean(&self, s: Slock) {
+        // later actions must not merge into the current group
+        self.undo.borrow_mut(s).last_group_state = Closed;
+        self.clean.set(self.position(s));
+        self.refresh_dirty(s);
+    }
+
+    fn revert_to_clean(&self, s: MSlock) -> bool {
+        let target = match self.clean.get() {
+            Savepoint::Unreachable => return false,
+            Savepoint::Base => None,
+            Savepoint::After(group) => Some(group),
+        };
+
+        let in_undo = |group| self.undo.borrow(s).grouped_actions.iter().any(|g| g.2 == group);
+        let in_redo = |group| self.redo.borrow(s).grouped_actions.iter().any(|g| g.2 == group);
+
+        match target {
+            Some(group) if !in_undo(group) && !in_redo(group) => {
+                // on an inactive branch
+                let leaf = self.tree.borrow(s).forks.iter()
+                    .find_map(|roots| BranchNode::find_group(roots, group));
+                match leaf {
+                    Some(leaf) => {
+                        self.checkout(leaf, s);
+                    },
+                    None => {
+                        self.clean.set(Savepoint::Unreachable);
+                        self.refresh_dirty(s.to_general_slock());
+                        return false;
+                    }
+                }
             }
+            Some(group) if in_redo(group) => {
+                while self.undo.borrow(s).top_group() != Some(group) {
+                    self.redo(s);
+                }
+            }
+            _ => { }
+        }
+
+        while self.undo.borrow(s).top_group() != target {
+            self.undo(s);
+        }
+
+        true
+    }
 
+    fn update_menus(&self, weak: Weak<SlockCell<UndoManagerInner>>, s: MSlock) {
+        // not yet mounted, or some other manager owns the menus
+        let Some(router) = self.router.as_ref() else {
+            return;
+        };
+        if !router.is_active(&weak, s) {
+            return;
+        }
+
+        router.inner.disable_menus(s);
+
+        {
+            let undo = self.undo.borrow(s);
             if !undo.grouped_actions.is_empty() {
                 let weak = weak.clone();
-                undo.menu.as_mut().unwrap().set(Box::new(move |s| {
+                let title = menu_title("Undo", undo.callbacks.back().unwrap().action.backward_description());
+                router.inner.undo_menu.set(Box::new(move |s| {
                     if let Some(strong) = weak.upgrade() {
                         strong.borrow(s)
                             .undo(s);
                     }
-                }), None, s);
+                }), title, s);
             }
 
         }
 
         {
-            let mut redo = self.redo.borrow_mut(s);
-            if redo.menu.as_ref().unwrap().is_set(s) {
-                redo.menu.as_mut().unwrap().unset(s);
-            }
-
+            let redo = self.redo.borrow(s);
             if !redo.grouped_actions.is_empty() {
                 let weak = weak.clone();
-                redo.menu.as_mut().unwrap().set(Box::new(move |s| {
+                let title = menu_title("Redo", redo.callbacks.back().unwrap().action.forward_description());
+                router.inner.redo_menu.set(Box::new(move |s| {
                     if let Some(strong) = weak.upgrade() {
                         strong.borrow(s)
                             .redo(s);
                     }
-                }), None, s);
+                }), title, s);
             }
         }
     }
@@ -159,8 +736,9 @@ impl UndoManagerInner {
     fn undo(&self, s: MSlock) {
         let mut undo = self.undo.borrow_mut(s);
 
-        let (_, multiplicity) = undo.grouped_actions.pop_back()
+        let (_, multiplicity, group) = undo.grouped_actions.pop_back()
             .expect("No actions to undo");
+        self.moving_group.set(group);
 
         let current_redo_count = self.redo.borrow(s)
             .callbacks.len();
@@ -172,19 +750,27 @@ impl UndoManagerInner {
 
         self.is_undoing.set(true);
         for _ in 0..multiplicity {
-            let mut action = undo.callbacks.pop_back().unwrap();
-            action.invert(s);
+            let mut inverse = undo.callbacks.pop_back().unwrap();
+            inverse.action.invert(s);
         }
         self.is_undoing.set(false);
 
         assert_eq!(expected_redo_count, self.redo.borrow(s).callbacks.len());
+
+        // if the redo stack was truncated, so are the branches that fork off of it
+        let active_depth = undo.grouped_actions.len() + self.redo.borrow(s).grouped_actions.len();
+        self.tree.borrow_mut(s).forks.truncate(active_depth + 1);
+
+        drop(undo);
+        self.refresh_dirty(s.to_general_slock());
     }
 
     fn redo(&self, s: MSlock) {
         let mut redo = self.redo.borrow_mut(s);
 
-        let (_, multiplicity) = redo.grouped_actions.pop_back()
+        let (_, multiplicity, group) = redo.grouped_actions.pop_back()
             .expect("No actions to redo");
+        self.moving_group.set(group);
 
         let current_undo_count = self.undo.borrow(s)
             .callbacks.len();
@@ -196,8 +782,8 @@ impl UndoManagerInner {
 
         self.is_redoing.set(true);
         for _ in 0..multiplicity {
-            let mut action = redo.callbacks.pop_back().unwrap();
-            action.invert(s);
+            let mut inverse = redo.callbacks.pop_back().unwrap();
+            inverse.action.invert(s);
         }
         self.is_redoing.set(false);
 
@@ -205,29 +791,239 @@ impl UndoManagerInner {
         self.undo.borrow_mut(s).last_group_state = Closed;
 
         assert_eq!(expected_undo_count, self.undo.borrow(s).callbacks.len());
+
+        drop(redo);
+        self.refresh_dirty(s.to_general_slock());
     }
 
     fn register_inverter(&self, action: Box<dyn DirectlyInvertible>, bucket: UndoBucket, s: Slock) {
-        if ELIDE_INVERSE.get() {
-            return;
+        match ELIDE_INVERSE.get() {
+            Elide::None => { }
+            Elide::Edit => {
+                // no state in the history matches the saved one anymore
+                self.clean.set(Savepoint::Unreachable);
+                self.refresh_dirty(s);
+                return;
+            }
+            Elide::Derived => return,
         }
 
+        let inverse = Inverse {
+            #[cfg(feature = "serde")]
+            persist: self.index.as_ref()
+                .and_then(|index| index.locate_inverse(&*action, s))
+                .map(Persist::Located),
+            action,
+        };
+
         if self.is_undoing.get() {
             self.redo.borrow_mut(s)
-                .register(action, bucket);
+                .register(inverse, bucket, self.moving_group.get(), None);
         }
         else if self.is_redoing.get() {
-            self.undo.borrow_mut(s)
-                .register(action, bucket);
+            let evicted = self.undo.borrow_mut(s)
+                .register(inverse, bucket, self.moving_group.get(), None);
+            self.evict(evicted, s);
         }
         else {
-            self.redo.borrow_mut(s)
-                .clear();
+            {
+                let mut tree = self.tree.borrow_mut(s);
+                let mut redo = self.redo.borrow_mut(s);
+                // rather than discarding the redo stack, keep it as its own branch
+                if tree.branching {
+                    let depth = self.undo.borrow(s).grouped_actions.len();
+                    if tree.stash(&mut redo, depth) {
+                        tree.active = tree.fresh_id();
+                    }
+                }
+
+                redo.clear();
+            }
+
+            // only the first registration of an action (e.g. its hook) decides the group
+            let coalesce = self.coalesce.take();
+            let mut undo = self.undo.borrow_mut(s);
+
+            // actions on the same store are combined into one where possible
+            let multiply = coalesce == Some(true) &&
+                !undo.needs_new_group(bucket, coalesce) &&
+                undo.callbacks.back().is_some_and(|c| c.action.id() != 0 && c.action.id() == inverse.action.id());
+            if let (true, Some(m)) = (multiply, s.try_to_main_slock()) {
+                // safety: equal ids imply that both were produced by the same store
+                unsafe {
+                    undo.callbacks.back_mut().unwrap()
+                        .action.right_multiply(inverse.action, m);
+                }
+                undo.last_group_state = GroupState::Open;
+                return;
+            }
+
+            let evicted = undo.register(inverse, bucket, self.fresh_group(), coalesce);
+            drop(undo);
+            self.evict(evicted, s);
+            self.refresh_dirty(s);
+        }
+    }
+
+    fn branches(&self, s: MSlock) -> Vec<UndoBranch> {
+        let undo = self.undo.borrow(s);
+        let redo = self.redo.borrow(s);
+        let tree = self.tree.borrow(s);
+
+        // the tip of the active branch is the furthest redo
+        let title = if let Some(&(_, count, _)) = redo.grouped_actions.front() {
+            redo.callbacks[count - 1].action.forward_description()
+        }
+        else {
+            undo.callbacks.back()
+                .map(|c| c.action.backward_description())
+                .unwrap_or_default()
+        };
+
+        let mut ret = vec![UndoBranch {
+            id: tree.active,
+            depth: undo.grouped_actions.len() + redo.grouped_actions.len(),
+            title,
+            active: true,
+        }];
+
+        for (depth, roots) in tree.forks.iter().enumerate() {
+            BranchNode::collect_leaves(roots, depth, &mut ret);
+        }
+
+        ret
+    }
 
-            self.undo.borrow_mut(s)
-                .register(action, bucket);
+    fn checkout(&self, id: UndoBranchId, s: MSlock) -> bool {
+        if self.tree.borrow(s).active != id {
+            let Some((depth, path)) = self.tree.borrow(s).locate(id) else {
+                return false;
+            };
+
+            // walk the active branch to the fork
+            // (forks are indexed relative to the oldest state,
+            // so moving along the active branch does not invalidate the path)
+            let mut current = self.undo.borrow(s).grouped_actions.len();
+            while current > depth {
+                self.undo(s);
+                current -= 1;
+            }
+            while current < depth {
+                self.redo(s);
+                current += 1;
+            }
+
+            let mut tree = self.tree.borrow_mut(s);
+            let mut redo = self.redo.borrow_mut(s);
+            // remove the target before stashing so that the path stays valid
+            let root = tree.forks_at(depth).remove(path[0]);
+            tree.stash(&mut redo, depth);
+            redo.clear();
+            tree.unstash(depth, root, &path[1..], &mut redo);
+        }
+
+        while !self.redo.borrow(s).grouped_actions.is_empty() {
+            self.redo(s);
+        }
+
+        true
+    }
+
+    #[cfg(feature = "serde")]
+    fn history(&self, s: MSlock) -> UndoHistory {
+        let undo = self.undo.borrow(s);
+        let redo = self.redo.borrow(s);
+        let tree = self.tree.borrow(s);
+
+        // groups before (or after in the case of redo)
+        // one that cannot be persisted are unreachable
+        let undo_groups = undo.persist_groups(s);
+        let start = undo_groups.iter()
+            .rposition(Option::is_none)
+            .map_or(0, |i| i + 1);
+        let redo_groups = redo.persist_groups(s);
+        let redo_start = redo_groups.iter()
+            .rposition(Option::is_none)
+            .map_or(0, |i| i + 1);
+        let end = undo_groups.len() + redo_groups.len() - redo_start;
+
+        let forks = tree.forks.iter()
+            .enumerate()
+            .take(end + 1)
+            .skip(start)
+            .flat_map(|(depth, roots)| {
+                roots.iter()
+                    .filter_map(move |root| root.persist(s).map(|node| (depth - start, node)))
+            })
+            .collect();
+
+        UndoHistory {
+            undo: undo_groups.into_iter().skip(start).flatten().collect(),
+            redo: redo_groups.into_iter().skip(redo_start).flatten().collect(),
+            forks,
+            active: tree.active,
+            next_id: tree.next_id,
         }
     }
+
+    #[cfg(feature = "serde")]
+    fn restore_history(&self, history: UndoHistory, s: MSlock) -> Result<(), JournalError> {
+        let index = self.index.as_ref()
+            .expect("Undo manager must be created with new_persistent to restore history");
+
+        // check everything up front so that a stale history
+        // is rejected rather than failing midway through an undo
+        for group in history.undo.iter().chain(history.redo.iter()) {
+            group.validate(index, s)?;
+        }
+        if self.tree.borrow(s).branching {
+            for (_, node) in &history.forks {
+                node.validate(index, s)?;
+            }
+        }
+
+        let mut undo = self.undo.borrow_mut(s);
+        let mut redo = self.redo.borrow_mut(s);
+        let mut tree = self.tree.borrow_mut(s);
+
+        let fresh_group = || self.fresh_group();
+
+        undo.clear();
+        for group in history.undo {
+            undo.push_group(UndoBucket::GLOBAL, fresh_group(), group.restore(index));
+        }
+
+        redo.clear();
+        for group in history.redo {
+            redo.push_group(UndoBucket::GLOBAL, fresh_group(), group.restore(index));
+        }
+
+        tree.forks.clear();
+        if tree.branching {
+            for (depth, node) in history.forks {
+                tree.forks_at(depth).push(node.restore(index, &fresh_group));
+            }
+        }
+        tree.active = history.active;
+        tree.next_id = tree.next_id.max(history.next_id);
+
+        // the restored state is the clean one
+        drop(undo);
+        self.clean.set(self.position(s.to_general_slock()));
+        self.refresh_dirty(s.to_general_slock());
+        Ok(())
+    }
+}
+
+// a group is titled by its most recent action
+// (i.e. the first one to be inverted)
+fn menu_title(prefix: &str, description: String) -> Option<String> {
+    if description.is_empty() {
+        None
+    }
+    else {
+        Some(format!("{} {}", prefix, description))
+    }
 }
 
 #[derive(Clone)]
@@ -235,7 +1031,7 @@ pub struct UndoManager {
     inner: Arc<SlockCell<UndoManagerInner>>
 }
 
-#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct UndoBucket(usize);
 
 impl UndoBucket {
@@ -257,6 +1053,9 @@ impl InverseListener for UMListener {
             return false;
         };
 
+        strong.borrow(s)
+            .begin_action(&*inverse_action, bucket, s);
+
         // if theres any hooks
         if FORWARD_HOOKS.with_borrow(|f| !f.is_empty()) {
             FORWARD_HOOKS.with_borrow_mut(|f| {
@@ -356,7 +1155,7 @@ impl UndoManager {
             // if the last group was the global group, then
             // close it no matter what
             let mut undo = borrow.undo.borrow_mut(s);
-            if undo.grouped_actions.back_mut().is_some_and(|v| v.0 == UndoBucket::GLOBAL) {
+            if undo.grouped_actions.back().is_some_and(|v| v.0 == UndoBucket::GLOBAL) {
                 undo.last_group_state = Closed;
             }
             else {
@@ -383,9 +1182,123 @@ impl UndoManager {
         }
     }
 
-    fn disable_menus(&self, s: MSlock) {
-        let mut inner = self.inner.borrow_mut(s);
-        inner.disable_menus(s);
+    /// Like `new`, but the history can be saved with `history`
+    /// and restored against a fresh copy of the model with `restore_history`.
+    /// Only inverses produced by journaled stores are persisted
+    #[cfg(feature = "serde")]
+    pub fn new_persistent(stores: &impl Journaled, s: MSlock) -> Self {
+        UndoManager::new_persistent_with_limit(stores, 8192, s)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn new_persistent_with_limit(stores: &impl Journaled, undo_limit: usize, s: MSlock) -> Self {
+        let ret = UndoManager::new_with_limit(stores, undo_limit, s);
+
+        let index = Journal::index_only();
+        stores.subtree_journal(&index, s);
+        ret.inner.borrow_mut(s).index = Some(index);
+
+        ret
+    }
+
+    /// Snapshot of the undo and redo stacks (as well as any other branches).
+    /// If some group cannot be persisted (e.g. it contains a history hook),
+    /// then only the groups that come after it are included
+    #[cfg(feature = "serde")]
+    pub fn history(&self, s: MSlock) -> UndoHistory {
+        self.inner.borrow(s).history(s)
+    }
+
+    /// Replaces the current history.
+    /// The model must be in the same state that it was when
+    /// the history was taken. If some persisted inverse does not fit
+    /// the model, an error is returned and the current history is kept
+    #[cfg(feature = "serde")]
+    pub fn restore_history(&self, history: UndoHistory, s: MSlock) -> Result<(), JournalError> {
+        self.inner.borrow(s).restore_history(history, s)?;
+        self.update_menus(s);
+        Ok(())
+    }
+
+    /// Marks the current state as the saved one
+    pub fn mark_clean(&self, s: MSlock) {
+        self.inner.borrow(s).mark_clean(s.to_general_slock());
+    }
+
+    /// Whether the current state differs from the one at the last `mark_clean`
+    /// (or the initial state if it was never called).
+    /// An edit made within `history_elide` cannot be undone,
+    /// so the document remains dirty until it is marked clean again
+    pub fn is_dirty(&self, s: MSlock) -> impl Signal<Target=bool> + Clone {
+        self.inner.borrow(s).dirty.signal()
+    }
+
+    /// Undoes or redoes (checking out another branch if necessary)
+    /// until the state at the last `mark_clean` is reached.
+    /// Returns false if that state is no longer part of the history
+    /// (e.g. it has been evicted due to the undo limit)
+    pub fn revert_to_clean(&self, s: MSlock) -> bool {
+        let ret = self.inner.borrow(s).revert_to_clean(s);
+        self.update_menus(s);
+        ret
+    }
+
+    /// Sets how actions in `bucket` are grouped together.
+    /// A bucket for a store container can be made with `group_undos`
+    pub fn set_coalesce_policy(&self, bucket: UndoBucket, policy: CoalescePolicy, s: MSlock) {
+        let inner = self.inner.borrow(s);
+        let mut policies = inner.policies.borrow_mut(s);
+        if matches!(policy, CoalescePolicy::Default) {
+            policies.remove(&bucket);
+        }
+        else {
+            policies.insert(bucket, policy);
+        }
+    }
+
+    /// When enabled, making an edit after an undo keeps the
+    /// redo stack as a separate branch rather than discarding it
+    pub fn set_branching(&self, branching: bool, s: MSlock) {
+        let inner = self.inner.borrow(s);
+        let mut tree = inner.tree.borrow_mut(s);
+        tree.branching = branching;
+        if !branching {
+            tree.forks.clear();
+        }
+    }
+
+    pub fn is_branching(&self, s: MSlock) -> bool {
+        self.inner.borrow(s).tree.borrow(s).branching
+    }
+
+    /// All branches of the undo tree, the active one being first
+    pub fn branches(&self, s: MSlock) -> Vec<UndoBranch> {
+        self.inner.borrow(s).branches(s)
+    }
+
+    /// Undoes to the point where `branch` diverges from the active branch,
+    /// and then redoes to the tip of `branch`.
+    /// Returns false if no such branch exists
+    pub fn checkout_branch(&self, branch: UndoBranchId, s: MSlock) -> bool {
+        let ret = self.inner.borrow(s).checkout(branch, s);
+        self.update_menus(s);
+        ret
+    }
+
+    /// Forgets an inactive branch.
+    /// Returns false if it is the active branch or does not exist
+    pub fn prune_branch(&self, branch: UndoBranchId, s: MSlock) -> bool {
+        let inner = self.inner.borrow(s);
+        let mut tree = inner.tree.borrow_mut(s);
+        tree.active != branch && tree.forks.iter_mut()
+            .any(|roots| BranchNode::prune_leaf(roots, branch))
+    }
+
+    /// Whether this manager currently receives the undo and redo menu actions
+    /// of its window
+    pub fn is_active(&self, s: MSlock) -> bool {
+        self.inner.borrow(s).router.as_ref()
+            .is_some_and(|r| r.is_active(&Arc::downgrade(&self.inner), s))
     }
 
     fn update_menus(&self, s: MSlock) {
@@ -438,16 +1351,10 @@ impl<E, I> IntoViewProvider<E> for UndoManagerIVP<E, I>
     type DownContext = I::DownContext;
 
     fn into_view_provider(self, env: &E::Const, s: MSlock) -> impl ViewProvider<E, UpContext=Self::UpContext, DownContext=Self::DownContext> {
-        // set undo channels
+        // menus are routed through the window
         self.undo_manager.inner
             .borrow_mut(s)
-            .undo.borrow_mut(s)
-            .menu = Some(env.as_ref().channels.undo_menu.clone());
-
-        self.undo_manager.inner
-            .borrow_mut(s)
-            .redo.borrow_mut(s)
-            .menu = Some(env.as_ref().channels.redo_menu.clone());
+            .router = Some(env.as_ref().undo_router.clone());
 
         UndoManagerVP {
             source: self.source.into_view_provider(env, s),
@@ -463,6 +1370,13 @@ struct UndoManagerVP<E, P> where E: Environment, E::Const: AsRef<StandardConstEn
     phantom: PhantomData<E>
 }
 
+impl<E, P> UndoManagerVP<E, P> where E: Environment, E::Const: AsRef<StandardConstEnv>, P: ViewProvider<E> {
+    fn router(&self, s: MSlock) -> UndoRouter {
+        self.undo_manager.inner.borrow(s).router.clone()
+            .expect("Undo manager should be mounted")
+    }
+}
+
 impl<E, P> ViewProvider<E> for UndoManagerVP<E, P>
     where E: Environment,
           E::Const: AsRef<StandardConstEnv>,
@@ -517,7 +1431,7 @@ impl<E, P> ViewProvider<E> for UndoManagerVP<E, P>
     }
 
     fn pre_show(&mut self, s: MSlock) {
-        self.undo_manager.update_menus(s);
+        self.router(s).show(&self.undo_manager, s);
         self.source.pre_show(s)
     }
 
@@ -526,7 +1440,7 @@ impl<E, P> ViewProvider<E> for UndoManagerVP<E, P>
     }
 
     fn pre_hide(&mut self, s: MSlock) {
-        self.undo_manager.disable_menus(s);
+        self.router(s).hide(&self.undo_manager, s);
         self.source.pre_hide(s)
     }
 
@@ -536,10 +1450,12 @@ impl<E, P> ViewProvider<E> for UndoManagerVP<E, P>
 
     fn focused(&self, rel_depth: u32, s: MSlock) {
         self.source.focused(rel_depth, s);
+        self.router(s).focus(&self.undo_manager, rel_depth, s);
     }
 
     fn unfocused(&self, rel_depth: u32, s: MSlock) {
         self.source.unfocused(rel_depth, s);
+        self.router(s).unfocus(&self.undo_manager, s);
     }
 
     fn push_environment(&mut self, env: &mut E::Variable, s: MSlock) {
@@ -557,6 +1473,141 @@ impl<E, P> ViewProvider<E> for UndoManagerVP<E, P>
     }
 }
 
+struct RouterState {
+    // in order of being shown
+    shown: Vec<Weak<SlockCell<UndoManagerInner>>>,
+    // ancestors of the focused view, with their distance to it
+    focused: Vec<(Weak<SlockCell<UndoManagerInner>>, u32)>,
+    active: Option<Weak<SlockCell<UndoManagerInner>>>,
+}
+
+struct UndoRouterInner {
+    undo_menu: MenuChannel,
+    redo_menu: MenuChannel,
+    state: SlockCell<RouterState>,
+}
+
+impl UndoRouterInner {
+    fn disable_menus(&self, s: MSlock) {
+        if self.undo_menu.is_set(s) {
+            self.undo_menu.unset(s);
+        }
+
+        if self.redo_menu.is_set(s) {
+            self.redo_menu.unset(s);
+        }
+    }
+}
+
+/// Decides which of the undo managers mounted in a window
+/// receives the undo and redo menu actions.
+/// This is the manager nearest to the focused view or, if no manager
+/// encloses the focused view, the one that was last active
+/// (or otherwise the last one shown)
+#[derive(Clone)]
+pub struct UndoRouter {
+    inner: Arc<UndoRouterInner>
+}
+
+impl UndoRouter {
+    pub(crate) fn new(undo_menu: &MenuChannel, redo_menu: &MenuChannel) -> Self {
+        UndoRouter {
+            inner: Arc::new(UndoRouterInner {
+                undo_menu: undo_menu.clone(),
+                redo_menu: redo_menu.clone(),
+                state: SlockCell::new(RouterState {
+                    shown: vec![],
+                    focused: vec![],
+                    active: None,
+                }),
+            })
+        }
+    }
+
+    /// The manager that currently owns the undo and redo menus, if any
+    pub fn active(&self, s: MSlock) -> Option<UndoManager> {
+        self.inner.state.borrow(s).active.as_ref()
+            .and_then(|a| a.upgrade())
+            .map(|inner| UndoManager { inner })
+    }
+
+    fn is_active(&self, um: &Weak<SlockCell<UndoManagerInner>>, s: MSlock) -> bool {
+        self.inner.state.borrow(s).active.as_ref()
+            .is_some_and(|a| a.ptr_eq(um))
+    }
+
+    fn show(&self, um: &UndoManager, s: MSlock) {
+        let weak = Arc::downgrade(&um.inner);
+        {
+            let mut state = self.inner.state.borrow_mut(s);
+            state.shown.retain(|w| !w.ptr_eq(&weak));
+            state.shown.push(weak);
+        }
+        self.route(s);
+    }
+
+    fn hide(&self, um: &UndoManager, s: MSlock) {
+        let weak = Arc::downgrade(&um.inner);
+        {
+            let mut state = self.inner.state.borrow_mut(s);
+            state.shown.retain(|w| !w.ptr_eq(&weak));
+            state.focused.retain(|(w, _)| !w.ptr_eq(&weak));
+        }
+        self.route(s);
+    }
+
+    fn focus(&self, um: &UndoManager, rel_depth: u32, s: MSlock) {
+        let weak = Arc::downgrade(&um.inner);
+        {
+            let mut state = self.inner.state.borrow_mut(s);
+            state.focused.retain(|(w, _)| !w.ptr_eq(&weak));
+            state.focused.push((weak, rel_depth));
+        }
+        self.route(s);
+    }
+
+    fn unfocus(&self, um: &UndoManager, s: MSlock) {
+        let weak = Arc::downgrade(&um.inner);
+        self.inner.state.borrow_mut(s)
+            .focused.retain(|(w, _)| !w.ptr_eq(&weak));
+        // the previously active manager is kept until focus lands elsewhere
+        self.route(s);
+    }
+
+    // recomputes the active manager, updating the menus if it changed
+    fn route(&self, s: MSlock) {
+        let target = {
+            let mut state = self.inner.state.borrow_mut(s);
+            state.shown.retain(|w| w.strong_count() > 0);
+            state.focused.retain(|(w, _)| w.strong_count() > 0);
+
+            let nearest = state.focused.iter()
+                .min_by_key(|(_, depth)| *depth)
+                .map(|(w, _)| w.clone());
+            let kept = state.active.clone()
+                .filter(|a| state.shown.iter().any(|w| w.ptr_eq(a)));
+
+            let target = nearest.or(kept).or_else(|| state.shown.last().cloned());
+            let changed = match (&state.active, &target) {
+                (Some(a), Some(t)) => !a.ptr_eq(t),
+                (None, None) => false,
+                _ => true
+            };
+            if !changed {
+                return;
+            }
+
+            state.active = target.clone();
+            target
+        };
+
+        match target.and_then(|t| t.upgrade()) {
+            Some(inner) => UndoManager { inner }.update_menus(s),
+            None => self.inner.disable_menus(s)
+        }
+    }
+}
+
 struct Hook {
     um: Weak<SlockCell<UndoManagerInner>>,
     forward: Option<Box<dyn FnMut(MSlock) + Send>>,
@@ -635,10 +1686,832 @@ pub fn history_hook(
     // assert!(FORWARD_HOOKS.with_borrow(|f| f.is_empty()), "Transaction must invoke an undoable action");
 }
 
+// history state of an action whose inverse is delivered later on (i.e. by a transaction)
+pub(crate) struct HistoryContext {
+    elide: Elide,
+    forward_hooks: HookStack,
+    inverse_hooks: HookStack,
+}
+
+impl HistoryContext {
+    // restores the captured state for the duration of f
+    pub(crate) fn enter<T>(self, f: impl FnOnce() -> T) -> T {
+        let old_elide = ELIDE_INVERSE.replace(self.elide);
+        let old_forward = FORWARD_HOOKS.replace(self.forward_hooks);
+        let old_inverse = INVERSE_HOOKS.replace(self.inverse_hooks);
+
+        let ret = f();
+
+        ELIDE_INVERSE.set(old_elide);
+        FORWARD_HOOKS.set(old_forward);
+        INVERSE_HOOKS.set(old_inverse);
+        ret
+    }
+}
+
+// pending hooks are taken, just as if the inverse was handled immediately
+pub(crate) fn capture_history_context() -> HistoryContext {
+    HistoryContext {
+        elide: ELIDE_INVERSE.get(),
+        forward_hooks: FORWARD_HOOKS.take(),
+        inverse_hooks: INVERSE_HOOKS.take(),
+    }
+}
+
 // all transactions will not incur an undo
 // to the undo manager
 pub fn history_elide(transaction: impl FnOnce()) {
-    let old = ELIDE_INVERSE.replace(true);
+    with_elide(Elide::Edit, transaction)
+}
+
+// like history_elide, but for state that is derived from the
+// rest of the document, which therefore does not become dirty
+pub(crate) fn history_elide_derived(transaction: impl FnOnce()) {
+    with_elide(Elide::Derived, transaction)
+}
+
+fn with_elide(elide: Elide, transaction: impl FnOnce()) {
+    let old = ELIDE_INVERSE.replace(elide);
     transaction();
     ELIDE_INVERSE.set(old);
 }
+
+/// All undoable actions applied within the transaction
+/// are titled `label` in the undo and redo menus
+/// (e.g. "Undo Rename Layer")
+pub fn history_label(label: impl Into<String>, transaction: impl FnOnce()) {
+    with_history_label(Some(label.into()), transaction)
+}
+
+pub(crate) fn with_history_label(label: Option<String>, transaction: impl FnOnce()) {
+    let old = HISTORY_LABEL.replace(label);
+    transaction();
+    HISTORY_LABEL.set(old);
+}
+
+pub(crate) fn current_history_label() -> Option<String> {
+    HISTORY_LABEL.with_borrow(|l| l.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use crate::core::{slock_main_owner, MSlock, SlockOwner};
+    use crate::native::global::mark_thread_main;
+    use crate::state::{Binding, Signal, Store, UndoBarrier};
+    use crate::state::SetAction::Set;
+    use crate::util::marker::MainThreadMarker;
+    use crate::view::undo_manager::{history_elide, history_hook, UndoManager};
+
+    fn mslock_owner() -> SlockOwner<MainThreadMarker> {
+        unsafe {
+            mark_thread_main()
+        }
+
+        slock_main_owner()
+    }
+
+    // each edit is its own undo group
+    fn edit(store: &Store<i32>, value: i32, s: MSlock) {
+        store.apply(Set(value), s);
+        store.undo_barrier(UndoBarrier::Strong, s);
+    }
+
+    fn undo_depth(um: &UndoManager, s: MSlock) -> usize {
+        um.inner.borrow(s).undo.borrow(s).grouped_actions.len()
+    }
+
+    fn redo_depth(um: &UndoManager, s: MSlock) -> usize {
+        um.inner.borrow(s).redo.borrow(s).grouped_actions.len()
+    }
+
+    #[test]
+    fn test_transaction_history_context() {
+        let s = mslock_owner();
+        let store = Store::new(0);
+        let um = UndoManager::new(&store, s.marker());
+
+        // elided edits stay elided even though the inverse is delivered at commit
+        let _: Result<(), ()> = s.marker().transaction(|_tx, s| {
+            history_elide(|| store.apply(Set(1), s));
+            Ok(())
+        });
+        assert!(um.inner.borrow(s.marker()).undo.borrow(s.marker()).grouped_actions.is_empty());
+
+        // hooks are registered alongside the deferred inverse
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (f, i) = (calls.clone(), calls.clone());
+        let _: Result<(), ()> = s.marker().transaction(|_tx, s| {
+            history_hook(
+                move |_| f.lock().unwrap().push("forward"),
+                || store.apply(Set(2), s),
+                move |_| i.lock().unwrap().push("inverse"),
+            );
+            Ok(())
+        });
+
+        let inner = um.inner.borrow(s.marker());
+        inner.undo(s.marker());
+        assert_eq!(*store.borrow(s.marker()), 1);
+        assert_eq!(*calls.lock().unwrap(), ["inverse"]);
+        assert!(inner.undo.borrow(s.marker()).grouped_actions.is_empty());
+
+        inner.redo(s.marker());
+        assert_eq!(*store.borrow(s.marker()), 2);
+        assert_eq!(*calls.lock().unwrap(), ["inverse", "forward"]);
+    }
+
+    #[test]
+    fn test_undo_branches() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new(&store, s);
+        um.set_branching(true, s);
+
+        for v in 1..=3 {
+            edit(&store, v, s);
+        }
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        assert_eq!(*store.borrow(s), 1);
+
+        // editing after an undo keeps the old redo stack as a branch
+        edit(&store, 10, s);
+        assert_eq!(redo_depth(&um, s), 0);
+        let branches = um.branches(s);
+        assert_eq!(branches.len(), 2);
+        assert!(branches[0].active);
+        assert_eq!(branches[0].depth, 2);
+        assert!(!branches[1].active);
+        assert_eq!(branches[1].depth, 3);
+        assert_eq!(branches[1].title, "Change");
+        let (edited, original) = (branches[0].id, branches[1].id);
+
+        assert!(um.checkout_branch(original, s));
+        assert_eq!(*store.borrow(s), 3);
+        assert_eq!(undo_depth(&um, s), 3);
+        let branches = um.branches(s);
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].id, original);
+        assert_eq!(branches[1].id, edited);
+        assert_eq!(branches[1].depth, 2);
+
+        // walking the history does not affect the branches
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).redo(s);
+        assert_eq!(*store.borrow(s), 2);
+        assert_eq!(um.branches(s).len(), 2);
+
+        assert!(um.checkout_branch(edited, s));
+        assert_eq!(*store.borrow(s), 10);
+
+        // the active branch cannot be pruned
+        assert!(!um.prune_branch(edited, s));
+        assert!(um.prune_branch(original, s));
+        assert!(!um.prune_branch(original, s));
+        assert!(!um.checkout_branch(original, s));
+        assert_eq!(um.branches(s).len(), 1);
+        assert_eq!(*store.borrow(s), 10);
+
+        // without branching, the redo stack is discarded
+        um.set_branching(false, s);
+        um.inner.borrow(s).undo(s);
+        edit(&store, 20, s);
+        assert_eq!(um.branches(s).len(), 1);
+        assert_eq!(undo_depth(&um, s), 2);
+    }
+
+    #[test]
+    fn test_undo_branches_eviction() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new_with_limit(&store, 3, s);
+        um.set_branching(true, s);
+
+        for v in 1..=3 {
+            edit(&store, v, s);
+        }
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        // forks off after the first group
+        edit(&store, 10, s);
+        let original = um.branches(s)[1].id;
+
+        // the oldest group is evicted, but the branch is still reachable
+        edit(&store, 11, s);
+        edit(&store, 12, s);
+        assert_eq!(undo_depth(&um, s), 3);
+        let branches = um.branches(s);
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[1].id, original);
+        assert_eq!(branches[1].depth, 2);
+
+        assert!(um.checkout_branch(original, s));
+        assert_eq!(*store.borrow(s), 3);
+        assert_eq!(undo_depth(&um, s), 2);
+
+        // evicting the state that a branch forks from forgets the branch
+        let edited = um.branches(s)[1].id;
+        edit(&store, 4, s);
+        edit(&store, 5, s);
+        assert_eq!(undo_depth(&um, s), 3);
+        assert!(um.branches(s).iter().all(|b| b.id != edited));
+        assert!(!um.checkout_branch(edited, s));
+        assert_eq!(*store.borrow(s), 5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_undo_history_round_trip() {
+        use quarve_derive::StoreContainer;
+
+        use crate::view::undo_manager::UndoHistory;
+
+        #[derive(StoreContainer)]
+        #[quarve(serde, journal)]
+        struct Model {
+            value: Store<i32>,
+        }
+
+        let s = mslock_owner();
+        let s = s.marker();
+        let model = Model { value: Store::new(0) };
+        let um = UndoManager::new_persistent(&model, s);
+        um.set_branching(true, s);
+
+        for v in 1..=3 {
+            edit(&model.value, v, s);
+        }
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        edit(&model.value, 10, s);
+        um.inner.borrow(s).undo(s);
+
+        let json = serde_json::to_string(&um.history(s)).unwrap();
+        let history: UndoHistory = serde_json::from_str(&json).unwrap();
+
+        // as if after a restart
+        let fresh = Model { value: Store::new(1) };
+        let restored = UndoManager::new_persistent(&fresh, s);
+        restored.set_branching(true, s);
+        restored.restore_history(history.clone(), s).unwrap();
+        assert_eq!(undo_depth(&restored, s), 1);
+        assert_eq!(redo_depth(&restored, s), 1);
+        assert!(!*restored.is_dirty(s).borrow(s));
+
+        restored.inner.borrow(s).redo(s);
+        assert_eq!(*fresh.value.borrow(s), 10);
+        restored.inner.borrow(s).undo(s);
+        restored.inner.borrow(s).undo(s);
+        assert_eq!(*fresh.value.borrow(s), 0);
+
+        let branches = restored.branches(s);
+        assert_eq!(branches.len(), 2);
+        assert!(restored.checkout_branch(branches[1].id, s));
+        assert_eq!(*fresh.value.borrow(s), 3);
+
+        // a history that does not fit the model is rejected up front
+        #[derive(StoreContainer)]
+        #[quarve(serde, journal)]
+        struct Renamed {
+            other: Store<i32>,
+        }
+
+        let renamed = Renamed { other: Store::new(1) };
+        let stale = UndoManager::new_persistent(&renamed, s);
+        edit(&renamed.other, 2, s);
+        assert!(stale.restore_history(history, s).is_err());
+        assert_eq!(undo_depth(&stale, s), 1);
+
+        let corrupt: UndoHistory = serde_json::from_str(&json.replace("10", "\"ten\"")).unwrap();
+        let model = Model { value: Store::new(1) };
+        let um = UndoManager::new_persistent(&model, s);
+        assert!(um.restore_history(corrupt, s).is_err());
+        assert_eq!(undo_depth(&um, s), 0);
+    }
+
+    #[test]
+    fn test_clean_state() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new(&store, s);
+        let dirty = um.is_dirty(s);
+        let is_dirty = || *dirty.borrow(s);
+        assert!(!is_dirty());
+
+        edit(&store, 1, s);
+        assert!(is_dirty());
+        um.inner.borrow(s).undo(s);
+        assert!(!is_dirty());
+        um.inner.borrow(s).redo(s);
+        assert!(is_dirty());
+
+        um.mark_clean(s);
+        assert!(!is_dirty());
+        edit(&store, 2, s);
+        um.inner.borrow(s).undo(s);
+        assert!(!is_dirty());
+        um.inner.borrow(s).undo(s);
+        assert!(is_dirty());
+        um.inner.borrow(s).redo(s);
+        assert!(!is_dirty());
+
+        // marking clean ends the current group, even without a barrier
+        store.apply(Set(3), s);
+        um.mark_clean(s);
+        store.apply(Set(4), s);
+        assert!(is_dirty());
+        um.inner.borrow(s).undo(s);
+        assert_eq!(*store.borrow(s), 3);
+        assert!(!is_dirty());
+        um.inner.borrow(s).redo(s);
+
+        edit(&store, 5, s);
+        edit(&store, 6, s);
+        assert!(um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 3);
+        assert!(!is_dirty());
+
+        // reverting forward, through the redo stack
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        assert!(is_dirty());
+        assert!(um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 3);
+        assert!(!is_dirty());
+    }
+
+    #[test]
+    fn test_clean_state_eviction() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new_with_limit(&store, 2, s);
+        let dirty = um.is_dirty(s);
+
+        // the initial state is forgotten once the first group is evicted
+        edit(&store, 1, s);
+        edit(&store, 2, s);
+        assert!(um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 0);
+        um.inner.borrow(s).redo(s);
+        um.inner.borrow(s).redo(s);
+        edit(&store, 3, s);
+        assert!(*dirty.borrow(s));
+        assert!(!um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 3);
+
+        // the clean mark becomes the oldest retained state
+        um.mark_clean(s);
+        edit(&store, 4, s);
+        edit(&store, 5, s);
+        assert!(*dirty.borrow(s));
+        assert!(um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 3);
+        assert!(!*dirty.borrow(s));
+    }
+
+    #[test]
+    fn test_clean_state_branches() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new(&store, s);
+        um.set_branching(true, s);
+        let dirty = um.is_dirty(s);
+
+        edit(&store, 1, s);
+        edit(&store, 2, s);
+        um.mark_clean(s);
+        um.inner.borrow(s).undo(s);
+        um.inner.borrow(s).undo(s);
+        edit(&store, 10, s);
+        assert!(*dirty.borrow(s));
+
+        // the clean state is on an inactive branch
+        assert!(um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 2);
+        assert!(!*dirty.borrow(s));
+        assert_eq!(um.branches(s).len(), 2);
+
+        // once its branch is pruned, it is unreachable
+        let other = um.branches(s)[1].id;
+        assert!(um.checkout_branch(other, s));
+        let clean = um.branches(s)[1].id;
+        assert!(um.prune_branch(clean, s));
+        assert!(!um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 10);
+        assert!(*dirty.borrow(s));
+    }
+
+    #[test]
+    fn test_clean_state_elided() {
+        let s = mslock_owner();
+        let s = s.marker();
+        let store = Store::new(0);
+        let um = UndoManager::new(&store, s);
+        let dirty = um.is_dirty(s);
+
+        // elided edits cannot be undone, so they cannot be reverted either
+        history_elide(|| store.apply(Set(1), s));
+        store.undo_barrier(UndoBarrier::Strong, s);
+        assert!(*dirty.borrow(s));
+        assert!(!um.revert_to_clean(s));
+        assert_eq!(*store.borrow(s), 1);
+
+        um.mark_clean(s);
+        assert!(!*dirty.borrow(s));
+
+        edit(&store, 2, s);
+        history_elide(|| store.apply(Set(3), s));
+        store.undo_barrier(UndoBarrier::Strong, s);
+        um.inner.borrow(s).undo(s);
+        assert!(*dirty.borrow(s));
+
+        // hooked edits are part of the history
+        um.inner.borrow(s).redo(s);
+        um.mark_clean(s);
+        history_hook(|_| (), || store.apply(Set(4), s), |_| ());
+        store.undo_barrier(UndoBarrier::Strong, s);
+        assert!(*dirty.borrow(s));
+        um.inner.borrow(s).undo(s);
+        assert!(!*dirty.borrow(s));
+    }
+
+    #[test]
+    fn test_coalesce_policies() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        use quarve_derive::StoreContainer;
+
+        use crate::core::TestClock;
+        use crate::state::StoreContainer;
+        use crate::view::undo_manager::CoalescePolicy;
+
+        #[derive(StoreContainer)]
+        struct Model {
+            a: Store<i32>,
+            b: Store<i32>,
+            c: Store<i32>,
+        }
+
+        let clock = TestClock::install();
+        let model = Model {
+            a: Store::new(0),
+            b: Store::new(0),
+            c: Store::new(0),
+        };
+        let merging = Arc::new(AtomicBool::new(true));
+        let (um, windowed) = {
+            let s = mslock_owner();
+            let s = s.marker();
+            let um = UndoManager::new(&model, s);
+            // a and b share a bucket, c has its own
+            let windowed = model.a.group_undos(s);
+            model.b.subtree_undo_bucket(windowed, s);
+            let never = model.c.group_undos(s);
+
+            um.set_coalesce_policy(windowed, CoalescePolicy::Window(Duration::from_millis(100)), s);
+            um.set_coalesce_policy(never, CoalescePolicy::Never, s);
+            (um, windowed)
+        };
+        // every step is its own slock iteration, so nothing is merged by default
+        let step = |f: &dyn Fn(MSlock)| {
+            let s = mslock_owner();
+            f(s.marker());
+        };
+        let callbacks = |s: MSlock| um.inner.borrow(s).undo.borrow(s).callbacks.len();
+
+        step(&|s| model.a.apply(Set(1), s));
+        clock.advance(Duration::from_millis(60));
+        step(&|s| model.a.apply(Set(2), s));
+        clock.advance(Duration::from_millis(60));
+        // the window is measured from the most recent action of the bucket
+        step(&|s| model.a.apply(Set(3), s));
+        step(&|s| {
+            assert_eq!(undo_depth(&um, s), 1);
+            // actions on the same store are multiplied into one
+            assert_eq!(callbacks(s), 1);
+        });
+
+        // an action in another bucket ends the group
+        step(&|s| model.c.apply(Set(1), s));
+        step(&|s| model.c.apply(Set(2), s));
+        step(&|s| assert_eq!(undo_depth(&um, s), 3));
+        step(&|s| model.a.apply(Set(4), s));
+        step(&|s| assert_eq!(undo_depth(&um, s), 4));
+
+        // a different store within the window does not merge
+        step(&|s| model.b.apply(Set(1), s));
+        step(&|s| assert_eq!(undo_depth(&um, s), 5));
+        clock.advance(Duration::from_millis(150));
+        step(&|s| model.b.apply(Set(2), s));
+        step(&|s| assert_eq!(undo_depth(&um, s), 6));
+
+        // even within one slock, every action of a Never bucket is its own group
+        step(&|s| {
+            model.c.apply(Set(3), s);
+            model.c.apply(Set(4), s);
+            assert_eq!(undo_depth(&um, s), 8);
+        });
+
+        step(&|s| {
+            let m = merging.clone();
+            um.set_coalesce_policy(windowed, CoalescePolicy::While(Box::new(move |_, _| m.load(Ordering::SeqCst))), s);
+        });
+        step(&|s| model.a.apply(Set(5), s));
+        step(&|s| model.b.apply(Set(3), s));
+        step(&|s| model.a.apply(Set(6), s));
+        step(&|s| {
+            assert_eq!(undo_depth(&um, s), 9);
+            assert_eq!(callbacks(s), 11);
+        });
+        merging.store(false, Ordering::SeqCst);
+        step(&|s| model.a.apply(Set(7), s));
+        step(&|s| {
+            assert_eq!(undo_depth(&um, s), 10);
+
+            let inner = um.inner.borrow(s);
+            inner.undo(s);
+            assert_eq!(*model.a.borrow(s), 6);
+            // the merged group is undone at once
+            inner.undo(s);
+            assert_eq!(*model.a.borrow(s), 4);
+            assert_eq!(*model.b.borrow(s), 2);
+            for _ in 0..8 {
+                inner.undo(s);
+            }
+            assert_eq!(*model.a.borrow(s), 0);
+            assert_eq!(*model.b.borrow(s), 0);
+            assert_eq!(*model.c.borrow(s), 0);
+        });
+    }
+
+    #[test]
+    fn test_coalesce_while_action() {
+        use quarve_derive::StoreContainer;
+
+        use crate::state::{Bindable, EditingString, StoreContainer, StringActionBasis};
+        use crate::view::undo_manager::CoalescePolicy;
+
+        #[derive(StoreContainer)]
+        struct Model {
+            text: Store<EditingString>,
+            other: Store<EditingString>,
+        }
+
+        let model = Model {
+            text: Store::new(EditingString(String::new())),
+            other: Store::new(EditingString(String::new())),
+        };
+        let (text, other) = (&model.text, &model.other);
+        let um = {
+            let s = mslock_owner();
+            let s = s.marker();
+            let um = UndoManager::new(&model, s);
+            let bucket = text.group_undos(s);
+            other.subtree_undo_bucket(bucket, s);
+            // words are merged, anything else starts a new group
+            let policy = CoalescePolicy::while_action(&text.binding(), |action, _| {
+                action.iter().all(|StringActionBasis::ReplaceSubrange(range, with)| {
+                    range.is_empty() && !with.is_empty() && with.chars().all(char::is_alphanumeric)
+                })
+            }, s);
+            um.set_coalesce_policy(bucket, policy, s);
+            um
+        };
+
+        // every character is typed in its own slock iteration
+        let type_into = |store: &Store<EditingString>, str: &str| {
+            for c in str.chars() {
+                let s = mslock_owner();
+                let len = store.borrow(s.marker()).0.len();
+                store.apply(StringActionBasis::ReplaceSubrange(len..len, c.to_string()), s.marker());
+            }
+        };
+
+        type_into(text, "ab");
+        assert_eq!(undo_depth(&um, mslock_owner().marker()), 1);
+        type_into(text, " cd");
+        assert_eq!(undo_depth(&um, mslock_owner().marker()), 2);
+        type_into(text, "e");
+        assert_eq!(undo_depth(&um, mslock_owner().marker()), 2);
+
+        // actions on another store of the bucket are not merged
+        type_into(other, "x");
+        assert_eq!(undo_depth(&um, mslock_owner().marker()), 3);
+
+        let s = mslock_owner();
+        let s = s.marker();
+        let inner = um.inner.borrow(s);
+        inner.undo(s);
+        assert_eq!(text.borrow(s).0, "ab cde");
+        assert_eq!(other.borrow(s).0, "");
+        inner.undo(s);
+        assert_eq!(text.borrow(s).0, "ab");
+        inner.undo(s);
+        assert_eq!(text.borrow(s).0, "");
+    }
+
+    #[cfg(feature = "headless_backend")]
+    mod headless {
+        use std::rc::Rc;
+        use std::sync::Arc;
+
+        use crate::core::{slock_main_owner, Application, ApplicationProvider, Environment, MSlock, StandardConstEnv, StandardVarEnv, WindowProvider};
+        use crate::headless::{Headless, HeadlessMenuItem, HeadlessView, HeadlessViewKind, ViewId, WindowId};
+        use crate::prelude::*;
+        use crate::state::{Binding, FixedSignal, Signal, Store, TokenStore, UndoBarrier};
+        use crate::state::SetAction::Set;
+        use crate::util::geo::Size;
+        use crate::view::ViewProvider;
+        use crate::view::text::TextField;
+        use crate::view::undo_manager::{UndoManager, UndoManagerExt, UndoRouter};
+
+        struct Env(StandardConstEnv, StandardVarEnv);
+
+        impl Environment for Env {
+            type Const = StandardConstEnv;
+            type Variable = StandardVarEnv;
+
+            fn root_environment() -> Self {
+                Env(StandardConstEnv::new(), StandardVarEnv::new())
+            }
+
+            fn const_env(&self) -> &Self::Const {
+                &self.0
+            }
+
+            fn variable_env(&self) -> &Self::Variable {
+                &self.1
+            }
+
+            fn variable_env_mut(&mut self) -> &mut Self::Variable {
+                &mut self.1
+            }
+        }
+
+        struct Model {
+            a: Store<String>,
+            b: Store<String>,
+            um_a: UndoManager,
+            um_b: UndoManager,
+            focus: TokenStore<Option<i32>>,
+        }
+
+        struct App(Rc<Model>);
+
+        impl ApplicationProvider for App {
+            fn name(&self) -> &str {
+                "Undo Router Test"
+            }
+
+            fn will_spawn(&self, app: &Application, s: MSlock) {
+                app.spawn_window(MainWindow(self.0.clone()), s);
+            }
+        }
+
+        struct MainWindow(Rc<Model>);
+
+        impl WindowProvider for MainWindow {
+            type Environment = Env;
+
+            fn title(&self, _env: &StandardConstEnv, _s: MSlock) -> impl Signal<Target=String> {
+                FixedSignal::new("Undo Router".to_string())
+            }
+
+            fn size(&self, _env: &StandardConstEnv, _s: MSlock) -> (Size, Size, Size) {
+                (Size::new(100.0, 100.0), Size::new(200.0, 200.0), Size::new(400.0, 400.0))
+            }
+
+            fn root(&self, env: &StandardConstEnv, s: MSlock) -> impl ViewProvider<Env, DownContext=()> {
+                let m = &self.0;
+                vstack()
+                    .push(
+                        TextField::new(m.a.binding())
+                            .focused_if_eq(m.focus.binding(), 1)
+                            .mount_undo_manager(m.um_a.clone())
+                    )
+                    .push(
+                        TextField::new(m.b.binding())
+                            .focused_if_eq(m.focus.binding(), 2)
+                            .mount_undo_manager(m.um_b.clone())
+                    )
+                    .into_view_provider(env, s)
+            }
+
+            fn menu(&self, env: &StandardConstEnv, s: MSlock) -> WindowMenu {
+                WindowMenu::standard(env, Menu::new("File"), Menu::new("Edit"), Menu::new("View"), Menu::new("Help"), s)
+            }
+        }
+
+        fn router(um: &UndoManager, s: MSlock) -> UndoRouter {
+            um.inner.borrow(s).router.clone().unwrap()
+        }
+
+        fn is_active_manager(router: &UndoRouter, um: &UndoManager, s: MSlock) -> bool {
+            router.active(s).is_some_and(|active| Arc::ptr_eq(&active.inner, &um.inner))
+        }
+
+        // title and enabled state of the undo item
+        fn undo_item(h: &Headless, window: WindowId) -> (String, bool) {
+            let window = h.windows().into_iter().find(|w| w.id == window).unwrap();
+            window.menu("Edit").unwrap().items.iter()
+                .find_map(|item| match item {
+                    HeadlessMenuItem::Button { title, key, enabled, .. } if key == "z" =>
+                        Some((title.clone(), *enabled)),
+                    _ => None
+                })
+                .unwrap()
+        }
+
+        fn fields(h: &Headless) -> (ViewId, ViewId) {
+            let root = h.windows().remove(0).root.unwrap();
+            let mut ids = vec![];
+            collect_fields(&root, &mut ids);
+            assert_eq!(ids.len(), 2);
+            (ids[0], ids[1])
+        }
+
+        fn collect_fields(view: &HeadlessView, ids: &mut Vec<ViewId>) {
+            if matches!(view.kind, HeadlessViewKind::TextField { .. }) {
+                ids.push(view.id);
+            }
+            for child in &view.children {
+                collect_fields(child, ids);
+            }
+        }
+
+        #[test]
+        fn test_undo_router() {
+            let model = {
+                let s = super::mslock_owner();
+                let a = Store::new(String::new());
+                let b = Store::new(String::new());
+                let um_a = UndoManager::new(&a, s.marker());
+                let um_b = UndoManager::new(&b, s.marker());
+                Rc::new(Model { a, b, um_a, um_b, focus: TokenStore::new(None) })
+            };
+
+            let m = model.clone();
+            crate::headless::run(App(model), move |h: &Headless| {
+                let window = h.windows()[0].id;
+                let (field_a, field_b) = fields(h);
+
+                {
+                    let s = slock_main_owner();
+                    let router = router(&m.um_a, s.marker());
+                    // with nothing focused, the first manager shown keeps the menus
+                    assert!(is_active_manager(&router, &m.um_a, s.marker()));
+                    assert!(m.um_a.is_active(s.marker()));
+                    assert!(!m.um_b.is_active(s.marker()));
+                }
+                assert_eq!(undo_item(h, window), ("Undo".to_string(), false));
+
+                {
+                    let s = slock_main_owner();
+                    m.a.apply(Set("a".to_string()), s.marker());
+                    m.a.undo_barrier(UndoBarrier::Strong, s.marker());
+                }
+                h.settle();
+                assert_eq!(undo_item(h, window), ("Undo Change".to_string(), true));
+
+                h.focus(field_b);
+                {
+                    let s = slock_main_owner();
+                    let router = router(&m.um_b, s.marker());
+                    assert!(is_active_manager(&router, &m.um_b, s.marker()));
+                    assert!(m.um_b.is_active(s.marker()));
+                    assert!(!m.um_a.is_active(s.marker()));
+                }
+                // b has no history, even though a does
+                assert_eq!(undo_item(h, window), ("Undo".to_string(), false));
+
+                h.focus(field_a);
+                {
+                    let s = slock_main_owner();
+                    let router = router(&m.um_a, s.marker());
+                    assert!(is_active_manager(&router, &m.um_a, s.marker()));
+                    assert!(m.um_a.is_active(s.marker()));
+                    assert!(!m.um_b.is_active(s.marker()));
+                }
+                assert_eq!(undo_item(h, window), ("Undo Change".to_string(), true));
+
+                // the menu action is routed to the focused manager
+                assert!(h.trigger_menu(window, &["Edit", "Undo Change"]));
+                {
+                    let s = slock_main_owner();
+                    assert_eq!(*m.a.borrow(s.marker()), "");
+                    assert!(m.um_a.is_active(s.marker()));
+                }
+                assert_eq!(undo_item(h, window), ("Undo".to_string(), false));
+            });
+        }
+    }
+}
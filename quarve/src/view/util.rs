@@ -80,6 +80,10 @@ mod size_container {
 pub use size_container::*;
 
 mod color {
+    use std::ops::{Add, Sub};
+
+    use crate::util::numeric::{Lerp, Norm};
+
     #[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
     #[repr(C)]
     pub struct Color {
@@ -132,7 +136,202 @@ mod color {
         pub fn a(&self) -> u8 {
             self.a
         }
+
+        /// Interpolates the raw sRGB channels.
+        /// This is cheap but tends to produce muddy midpoints;
+        /// the `Lerp` implementation interpolates in linear RGB instead
+        pub fn lerp_srgb(lhs: Color, factor: f64, rhs: Color) -> Color {
+            interpolate(lhs, factor, rhs, |c| c, |c| c)
+        }
+
+        /// Interpolates in linear RGB, preserving perceived intensity
+        /// better than raw sRGB (this is what `Lerp` uses)
+        pub fn lerp_linear(lhs: Color, factor: f64, rhs: Color) -> Color {
+            interpolate(lhs, factor, rhs, |c| c.map(srgb_to_linear), |c| c.map(linear_to_srgb))
+        }
+
+        /// Interpolates in the OKLab perceptual color space,
+        /// which gives even hue and lightness transitions
+        pub fn lerp_oklab(lhs: Color, factor: f64, rhs: Color) -> Color {
+            interpolate(
+                lhs, factor, rhs,
+                |c| linear_to_oklab(c.map(srgb_to_linear)),
+                |c| oklab_to_linear(c).map(linear_to_srgb)
+            )
+        }
+
+        fn channels(self) -> [f64; 3] {
+            [self.r, self.g, self.b].map(|c| c as f64 / u8::MAX as f64)
+        }
+
+        fn from_channels(channels: [f64; 3], alpha: f64) -> Color {
+            let [r, g, b, a] = [channels[0], channels[1], channels[2], alpha]
+                .map(|c| (c.clamp(0.0, 1.0) * u8::MAX as f64).round() as u8);
+
+            Color { r, g, b, a }
+        }
+    }
+
+    // interpolates with premultiplied alpha so that fading
+    // to or from clear does not darken the intermediate colors
+    fn interpolate(
+        lhs: Color,
+        factor: f64,
+        rhs: Color,
+        into_space: impl Fn([f64; 3]) -> [f64; 3],
+        from_space: impl Fn([f64; 3]) -> [f64; 3]
+    ) -> Color {
+        let la = lhs.a as f64 / u8::MAX as f64;
+        let ra = rhs.a as f64 / u8::MAX as f64;
+        let alpha = la * (1.0 - factor) + ra * factor;
+        if alpha <= 0.0 {
+            return Color::clear();
+        }
+
+        let lc = into_space(lhs.channels());
+        let rc = into_space(rhs.channels());
+        let mixed: [f64; 3] = std::array::from_fn(|i| {
+            (lc[i] * la * (1.0 - factor) + rc[i] * ra * factor) / alpha
+        });
+
+        Color::from_channels(from_space(mixed), alpha)
+    }
+
+    fn srgb_to_linear(c: f64) -> f64 {
+        if c <= 0.04045 {
+            c / 12.92
+        }
+        else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn linear_to_srgb(c: f64) -> f64 {
+        if c <= 0.0031308 {
+            12.92 * c
+        }
+        else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    }
+
+    // https://bottosson.github.io/posts/oklab/
+    fn linear_to_oklab([r, g, b]: [f64; 3]) -> [f64; 3] {
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+        [
+            0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        ]
+    }
+
+    fn oklab_to_linear([l, a, b]: [f64; 3]) -> [f64; 3] {
+        let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+        let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+        let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+        let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+
+        [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ]
     }
 
+    impl Lerp for Color {
+        fn lerp(lhs: Self, factor: f64, rhs: Self) -> Self {
+            Color::lerp_linear(lhs, factor, rhs)
+        }
+    }
+
+    impl Norm for Color {
+        // euclidean length of the normalized rgba channels
+        fn norm(&self) -> f64 {
+            [self.r, self.g, self.b, self.a]
+                .map(|c| c as f64 / u8::MAX as f64)
+                .iter()
+                .map(|c| c * c)
+                .sum::<f64>()
+                .sqrt()
+        }
+    }
+
+    /// A color with floating point channels in linear RGB (and straight alpha).
+    /// Unlike `Color`, it supports the arithmetic needed by capacitors
+    /// such as `ConstantSpeedCapacitor`, `SpringCapacitor`, and `DecayCapacitor`,
+    /// e.g. `color.map(|c| LinearColor::from(*c)).with_capacitor(spring, s)`.
+    /// Channels are clamped when converting back into a `Color`,
+    /// so overshoot is allowed in between
+    #[derive(Default, Copy, Clone, PartialEq, Debug)]
+    pub struct LinearColor {
+        pub r: f64,
+        pub g: f64,
+        pub b: f64,
+        pub a: f64,
+    }
+
+    impl From<Color> for LinearColor {
+        fn from(color: Color) -> Self {
+            let [r, g, b] = color.channels().map(srgb_to_linear);
+            LinearColor {
+                r, g, b,
+                a: color.a as f64 / u8::MAX as f64
+            }
+        }
+    }
+
+    impl From<LinearColor> for Color {
+        fn from(color: LinearColor) -> Self {
+            Color::from_channels([color.r, color.g, color.b].map(|c| linear_to_srgb(c.clamp(0.0, 1.0))), color.a)
+        }
+    }
+
+    impl Add for LinearColor {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self::Output {
+            LinearColor {
+                r: self.r + rhs.r,
+                g: self.g + rhs.g,
+                b: self.b + rhs.b,
+                a: self.a + rhs.a,
+            }
+        }
+    }
+
+    impl Sub for LinearColor {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self::Output {
+            LinearColor {
+                r: self.r - rhs.r,
+                g: self.g - rhs.g,
+                b: self.b - rhs.b,
+                a: self.a - rhs.a,
+            }
+        }
+    }
+
+    // unlike Color, interpolation is linear in each channel
+    // so that velocities may be scaled
+    impl Lerp for LinearColor {
+        fn lerp(lhs: Self, factor: f64, rhs: Self) -> Self {
+            LinearColor {
+                r: f64::lerp(lhs.r, factor, rhs.r),
+                g: f64::lerp(lhs.g, factor, rhs.g),
+                b: f64::lerp(lhs.b, factor, rhs.b),
+                a: f64::lerp(lhs.a, factor, rhs.a),
+            }
+        }
+    }
+
+    impl Norm for LinearColor {
+        fn norm(&self) -> f64 {
+            (self.r * self.r + self.g * self.g + self.b * self.b + self.a * self.a).sqrt()
+        }
+    }
 }
 pub use color::*;
\ No newline at end of file
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use proc_macro_crate::{crate_name, FoundCrate};
-use syn::{parse_macro_input, DeriveInput, Data, Meta, Token, DataStruct, Path};
+use syn::{parse_macro_input, DeriveInput, Data, Meta, Token, DataStruct, Path, Attribute, Generics, Ident, Type};
 use syn::punctuated::Punctuated;
 use quote::quote;
 
@@ -28,12 +28,27 @@ pub fn store_container_derive(input: TokenStream) -> TokenStream {
 
         let sub_stores_clone = sub_stores.clone();
 
-        let quarve_path: Path = match crate_name("quarve").expect("Error finding crate name") {
-            FoundCrate::Itself => syn::parse_quote!(crate),
-            FoundCrate::Name(_) => syn::parse_quote!(::quarve),
+        let quarve_path = quarve_path();
+
+        let serde_impl = if has_struct_flag(&input.attrs, "serde") {
+            serde_derive(&ident, &generics, &strct, &quarve_path)
+        }
+        else {
+            quote! {}
+        };
+
+        let journal_impl = if has_struct_flag(&input.attrs, "journal") {
+            journal_derive(&ident, &generics, &strct, &quarve_path)
+        }
+        else {
+            quote! {}
         };
 
         quote! {
+            #serde_impl
+
+            #journal_impl
+
             #[doc = #doc_str]
             impl #impl_generics #quarve_path::state::StoreContainer for #ident #ty_generics #where_clause {
                 fn subtree_general_listener<__F_SC_FUNC: #quarve_path::state::GeneralListener + Clone>(&self, f: __F_SC_FUNC, s: #quarve_path::core::Slock<impl #quarve_path::util::marker::ThreadMarker>) {
@@ -55,6 +70,185 @@ pub fn store_container_derive(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Derives `Stateful` for a struct or enum whose fields are themselves
+/// `Stateful` (without inner stores).
+///
+/// Generates `{Ident}ActionBasis`, which has a variant per field
+/// wrapping that field's action, as well as a `Set` variant
+/// that replaces the entire value. Struct fields are named by
+/// their camel cased identifier (`Field0` for tuple structs),
+/// and enum fields are additionally prefixed by their variant name
+/// (e.g. `CircleRadius` or `Circle0`).
+/// Applying a field action while the value is a different
+/// enum variant panics.
+#[proc_macro_derive(Stateful)]
+pub fn stateful_derive(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let quarve_path = quarve_path();
+    let basis_ident = quote::format_ident!("{}ActionBasis", ident);
+
+    // (action variant, field type, pattern binding the field to `__field`)
+    let fields: Vec<(Ident, Type, proc_macro2::TokenStream)> = match &input.data {
+        Data::Struct(strct) => strct.fields.iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let member = field_member(field, i);
+                (field_variant("Field", "", field, i), field.ty.clone(), quote!(#ident { #member: __field, .. }))
+            })
+            .collect(),
+        Data::Enum(enm) => enm.variants.iter()
+            .flat_map(|variant| {
+                let var = &variant.ident;
+                variant.fields.iter()
+                    .enumerate()
+                    .map(move |(i, field)| {
+                        let member = field_member(field, i);
+                        let name = var.to_string();
+                        (field_variant(&name, &name, field, i), field.ty.clone(), quote!(#ident::#var { #member: __field, .. }))
+                    })
+            })
+            .collect(),
+        Data::Union(_) => panic!("Can only derive Stateful for a struct or enum"),
+    };
+
+    if fields.iter().any(|(variant, _, _)| variant == "Set") {
+        panic!("Cannot derive Stateful for {}: a field action would be named `Set`, which is reserved for replacing the entire value", ident);
+    }
+
+    let variants: Vec<_> = fields.iter().map(|(variant, _, _)| variant).collect();
+    let types: Vec<_> = fields.iter().map(|(_, ty, _)| ty).collect();
+    let patterns: Vec<_> = fields.iter().map(|(_, _, pattern)| pattern).collect();
+
+    // only enums can have a field action that does not match
+    let is_enum = matches!(input.data, Data::Enum(_));
+    let mismatches: Vec<_> = variants.iter()
+        .map(|variant| if is_enum {
+            quote! {
+                #[allow(unreachable_patterns)]
+                _ => panic!("Attempted to apply {}::{} to a different variant", stringify!(#basis_ident), stringify!(#variant))
+            }
+        }
+        else {
+            quote! {}
+        })
+        .collect();
+
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut generics = input.generics.clone();
+    {
+        let where_clause = generics.make_where_clause();
+        where_clause.predicates.extend(types.iter().map(|ty| -> syn::WherePredicate {
+            syn::parse_quote!(#ty: #quarve_path::state::Stateful<HasInnerStores=#quarve_path::util::marker::FalseMarker>)
+        }));
+        where_clause.predicates.push(syn::parse_quote!(#ident #ty_generics: ::std::marker::Send + 'static));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut array_generics = generics.clone();
+    array_generics.params.push(syn::parse_quote!(const __N: usize));
+    let (array_impl_generics, _, _) = array_generics.split_for_impl();
+
+    let doc_str = format!(
+        "Action basis of [`{}`]; either an action on a single field or a replacement of the entire value.\n ",
+        ident
+    );
+
+    quote! {
+        #[doc = #doc_str]
+        #vis enum #basis_ident #impl_generics #where_clause {
+            #(#variants(<#types as #quarve_path::state::Stateful>::Action),)*
+            Set(#ident #ty_generics),
+        }
+
+        impl #impl_generics #quarve_path::state::GroupBasis<#ident #ty_generics> for #basis_ident #ty_generics #where_clause {
+            fn apply(self, to: &mut #ident #ty_generics) -> Self {
+                match self {
+                    #(#basis_ident::#variants(action) => match to {
+                        #patterns => #basis_ident::#variants(
+                            <<#types as #quarve_path::state::Stateful>::Action as #quarve_path::state::GroupBasis<#types>>::apply(action, __field)
+                        ),
+                        #mismatches
+                    },)*
+                    #basis_ident::Set(value) => #basis_ident::Set(::std::mem::replace(to, value)),
+                }
+            }
+
+            fn forward_description(&self) -> impl Into<String> {
+                let ret: String = match self {
+                    #(#basis_ident::#variants(action) =>
+                        <<#types as #quarve_path::state::Stateful>::Action as #quarve_path::state::GroupBasis<#types>>::forward_description(action).into(),
+                    )*
+                    #basis_ident::Set(_) => "Change".into(),
+                };
+                ret
+            }
+
+            fn backward_description(&self) -> impl Into<String> {
+                let ret: String = match self {
+                    #(#basis_ident::#variants(action) =>
+                        <<#types as #quarve_path::state::Stateful>::Action as #quarve_path::state::GroupBasis<#types>>::backward_description(action).into(),
+                    )*
+                    #basis_ident::Set(_) => "Change".into(),
+                };
+                ret
+            }
+        }
+
+        impl #impl_generics #quarve_path::state::Stateful for #ident #ty_generics #where_clause {
+            type Action = #quarve_path::state::Word<#basis_ident #ty_generics>;
+            type HasInnerStores = #quarve_path::util::marker::FalseMarker;
+        }
+
+        impl #array_impl_generics #quarve_path::state::IntoAction<#quarve_path::state::Word<#basis_ident #ty_generics>, #ident #ty_generics> for [#basis_ident #ty_generics; __N] #where_clause {
+            fn into_action(self, _target: &#ident #ty_generics) -> #quarve_path::state::Word<#basis_ident #ty_generics> {
+                #quarve_path::state::Word::new(self.into_iter().collect())
+            }
+        }
+    }
+        .into()
+}
+
+fn field_member(field: &syn::Field, index: usize) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(index.into()),
+    }
+}
+
+// snake_case field -> {prefix}CamelCase variant
+// unnamed fields become {unnamed_prefix}{index}
+fn field_variant(unnamed_prefix: &str, prefix: &str, field: &syn::Field, index: usize) -> Ident {
+    let name = match &field.ident {
+        Some(ident) => {
+            let camel: String = ident.to_string()
+                .trim_start_matches("r#")
+                .split('_')
+                .map(|part| {
+                    let mut chars = part.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new()
+                    }
+                })
+                .collect();
+            format!("{}{}", prefix, camel)
+        }
+        None => format!("{}{}", unnamed_prefix, index)
+    };
+
+    Ident::new(&name, proc_macro2::Span::call_site())
+}
+
+fn quarve_path() -> Path {
+    match crate_name("quarve").expect("Error finding crate name") {
+        FoundCrate::Itself => syn::parse_quote!(crate),
+        FoundCrate::Name(_) => syn::parse_quote!(::quarve),
+    }
+}
+
 fn filter_ignored_stores(data: &DataStruct) -> impl Iterator<Item=&syn::Field> {
     data.fields.iter()
         .filter(
@@ -73,4 +267,161 @@ fn filter_ignored_stores(data: &DataStruct) -> impl Iterator<Item=&syn::Field> {
                     }
                 })
         )
-}
\ No newline at end of file
+}
+fn quarve_flags(attrs: &[Attribute]) -> impl Iterator<Item=syn::Ident> + '_ {
+    attrs.iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::List(lst) if lst.path.is_ident("quarve") => Some(lst),
+            _ => None
+        })
+        .flat_map(|lst| {
+            lst.parse_args_with(Punctuated::<syn::Ident, Token![,]>::parse_terminated)
+                .unwrap_or_else(|err| {
+                    panic!("Invalid use of `quarve` attribute. Expected #[quarve(serde, journal)] {}", err)
+                })
+        })
+}
+
+fn has_struct_flag(attrs: &[Attribute], flag: &str) -> bool {
+    quarve_flags(attrs).any(|ident| ident == flag)
+}
+
+// Serializes all non ignored fields as a single struct
+// Ignored fields are restored with their default value.
+// Done by deriving serde on mirror structs so that the output format
+// is the same as if the user had derived it themselves
+#[cfg(feature = "serde")]
+fn serde_derive(ident: &Ident, generics: &Generics, strct: &DataStruct, quarve_path: &Path) -> proc_macro2::TokenStream {
+    let serde = quote!(#quarve_path::__private::serde);
+    let serde_str = serde.to_string().replace(' ', "");
+    let name = ident.to_string();
+
+    let ser_ident = quote::format_ident!("__QuarveSerialize{}", ident);
+    let de_ident = quote::format_ident!("__QuarveDeserialize{}", ident);
+
+    let stored: Vec<_> = filter_ignored_stores(strct).collect();
+    let stored_names: Vec<_> = stored.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let stored_types: Vec<_> = stored.iter().map(|f| &f.ty).collect();
+
+    let ignored_names: Vec<_> = strct.fields.iter()
+        .map(|f| f.ident.as_ref().unwrap())
+        .filter(|f| !stored_names.contains(f))
+        .collect();
+
+    // type parameters may only appear in ignored fields
+    let type_params: Vec<_> = generics.type_params()
+        .map(|t| &t.ident)
+        .collect();
+
+    // the mirror and the outer impls must agree on bounds
+    let ser_bound = stored_types.iter()
+        .map(|ty| quote!(#ty: #serde::Serialize).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let de_bound = stored_types.iter()
+        .map(|ty| quote!(#ty: #serde::Deserialize<'de>).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut ser_generics = generics.clone();
+    ser_generics.params.insert(0, syn::parse_quote!('__quarve_a));
+    let (ser_impl_generics, _, _) = ser_generics.split_for_impl();
+
+    let mut outer_ser_generics = generics.clone();
+    outer_ser_generics.make_where_clause().predicates.extend(
+        stored_types.iter().map(|ty| -> syn::WherePredicate { syn::parse_quote!(#ty: #serde::Serialize) })
+    );
+    let outer_ser_where = &outer_ser_generics.where_clause;
+
+    let mut outer_de_generics = generics.clone();
+    outer_de_generics.params.insert(0, syn::parse_quote!('de));
+    outer_de_generics.make_where_clause().predicates.extend(
+        stored_types.iter().map(|ty| -> syn::WherePredicate { syn::parse_quote!(#ty: #serde::Deserialize<'de>) })
+    );
+    let (outer_de_impl_generics, _, outer_de_where) = outer_de_generics.split_for_impl();
+
+    quote! {
+        const _: () = {
+            #[derive(#serde::Serialize)]
+            #[serde(crate = #serde_str, rename = #name, bound(serialize = #ser_bound))]
+            struct #ser_ident #ser_impl_generics #where_clause {
+                #(#stored_names: &'__quarve_a #stored_types,)*
+                #[serde(skip)]
+                __quarve_phantom: ::std::marker::PhantomData<fn() -> (#(#type_params,)*)>
+            }
+
+            #[derive(#serde::Deserialize)]
+            #[serde(crate = #serde_str, rename = #name, bound(deserialize = #de_bound))]
+            struct #de_ident #impl_generics #where_clause {
+                #(#stored_names: #stored_types,)*
+                #[serde(skip)]
+                __quarve_phantom: ::std::marker::PhantomData<fn() -> (#(#type_params,)*)>
+            }
+
+            impl #impl_generics #serde::Serialize for #ident #ty_generics #outer_ser_where {
+                fn serialize<__Z>(&self, serializer: __Z) -> ::std::result::Result<__Z::Ok, __Z::Error>
+                    where __Z: #serde::Serializer
+                {
+                    let mirror = #ser_ident {
+                        #(#stored_names: &self.#stored_names,)*
+                        __quarve_phantom: ::std::marker::PhantomData::<fn() -> (#(#type_params,)*)>
+                    };
+                    #serde::Serialize::serialize(&mirror, serializer)
+                }
+            }
+
+            impl #outer_de_impl_generics #serde::Deserialize<'de> for #ident #ty_generics #outer_de_where {
+                fn deserialize<__D>(deserializer: __D) -> ::std::result::Result<Self, __D::Error>
+                    where __D: #serde::Deserializer<'de>
+                {
+                    let mirror: #de_ident #ty_generics = #serde::Deserialize::deserialize(deserializer)?;
+                    ::std::result::Result::Ok(#ident {
+                        #(#stored_names: mirror.#stored_names,)*
+                        #(#ignored_names: ::std::default::Default::default(),)*
+                    })
+                }
+            }
+        };
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn serde_derive(_ident: &Ident, _generics: &Generics, _strct: &DataStruct, _quarve_path: &Path) -> proc_macro2::TokenStream {
+    panic!("#[quarve(serde)] requires the `serde` feature of quarve to be enabled")
+}
+
+#[cfg(feature = "serde")]
+fn journal_derive(ident: &Ident, generics: &Generics, strct: &DataStruct, quarve_path: &Path) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let stored: Vec<_> = filter_ignored_stores(strct)
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect();
+    let stored_names: Vec<_> = stored.iter()
+        .map(|field| field.to_string())
+        .collect();
+
+    quote! {
+        impl #impl_generics #quarve_path::state::Journaled for #ident #ty_generics #where_clause {
+            fn subtree_journal_at(&self, path: &#quarve_path::state::JournalPath, journal: &#quarve_path::state::Journal, s: #quarve_path::core::Slock<impl #quarve_path::util::marker::ThreadMarker>) {
+                #(self.#stored.subtree_journal_at(&path.field(#stored_names), journal, s);)*
+            }
+
+            fn replay_at(&self, path: &[#quarve_path::state::PathSegment], action: #quarve_path::state::JournalValue, s: #quarve_path::core::Slock<impl #quarve_path::util::marker::ThreadMarker>) -> ::std::result::Result<(), #quarve_path::state::JournalError> {
+                match path.split_first() {
+                    #(::std::option::Option::Some((#quarve_path::state::PathSegment::Field(field), rest)) if field == #stored_names => {
+                        self.#stored.replay_at(rest, action, s)
+                    })*
+                    _ => ::std::result::Result::Err(#quarve_path::state::JournalError::InvalidPath(path.to_vec()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn journal_derive(_ident: &Ident, _generics: &Generics, _strct: &DataStruct, _quarve_path: &Path) -> proc_macro2::TokenStream {
+    panic!("#[quarve(journal)] requires the `serde` feature of quarve to be enabled")
+}